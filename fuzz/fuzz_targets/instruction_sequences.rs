@@ -0,0 +1,347 @@
+//! Runs arbitrary sequences of order instructions against the built program in LiteSVM,
+//! with accounts of each instruction swapped for other accounts of the same setup, and
+//! checks the escrow invariants after every step, whether the step failed or not.
+//!
+//! Build the program with `cargo build-sbf` first, then from this directory:
+//! `cargo +nightly fuzz run instruction_sequences`
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use litesvm::LiteSVM;
+use solana_sdk::{
+    account::Account, clock::Clock, instruction::Instruction, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
+use spl_p2p_client::{
+    decode_order, instructions, pda, program::state::MEMO_LEN, FillParams, OrderParams, SwapOrder,
+    ID,
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+/// Orders the maker can have open at once, by order id
+const ORDERS: u8 = 4;
+/// Minted to the maker in the maker mint and to the taker in the taker mint
+const SUPPLY: u64 = 1_000_000;
+
+#[derive(Arbitrary, Debug)]
+enum Action {
+    Initialize {
+        order: u8,
+        maker_amount: u32,
+        taker_amount: u32,
+        all_or_none: bool,
+        any_taker: bool,
+    },
+    ChangeAmounts {
+        order: u8,
+        maker_amount: u32,
+        taker_amount: u32,
+    },
+    ChangeTaker {
+        order: u8,
+        any_taker: bool,
+    },
+    Fill {
+        order: u8,
+        fill_amount: u32,
+        max_taker_amount: u32,
+    },
+    Close {
+        order: u8,
+    },
+    Warp {
+        seconds: u16,
+    },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Step {
+    action: Action,
+    /// Puts account `.1` of the setup in place of non-signer account `.0` of the
+    /// instruction
+    swap_account: Option<(u8, u8)>,
+}
+
+struct Env {
+    svm: LiteSVM,
+    maker: Keypair,
+    taker: Keypair,
+    maker_mint: Pubkey,
+    taker_mint: Pubkey,
+    /// Every account of the setup, candidates for `Step::swap_account`
+    accounts: Vec<Pubkey>,
+}
+
+impl Env {
+    fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(ID, "../target/deploy/splerg_p2p.so")
+            .expect("build the program with `cargo build-sbf` first");
+
+        let (maker, taker) = (Keypair::new(), Keypair::new());
+        svm.airdrop(&maker.pubkey(), 10_000_000_000).unwrap();
+        svm.airdrop(&taker.pubkey(), 10_000_000_000).unwrap();
+
+        let (maker_mint, taker_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut env = Self {
+            svm,
+            maker,
+            taker,
+            maker_mint,
+            taker_mint,
+            accounts: Vec::new(),
+        };
+        env.set_mint(&maker_mint);
+        env.set_mint(&taker_mint);
+        let (maker_key, taker_key) = (env.maker.pubkey(), env.taker.pubkey());
+        env.set_token_account(&maker_key, &maker_mint, SUPPLY);
+        env.set_token_account(&maker_key, &taker_mint, 0);
+        env.set_token_account(&taker_key, &maker_mint, 0);
+        env.set_token_account(&taker_key, &taker_mint, SUPPLY);
+
+        env.accounts.extend([
+            maker_key,
+            taker_key,
+            maker_mint,
+            taker_mint,
+            spl_token::id(),
+            solana_sdk::system_program::id(),
+        ]);
+        for order in 0..ORDERS {
+            let address = env.order_address(order);
+            env.accounts
+                .extend([address, pda::vault(&address, &maker_mint)]);
+        }
+        env
+    }
+
+    fn set_mint(&mut self, mint: &Pubkey) {
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            mint_authority: COption::Some(self.maker.pubkey()),
+            supply: SUPPLY,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.set_account(mint, data);
+    }
+
+    fn set_token_account(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) {
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        }
+        .pack_into_slice(&mut data);
+        let address = self.ata(owner, mint);
+        self.set_account(&address, data);
+        self.accounts.push(address);
+    }
+
+    fn set_account(&mut self, address: &Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(*address, account).unwrap();
+    }
+
+    fn ata(&self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        spl_associated_token_account::get_associated_token_address(wallet, mint)
+    }
+
+    fn order_address(&self, order: u8) -> Pubkey {
+        pda::order(
+            &self.maker.pubkey(),
+            &self.maker_mint,
+            &self.taker_mint,
+            (order % ORDERS) as u64,
+        )
+        .unwrap()
+    }
+
+    fn order(&self, order: u8) -> Option<SwapOrder> {
+        let account = self.svm.get_account(&self.order_address(order))?;
+        decode_order(&account.data).ok()
+    }
+
+    fn balance(&self, address: &Pubkey) -> u64 {
+        self.svm
+            .get_account(address)
+            .and_then(|account| TokenAccount::unpack(&account.data).ok())
+            .map_or(0, |account| account.amount)
+    }
+
+    /// The instruction for `action` and whether the taker signs it rather than the maker
+    fn instruction(&mut self, action: &Action) -> Option<(Instruction, bool)> {
+        let maker = self.maker.pubkey();
+        let taker = self.taker.pubkey();
+        let token_program = spl_token::id();
+        let instruction = match *action {
+            Action::Initialize {
+                order,
+                maker_amount,
+                taker_amount,
+                all_or_none,
+                any_taker,
+            } => {
+                let params = OrderParams {
+                    order_id: (order % ORDERS) as u64,
+                    maker_amount: maker_amount as u64,
+                    taker_amount: taker_amount as u64,
+                    requires_confirmation: false,
+                    dual_escrow: false,
+                    all_or_none,
+                    memo: [0; MEMO_LEN],
+                };
+                let order_taker = if any_taker { Pubkey::default() } else { taker };
+                let instruction = instructions::initialize_order(
+                    &maker,
+                    &order_taker,
+                    &self.maker_mint,
+                    &self.taker_mint,
+                    &token_program,
+                    &token_program,
+                    &params,
+                )
+                .ok()?;
+                (instruction, false)
+            }
+            Action::ChangeAmounts {
+                order,
+                maker_amount,
+                taker_amount,
+            } => (
+                instructions::change_order_amounts(
+                    &maker,
+                    &self.order_address(order),
+                    &self.order(order)?,
+                    &token_program,
+                    maker_amount as u64,
+                    taker_amount as u64,
+                ),
+                false,
+            ),
+            Action::ChangeTaker { order, any_taker } => {
+                let new_taker = if any_taker { Pubkey::default() } else { taker };
+                (
+                    instructions::change_taker(&maker, &self.order_address(order), &new_taker),
+                    false,
+                )
+            }
+            Action::Fill {
+                order,
+                fill_amount,
+                max_taker_amount,
+            } => (
+                instructions::complete_swap(
+                    &taker,
+                    &self.order_address(order),
+                    &self.order(order)?,
+                    &token_program,
+                    &token_program,
+                    &FillParams {
+                        fill_amount: fill_amount as u64,
+                        max_taker_amount: max_taker_amount as u64,
+                        min_maker_amount: 0,
+                    },
+                ),
+                true,
+            ),
+            Action::Close { order } => (
+                instructions::close_order(
+                    &maker,
+                    &self.order_address(order),
+                    &self.order(order)?,
+                    &token_program,
+                    None,
+                ),
+                false,
+            ),
+            Action::Warp { seconds } => {
+                let mut clock = self.svm.get_sysvar::<Clock>();
+                clock.unix_timestamp += seconds as i64;
+                clock.slot += 1;
+                self.svm.set_sysvar(&clock);
+                return None;
+            }
+        };
+        Some(instruction)
+    }
+
+    fn step(&mut self, step: &Step) {
+        let Some((mut instruction, taker_signs)) = self.instruction(&step.action) else {
+            return;
+        };
+        if let Some((index, with)) = step.swap_account {
+            let metas = &mut instruction.accounts;
+            let index = index as usize % metas.len();
+            if !metas[index].is_signer {
+                metas[index].pubkey = self.accounts[with as usize % self.accounts.len()];
+            }
+        }
+
+        let signer = if taker_signs {
+            &self.taker
+        } else {
+            &self.maker
+        };
+        self.svm.expire_blockhash();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signer.pubkey()),
+            &[signer],
+            self.svm.latest_blockhash(),
+        );
+        // Failing is fine, the invariants have to hold either way
+        let _ = self.svm.send_transaction(transaction);
+    }
+
+    fn check_invariants(&self) {
+        let mut escrowed = 0;
+        for order in 0..ORDERS {
+            let address = self.order_address(order);
+            let vault = self.balance(&pda::vault(&address, &self.maker_mint));
+            escrowed += vault;
+            let Some(swap_order) = self.order(order) else {
+                continue;
+            };
+            let maker_amount = swap_order.maker_amount;
+            if swap_order.is_open() {
+                assert!(
+                    vault >= maker_amount,
+                    "order {order} escrows {vault} for an open maker amount of {maker_amount}"
+                );
+            }
+        }
+
+        // Tokens only ever move between the two parties and the escrows
+        let (maker, taker) = (self.maker.pubkey(), self.taker.pubkey());
+        let maker_side = self.balance(&self.ata(&maker, &self.maker_mint))
+            + self.balance(&self.ata(&taker, &self.maker_mint))
+            + escrowed;
+        assert_eq!(maker_side, SUPPLY, "maker mint tokens left the setup");
+        let taker_side = self.balance(&self.ata(&maker, &self.taker_mint))
+            + self.balance(&self.ata(&taker, &self.taker_mint));
+        assert_eq!(taker_side, SUPPLY, "taker mint tokens left the setup");
+    }
+}
+
+fuzz_target!(|steps: Vec<Step>| {
+    let mut env = Env::new();
+    for step in steps.iter().take(32) {
+        env.step(step);
+        env.check_invariants();
+    }
+});
@@ -0,0 +1,265 @@
+//! `spl-p2p`: create, list, change, fill and close orders from the command line, signing
+//! with a local keypair against any RPC endpoint
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+use spl_p2p_client::{
+    instructions, pda,
+    rpc::{fetch_order, fetch_orders_by_maker},
+    FillParams, OrderParams, SwapOrder,
+};
+
+#[derive(Parser)]
+#[command(name = "spl-p2p", version, about = "Manage splerg-p2p orders")]
+struct Cli {
+    /// RPC endpoint
+    #[arg(short, long, global = true, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Keypair signing and paying for transactions [default: ~/.config/solana/id.json]
+    #[arg(short, long, global = true)]
+    keypair: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Escrow `maker-amount` of MAKER_MINT for `taker-amount` of TAKER_MINT
+    CreateOrder {
+        maker_mint: Pubkey,
+        taker_mint: Pubkey,
+        maker_amount: u64,
+        taker_amount: u64,
+        /// Only taker allowed to fill [default: anyone]
+        #[arg(long)]
+        taker: Option<Pubkey>,
+        /// Seed telling apart orders of the same pair [default: current unix time]
+        #[arg(long)]
+        order_id: Option<u64>,
+        /// Reject partial fills
+        #[arg(long)]
+        all_or_none: bool,
+        /// Note stored on the order, up to 64 bytes
+        #[arg(long)]
+        memo: Option<String>,
+    },
+    /// List the orders of a maker
+    ListOrders {
+        /// [default: the keypair's pubkey]
+        maker: Option<Pubkey>,
+    },
+    /// Reprice or resize an open order
+    ChangeAmounts {
+        order: Pubkey,
+        maker_amount: u64,
+        taker_amount: u64,
+    },
+    /// Reserve an order for another taker, or the default pubkey to open it to anyone
+    ChangeTaker { order: Pubkey, taker: Pubkey },
+    /// Fill an order from the keypair's token accounts
+    Fill {
+        order: Pubkey,
+        /// Maker amount to take [default: everything left]
+        #[arg(long, default_value_t = 0)]
+        amount: u64,
+        /// Most the keypair will pay
+        #[arg(long, default_value_t = u64::MAX)]
+        max_taker_amount: u64,
+        /// Least the keypair will receive
+        #[arg(long, default_value_t = 0)]
+        min_maker_amount: u64,
+    },
+    /// Refund and close an order
+    Close {
+        order: Pubkey,
+        #[arg(long)]
+        memo: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let keypair_path = match cli.keypair {
+        Some(path) => path,
+        None => std::env::var("HOME")
+            .map(|home| format!("{home}/.config/solana/id.json"))
+            .context("no --keypair given and HOME is not set")?,
+    };
+    let signer = read_keypair_file(&keypair_path)
+        .map_err(|error| anyhow!("reading keypair {keypair_path}: {error}"))?;
+    let client = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+
+    match cli.command {
+        Command::CreateOrder {
+            maker_mint,
+            taker_mint,
+            maker_amount,
+            taker_amount,
+            taker,
+            order_id,
+            all_or_none,
+            memo,
+        } => {
+            let order_id = match order_id {
+                Some(order_id) => order_id,
+                None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            };
+            let params = OrderParams {
+                order_id,
+                maker_amount,
+                taker_amount,
+                requires_confirmation: false,
+                dual_escrow: false,
+                all_or_none,
+                memo: pad_memo(memo.as_deref().unwrap_or_default())?,
+            };
+            let instruction = instructions::initialize_order(
+                &signer.pubkey(),
+                &taker.unwrap_or_default(),
+                &maker_mint,
+                &taker_mint,
+                &mint_program(&client, &maker_mint).await?,
+                &mint_program(&client, &taker_mint).await?,
+                &params,
+            )?;
+            send(&client, &signer, instruction).await?;
+            let order = pda::order(&signer.pubkey(), &maker_mint, &taker_mint, order_id)?;
+            println!("Order: {order}");
+        }
+        Command::ListOrders { maker } => {
+            let maker = maker.unwrap_or(signer.pubkey());
+            for (address, order) in fetch_orders_by_maker(&client, &maker).await? {
+                print_order(&address, &order);
+            }
+        }
+        Command::ChangeAmounts {
+            order: address,
+            maker_amount,
+            taker_amount,
+        } => {
+            let order = fetch_order(&client, &address).await?;
+            let instruction = instructions::change_order_amounts(
+                &signer.pubkey(),
+                &address,
+                &order,
+                &mint_program(&client, &order.maker_token_mint).await?,
+                maker_amount,
+                taker_amount,
+            );
+            send(&client, &signer, instruction).await?;
+        }
+        Command::ChangeTaker {
+            order: address,
+            taker,
+        } => {
+            let instruction = instructions::change_taker(&signer.pubkey(), &address, &taker);
+            send(&client, &signer, instruction).await?;
+        }
+        Command::Fill {
+            order: address,
+            amount,
+            max_taker_amount,
+            min_maker_amount,
+        } => {
+            let order = fetch_order(&client, &address).await?;
+            let fill = FillParams {
+                fill_amount: amount,
+                max_taker_amount,
+                min_maker_amount,
+            };
+            let mut instruction = instructions::complete_swap(
+                &signer.pubkey(),
+                &address,
+                &order,
+                &mint_program(&client, &order.maker_token_mint).await?,
+                &mint_program(&client, &order.taker_token_mint).await?,
+                &fill,
+            );
+            // Lets the program create missing receiving accounts and close the order once
+            // it is filled
+            let rent_refund_account = order.rent_refund_account();
+            instruction.accounts.extend([
+                AccountMeta::new(rent_refund_account, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            ]);
+            if rent_refund_account != order.maker {
+                instruction
+                    .accounts
+                    .push(AccountMeta::new_readonly(order.maker, false));
+            }
+            send(&client, &signer, instruction).await?;
+        }
+        Command::Close {
+            order: address,
+            memo,
+        } => {
+            let order = fetch_order(&client, &address).await?;
+            let instruction = instructions::close_order(
+                &signer.pubkey(),
+                &address,
+                &order,
+                &mint_program(&client, &order.maker_token_mint).await?,
+                memo,
+            );
+            send(&client, &signer, instruction).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the token program owning `mint`
+async fn mint_program(client: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    let account = client
+        .get_account(mint)
+        .await
+        .with_context(|| format!("fetching mint {mint}"))?;
+    Ok(account.owner)
+}
+
+fn pad_memo(memo: &str) -> Result<[u8; 64]> {
+    let mut padded = [0; 64];
+    padded
+        .get_mut(..memo.len())
+        .ok_or_else(|| anyhow!("memo is longer than 64 bytes"))?
+        .copy_from_slice(memo.as_bytes());
+    Ok(padded)
+}
+
+async fn send(client: &RpcClient, signer: &Keypair, instruction: Instruction) -> Result<()> {
+    let blockhash = client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&signer.pubkey()),
+        &[signer],
+        blockhash,
+    );
+    let signature = client.send_and_confirm_transaction(&transaction).await?;
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn print_order(address: &Pubkey, order: &SwapOrder) {
+    println!(
+        "{address}  {:?}  {} of {} for {} of {}  taker {}",
+        { order.status },
+        { order.maker_amount },
+        order.maker_token_mint,
+        { order.taker_amount },
+        order.taker_token_mint,
+        order.taker,
+    );
+}
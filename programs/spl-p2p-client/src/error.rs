@@ -0,0 +1,151 @@
+use solana_program::{
+    instruction::InstructionError, message::CompileError, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+#[cfg(feature = "rpc")]
+use solana_rpc_client_api::client_error::Error as RpcError;
+use solana_sdk::{signer::SignerError, transaction::TransactionError};
+use splerg_p2p::error::SwapError;
+use thiserror::Error;
+
+use crate::preflight::FillDiagnostic;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The RPC request failed
+    #[cfg(feature = "rpc")]
+    #[error("RPC error: {0}")]
+    Rpc(Box<RpcError>),
+    /// Decoding or building failed the way the program would fail it
+    #[error("program error: {0}")]
+    Program(#[from] ProgramError),
+    #[error("account {0} is not owned by the program")]
+    WrongOwner(Pubkey),
+    #[error("account is not an order")]
+    NotAnOrder,
+    /// The order is on an older layout and has to be migrated with `MigrateOrder`
+    #[error("order layout is outdated")]
+    OutdatedOrder,
+    /// A preflight found a reason the program would reject the fill
+    #[error("fill would fail: {0}")]
+    Fill(#[from] FillDiagnostic),
+    /// The instructions do not fit a v0 message with the lookup tables given
+    #[error("message compile error: {0}")]
+    Compile(#[from] CompileError),
+    #[error("signing failed: {0}")]
+    Signer(#[from] SignerError),
+}
+
+impl ClientError {
+    /// The program error this failure carries, if any: a custom code from decoding or
+    /// building, or one a transaction sent over RPC failed with
+    pub fn swap_error(&self) -> Option<SwapError> {
+        match self {
+            Self::Program(ProgramError::Custom(code)) => swap_error(*code),
+            #[cfg(feature = "rpc")]
+            Self::Rpc(error) => transaction_swap_error(&error.get_transaction_error()?),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "rpc")]
+impl From<RpcError> for ClientError {
+    fn from(error: RpcError) -> Self {
+        Self::Rpc(Box::new(error))
+    }
+}
+
+/// Maps a `ProgramError::Custom` code of the program back to its `SwapError`, `None`
+/// for codes the program does not define
+pub fn swap_error(code: u32) -> Option<SwapError> {
+    use SwapError::*;
+    Some(match code {
+        0 => InvalidInstruction,
+        1 => OrderAlreadyInitialized,
+        2 => TakerAlreadyAssigned,
+        3 => MakerTokensNotDeposited,
+        4 => UnauthorizedSigner,
+        5 => InvalidOrderState,
+        6 => InvalidMint,
+        7 => InvalidAmount,
+        8 => InvalidTokenProgram,
+        9 => InvalidTokenAccount,
+        10 => InsufficientFunds,
+        11 => ConfirmationRequired,
+        12 => TakerDepositRequired,
+        13 => OrderPaused,
+        14 => OrderExpired,
+        15 => InvalidExpiry,
+        16 => AuctionEnded,
+        17 => AuctionNotEnded,
+        18 => BidTooLow,
+        19 => InvalidQuoteSignature,
+        20 => QuoteExpired,
+        21 => InvalidOracle,
+        22 => StalePrice,
+        23 => PriceConfidenceTooWide,
+        24 => SlippageExceeded,
+        25 => PartialFillNotAllowed,
+        26 => InvalidSwapChain,
+        27 => InvalidPreimage,
+        28 => HtlcTimedOut,
+        29 => HtlcLocked,
+        30 => VestingLocked,
+        31 => TrancheNotDue,
+        32 => RenewalNotDue,
+        33 => TooManyTakers,
+        34 => TokenGateNotMet,
+        35 => CommitmentRequired,
+        36 => CommitmentPending,
+        37 => InvalidCommitment,
+        38 => InvalidMemo,
+        39 => OutdatedOrder,
+        40 => InvalidDiscriminator,
+        41 => NonTransferableMint,
+        42 => TokenAccountFrozen,
+        43 => MemoRequired,
+        44 => CpiGuardEnabled,
+        45 => InvalidNftMint,
+        46 => TooManyBundleLegs,
+        47 => NotInCollection,
+        48 => NotInGroup,
+        49 => InvalidRentDestination,
+        50 => OrderNotExpired,
+        51 => TooManyOrders,
+        52 => ProtocolPaused,
+        53 => MintBlocked,
+        54 => RegistryPageFull,
+        55 => OrderNotOpen,
+        56 => InvalidOrderAddress,
+        57 => WrongTokenAccountMint,
+        58 => WrongTokenAccountOwner,
+        59 => NotRentExempt,
+        60 => WrongAccountOwner,
+        61 => MissingSignature,
+        62 => UnsupportedOrderType,
+        63 => IncompatibleOrderOptions,
+        64 => SelfTrade,
+        65 => SameMint,
+        66 => InvalidReferrer,
+        _ => return None,
+    })
+}
+
+/// The `SwapError` a transaction failed with. A custom code raised by a program the
+/// instruction called into reads the same, so a token program failure inside a CPI can
+/// map to an unrelated variant.
+pub fn transaction_swap_error(error: &TransactionError) -> Option<SwapError> {
+    match error {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => swap_error(*code),
+        _ => None,
+    }
+}
+
+/// A human-readable message for a custom error code of the program
+pub fn error_message(code: u32) -> String {
+    match swap_error(code) {
+        Some(error) => error.to_string(),
+        None => format!("unknown error code {code}"),
+    }
+}
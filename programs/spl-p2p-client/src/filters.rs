@@ -0,0 +1,53 @@
+//! `getProgramAccounts` filters over order accounts, built on `splerg_p2p::offsets`.
+//! Each helper returns the full filter set, order-type filters included, ready for
+//! `RpcProgramAccountsConfig::filters`.
+
+use solana_program::pubkey::Pubkey;
+use splerg_p2p::{
+    offsets,
+    state::{Discriminator, OrderStatus, SwapOrder},
+};
+
+pub use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+
+/// Matches every order account at the current layout
+pub fn orders() -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::DataSize(SwapOrder::LEN as u64),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            offsets::DISCRIMINATOR,
+            &SwapOrder::DISCRIMINATOR,
+        )),
+    ]
+}
+
+/// Matches the orders currently owned by `maker`
+pub fn by_maker(maker: &Pubkey) -> Vec<RpcFilterType> {
+    with(offsets::MAKER, maker.as_ref())
+}
+
+/// Matches the orders reserved for `taker`; the default pubkey matches open orders
+pub fn by_taker(taker: &Pubkey) -> Vec<RpcFilterType> {
+    with(offsets::TAKER, taker.as_ref())
+}
+
+/// Matches the orders selling `maker_mint` for `taker_mint`
+pub fn by_mint_pair(maker_mint: &Pubkey, taker_mint: &Pubkey) -> Vec<RpcFilterType> {
+    // The two mints sit next to each other, so one comparison covers both
+    let mut mints = maker_mint.to_bytes().to_vec();
+    mints.extend_from_slice(taker_mint.as_ref());
+    with(offsets::MAKER_TOKEN_MINT, &mints)
+}
+
+/// Matches the orders in `status`
+pub fn by_status(status: OrderStatus) -> Vec<RpcFilterType> {
+    with(offsets::STATUS, &[status as u8])
+}
+
+fn with(offset: usize, bytes: &[u8]) -> Vec<RpcFilterType> {
+    let mut filters = orders();
+    filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        offset, bytes,
+    )));
+    filters
+}
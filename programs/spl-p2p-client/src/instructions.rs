@@ -0,0 +1,140 @@
+//! Instruction builders that derive every account from the order and its parties.
+//! Token programs are passed in, since a mint's program cannot be told from its address.
+//! Optional accounts (stats, registry pages, receipts, transfer hook accounts, ...) are
+//! appended to the returned instruction by the caller, as documented on
+//! `SwapInstruction`.
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use splerg_p2p::instruction::{
+    self, CloseOrderAccounts, CompleteSwapAccounts, InitializeOrderAccounts, SwapInstruction,
+};
+
+use crate::{pda, FillParams, OrderParams, SwapOrder, ID};
+
+/// Builds an `InitializeOrder` by `maker` of the order described by `params`, funded
+/// from the maker's ATA into the order's vault
+pub fn initialize_order(
+    maker: &Pubkey,
+    taker: &Pubkey,
+    maker_mint: &Pubkey,
+    taker_mint: &Pubkey,
+    maker_token_program: &Pubkey,
+    taker_token_program: &Pubkey,
+    params: &OrderParams,
+) -> Result<Instruction, ProgramError> {
+    let accounts = InitializeOrderAccounts::new(
+        &ID,
+        maker,
+        taker,
+        maker_mint,
+        taker_mint,
+        maker_token_program,
+        taker_token_program,
+        params.order_id,
+    )?;
+    Ok(instruction::initialize_order(&ID, &accounts, params))
+}
+
+/// Builds a `ChangeOrderAmounts` by `authority`, the maker or its delegate, topping up or
+/// refunding the vault from the maker's ATA
+pub fn change_order_amounts(
+    authority: &Pubkey,
+    order_address: &Pubkey,
+    order: &SwapOrder,
+    token_program: &Pubkey,
+    new_maker_amount: u64,
+    new_taker_amount: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &SwapInstruction::ChangeOrderAmounts {
+            new_maker_amount,
+            new_taker_amount,
+        }
+        .pack(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*order_address, false),
+            AccountMeta::new(pda::vault(order_address, &order.maker_token_mint), false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(
+                    &order.maker,
+                    &order.maker_token_mint,
+                    token_program,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(order.maker_token_mint, false),
+            AccountMeta::new_readonly(order.taker_token_mint, false),
+        ],
+    )
+}
+
+/// Builds a `ChangeTaker` by `authority`, the maker or its delegate. The default pubkey
+/// opens the order to any taker.
+pub fn change_taker(authority: &Pubkey, order_address: &Pubkey, new_taker: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &SwapInstruction::ChangeTaker {
+            new_taker: new_taker.to_bytes(),
+        }
+        .pack(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*order_address, false),
+            AccountMeta::new_readonly(*new_taker, false),
+        ],
+    )
+}
+
+/// Builds a `CompleteSwap` by `taker` of `fill`, paying from and receiving into the
+/// taker's ATAs
+pub fn complete_swap(
+    taker: &Pubkey,
+    order_address: &Pubkey,
+    order: &SwapOrder,
+    maker_token_program: &Pubkey,
+    taker_token_program: &Pubkey,
+    fill: &FillParams,
+) -> Instruction {
+    let accounts = CompleteSwapAccounts::new(
+        &ID,
+        order_address,
+        &order.maker,
+        taker,
+        &order.maker_token_mint,
+        &order.taker_token_mint,
+        maker_token_program,
+        taker_token_program,
+    );
+    instruction::complete_swap(&ID, &accounts, fill)
+}
+
+/// Builds a `CloseOrder` by `authority` refunding the order into the maker's ATA. The
+/// rent goes back to whoever paid it and the order is dropped from the index of the
+/// maker that created it, even after `TransferOrderAuthority`.
+pub fn close_order(
+    authority: &Pubkey,
+    order_address: &Pubkey,
+    order: &SwapOrder,
+    token_program: &Pubkey,
+    memo: Option<String>,
+) -> Instruction {
+    let mut accounts = CloseOrderAccounts::new(
+        &ID,
+        order_address,
+        authority,
+        &order.maker,
+        &order.maker_token_mint,
+        token_program,
+    );
+    accounts.rent_destination = order.rent_refund_account();
+    accounts.maker_index = pda::maker_index(&order.seed_maker);
+    instruction::close_order(&ID, &accounts, memo)
+}
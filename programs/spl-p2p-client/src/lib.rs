@@ -0,0 +1,43 @@
+//! Client for the splerg-p2p program: instruction builders that derive every account
+//! from an order and its parties, PDA helpers, order decoding, fill preflight checks, a
+//! transaction builder and async RPC helpers.
+//!
+//! The RPC helpers and filters sit behind the default `rpc` feature. Without it the
+//! crate builds for `wasm32-unknown-unknown`, so browser dApps can derive addresses,
+//! decode orders and build instructions with the program's own layouts.
+
+pub mod error;
+#[cfg(feature = "rpc")]
+pub mod filters;
+pub mod instructions;
+pub mod pda;
+pub mod preflight;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod transaction;
+
+use solana_program::pubkey::Pubkey;
+use splerg_p2p::state::{Discriminator, ORDER_VERSION};
+
+pub use error::ClientError;
+pub use splerg_p2p::{
+    self as program,
+    instruction::SwapInstruction,
+    state::{FillParams, OrderParams, OrderStatus, OrderType, SwapOrder},
+};
+
+/// The program's address
+pub const ID: Pubkey = splerg_p2p::splerg_p2p::ID;
+
+/// Decodes the data of an order account. Accounts of other types are rejected, as are
+/// orders on a layout older than `ORDER_VERSION`, which have to go through
+/// `MigrateOrder` first.
+pub fn decode_order(data: &[u8]) -> Result<SwapOrder, ClientError> {
+    if !SwapOrder::has_discriminator(data) {
+        return Err(ClientError::NotAnOrder);
+    }
+    if data.len() != SwapOrder::LEN || data[8] != ORDER_VERSION {
+        return Err(ClientError::OutdatedOrder);
+    }
+    Ok(SwapOrder::unpack(data)?)
+}
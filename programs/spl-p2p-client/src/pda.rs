@@ -0,0 +1,80 @@
+//! Addresses of the program's accounts, derived with the program's `ID`. Each wraps the
+//! `get_*_pda` function of `splerg_p2p::validation` that the program checks against.
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use splerg_p2p::validation::{
+    get_blocked_mint_pda, get_config_pda, get_event_authority_pda, get_fee_exemption_pda,
+    get_fill_receipt_pda, get_maker_index_pda, get_order_pda, get_pair_stats_pda,
+    get_protocol_stats_pda, get_registry_page_pda, get_trade_history_pda, get_treasury_pda,
+    get_user_stats_pda, get_vault_pda,
+};
+
+use crate::ID;
+
+/// Order `order_id` of `maker` selling `maker_mint` for `taker_mint`
+pub fn order(
+    maker: &Pubkey,
+    maker_mint: &Pubkey,
+    taker_mint: &Pubkey,
+    order_id: u64,
+) -> Result<Pubkey, ProgramError> {
+    Ok(get_order_pda(&ID, maker, maker_mint, taker_mint, order_id)?.0)
+}
+
+/// Vault token account escrowing `mint` for `order`
+pub fn vault(order: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_vault_pda(&ID, order, mint).0
+}
+
+pub fn config() -> Pubkey {
+    get_config_pda(&ID).0
+}
+
+/// Treasury collecting the protocol fee in `mint`
+pub fn treasury(mint: &Pubkey) -> Pubkey {
+    get_treasury_pda(&ID, mint).0
+}
+
+pub fn fee_exemption(account: &Pubkey) -> Pubkey {
+    get_fee_exemption_pda(&ID, account).0
+}
+
+pub fn blocked_mint(mint: &Pubkey) -> Pubkey {
+    get_blocked_mint_pda(&ID, mint).0
+}
+
+pub fn user_stats(user: &Pubkey) -> Pubkey {
+    get_user_stats_pda(&ID, user).0
+}
+
+pub fn protocol_stats() -> Pubkey {
+    get_protocol_stats_pda(&ID).0
+}
+
+pub fn pair_stats(maker_mint: &Pubkey, taker_mint: &Pubkey) -> Pubkey {
+    get_pair_stats_pda(&ID, maker_mint, taker_mint).0
+}
+
+/// Index of the open orders `maker` created
+pub fn maker_index(maker: &Pubkey) -> Pubkey {
+    get_maker_index_pda(&ID, maker).0
+}
+
+/// Page `page` of the order registry of `maker_mint`/`taker_mint`
+pub fn registry_page(maker_mint: &Pubkey, taker_mint: &Pubkey, page: u32) -> Pubkey {
+    get_registry_page_pda(&ID, maker_mint, taker_mint, page).0
+}
+
+pub fn trade_history(maker_mint: &Pubkey, taker_mint: &Pubkey) -> Pubkey {
+    get_trade_history_pda(&ID, maker_mint, taker_mint).0
+}
+
+/// Receipt of fill `fill_index` of `order`
+pub fn fill_receipt(order: &Pubkey, fill_index: u64) -> Pubkey {
+    get_fill_receipt_pda(&ID, order, fill_index).0
+}
+
+/// Signer of the program's event self-CPIs
+pub fn event_authority() -> Pubkey {
+    get_event_authority_pda(&ID).0
+}
@@ -0,0 +1,217 @@
+//! Client-side preflight of a `CompleteSwap`: the checks the program makes on a fill,
+//! run against the order and the accounts a fill built by `instructions::complete_swap`
+//! touches, so a fill that would fail is reported with a typed reason before it is
+//! signed and sent. `rpc::validate_fill` fetches the accounts and runs all of them.
+
+use solana_program::pubkey::Pubkey;
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    state::{Account, AccountState},
+};
+use thiserror::Error;
+
+use crate::{FillParams, OrderStatus, OrderType, SwapOrder};
+
+/// Why the program would reject a fill
+#[derive(Clone, Copy, Debug, Error, PartialEq)]
+pub enum FillDiagnostic {
+    #[error("order is {0:?}, not open")]
+    NotOpen(OrderStatus),
+    #[error("the maker has paused fills")]
+    Paused,
+    #[error("order expired at {0}")]
+    Expired(i64),
+    #[error("the maker has to confirm fills first")]
+    ConfirmationRequired,
+    /// Dual-escrow orders are only filled by the taker that deposited its side
+    #[error("the taker side has to be deposited first")]
+    TakerDepositRequired,
+    #[error("{0:?} orders are not filled with CompleteSwap")]
+    UnsupportedOrderType(OrderType),
+    #[error("the taker has to reveal a fill commitment first")]
+    CommitmentRequired,
+    /// Merkle allowlist members need a proof, which `instructions::complete_swap` does
+    /// not send
+    #[error("{0} may not take the order")]
+    TakerNotAllowed(Pubkey),
+    #[error("fill of {fill_amount} is zero or above the remaining {remaining}")]
+    InvalidAmount { fill_amount: u64, remaining: u64 },
+    #[error("the order only fills in full")]
+    PartialFillNotAllowed,
+    #[error("mint {0} is blocked")]
+    MintBlocked(Pubkey),
+    #[error("token account {0} does not exist")]
+    MissingTokenAccount(Pubkey),
+    /// The account is not a token account of the expected owner and mint
+    #[error("token account {0} has the wrong owner or mint")]
+    WrongTokenAccount(Pubkey),
+    #[error("token account {0} is frozen")]
+    TokenAccountFrozen(Pubkey),
+    #[error("account {account} holds {available}, the fill needs {needed}")]
+    InsufficientBalance {
+        account: Pubkey,
+        needed: u64,
+        available: u64,
+    },
+    #[error("fill costs {taker_amount}, above the maximum of {max_taker_amount}")]
+    SlippageExceeded {
+        taker_amount: u64,
+        max_taker_amount: u64,
+    },
+    #[error("the taker holds {available} of the gate mint, the order needs {needed}")]
+    TokenGateNotMet { needed: u64, available: u64 },
+}
+
+/// The fields of a token account a fill depends on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TokenAccountState {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub frozen: bool,
+}
+
+impl TokenAccountState {
+    /// Decodes the data of a Token or Token-2022 account, `None` for any other data
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let account = StateWithExtensions::<Account>::unpack(data).ok()?.base;
+        Some(Self {
+            mint: account.mint,
+            owner: account.owner,
+            amount: account.amount,
+            frozen: account.state == AccountState::Frozen,
+        })
+    }
+
+    /// Returns the account's balance once it is checked to belong to `owner` in `mint`
+    /// and to be able to move tokens
+    pub fn check(
+        &self,
+        address: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+    ) -> Result<u64, FillDiagnostic> {
+        if self.owner != *owner || self.mint != *mint {
+            return Err(FillDiagnostic::WrongTokenAccount(*address));
+        }
+        if self.frozen {
+            return Err(FillDiagnostic::TokenAccountFrozen(*address));
+        }
+        Ok(self.amount)
+    }
+}
+
+/// Returns what the taker pays for `fill_amount` at unix time `now`, or `None` for
+/// oracle and UI-amount orders, which are priced from accounts at the moment of the
+/// fill
+pub fn quote_taker_amount(order: &SwapOrder, fill_amount: u64, now: i64) -> Option<u64> {
+    if order.dual_escrow {
+        return Some(order.taker_amount);
+    }
+    if order.order_type == OrderType::Oracle || order.ui_amounts {
+        return None;
+    }
+    Some(order.taker_amount_for(fill_amount, now))
+}
+
+/// Runs the checks `CompleteSwap` makes on the order itself for `taker` filling it with
+/// `fill` at unix time `now`, and returns the maker amount of the fill
+pub fn check_order(
+    order: &SwapOrder,
+    taker: &Pubkey,
+    fill: &FillParams,
+    now: i64,
+) -> Result<u64, FillDiagnostic> {
+    if !order.is_allowed_taker(taker) {
+        return Err(FillDiagnostic::TakerNotAllowed(*taker));
+    }
+    if order.commit_reveal && order.revealed_taker != *taker {
+        return Err(FillDiagnostic::CommitmentRequired);
+    }
+    if order.paused {
+        return Err(FillDiagnostic::Paused);
+    }
+    if order.expires_at != 0 && order.is_expired(now) {
+        return Err(FillDiagnostic::Expired(order.expires_at));
+    }
+    if order.requires_confirmation {
+        return Err(FillDiagnostic::ConfirmationRequired);
+    }
+    if matches!(
+        order.order_type,
+        OrderType::EnglishAuction | OrderType::Htlc | OrderType::SecretClaim
+    ) {
+        return Err(FillDiagnostic::UnsupportedOrderType(order.order_type));
+    }
+    if order.dual_escrow {
+        if order.status != OrderStatus::Accepted || order.accepted_taker != *taker {
+            return Err(FillDiagnostic::TakerDepositRequired);
+        }
+        if order.is_streaming() {
+            return Err(FillDiagnostic::NotOpen(order.status));
+        }
+    } else if !order.is_open() {
+        return Err(FillDiagnostic::NotOpen(order.status));
+    }
+
+    let remaining = order.remaining();
+    let fill_amount = if fill.fill_amount == 0 {
+        remaining
+    } else {
+        fill.fill_amount
+    };
+    if fill_amount == 0 || fill_amount > remaining {
+        return Err(FillDiagnostic::InvalidAmount {
+            fill_amount,
+            remaining,
+        });
+    }
+    if fill_amount < remaining && !order.allows_partial_fill() {
+        return Err(FillDiagnostic::PartialFillNotAllowed);
+    }
+    if let Some(taker_amount) = quote_taker_amount(order, fill_amount, now) {
+        if taker_amount > fill.max_taker_amount {
+            return Err(FillDiagnostic::SlippageExceeded {
+                taker_amount,
+                max_taker_amount: fill.max_taker_amount,
+            });
+        }
+    }
+
+    Ok(fill_amount)
+}
+
+/// Checks that a token account a fill pays from exists and holds `needed`, on top of
+/// what `TokenAccountState::check` checks
+pub fn check_paying_account(
+    address: &Pubkey,
+    state: Option<&TokenAccountState>,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    needed: u64,
+) -> Result<(), FillDiagnostic> {
+    let state = state.ok_or(FillDiagnostic::MissingTokenAccount(*address))?;
+    let available = state.check(address, owner, mint)?;
+    if available < needed {
+        return Err(FillDiagnostic::InsufficientBalance {
+            account: *address,
+            needed,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/// Checks an account a fill pays into. The program creates a missing associated token
+/// account, so only an existing account of the wrong owner or mint fails.
+pub fn check_receiving_account(
+    address: &Pubkey,
+    state: Option<&TokenAccountState>,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<(), FillDiagnostic> {
+    match state {
+        Some(state) => state.check(address, owner, mint).map(|_| ()),
+        None => Ok(()),
+    }
+}
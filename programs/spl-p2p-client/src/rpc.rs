@@ -0,0 +1,252 @@
+//! Async RPC helpers for reading orders
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_program::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    clock::Clock,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar,
+};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::{
+    config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    filter::RpcFilterType,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use splerg_p2p::error::SwapError;
+
+use crate::{
+    decode_order, filters, pda,
+    preflight::{
+        check_order, check_paying_account, check_receiving_account, quote_taker_amount,
+        FillDiagnostic, TokenAccountState,
+    },
+    ClientError, FillParams, SwapOrder, ID,
+};
+
+/// Fetches and decodes the order at `address`
+pub async fn fetch_order(client: &RpcClient, address: &Pubkey) -> Result<SwapOrder, ClientError> {
+    let account = client.get_account(address).await?;
+    if account.owner != ID {
+        return Err(ClientError::WrongOwner(*address));
+    }
+    decode_order(&account.data)
+}
+
+/// Fetches the orders currently owned by `maker`, open or not, with their addresses
+pub async fn fetch_orders_by_maker(
+    client: &RpcClient,
+    maker: &Pubkey,
+) -> Result<Vec<(Pubkey, SwapOrder)>, ClientError> {
+    fetch_orders(client, filters::by_maker(maker)).await
+}
+
+/// Fetches the orders matching `filters`, as built by the `filters` module, with their
+/// addresses. Orders that fail to decode are skipped.
+pub async fn fetch_orders(
+    client: &RpcClient,
+    filters: Vec<RpcFilterType>,
+) -> Result<Vec<(Pubkey, SwapOrder)>, ClientError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = client.get_program_accounts_with_config(&ID, config).await?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            decode_order(&account.data)
+                .ok()
+                .map(|order| (address, order))
+        })
+        .collect())
+}
+
+/// Fetches the address lookup table at `address`, for `transaction::TransactionBuilder::lookup_table`
+pub async fn fetch_lookup_table(
+    client: &RpcClient,
+    address: &Pubkey,
+) -> Result<AddressLookupTableAccount, ClientError> {
+    let account = client.get_account(address).await?;
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Runs every check `CompleteSwap` makes on `taker` filling the order at `order_address`
+/// with `fill`, as built by `instructions::complete_swap`, against the cluster's current
+/// state. A fill the program would reject fails with `ClientError::Fill` and the reason.
+///
+/// Oracle and UI-amount orders are priced on chain, so their slippage and balance
+/// checks are left to the program.
+pub async fn validate_fill(
+    client: &RpcClient,
+    order_address: &Pubkey,
+    order: &SwapOrder,
+    taker: &Pubkey,
+    fill: &FillParams,
+) -> Result<(), ClientError> {
+    let clock = client.get_account(&sysvar::clock::ID).await?;
+    let now = bincode::deserialize::<Clock>(&clock.data)
+        .map_err(|_| ProgramError::InvalidAccountData)?
+        .unix_timestamp;
+    let fill_amount = check_order(order, taker, fill, now)?;
+
+    // Token accounts are derived under the program owning their mint
+    let mint_programs: Vec<Option<Pubkey>> = client
+        .get_multiple_accounts(&[
+            order.maker_token_mint,
+            order.taker_token_mint,
+            order.gate_mint,
+        ])
+        .await?
+        .into_iter()
+        .map(|account| account.map(|account| account.owner))
+        .collect();
+    let maker_token_program = mint_programs[0].ok_or(ProgramError::from(SwapError::InvalidMint))?;
+    let taker_token_program = mint_programs[1].ok_or(ProgramError::from(SwapError::InvalidMint))?;
+
+    let maker_receiving = get_associated_token_address_with_program_id(
+        &order.maker,
+        &order.taker_token_mint,
+        &taker_token_program,
+    );
+    let taker_sending = get_associated_token_address_with_program_id(
+        taker,
+        &order.taker_token_mint,
+        &taker_token_program,
+    );
+    let taker_receiving = get_associated_token_address_with_program_id(
+        taker,
+        &order.maker_token_mint,
+        &maker_token_program,
+    );
+    let escrow = pda::vault(order_address, &order.maker_token_mint);
+    let mut addresses = vec![
+        maker_receiving,
+        taker_sending,
+        taker_receiving,
+        escrow,
+        pda::blocked_mint(&order.maker_token_mint),
+        pda::blocked_mint(&order.taker_token_mint),
+        *taker,
+        *order_address,
+    ];
+    if let Some(gate_program) = mint_programs[2].filter(|_| order.is_token_gated()) {
+        addresses.push(get_associated_token_address_with_program_id(
+            taker,
+            &order.gate_mint,
+            &gate_program,
+        ));
+    }
+    let accounts = client.get_multiple_accounts(&addresses).await?;
+    let lamports = |index: usize| {
+        accounts[index]
+            .as_ref()
+            .map_or(0, |account| account.lamports)
+    };
+    let token_account = |index: usize| {
+        accounts
+            .get(index)?
+            .as_ref()
+            .filter(|account| {
+                spl_token_2022::check_spl_token_program_account(&account.owner).is_ok()
+            })
+            .and_then(|account| TokenAccountState::unpack(&account.data))
+    };
+
+    for (index, mint) in [(4, order.maker_token_mint), (5, order.taker_token_mint)] {
+        if accounts[index]
+            .as_ref()
+            .is_some_and(|account| account.owner == ID && account.lamports > 0)
+        {
+            return Err(FillDiagnostic::MintBlocked(mint).into());
+        }
+    }
+
+    // UI-amount orders settle a raw amount only the program computes
+    let maker_fill = if order.ui_amounts { 0 } else { fill_amount };
+    if order.maker_is_native() {
+        let rent = client
+            .get_minimum_balance_for_rent_exemption(SwapOrder::LEN)
+            .await?;
+        let escrowed = lamports(7).saturating_sub(rent);
+        if escrowed < maker_fill {
+            return Err(FillDiagnostic::InsufficientBalance {
+                account: *order_address,
+                needed: maker_fill,
+                available: escrowed,
+            }
+            .into());
+        }
+    } else {
+        if !order.programmable {
+            check_receiving_account(
+                &taker_receiving,
+                token_account(2).as_ref(),
+                taker,
+                &order.maker_token_mint,
+            )?;
+        }
+        check_paying_account(
+            &escrow,
+            token_account(3).as_ref(),
+            order_address,
+            &order.maker_token_mint,
+            maker_fill,
+        )?;
+    }
+
+    let taker_amount = quote_taker_amount(order, fill_amount, now).unwrap_or(0);
+    if order.taker_is_native() {
+        if lamports(6) < taker_amount {
+            return Err(FillDiagnostic::InsufficientBalance {
+                account: *taker,
+                needed: taker_amount,
+                available: lamports(6),
+            }
+            .into());
+        }
+    } else {
+        check_receiving_account(
+            &maker_receiving,
+            token_account(0).as_ref(),
+            &order.maker,
+            &order.taker_token_mint,
+        )?;
+        // Dual-escrow takers already deposited their side with the order
+        if !order.dual_escrow {
+            check_paying_account(
+                &taker_sending,
+                token_account(1).as_ref(),
+                taker,
+                &order.taker_token_mint,
+                taker_amount,
+            )?;
+        }
+    }
+
+    if order.is_token_gated() {
+        let available = token_account(8)
+            .filter(|account| account.owner == *taker && account.mint == order.gate_mint)
+            .map_or(0, |account| account.amount);
+        if available < order.gate_min_balance {
+            return Err(FillDiagnostic::TokenGateNotMet {
+                needed: order.gate_min_balance,
+                available,
+            }
+            .into());
+        }
+    }
+
+    Ok(())
+}
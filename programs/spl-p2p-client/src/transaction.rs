@@ -0,0 +1,135 @@
+//! Assembles program instructions into v0 transactions: compute budget and priority fee
+//! first, then any associated token accounts the instructions need, then the
+//! instructions themselves, with accounts resolved through address lookup tables where
+//! given.
+
+use solana_program::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, signature::Signature, signers::Signers,
+    transaction::VersionedTransaction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::ClientError;
+
+/// Builds a v0 transaction paid by `payer`
+#[derive(Clone, Debug)]
+pub struct TransactionBuilder {
+    payer: Pubkey,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+    atas: Vec<Instruction>,
+    instructions: Vec<Instruction>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+impl TransactionBuilder {
+    pub fn new(payer: &Pubkey) -> Self {
+        Self {
+            payer: *payer,
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            atas: Vec::new(),
+            instructions: Vec::new(),
+            lookup_tables: Vec::new(),
+        }
+    }
+
+    /// Caps the transaction at `units` compute units, which also bounds what the
+    /// priority fee is charged on
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+
+    /// Pays a priority fee of `micro_lamports` per requested compute unit
+    pub fn priority_fee(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Creates `wallet`'s associated token account of `mint` ahead of the instructions,
+    /// paid by the payer. The creation is idempotent, so an existing account is left as
+    /// it is.
+    pub fn create_ata(mut self, wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Self {
+        let instruction =
+            create_associated_token_account_idempotent(&self.payer, wallet, mint, token_program);
+        if !self.atas.contains(&instruction) {
+            self.atas.push(instruction);
+        }
+        self
+    }
+
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl IntoIterator<Item = Instruction>) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    /// Resolves the accounts `table` holds through it rather than listing them in the
+    /// message. Signers and the program ids stay in the message.
+    pub fn lookup_table(mut self, table: AddressLookupTableAccount) -> Self {
+        self.lookup_tables.push(table);
+        self
+    }
+
+    /// Every instruction of the transaction, in order
+    pub fn to_instructions(&self) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        if let Some(units) = self.compute_unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.compute_unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+                micro_lamports,
+            ));
+        }
+        instructions.extend(self.atas.iter().cloned());
+        instructions.extend(self.instructions.iter().cloned());
+        instructions
+    }
+
+    /// Compiles the v0 message of the transaction
+    pub fn message(&self, recent_blockhash: Hash) -> Result<VersionedMessage, ClientError> {
+        let message = v0::Message::try_compile(
+            &self.payer,
+            &self.to_instructions(),
+            &self.lookup_tables,
+            recent_blockhash,
+        )?;
+        Ok(VersionedMessage::V0(message))
+    }
+
+    /// Builds the transaction with every signature left empty, for a wallet to sign
+    pub fn build(&self, recent_blockhash: Hash) -> Result<VersionedTransaction, ClientError> {
+        let message = self.message(recent_blockhash)?;
+        let signers = message.header().num_required_signatures as usize;
+        Ok(VersionedTransaction {
+            signatures: vec![Signature::default(); signers],
+            message,
+        })
+    }
+
+    /// Builds the transaction signed by `signers`, which have to cover every signer of
+    /// the instructions and the payer
+    pub fn build_signed<T: Signers + ?Sized>(
+        &self,
+        recent_blockhash: Hash,
+        signers: &T,
+    ) -> Result<VersionedTransaction, ClientError> {
+        Ok(VersionedTransaction::try_new(
+            self.message(recent_blockhash)?,
+            signers,
+        )?)
+    }
+}
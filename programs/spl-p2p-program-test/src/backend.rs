@@ -0,0 +1,66 @@
+//! The test validators the fixtures run against
+
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    account::Account, instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
+
+/// What the fixtures need from a test validator: sending transactions and reading
+/// accounts. Implemented for a started `ProgramTest` and for `LiteBackend`.
+#[allow(async_fn_in_trait)]
+pub trait Backend {
+    type Error: std::fmt::Debug;
+
+    /// Pays for every transaction and is the mint authority of `create_mint` mints
+    fn payer(&self) -> &Keypair;
+
+    /// Sends `instructions` in one transaction paid by `payer` and signed by it and
+    /// `signers`
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), Self::Error>;
+
+    async fn get_account(&mut self, address: &Pubkey) -> Option<Account>;
+
+    async fn minimum_balance(&mut self, space: usize) -> u64;
+}
+
+impl Backend for ProgramTestContext {
+    type Error = BanksClientError;
+
+    fn payer(&self) -> &Keypair {
+        &self.payer
+    }
+
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let blockhash = self.get_new_latest_blockhash().await?;
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.banks_client.process_transaction(transaction).await
+    }
+
+    async fn get_account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.banks_client.get_account(*address).await.unwrap()
+    }
+
+    async fn minimum_balance(&mut self, space: usize) -> u64 {
+        self.banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(space)
+    }
+}
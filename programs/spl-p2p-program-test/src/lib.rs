@@ -0,0 +1,232 @@
+//! Test fixtures for the splerg-p2p program: mint, token account and order factories
+//! that work the same for spl-token and Token-2022 mints, over either backend:
+//!
+//! - `program_test()`, a `solana-program-test` `ProgramTest` running the program
+//!   natively. Set `BPF_OUT_DIR` (or `SBF_OUT_DIR`) to the directory holding
+//!   `splerg_p2p.so` and call `ProgramTest::prefer_bpf(true)` to run the deployed build
+//!   instead.
+//! - `LiteBackend`, a LiteSVM running the SBF build, which starts in milliseconds and
+//!   suits tests of single processor branches.
+
+mod backend;
+mod lite;
+
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_p2p_client::{decode_order, OrderParams, SwapOrder, ID};
+use spl_token_2022::{extension::StateWithExtensions, state::Account};
+
+pub use backend::Backend;
+pub use lite::LiteBackend;
+
+/// The program under test, loaded at its own address
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "splerg_p2p",
+        ID,
+        processor!(splerg_p2p::splerg_p2p::process_instruction),
+    )
+}
+
+/// Funds `wallet` with `lamports` from the backend's payer
+pub async fn airdrop<B: Backend>(
+    backend: &mut B,
+    wallet: &Pubkey,
+    lamports: u64,
+) -> Result<(), B::Error> {
+    let instruction = system_instruction::transfer(&backend.payer().pubkey(), wallet, lamports);
+    backend.process(&[instruction], &[]).await
+}
+
+/// Creates a mint of `token_program` with `decimals`, its mint authority the
+/// backend's payer
+pub async fn create_mint<B: Backend>(
+    backend: &mut B,
+    token_program: &Pubkey,
+    decimals: u8,
+) -> Result<Pubkey, B::Error> {
+    let mint = Keypair::new();
+    let space = spl_token_2022::state::Mint::LEN;
+    let lamports = backend.minimum_balance(space).await;
+    let payer = backend.payer().pubkey();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            lamports,
+            space as u64,
+            token_program,
+        ),
+        spl_token_2022::instruction::initialize_mint2(
+            token_program,
+            &mint.pubkey(),
+            &payer,
+            None,
+            decimals,
+        )
+        .unwrap(),
+    ];
+    backend.process(&instructions, &[&mint]).await?;
+    Ok(mint.pubkey())
+}
+
+/// Creates `wallet`'s associated token account of `mint` unless it exists, and returns
+/// its address
+pub async fn create_ata<B: Backend>(
+    backend: &mut B,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<Pubkey, B::Error> {
+    let instruction = create_associated_token_account_idempotent(
+        &backend.payer().pubkey(),
+        wallet,
+        mint,
+        token_program,
+    );
+    backend.process(&[instruction], &[]).await?;
+    Ok(get_associated_token_address_with_program_id(
+        wallet,
+        mint,
+        token_program,
+    ))
+}
+
+/// Mints `amount` of a mint made by `create_mint` to `wallet`'s associated token
+/// account, creating it if needed, and returns the account's address
+pub async fn mint_to<B: Backend>(
+    backend: &mut B,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    wallet: &Pubkey,
+    amount: u64,
+) -> Result<Pubkey, B::Error> {
+    let ata = create_ata(backend, wallet, mint, token_program).await?;
+    let instruction = spl_token_2022::instruction::mint_to(
+        token_program,
+        mint,
+        &ata,
+        &backend.payer().pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap();
+    backend.process(&[instruction], &[]).await?;
+    Ok(ata)
+}
+
+/// Returns the balance of a token account, 0 if it does not exist
+pub async fn token_balance<B: Backend>(backend: &mut B, address: &Pubkey) -> u64 {
+    let Some(account) = backend.get_account(address).await else {
+        return 0;
+    };
+    StateWithExtensions::<Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+/// Returns the order at `address`, `None` once it is closed
+pub async fn get_order<B: Backend>(backend: &mut B, address: &Pubkey) -> Option<SwapOrder> {
+    let account = backend.get_account(address).await?;
+    Some(decode_order(&account.data).unwrap())
+}
+
+/// A maker and a taker, funded with SOL and holding tokens of a fresh mint each
+pub struct Parties {
+    pub maker: Keypair,
+    pub taker: Keypair,
+    pub maker_mint: Pubkey,
+    pub taker_mint: Pubkey,
+    pub maker_token_program: Pubkey,
+    pub taker_token_program: Pubkey,
+}
+
+impl Parties {
+    /// Creates both mints, 9 decimals each, and mints `amount` of the maker mint to the
+    /// maker and of the taker mint to the taker
+    pub async fn new<B: Backend>(
+        backend: &mut B,
+        maker_token_program: &Pubkey,
+        taker_token_program: &Pubkey,
+        amount: u64,
+    ) -> Result<Self, B::Error> {
+        let (maker, taker) = (Keypair::new(), Keypair::new());
+        airdrop(backend, &maker.pubkey(), 1_000_000_000).await?;
+        airdrop(backend, &taker.pubkey(), 1_000_000_000).await?;
+
+        let maker_mint = create_mint(backend, maker_token_program, 9).await?;
+        let taker_mint = create_mint(backend, taker_token_program, 9).await?;
+        mint_to(
+            backend,
+            &maker_mint,
+            maker_token_program,
+            &maker.pubkey(),
+            amount,
+        )
+        .await?;
+        mint_to(
+            backend,
+            &taker_mint,
+            taker_token_program,
+            &taker.pubkey(),
+            amount,
+        )
+        .await?;
+
+        Ok(Self {
+            maker,
+            taker,
+            maker_mint,
+            taker_mint,
+            maker_token_program: *maker_token_program,
+            taker_token_program: *taker_token_program,
+        })
+    }
+
+    /// Opens order `params.order_id` of the maker for the taker and returns its address
+    pub async fn create_order<B: Backend>(
+        &self,
+        backend: &mut B,
+        params: &OrderParams,
+    ) -> Result<Pubkey, B::Error> {
+        let instruction = spl_p2p_client::instructions::initialize_order(
+            &self.maker.pubkey(),
+            &self.taker.pubkey(),
+            &self.maker_mint,
+            &self.taker_mint,
+            &self.maker_token_program,
+            &self.taker_token_program,
+            params,
+        )
+        .unwrap();
+        backend.process(&[instruction], &[&self.maker]).await?;
+        Ok(spl_p2p_client::pda::order(
+            &self.maker.pubkey(),
+            &self.maker_mint,
+            &self.taker_mint,
+            params.order_id,
+        )
+        .unwrap())
+    }
+
+    /// Address of `wallet`'s associated token account of `mint`, under the program of
+    /// whichever of the two mints it is
+    pub fn ata(&self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let token_program = if *mint == self.maker_mint {
+            &self.maker_token_program
+        } else {
+            &self.taker_token_program
+        };
+        get_associated_token_address_with_program_id(wallet, mint, token_program)
+    }
+}
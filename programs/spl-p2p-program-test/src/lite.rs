@@ -0,0 +1,81 @@
+//! A LiteSVM backend: no validator to start, so a test runs in milliseconds against the
+//! program's SBF build
+
+use std::path::PathBuf;
+
+use litesvm::{types::FailedTransactionMetadata, LiteSVM};
+use solana_sdk::{
+    account::Account, instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
+use spl_p2p_client::ID;
+
+use crate::Backend;
+
+/// A LiteSVM with the SPL programs and splerg-p2p loaded, and a funded payer
+pub struct LiteBackend {
+    pub svm: LiteSVM,
+    payer: Keypair,
+}
+
+impl LiteBackend {
+    /// Loads `splerg_p2p.so` from `SBF_OUT_DIR`, or from the workspace's
+    /// `target/deploy` where `cargo build-sbf` puts it
+    pub fn new() -> Self {
+        let program = std::env::var_os("SBF_OUT_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy")
+            })
+            .join("splerg_p2p.so");
+
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(ID, &program).unwrap_or_else(|_| {
+            panic!("no program at {}, run `cargo build-sbf`", program.display())
+        });
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+
+        Self { svm, payer }
+    }
+}
+
+impl Default for LiteBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for LiteBackend {
+    type Error = FailedTransactionMetadata;
+
+    fn payer(&self) -> &Keypair {
+        &self.payer
+    }
+
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), FailedTransactionMetadata> {
+        // A fresh blockhash keeps repeated identical transactions apart
+        self.svm.expire_blockhash();
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(transaction).map(|_| ())
+    }
+
+    async fn get_account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.svm.get_account(address)
+    }
+
+    async fn minimum_balance(&mut self, space: usize) -> u64 {
+        self.svm.minimum_balance_for_rent_exemption(space)
+    }
+}
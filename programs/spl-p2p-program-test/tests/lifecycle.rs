@@ -0,0 +1,175 @@
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use spl_p2p_client::{
+    instructions, pda, program::state::MEMO_LEN, FillParams, OrderParams, OrderStatus,
+};
+use spl_p2p_program_test::{
+    create_ata, get_order, program_test, token_balance, Backend, LiteBackend, Parties,
+};
+
+const SUPPLY: u64 = 1_000_000;
+
+fn params(order_id: u64, maker_amount: u64, taker_amount: u64) -> OrderParams {
+    OrderParams {
+        order_id,
+        maker_amount,
+        taker_amount,
+        requires_confirmation: false,
+        dual_escrow: false,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    }
+}
+
+/// Both parties and their receiving accounts for a fill of the other side
+async fn setup<B: Backend>(backend: &mut B, token_program: &Pubkey) -> Parties {
+    let parties = Parties::new(backend, token_program, token_program, SUPPLY)
+        .await
+        .unwrap();
+    let (maker, taker) = (parties.maker.pubkey(), parties.taker.pubkey());
+    create_ata(backend, &maker, &parties.taker_mint, token_program)
+        .await
+        .unwrap();
+    create_ata(backend, &taker, &parties.maker_mint, token_program)
+        .await
+        .unwrap();
+    parties
+}
+
+/// Opens an order, reprices it, fills part of it and closes the rest
+async fn lifecycle<B: Backend>(mut backend: B, token_program: Pubkey) {
+    let parties = setup(&mut backend, &token_program).await;
+    let (maker, taker) = (parties.maker.pubkey(), parties.taker.pubkey());
+    let (maker_mint, taker_mint) = (parties.maker_mint, parties.taker_mint);
+
+    let order_address = parties
+        .create_order(&mut backend, &params(1, 1_000, 2_000))
+        .await
+        .unwrap();
+    let vault = pda::vault(&order_address, &maker_mint);
+    assert_eq!(token_balance(&mut backend, &vault).await, 1_000);
+    assert_eq!(
+        token_balance(&mut backend, &parties.ata(&maker, &maker_mint)).await,
+        SUPPLY - 1_000
+    );
+
+    // Shrinking the order returns the difference to the maker
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    let change = instructions::change_order_amounts(
+        &maker,
+        &order_address,
+        &order,
+        &token_program,
+        800,
+        1_600,
+    );
+    backend.process(&[change], &[&parties.maker]).await.unwrap();
+    assert_eq!(token_balance(&mut backend, &vault).await, 800);
+
+    // Half of it fills at the order's price
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    let fill = instructions::complete_swap(
+        &taker,
+        &order_address,
+        &order,
+        &token_program,
+        &token_program,
+        &FillParams {
+            fill_amount: 400,
+            max_taker_amount: 800,
+            min_maker_amount: 400,
+        },
+    );
+    backend.process(&[fill], &[&parties.taker]).await.unwrap();
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    assert_eq!({ order.status }, OrderStatus::Open);
+    assert_eq!(({ order.maker_amount }, { order.taker_amount }), (400, 800));
+    assert_eq!(
+        token_balance(&mut backend, &parties.ata(&taker, &maker_mint)).await,
+        400
+    );
+    assert_eq!(
+        token_balance(&mut backend, &parties.ata(&maker, &taker_mint)).await,
+        800
+    );
+
+    // Closing refunds what is left in escrow and the order's rent
+    let close = instructions::close_order(&maker, &order_address, &order, &token_program, None);
+    backend.process(&[close], &[&parties.maker]).await.unwrap();
+    assert!(get_order(&mut backend, &order_address).await.is_none());
+    assert_eq!(
+        token_balance(&mut backend, &parties.ata(&maker, &maker_mint)).await,
+        SUPPLY - 400
+    );
+}
+
+/// Fills a whole order in one go
+async fn full_fill<B: Backend>(mut backend: B, token_program: Pubkey) {
+    let parties = setup(&mut backend, &token_program).await;
+    let taker = parties.taker.pubkey();
+
+    let order_address = parties
+        .create_order(&mut backend, &params(7, 500, 250))
+        .await
+        .unwrap();
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    let fill = instructions::complete_swap(
+        &taker,
+        &order_address,
+        &order,
+        &token_program,
+        &token_program,
+        &FillParams {
+            fill_amount: 0,
+            max_taker_amount: 250,
+            min_maker_amount: 500,
+        },
+    );
+    backend.process(&[fill], &[&parties.taker]).await.unwrap();
+
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    assert_eq!({ order.status }, OrderStatus::Completed);
+    assert_eq!(
+        token_balance(&mut backend, &parties.ata(&taker, &parties.maker_mint)).await,
+        500
+    );
+    assert_eq!(
+        token_balance(&mut backend, &parties.ata(&taker, &parties.taker_mint)).await,
+        SUPPLY - 250
+    );
+}
+
+#[tokio::test]
+async fn test_lifecycle_spl_token() {
+    let context = program_test().start_with_context().await;
+    lifecycle(context, spl_token::id()).await;
+}
+
+#[tokio::test]
+async fn test_lifecycle_token_2022() {
+    let context = program_test().start_with_context().await;
+    lifecycle(context, spl_token_2022::id()).await;
+}
+
+#[tokio::test]
+async fn test_full_fill_spl_token() {
+    let context = program_test().start_with_context().await;
+    full_fill(context, spl_token::id()).await;
+}
+
+#[tokio::test]
+async fn test_full_fill_token_2022() {
+    let context = program_test().start_with_context().await;
+    full_fill(context, spl_token_2022::id()).await;
+}
+
+#[tokio::test]
+async fn test_lifecycle_lite() {
+    lifecycle(LiteBackend::new(), spl_token::id()).await;
+    lifecycle(LiteBackend::new(), spl_token_2022::id()).await;
+}
+
+#[tokio::test]
+async fn test_full_fill_lite() {
+    full_fill(LiteBackend::new(), spl_token::id()).await;
+    full_fill(LiteBackend::new(), spl_token_2022::id()).await;
+}
@@ -0,0 +1,317 @@
+//! One processor branch per test, on `LiteBackend`
+
+use litesvm::types::FailedTransactionMetadata;
+use solana_sdk::{
+    instruction::InstructionError, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    transaction::TransactionError,
+};
+use spl_p2p_client::{
+    instructions, pda, program::error::SwapError, program::state::MEMO_LEN, FillParams, OrderParams,
+};
+use spl_p2p_program_test::{
+    airdrop, create_ata, get_order, token_balance, Backend, LiteBackend, Parties,
+};
+
+const SUPPLY: u64 = 1_000_000;
+
+/// An order of 1_000 maker tokens for 2_000 taker tokens, open to the parties' taker
+async fn setup(all_or_none: bool) -> (LiteBackend, Parties, Pubkey) {
+    let (mut backend, parties) = parties().await;
+    let order_address = parties
+        .create_order(&mut backend, &params(1, 1_000, 2_000, all_or_none))
+        .await
+        .unwrap();
+    (backend, parties, order_address)
+}
+
+/// Both parties and their receiving accounts, before any order
+async fn parties() -> (LiteBackend, Parties) {
+    let mut backend = LiteBackend::new();
+    let token_program = spl_token::id();
+    let parties = Parties::new(&mut backend, &token_program, &token_program, SUPPLY)
+        .await
+        .unwrap();
+    let (maker, taker) = (parties.maker.pubkey(), parties.taker.pubkey());
+    create_ata(&mut backend, &maker, &parties.taker_mint, &token_program)
+        .await
+        .unwrap();
+    create_ata(&mut backend, &taker, &parties.maker_mint, &token_program)
+        .await
+        .unwrap();
+    (backend, parties)
+}
+
+fn params(order_id: u64, maker_amount: u64, taker_amount: u64, all_or_none: bool) -> OrderParams {
+    OrderParams {
+        order_id,
+        maker_amount,
+        taker_amount,
+        requires_confirmation: false,
+        dual_escrow: false,
+        all_or_none,
+        memo: [0; MEMO_LEN],
+    }
+}
+
+async fn fill(
+    backend: &mut LiteBackend,
+    taker: &Keypair,
+    order_address: &Pubkey,
+    fill: FillParams,
+) -> Result<(), FailedTransactionMetadata> {
+    let order = get_order(backend, order_address).await.unwrap();
+    let instruction = instructions::complete_swap(
+        &taker.pubkey(),
+        order_address,
+        &order,
+        &spl_token::id(),
+        &spl_token::id(),
+        &fill,
+    );
+    backend.process(&[instruction], &[taker]).await
+}
+
+fn assert_swap_error(result: Result<(), FailedTransactionMetadata>, error: SwapError) {
+    assert_eq!(
+        result.unwrap_err().err,
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+    );
+}
+
+#[tokio::test]
+async fn test_change_amounts_moves_escrow() {
+    let (mut backend, parties, order_address) = setup(false).await;
+    let maker = parties.maker.pubkey();
+    let vault = pda::vault(&order_address, &parties.maker_mint);
+    let maker_ata = parties.ata(&maker, &parties.maker_mint);
+
+    for (maker_amount, taker_amount) in [(1_500, 3_000), (600, 1_200)] {
+        let order = get_order(&mut backend, &order_address).await.unwrap();
+        let change = instructions::change_order_amounts(
+            &maker,
+            &order_address,
+            &order,
+            &spl_token::id(),
+            maker_amount,
+            taker_amount,
+        );
+        backend.process(&[change], &[&parties.maker]).await.unwrap();
+        assert_eq!(token_balance(&mut backend, &vault).await, maker_amount);
+        assert_eq!(
+            token_balance(&mut backend, &maker_ata).await,
+            SUPPLY - maker_amount
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_change_amounts_by_stranger() {
+    let (mut backend, _, order_address) = setup(false).await;
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    let stranger = Keypair::new();
+    let change = instructions::change_order_amounts(
+        &stranger.pubkey(),
+        &order_address,
+        &order,
+        &spl_token::id(),
+        500,
+        1_000,
+    );
+    let result = backend.process(&[change], &[&stranger]).await;
+    assert_swap_error(result, SwapError::UnauthorizedSigner);
+}
+
+#[tokio::test]
+async fn test_unauthorized_taker() {
+    let (mut backend, _, order_address) = setup(false).await;
+    let stranger = Keypair::new();
+    let result = fill(
+        &mut backend,
+        &stranger,
+        &order_address,
+        FillParams {
+            fill_amount: 0,
+            max_taker_amount: 2_000,
+            min_maker_amount: 0,
+        },
+    )
+    .await;
+    assert_swap_error(result, SwapError::UnauthorizedSigner);
+}
+
+#[tokio::test]
+async fn test_change_taker() {
+    let (mut backend, parties, order_address) = setup(false).await;
+    let new_taker = Keypair::new();
+    let change =
+        instructions::change_taker(&parties.maker.pubkey(), &order_address, &new_taker.pubkey());
+    backend.process(&[change], &[&parties.maker]).await.unwrap();
+
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    assert_eq!({ order.taker }, new_taker.pubkey());
+    let result = fill(
+        &mut backend,
+        &parties.taker,
+        &order_address,
+        FillParams {
+            fill_amount: 0,
+            max_taker_amount: 2_000,
+            min_maker_amount: 0,
+        },
+    )
+    .await;
+    assert_swap_error(result, SwapError::UnauthorizedSigner);
+}
+
+#[tokio::test]
+async fn test_fill_slippage() {
+    let (mut backend, parties, order_address) = setup(false).await;
+
+    // Paying more than the taker allows
+    let result = fill(
+        &mut backend,
+        &parties.taker,
+        &order_address,
+        FillParams {
+            fill_amount: 500,
+            max_taker_amount: 999,
+            min_maker_amount: 0,
+        },
+    )
+    .await;
+    assert_swap_error(result, SwapError::SlippageExceeded);
+
+    // Receiving less than the taker asks for
+    let result = fill(
+        &mut backend,
+        &parties.taker,
+        &order_address,
+        FillParams {
+            fill_amount: 500,
+            max_taker_amount: 1_000,
+            min_maker_amount: 501,
+        },
+    )
+    .await;
+    assert_swap_error(result, SwapError::SlippageExceeded);
+}
+
+#[tokio::test]
+async fn test_fill_above_order() {
+    let (mut backend, parties, order_address) = setup(false).await;
+    let result = fill(
+        &mut backend,
+        &parties.taker,
+        &order_address,
+        FillParams {
+            fill_amount: 1_001,
+            max_taker_amount: u64::MAX,
+            min_maker_amount: 0,
+        },
+    )
+    .await;
+    assert_swap_error(result, SwapError::InvalidAmount);
+}
+
+#[tokio::test]
+async fn test_all_or_none_rejects_partial_fill() {
+    let (mut backend, parties, order_address) = setup(true).await;
+    let result = fill(
+        &mut backend,
+        &parties.taker,
+        &order_address,
+        FillParams {
+            fill_amount: 500,
+            max_taker_amount: 1_000,
+            min_maker_amount: 0,
+        },
+    )
+    .await;
+    assert_swap_error(result, SwapError::PartialFillNotAllowed);
+
+    fill(
+        &mut backend,
+        &parties.taker,
+        &order_address,
+        FillParams {
+            fill_amount: 1_000,
+            max_taker_amount: 2_000,
+            min_maker_amount: 1_000,
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_close_refunds_maker() {
+    let (mut backend, parties, order_address) = setup(false).await;
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+
+    let stranger = Keypair::new();
+    let close = instructions::close_order(
+        &stranger.pubkey(),
+        &order_address,
+        &order,
+        &spl_token::id(),
+        None,
+    );
+    let result = backend.process(&[close], &[&stranger]).await;
+    assert_swap_error(result, SwapError::UnauthorizedSigner);
+
+    let maker = parties.maker.pubkey();
+    let lamports = backend.svm.get_balance(&maker).unwrap();
+    let close = instructions::close_order(&maker, &order_address, &order, &spl_token::id(), None);
+    backend.process(&[close], &[&parties.maker]).await.unwrap();
+    assert!(get_order(&mut backend, &order_address).await.is_none());
+    assert_eq!(
+        token_balance(&mut backend, &parties.ata(&maker, &parties.maker_mint)).await,
+        SUPPLY
+    );
+    assert!(backend.svm.get_balance(&maker).unwrap() > lamports);
+}
+
+#[tokio::test]
+async fn test_reinitialize_order() {
+    let (mut backend, parties, order_address) = setup(false).await;
+    let result = parties
+        .create_order(&mut backend, &params(1, 1, 1_000_000, false))
+        .await
+        .map(|_| ());
+    assert_swap_error(result, SwapError::OrderAlreadyInitialized);
+
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    assert_eq!(
+        ({ order.maker_amount }, { order.taker_amount }),
+        (1_000, 2_000)
+    );
+    let vault = pda::vault(&order_address, &parties.maker_mint);
+    assert_eq!(token_balance(&mut backend, &vault).await, 1_000);
+}
+
+#[tokio::test]
+async fn test_initialize_prefunded_order() {
+    let (mut backend, parties) = parties().await;
+    let order_address = pda::order(
+        &parties.maker.pubkey(),
+        &parties.maker_mint,
+        &parties.taker_mint,
+        1,
+    )
+    .unwrap();
+    // Lamports sent to the address beforehand do not block the order
+    let lamports = backend.minimum_balance(0).await;
+    airdrop(&mut backend, &order_address, lamports)
+        .await
+        .unwrap();
+
+    parties
+        .create_order(&mut backend, &params(1, 1_000, 2_000, false))
+        .await
+        .unwrap();
+    let order = get_order(&mut backend, &order_address).await.unwrap();
+    assert_eq!(
+        ({ order.maker_amount }, { order.taker_amount }),
+        (1_000, 2_000)
+    );
+}
@@ -0,0 +1,81 @@
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Metaplex Bubblegum program, which owns compressed NFTs
+pub const BUBBLEGUM_PROGRAM_ID: Pubkey = pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+
+/// SPL Account Compression program, which keeps the Merkle trees of compressed NFTs
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey =
+    pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+/// SPL Noop program, through which Account Compression logs leaf changes for indexers
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+/// Anchor discriminator of Bubblegum's `transfer`: `sha256("global:transfer")[..8]`
+const TRANSFER_DISCRIMINATOR: [u8; 8] = [163, 52, 200, 231, 140, 3, 69, 186];
+
+/// Returns the tree config PDA Bubblegum keeps for `merkle_tree`
+pub fn find_tree_config_address(merkle_tree: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[merkle_tree.as_ref()], &BUBBLEGUM_PROGRAM_ID).0
+}
+
+/// Returns the asset id of the compressed NFT minted into `merkle_tree` as `nonce`
+pub fn find_asset_id(merkle_tree: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"asset", merkle_tree.as_ref(), &nonce.to_le_bytes()],
+        &BUBBLEGUM_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Builds a Bubblegum `transfer` moving the compressed NFT at leaf `index` of
+/// `merkle_tree` from `leaf_owner`, who signs, to `new_leaf_owner`. `root` is the tree
+/// root `proof` leads to, and `data_hash`, `creator_hash` and `nonce` are the leaf's.
+/// Bubblegum checks the proof against the tree through Account Compression, so a stale
+/// root or a leaf that does not match fails the transfer.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer(
+    merkle_tree: &Pubkey,
+    leaf_owner: &Pubkey,
+    leaf_delegate: &Pubkey,
+    new_leaf_owner: &Pubkey,
+    root: [u8; 32],
+    data_hash: [u8; 32],
+    creator_hash: [u8; 32],
+    nonce: u64,
+    index: u32,
+    proof: &[Pubkey],
+) -> Instruction {
+    let mut data = TRANSFER_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&root);
+    data.extend_from_slice(&data_hash);
+    data.extend_from_slice(&creator_hash);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&index.to_le_bytes());
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(find_tree_config_address(merkle_tree), false),
+        AccountMeta::new_readonly(*leaf_owner, true),
+        AccountMeta::new_readonly(*leaf_delegate, false),
+        AccountMeta::new_readonly(*new_leaf_owner, false),
+        AccountMeta::new(*merkle_tree, false),
+        AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(
+        proof
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false)),
+    );
+
+    Instruction {
+        program_id: BUBBLEGUM_PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
@@ -1,18 +1,144 @@
 use solana_program::program_error::ProgramError;
+use thiserror::Error;
 
-#[derive(Debug)]
+/// Errors the program fails with, as `ProgramError::Custom` of their code. Codes are
+/// stable: variants are only ever appended, never renumbered or reused.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
 pub enum SwapError {
-    InvalidInstruction,
-    OrderAlreadyInitialized,
-    TakerAlreadyAssigned,
-    MakerTokensNotDeposited,
-    UnauthorizedSigner,
-    InvalidOrderState,
-    InvalidMint,
-    InvalidAmount,
-    InvalidTokenProgram,
-    InvalidTokenAccount,
-    InsufficientFunds,
+    #[error("instruction data is invalid")]
+    InvalidInstruction = 0,
+    #[error("order is already initialized")]
+    OrderAlreadyInitialized = 1,
+    #[error("order already has a taker")]
+    TakerAlreadyAssigned = 2,
+    #[error("maker tokens are not deposited")]
+    MakerTokensNotDeposited = 3,
+    #[error("signer is not allowed to do this")]
+    UnauthorizedSigner = 4,
+    #[error("order is in the wrong state for this instruction")]
+    InvalidOrderState = 5,
+    #[error("mint is invalid or does not match the order")]
+    InvalidMint = 6,
+    #[error("amount is invalid")]
+    InvalidAmount = 7,
+    #[error("token program is invalid or does not own the account")]
+    InvalidTokenProgram = 8,
+    #[error("account is not a token account")]
+    InvalidTokenAccount = 9,
+    #[error("insufficient funds")]
+    InsufficientFunds = 10,
+    #[error("order has to be confirmed by the maker first")]
+    ConfirmationRequired = 11,
+    #[error("order has to be accepted with a taker deposit first")]
+    TakerDepositRequired = 12,
+    #[error("order is paused")]
+    OrderPaused = 13,
+    #[error("order has expired")]
+    OrderExpired = 14,
+    #[error("expiry is in the past")]
+    InvalidExpiry = 15,
+    #[error("auction has ended")]
+    AuctionEnded = 16,
+    #[error("auction has not ended yet")]
+    AuctionNotEnded = 17,
+    #[error("bid does not beat the best bid")]
+    BidTooLow = 18,
+    #[error("quote is not signed by the maker")]
+    InvalidQuoteSignature = 19,
+    #[error("quote has expired")]
+    QuoteExpired = 20,
+    #[error("oracle account is invalid")]
+    InvalidOracle = 21,
+    #[error("oracle price is stale")]
+    StalePrice = 22,
+    #[error("oracle price confidence is too wide")]
+    PriceConfidenceTooWide = 23,
+    #[error("price moved beyond the slippage limit")]
+    SlippageExceeded = 24,
+    #[error("order only fills in full")]
+    PartialFillNotAllowed = 25,
+    #[error("swap chain legs do not connect")]
+    InvalidSwapChain = 26,
+    #[error("preimage does not match the hashlock")]
+    InvalidPreimage = 27,
+    #[error("HTLC has timed out")]
+    HtlcTimedOut = 28,
+    #[error("HTLC is locked until it times out")]
+    HtlcLocked = 29,
+    #[error("tokens are still vesting")]
+    VestingLocked = 30,
+    #[error("no tranche is due yet")]
+    TrancheNotDue = 31,
+    #[error("order is not due for renewal")]
+    RenewalNotDue = 32,
+    #[error("too many allowed takers")]
+    TooManyTakers = 33,
+    #[error("taker does not hold enough of the gate token")]
+    TokenGateNotMet = 34,
+    #[error("taker has to commit to the fill first")]
+    CommitmentRequired = 35,
+    #[error("commitment cannot be revealed yet")]
+    CommitmentPending = 36,
+    #[error("reveal does not match the commitment")]
+    InvalidCommitment = 37,
+    #[error("memo is not valid UTF-8")]
+    InvalidMemo = 38,
+    #[error("order layout is outdated, migrate it first")]
+    OutdatedOrder = 39,
+    #[error("account discriminator does not match")]
+    InvalidDiscriminator = 40,
+    #[error("mint is non-transferable")]
+    NonTransferableMint = 41,
+    #[error("token account is frozen")]
+    TokenAccountFrozen = 42,
+    #[error("token account requires memos on incoming transfers")]
+    MemoRequired = 43,
+    #[error("token account has the CPI guard enabled")]
+    CpiGuardEnabled = 44,
+    #[error("mint is not an NFT")]
+    InvalidNftMint = 45,
+    #[error("too many bundle legs")]
+    TooManyBundleLegs = 46,
+    #[error("NFT is not in the collection")]
+    NotInCollection = 47,
+    #[error("mint is not in the group")]
+    NotInGroup = 48,
+    #[error("rent has to go back to the account that paid it")]
+    InvalidRentDestination = 49,
+    #[error("order has not expired")]
+    OrderNotExpired = 50,
+    #[error("maker has too many open orders")]
+    TooManyOrders = 51,
+    #[error("protocol is paused")]
+    ProtocolPaused = 52,
+    #[error("mint is blocked")]
+    MintBlocked = 53,
+    #[error("registry page is full")]
+    RegistryPageFull = 54,
+    #[error("order is not open")]
+    OrderNotOpen = 55,
+    #[error("account is not the order's address")]
+    InvalidOrderAddress = 56,
+    #[error("token account holds a different mint")]
+    WrongTokenAccountMint = 57,
+    #[error("token account belongs to a different wallet")]
+    WrongTokenAccountOwner = 58,
+    #[error("account is not rent-exempt")]
+    NotRentExempt = 59,
+    #[error("account is not owned by the program")]
+    WrongAccountOwner = 60,
+    #[error("a required signature is missing")]
+    MissingSignature = 61,
+    #[error("instruction does not apply to this order type")]
+    UnsupportedOrderType = 62,
+    #[error("order options cannot be combined")]
+    IncompatibleOrderOptions = 63,
+    #[error("order's taker is its maker")]
+    SelfTrade = 64,
+    #[error("order trades a mint for itself")]
+    SameMint = 65,
+    #[error("account is not the order's referrer")]
+    InvalidReferrer = 66,
 }
 
 impl From<SwapError> for ProgramError {
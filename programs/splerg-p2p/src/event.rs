@@ -0,0 +1,182 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, log::sol_log_data,
+    program_error::ProgramError, pubkey::Pubkey,
+};
+#[cfg(feature = "event-cpi")]
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+};
+
+use crate::state::OrderStatus;
+#[cfg(feature = "event-cpi")]
+use crate::validation::get_event_authority_pda;
+
+/// Layout version of every event, logged after its discriminator. Fields are only ever
+/// appended, so an event logged by a later version still decodes here, less the fields
+/// added since.
+pub const EVENT_VERSION: u8 = 1;
+
+/// Leads the data of an event self-CPI, as Anchor's `emit_cpi!` does
+/// (`sha256("anchor:event")[..8]`, little-endian)
+pub const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+
+/// A Borsh payload logged through `sol_log_data` as one `Program data:` entry:
+/// discriminator, `EVENT_VERSION`, then the event's fields
+pub trait Event: BorshSerialize {
+    /// `sha256("event:<TypeName>")[..8]`
+    const DISCRIMINATOR: [u8; 8];
+
+    /// The event as logged: discriminator, `EVENT_VERSION`, then the event's fields
+    fn data(&self) -> Vec<u8> {
+        let mut data = Self::DISCRIMINATOR.to_vec();
+        data.push(EVENT_VERSION);
+        // Serializing into a Vec cannot fail
+        self.serialize(&mut data).unwrap();
+        data
+    }
+
+    /// Logs the event. With the `event-cpi` feature it is also sent through a self-CPI,
+    /// see `emit_cpi`.
+    fn emit(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let data = self.data();
+        sol_log_data(&[&data]);
+
+        #[cfg(feature = "event-cpi")]
+        emit_cpi(program_id, accounts, data)?;
+        #[cfg(not(feature = "event-cpi"))]
+        let _ = (program_id, accounts);
+
+        Ok(())
+    }
+}
+
+/// Sends `data` to the program itself as the data of an `EVENT_IX_TAG` instruction
+/// signed by the event authority PDA, where it stays in the transaction's inner
+/// instructions even once the logs are truncated. Skipped unless the event authority is
+/// passed among `accounts`.
+#[cfg(feature = "event-cpi")]
+fn emit_cpi(program_id: &Pubkey, accounts: &[AccountInfo], data: Vec<u8>) -> ProgramResult {
+    let (authority, bump) = get_event_authority_pda(program_id);
+    let Some(authority_info) = accounts.iter().find(|account| *account.key == authority) else {
+        return Ok(());
+    };
+
+    let mut ix_data = EVENT_IX_TAG.to_vec();
+    ix_data.extend_from_slice(&data);
+    let mut account_infos = vec![authority_info.clone()];
+    if let Some(program_info) = accounts.iter().find(|account| account.key == program_id) {
+        account_infos.push(program_info.clone());
+    }
+
+    invoke_signed(
+        &Instruction {
+            program_id: *program_id,
+            accounts: vec![AccountMeta::new_readonly(authority, true)],
+            data: ix_data,
+        },
+        &account_infos,
+        &[&[b"__event_authority", &[bump]]],
+    )
+}
+
+/// An order was created and its maker amount escrowed
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OrderCreated {
+    pub order: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub maker_mint: Pubkey,
+    pub taker_mint: Pubkey,
+    pub order_id: u64,
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+}
+
+impl Event for OrderCreated {
+    const DISCRIMINATOR: [u8; 8] = [224, 1, 229, 63, 254, 60, 190, 159];
+}
+
+/// The maker repriced or resized an open order
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OrderAmountsChanged {
+    pub order: Pubkey,
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+}
+
+impl Event for OrderAmountsChanged {
+    const DISCRIMINATOR: [u8; 8] = [188, 158, 110, 55, 49, 227, 36, 0];
+}
+
+/// A taker filled an order. `maker_amount` left the escrow, referral cut included, and
+/// `taker_amount` is what the taker paid, protocol fee included.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OrderFilled {
+    pub order: Pubkey,
+    pub taker: Pubkey,
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+    /// Maker amount still open after the fill (0 once completed)
+    pub remaining_maker_amount: u64,
+    pub status: OrderStatus,
+}
+
+impl Event for OrderFilled {
+    const DISCRIMINATOR: [u8; 8] = [120, 124, 109, 66, 249, 116, 174, 30];
+}
+
+/// An order account was closed and its rent returned, whichever instruction closed it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OrderClosed {
+    pub order: Pubkey,
+    pub maker: Pubkey,
+    /// Status of the order when it closed
+    pub status: OrderStatus,
+}
+
+impl Event for OrderClosed {
+    const DISCRIMINATOR: [u8; 8] = [237, 77, 101, 123, 72, 43, 149, 123];
+}
+
+/// Any event the program emits, as decoded from its `Program data:` log entry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SwapEvent {
+    OrderCreated(OrderCreated),
+    OrderAmountsChanged(OrderAmountsChanged),
+    OrderFilled(OrderFilled),
+    OrderClosed(OrderClosed),
+}
+
+impl SwapEvent {
+    /// Decodes the bytes of one `sol_log_data` entry, failing on data that is not an
+    /// event of this program
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let (discriminator, rest) = data
+            .split_first_chunk::<8>()
+            .ok_or(ProgramError::InvalidAccountData)?;
+        // Any version decodes, see `EVENT_VERSION`
+        let (_version, mut fields) = rest.split_first().ok_or(ProgramError::InvalidAccountData)?;
+
+        let event = match *discriminator {
+            OrderCreated::DISCRIMINATOR => {
+                Self::OrderCreated(OrderCreated::deserialize(&mut fields)?)
+            }
+            OrderAmountsChanged::DISCRIMINATOR => {
+                Self::OrderAmountsChanged(OrderAmountsChanged::deserialize(&mut fields)?)
+            }
+            OrderFilled::DISCRIMINATOR => Self::OrderFilled(OrderFilled::deserialize(&mut fields)?),
+            OrderClosed::DISCRIMINATOR => Self::OrderClosed(OrderClosed::deserialize(&mut fields)?),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(event)
+    }
+
+    /// Decodes the data of an event self-CPI, failing on any other instruction data
+    pub fn unpack_cpi(data: &[u8]) -> Result<Self, ProgramError> {
+        data.strip_prefix(&EVENT_IX_TAG)
+            .ok_or(ProgramError::InvalidInstructionData)
+            .and_then(Self::unpack)
+    }
+}
@@ -1,63 +1,2064 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+use crate::{
+    state::{FeeTier, FillParams, OrderParams, Quote},
+    validation::{
+        get_blocked_mint_pda, get_config_pda, get_fee_exemption_pda, get_maker_index_pda,
+        get_order_pda, get_pair_stats_pda, get_protocol_stats_pda, get_registry_page_pda,
+        get_treasury_pda, get_user_stats_pda, get_vault_pda, get_wsol_pda,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankInstruction)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[rustfmt::skip]
 pub enum SwapInstruction {
-    /// Initialize P2P swap order
+    /// Initialize P2P swap order. `order_id` is a maker-chosen seed that lets a
+    /// maker keep several orders open for the same mint pair. Orders created with
+    /// `requires_confirmation` can only settle through `AcceptOrder` + `ConfirmSwap`.
+    /// Orders created with `dual_escrow` require `DepositTakerSide` before `CompleteSwap`.
+    /// Orders created with `all_or_none` reject partial fills. `memo` is zero-padded
+    /// UTF-8 text stored on the order.
+    /// Either mint may be the native mint to trade SOL without wrapping it: a native maker
+    /// side passes the maker's wallet and the order PDA as the two token accounts and is
+    /// escrowed as lamports in the order PDA. Native orders cannot use `dual_escrow` and
+    /// settle through `CompleteSwap` and `CloseOrder`.
+    /// For Token-2022 mints with a transfer fee, the maker pays `maker_amount` plus the
+    /// fee so the escrow holds exactly `maker_amount`; the order records both amounts.
+    /// Non-transferable mints are refused. Permanent delegates and freeze authorities are
+    /// allowed but recorded in the order's `maker_mint_risk`/`taker_mint_risk` bits. Mints
+    /// whose accounts start frozen need the escrows thawed by their freeze authority first,
+    /// e.g. with a `ThawAccount` earlier in the same transaction.
+    /// Token-2022 accounts with CPI guard enabled refuse owner transfers made through a
+    /// program. Such makers fund the escrow with their own `TransferChecked` earlier in
+    /// the same transaction: an escrow already holding `maker_amount` is taken as the
+    /// deposit and nothing is pulled from the maker mint ATA.
+    /// Escrows are vault token accounts at `[b"vault", order PDA, mint]`, created by the
+    /// program and paid by the maker. The order PDA's ATAs are still accepted as escrows
+    /// set up beforehand, and created if missing, for the deposits that have to reach the
+    /// escrow before it is checked: pre-funded ones and escrows of default-frozen mints.
+    /// No other escrow address is accepted.
     /// Accounts:
-    /// * [signer] Maker (order creator, pays rent)
+    /// * [writable, signer] Maker (order creator, pays rent unless a rent payer signs)
     /// * [writable] Order PDA account (to be created)
     /// * [writable] Maker mint ATA (initialized)
-    /// * [writable] PDA ATA (initialized)
-    /// * [] Taker (order counterparty)
+    /// * [writable] Order's maker-mint vault or ATA (created if missing)
+    /// * [] Taker (order counterparty, or the default pubkey for an open order)
     /// * [] Maker token mint
     /// * [] Taker token mint
     /// * [] System program
     /// * [] Rent sysvar
-    /// * [] Token Program (optional Token 2022)
+    /// * [] Maker mint's token program (SPL Token or Token-2022)
+    /// * [] Taker mint's token program (SPL Token or Token-2022)
+    /// * [] Config PDA account (need not exist), checked for a protocol pause and, for an
+    ///   order of the maker to itself or of a mint for itself, `SetTradeGuards`
+    /// * [writable, optional] Order's taker-mint vault or ATA (dual-escrow only, created if
+    ///   missing)
+    /// * [writable, signer, optional] Rent payer (a platform sponsoring the order: pays the
+    ///   rent of the order and its escrows in place of the maker, and gets it back on close).
+    ///   Only a writable signer at exactly this position, right after the taker-mint
+    ///   escrow of a dual-escrow order or at index 12 otherwise, is taken as rent payer
+    /// * ...Transfer hook accounts (hooked Token-2022 mints only): the hook program, its
+    ///   `ExtraAccountMetaList` PDA and the accounts that list resolves
+    /// * [] Associated Token Account program (if an ATA escrow has to be created)
+    /// * [] Blocked-mint PDAs of the maker mint and the taker mint (need not exist)
+    /// * [writable] Maker's order index PDA (created if missing, grown by the rent payer)
+    /// * [writable, optional] A registry page of the mint pair with room for the order, or
+    ///   else page 0 if the pair has no pages yet, or the page after the pair's full last
+    ///   page along with that page (created and linked, paid by the rent payer). Without
+    ///   one the order is created but not listed.
+    #[account(0, writable, signer, name = "maker", desc = "Maker (order creator, pays rent unless a rent payer signs)")]
+    #[account(1, writable, name = "order", desc = "Order PDA account (to be created)")]
+    #[account(2, writable, name = "maker_token_account", desc = "Maker mint ATA (initialized)")]
+    #[account(3, writable, name = "escrow", desc = "Order's maker-mint vault or ATA (created if missing)")]
+    #[account(4, name = "taker", desc = "Taker (order counterparty, or the default pubkey for an open order)")]
+    #[account(5, name = "maker_mint", desc = "Maker token mint")]
+    #[account(6, name = "taker_mint", desc = "Taker token mint")]
+    #[account(7, name = "system_program", desc = "System program")]
+    #[account(8, name = "rent", desc = "Rent sysvar")]
+    #[account(9, name = "maker_mint_token_program", desc = "Maker mint's token program (SPL Token or Token-2022)")]
+    #[account(10, name = "taker_mint_token_program", desc = "Taker mint's token program (SPL Token or Token-2022)")]
+    #[account(11, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause and, for an order of the maker to itself or of a mint for itself, `SetTradeGuards`")]
+    #[account(12, writable, optional, name = "taker_escrow", desc = "Order's taker-mint vault or ATA (dual-escrow only, created if missing)")]
+    #[account(13, writable, signer, optional, name = "rent_payer", desc = "Rent payer (a platform sponsoring the order: pays the rent of the order and its escrows in place of the maker, and gets it back on close)")]
     InitializeOrder {
+        order_id: u64,
         maker_amount: u64,
         taker_amount: u64,
+        requires_confirmation: bool,
+        dual_escrow: bool,
+        all_or_none: bool,
+        memo: [u8; 64],
     },
 
-    /// Change order amounts
+    /// Change order amounts. Top-ups include the maker mint's transfer fee, if any. UI-amount
+    /// orders have to switch back to raw amounts first.
     /// Accounts:
-    /// * [signer] Maker
+    /// * [signer] Maker or delegate
     /// * [writable] Order PDA account
     /// * [writable] Program's escrow token account
     /// * [writable] Maker's token account
     /// * [] Token program
+    /// * [] Maker mint
+    /// * [] Taker mint
+    /// * [writable, optional] Registry page listing the order, updated with the new amounts
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "escrow", desc = "Program's escrow token account")]
+    #[account(3, writable, name = "maker_token_account", desc = "Maker's token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "maker_mint", desc = "Maker mint")]
+    #[account(6, name = "taker_mint", desc = "Taker mint")]
     ChangeOrderAmounts {
         new_maker_amount: u64,
         new_taker_amount: u64,
     },
 
-    /// Change order taker
+    /// Change order taker. Passing the default pubkey opens the order to any taker.
     /// Accounts:
-    /// * [signer] Maker
+    /// * [signer] Maker or delegate
     /// * [writable] Order PDA account
     /// * [] New taker pubkey
+    /// * [] Config PDA account (only to make the maker its own taker, or to retarget an
+    ///   order of a mint for itself, as allowed by `SetTradeGuards`)
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, name = "new_taker", desc = "New taker pubkey")]
+    #[account(3, optional, name = "config", desc = "Config PDA account (only to make the maker its own taker, or to retarget an order of a mint for itself, as allowed by `SetTradeGuards`)")]
     ChangeTaker { new_taker: [u8; 32] },
 
-    /// Complete swap
+    /// Complete swap for `fill_amount` of the remaining maker size (0 fills everything).
+    /// Partial fills pay a pro-rata taker amount and leave the rest of the order open;
+    /// `all_or_none` orders only accept full fills. Fails if the taker would pay more than
+    /// `max_taker_amount` or receive less than `min_maker_amount` (after any referral cut
+    /// and maker-mint transfer fee), e.g. after a same-slot `ChangeOrderAmounts`. The protocol fee is deducted from the
+    /// taker side before it reaches the maker, or paid separately by the taker in the
+    /// config's fee mint when one is set. The rate follows the fee tier of whoever pays
+    /// it, and the fill counts toward both parties' tracked volume.
+    /// Takers in the order's Merkle allowlist pass their `taker_proof`; listed takers
+    /// pass an empty one. `memo` is logged through the SPL Memo program before each
+    /// transfer into a Token-2022 account that requires incoming transfer memos.
+    /// Missing receiving accounts are created as the maker's and taker's ATAs, paid by the
+    /// taker, so a fill never waits on the maker and a fresh wallet fills in one transaction.
+    /// Accounts:
+    /// * [writable, signer] Taker (any signer if the order is open; pays for receiving
+    ///   accounts it creates)
+    /// * [writable] Order PDA account
+    /// * [writable] Maker's receiving token account (maker's wallet for native SOL; the
+    ///   maker's ATA, created if missing)
+    /// * [writable] Taker's sending token account (taker-mint escrow for dual-escrow orders,
+    ///   system program for native SOL)
+    /// * [writable] Taker's receiving token account (taker's wallet for native SOL; the
+    ///   taker's ATA, created if missing)
+    /// * [writable] Program's escrow token account (order PDA for native SOL)
+    /// * [] Maker mint's token program
+    /// * [] Taker mint's token program (also used for the fee mint unless it shares the
+    ///   maker mint's)
+    /// * [] Maker mint (native mint for native SOL)
+    /// * [] Taker mint (native mint for native SOL, the taker's NFT for collection orders,
+    ///   any member mint of the group for taker-group orders)
+    /// * [] Config PDA account (need not exist), checked for a protocol pause
+    /// * [writable] Treasury PDA of the taker mint (or fee mint) (unused while no fee is
+    ///   set)
+    /// * [] Maker's fee-exemption PDA (need not exist)
+    /// * [writable] Maker's user stats PDA (need not exist)
+    /// * [writable] Taker's user stats PDA (need not exist)
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * [writable] Protocol stats PDA (need not exist)
+    /// * [writable] Pair stats PDA of the order's maker and taker mints (need not exist)
+    /// * [] Pyth price account (oracle-priced orders only)
+    /// * [] Taker's gating-mint token account (token-gated orders only)
+    /// * [] Fee feed PDA of the taker mint (fee mint set only)
+    /// * [] Pyth price account of the fee feed (fee mint set only)
+    /// * [writable] Taker's fee-mint token account (fee mint set only)
+    /// * [] Fee mint (fee mint set only)
+    /// * [writable] The order's referrer's maker-mint token account (its wallet for native
+    ///   SOL), paid `referral_bps` of the maker side (orders with a referral fee only)
+    /// * ...Transfer hook accounts of either mint, as for `InitializeOrder`
+    /// * [] SPL Memo program (if `memo` is set)
+    /// * ...Programmable NFT accounts, as for `InitializeNftOrder` (programmable NFT orders
+    ///   only); the taker's receiving account is the ATA Token Metadata creates if needed
+    /// * [] Metadata account of the taker's NFT (collection orders only)
+    /// * [] Maker (if the maker's receiving account has to be created)
+    /// * [] System program and Associated Token Account program (if a receiving account
+    ///   has to be created)
+    /// * [writable, optional] Maker, or the order's rent payer if it was sponsored: a fill
+    ///   that completes an order with nothing left to vest, renew or refund then closes
+    ///   the order and its emptied escrow and returns their rent there (to the rent payer
+    ///   if both are passed)
+    /// * [writable, optional] Maker's order index PDA, which the order is dropped from
+    ///   when the fill closes it
+    /// * [writable, optional] Registry page listing the order, updated with its remaining
+    ///   amounts or, once the order is no longer open, dropping it
+    /// * [writable, optional] Fill receipt PDA at `[b"receipt", order, fill_index]`, where
+    ///   `fill_index` is the order's `fill_count` before the fill, created by the taker to
+    ///   record it (with the system program)
+    /// * [writable, optional] Trade history PDA of the order's maker and taker mints, which
+    ///   the fill is recorded in once initialized
+    ///
+    /// Bundle orders are filled whole (`fill_amount` must be 0), with the protocol fee
+    /// skimmed from each taker leg at the config's base rate, and take instead:
+    /// * [signer] Taker
+    /// * [writable] Bundle PDA account
+    /// * [] Config PDA account (need not exist), checked for a protocol pause
+    /// * ...For each taker leg: [writable] taker's sending token account, [writable]
+    ///   maker's receiving token account, [writable] treasury PDA of the leg's mint (unused
+    ///   while no fee is set), [] mint, [] token program
+    /// * ...For each maker leg: [writable] the bundle's escrow token account, [writable]
+    ///   taker's receiving token account, [] mint, [] token program
+    /// * ...Blocked-mint PDAs of every mint of the bundle (need not exist)
+    /// * ...Transfer hook accounts and the SPL Memo program, as above
+    #[account(0, writable, signer, name = "taker", desc = "Taker (any signer if the order is open; pays for receiving accounts it creates)")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "maker_receiving_token_account", desc = "Maker's receiving token account (maker's wallet for native SOL; the maker's ATA, created if missing)")]
+    #[account(3, writable, name = "taker_sending_token_account", desc = "Taker's sending token account (taker-mint escrow for dual-escrow orders, system program for native SOL)")]
+    #[account(4, writable, name = "taker_receiving_token_account", desc = "Taker's receiving token account (taker's wallet for native SOL; the taker's ATA, created if missing)")]
+    #[account(5, writable, name = "escrow", desc = "Program's escrow token account (order PDA for native SOL)")]
+    #[account(6, name = "maker_mint_token_program", desc = "Maker mint's token program")]
+    #[account(7, name = "taker_mint_token_program", desc = "Taker mint's token program (also used for the fee mint unless it shares the maker mint's)")]
+    #[account(8, name = "maker_mint", desc = "Maker mint (native mint for native SOL)")]
+    #[account(9, name = "taker_mint", desc = "Taker mint (native mint for native SOL, the taker's NFT for collection orders, any member mint of the group for taker-group orders)")]
+    #[account(10, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause")]
+    #[account(11, writable, name = "treasury", desc = "Treasury PDA of the taker mint (or fee mint) (unused while no fee is set)")]
+    #[account(12, name = "maker_fee_exemption", desc = "Maker's fee-exemption PDA (need not exist)")]
+    #[account(13, writable, name = "maker_stats", desc = "Maker's user stats PDA (need not exist)")]
+    #[account(14, writable, name = "taker_stats", desc = "Taker's user stats PDA (need not exist)")]
+    #[account(15, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(16, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    #[account(17, writable, name = "protocol_stats", desc = "Protocol stats PDA (need not exist)")]
+    #[account(18, writable, name = "pair_stats", desc = "Pair stats PDA of the order's maker and taker mints (need not exist)")]
+    #[account(19, optional, name = "price_account", desc = "Pyth price account (oracle-priced orders only)")]
+    #[account(20, optional, name = "gating_token_account", desc = "Taker's gating-mint token account (token-gated orders only)")]
+    #[account(21, optional, name = "fee_feed", desc = "Fee feed PDA of the taker mint (fee mint set only)")]
+    #[account(22, optional, name = "fee_feed_price", desc = "Pyth price account of the fee feed (fee mint set only)")]
+    #[account(23, writable, optional, name = "taker_fee_mint_token_account", desc = "Taker's fee-mint token account (fee mint set only)")]
+    #[account(24, optional, name = "fee_mint", desc = "Fee mint (fee mint set only)")]
+    #[account(25, writable, optional, name = "referrer_token_account", desc = "The order's referrer's maker-mint token account (its wallet for native SOL), paid `referral_bps` of the maker side (orders with a referral fee only)")]
+    CompleteSwap {
+        fill_amount: u64,
+        max_taker_amount: u64,
+        min_maker_amount: u64,
+        taker_proof: Vec<[u8; 32]>,
+        memo: Option<String>,
+    },
+
+    /// Close order and reclaim rent. `memo` is logged before refunds into accounts that
+    /// require incoming transfer memos, as for `CompleteSwap`. The rent of the order and its
+    /// escrow goes back to the maker or, for a sponsored order, to the account that paid
+    /// it, whichever the closer passes as rent destination.
+    /// Accounts:
+    /// * [signer] Order authority (maker or delegate if incomplete, either party if
+    ///   complete, anyone if declined)
+    /// * [writable] Order PDA account
+    /// * [writable] Program's escrow token account (order PDA for native SOL)
+    /// * [writable] Maker's token account (refund; maker's wallet for native SOL)
+    /// * [] Token program
+    /// * [writable] Maker mint (collects fees withheld in the escrow before it closes)
+    /// * [writable] Rent destination: the maker or the order's rent payer, whoever the
+    ///   authority is
+    /// * [writable, optional] Program's taker-mint escrow token account (if accepted)
+    /// * [writable, optional] Accepted taker's token account (refund, if accepted)
+    /// * [] Taker mint (if accepted)
+    /// * ...Transfer hook accounts of either mint, as for `InitializeOrder`
+    /// * [] SPL Memo program (if `memo` is set)
+    /// * ...Programmable NFT accounts, as for `InitializeNftOrder` (programmable NFT orders
+    ///   only); the maker must be among the accounts
+    /// * [writable, optional] Order index PDA of the maker the order was created by, which
+    ///   the order is removed from
+    /// * [writable, optional] Registry page listing the order, which it is removed from
+    ///
+    /// Bundle orders are closed by their maker, refunding and closing every escrow:
+    /// * [signer, writable] Maker
+    /// * [writable] Bundle PDA account
+    /// * ...For each maker leg: [writable] the bundle's escrow token account, [writable]
+    ///   maker's token account, [writable] mint, [] token program
+    /// * ...Transfer hook accounts and the SPL Memo program, as above
+    #[account(0, signer, name = "order_authority", desc = "Order authority (maker or delegate if incomplete, either party if complete, anyone if declined)")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "escrow", desc = "Program's escrow token account (order PDA for native SOL)")]
+    #[account(3, writable, name = "maker_token_account", desc = "Maker's token account (refund; maker's wallet for native SOL)")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, writable, name = "maker_mint", desc = "Maker mint (collects fees withheld in the escrow before it closes)")]
+    #[account(6, writable, name = "rent_destination", desc = "Rent destination: the maker or the order's rent payer, whoever the authority is")]
+    #[account(7, writable, optional, name = "taker_escrow", desc = "Program's taker-mint escrow token account (if accepted)")]
+    #[account(8, writable, optional, name = "accepted_taker_token_account", desc = "Accepted taker's token account (refund, if accepted)")]
+    #[account(9, optional, name = "taker_mint", desc = "Taker mint (if accepted)")]
+    CloseOrder { memo: Option<String> },
+
+    /// Lock the taker's side of the trade in escrow, pending maker confirmation
+    /// Accounts:
+    /// * [signer] Taker
+    /// * [writable] Order PDA account
+    /// * [writable] Taker's sending token account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [] Token program
+    /// * [] Taker mint
+    #[account(0, signer, name = "taker", desc = "Taker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "taker_sending_token_account", desc = "Taker's sending token account")]
+    #[account(3, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "taker_mint", desc = "Taker mint")]
+    AcceptOrder,
+
+    /// Release both escrows for an accepted order. The protocol fee is skimmed from the
+    /// taker side paid to the maker, at the maker's volume tier and in the taker mint even
+    /// with a fee mint configured, unless the maker is fee-exempt.
+    /// Accounts:
+    /// * [writable, signer] Maker
+    /// * [writable] Order PDA account
+    /// * [writable] Program's escrow token account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [writable] Maker's receiving token account
+    /// * [writable] Taker's receiving token account
+    /// * [] Maker mint's token program
+    /// * [] Taker mint's token program
+    /// * [] Maker mint
+    /// * [] Taker mint
+    /// * [] Config PDA account (need not exist), checked for a protocol pause and the
+    ///   trade guards
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * [writable] Treasury PDA of the taker mint (unused while no fee is set)
+    /// * [] Maker's fee-exemption PDA (need not exist)
+    /// * [writable] Maker's user stats PDA (need not exist)
+    /// * ...Transfer hook accounts of either mint, as for `InitializeOrder`
+    /// * ...[writable, optional] Bookkeeping accounts, looked up by key as for `CompleteSwap`:
+    ///   the maker's and taker's user stats PDAs, the protocol and pair stats PDAs, the
+    ///   maker or rent payer and the maker's order index PDA (closing a completed order),
+    ///   the registry page listing the order, the fill receipt PDA (paid for by the signer,
+    ///   with the system program) and the trade history PDA
+    #[account(0, writable, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "escrow", desc = "Program's escrow token account")]
+    #[account(3, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(4, writable, name = "maker_receiving_token_account", desc = "Maker's receiving token account")]
+    #[account(5, writable, name = "taker_receiving_token_account", desc = "Taker's receiving token account")]
+    #[account(6, name = "maker_mint_token_program", desc = "Maker mint's token program")]
+    #[account(7, name = "taker_mint_token_program", desc = "Taker mint's token program")]
+    #[account(8, name = "maker_mint", desc = "Maker mint")]
+    #[account(9, name = "taker_mint", desc = "Taker mint")]
+    #[account(10, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause and the trade guards")]
+    #[account(11, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(12, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    #[account(13, writable, name = "treasury", desc = "Treasury PDA of the taker mint (unused while no fee is set)")]
+    #[account(14, name = "maker_fee_exemption", desc = "Maker's fee-exemption PDA (need not exist)")]
+    #[account(15, writable, name = "maker_stats", desc = "Maker's user stats PDA (need not exist)")]
+    ConfirmSwap,
+
+    /// Withdraw an acceptance or counter-offer the maker has not acted on yet
+    /// Accounts:
+    /// * [signer] Accepted taker
+    /// * [writable] Order PDA account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [writable] Taker's token account (refund)
+    /// * [] Token program
+    /// * [] Taker mint
+    #[account(0, signer, name = "accepted_taker", desc = "Accepted taker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(3, writable, name = "taker_token_account", desc = "Taker's token account (refund)")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "taker_mint", desc = "Taker mint")]
+    CancelAcceptance,
+
+    /// Propose new amounts as the designated taker, escrowing the proposed taker amount
+    /// Accounts:
+    /// * [signer] Designated taker
+    /// * [writable] Order PDA account
+    /// * [writable] Taker's sending token account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [] Taker mint's token program
+    /// * [] Taker mint
+    /// * [] Config PDA account (need not exist), checked for the trade guards
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    #[account(0, signer, name = "designated_taker", desc = "Designated taker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "taker_sending_token_account", desc = "Taker's sending token account")]
+    #[account(3, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(4, name = "token_program", desc = "Taker mint's token program")]
+    #[account(5, name = "taker_mint", desc = "Taker mint")]
+    #[account(6, name = "config", desc = "Config PDA account (need not exist), checked for the trade guards")]
+    #[account(7, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(8, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    ProposeCounterOffer {
+        new_maker_amount: u64,
+        new_taker_amount: u64,
+    },
+
+    /// Apply the pending counter-offer and settle the swap at the proposed amounts. The
+    /// protocol fee is skimmed as for `ConfirmSwap`.
+    /// Accounts:
+    /// * [writable, signer] Maker
+    /// * [writable] Order PDA account
+    /// * [writable] Program's escrow token account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [writable] Maker's token account (top-up source or refund)
+    /// * [writable] Maker's receiving token account
+    /// * [writable] Taker's receiving token account
+    /// * [] Maker mint's token program
+    /// * [] Taker mint's token program
+    /// * [] Maker mint
+    /// * [] Taker mint
+    /// * [] Config PDA account (need not exist), checked for a protocol pause and the
+    ///   trade guards
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * [writable] Treasury PDA of the taker mint (unused while no fee is set)
+    /// * [] Maker's fee-exemption PDA (need not exist)
+    /// * [writable] Maker's user stats PDA (need not exist)
+    /// * ...Transfer hook accounts of either mint, as for `InitializeOrder`
+    /// * ...[writable, optional] Bookkeeping accounts, looked up by key as for `CompleteSwap`:
+    ///   the maker's and taker's user stats PDAs, the protocol and pair stats PDAs, the
+    ///   maker or rent payer and the maker's order index PDA (closing a completed order),
+    ///   the registry page listing the order, the fill receipt PDA (paid for by the signer,
+    ///   with the system program) and the trade history PDA
+    #[account(0, writable, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "escrow", desc = "Program's escrow token account")]
+    #[account(3, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(4, writable, name = "maker_token_account", desc = "Maker's token account (top-up source or refund)")]
+    #[account(5, writable, name = "maker_receiving_token_account", desc = "Maker's receiving token account")]
+    #[account(6, writable, name = "taker_receiving_token_account", desc = "Taker's receiving token account")]
+    #[account(7, name = "maker_mint_token_program", desc = "Maker mint's token program")]
+    #[account(8, name = "taker_mint_token_program", desc = "Taker mint's token program")]
+    #[account(9, name = "maker_mint", desc = "Maker mint")]
+    #[account(10, name = "taker_mint", desc = "Taker mint")]
+    #[account(11, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause and the trade guards")]
+    #[account(12, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(13, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    #[account(14, writable, name = "treasury", desc = "Treasury PDA of the taker mint (unused while no fee is set)")]
+    #[account(15, name = "maker_fee_exemption", desc = "Maker's fee-exemption PDA (need not exist)")]
+    #[account(16, writable, name = "maker_stats", desc = "Maker's user stats PDA (need not exist)")]
+    AcceptCounterOffer,
+
+    /// Escrow the taker's side of a dual-escrow order ahead of `CompleteSwap`
     /// Accounts:
     /// * [signer] Taker
     /// * [writable] Order PDA account
+    /// * [writable] Taker's sending token account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [] Token program
+    /// * [] Taker mint
+    #[account(0, signer, name = "taker", desc = "Taker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "taker_sending_token_account", desc = "Taker's sending token account")]
+    #[account(3, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "taker_mint", desc = "Taker mint")]
+    DepositTakerSide,
+
+    /// Decline an order as its designated taker so it can be closed without the maker
+    /// Accounts:
+    /// * [signer] Designated taker
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "designated_taker", desc = "Designated taker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    DeclineOrder,
+
+    /// Temporarily stop fills on an order while keeping its escrow in place
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    PauseOrder,
+
+    /// Allow fills on a paused order again
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    ResumeOrder,
+
+    /// Set a new expiry timestamp, which must be in the future (0 removes the expiry)
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    UpdateExpiry { new_expires_at: i64 },
+
+    /// Turn an open order into a Dutch auction whose taker amount decays linearly
+    /// from the current `taker_amount` to `floor_taker_amount` over `duration` seconds
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    StartDutchAuction {
+        floor_taker_amount: u64,
+        duration: i64,
+    },
+
+    /// Turn an open order into an English auction running for `duration` seconds,
+    /// with the current `taker_amount` as the reserve price. Once it holds a bid, the order
+    /// can no longer be closed, retargeted or repriced until `SettleAuction`.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    StartEnglishAuction { duration: i64 },
+
+    /// Escrow a bid above the current best bid, refunding the previous best bidder
+    /// Accounts:
+    /// * [signer] Bidder
+    /// * [writable] Order PDA account
+    /// * [writable] Bidder's sending token account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [] Token program
+    /// * [] Taker mint
+    /// * [writable, optional] Previous best bidder's token account (refund, if any bid exists)
+    #[account(0, signer, name = "bidder", desc = "Bidder")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "bidder_sending_token_account", desc = "Bidder's sending token account")]
+    #[account(3, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "taker_mint", desc = "Taker mint")]
+    #[account(6, writable, optional, name = "previous_best_bidder_token_account", desc = "Previous best bidder's token account (refund, if any bid exists)")]
+    PlaceBid { amount: u64 },
+
+    /// Swap with the highest bidder once the auction has ended. Callable by anyone.
+    /// Accounts:
+    /// * [signer] Caller
+    /// * [writable] Order PDA account
+    /// * [writable] Program's escrow token account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [writable] Maker's receiving token account
+    /// * [writable] Winning bidder's receiving token account
+    /// * [] Token program
+    /// * [] Maker mint
+    /// * [] Taker mint
+    /// * [] Config PDA account (need not exist), checked for a protocol pause
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * ...[writable, optional] Bookkeeping accounts, looked up by key as for `CompleteSwap`:
+    ///   the maker's and taker's user stats PDAs, the protocol and pair stats PDAs, the
+    ///   maker or rent payer and the maker's order index PDA (closing a completed order),
+    ///   the registry page listing the order, the fill receipt PDA (paid for by the signer,
+    ///   with the system program) and the trade history PDA
+    #[account(0, signer, name = "caller", desc = "Caller")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "escrow", desc = "Program's escrow token account")]
+    #[account(3, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(4, writable, name = "maker_receiving_token_account", desc = "Maker's receiving token account")]
+    #[account(5, writable, name = "winning_bidder_receiving_token_account", desc = "Winning bidder's receiving token account")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    #[account(7, name = "maker_mint", desc = "Maker mint")]
+    #[account(8, name = "taker_mint", desc = "Taker mint")]
+    #[account(9, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause")]
+    #[account(10, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(11, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    SettleAuction,
+
+    /// Settle a maker-signed quote without an order account. The preceding instruction
+    /// must be an Ed25519 program instruction verifying the maker's signature over the
+    /// Borsh-encoded quote, and the maker must have approved the RFQ authority PDA
+    /// as delegate on their sending token account.
+    /// Accounts:
+    /// * [signer] Taker (pays for the nonce marker)
+    /// * [writable] Quote nonce PDA (to be created)
+    /// * [writable] Maker's sending token account
     /// * [writable] Maker's receiving token account
     /// * [writable] Taker's sending token account
     /// * [writable] Taker's receiving token account
+    /// * [] RFQ authority PDA
+    /// * [] Instructions sysvar
+    /// * [] System program
+    /// * [] Token program
+    /// * [] Maker mint
+    /// * [] Taker mint
+    /// * [] Config PDA account (need not exist), checked for a protocol pause and the
+    ///   trade guards
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    #[account(0, signer, name = "taker", desc = "Taker (pays for the nonce marker)")]
+    #[account(1, writable, name = "quote_nonce", desc = "Quote nonce PDA (to be created)")]
+    #[account(2, writable, name = "maker_sending_token_account", desc = "Maker's sending token account")]
+    #[account(3, writable, name = "maker_receiving_token_account", desc = "Maker's receiving token account")]
+    #[account(4, writable, name = "taker_sending_token_account", desc = "Taker's sending token account")]
+    #[account(5, writable, name = "taker_receiving_token_account", desc = "Taker's receiving token account")]
+    #[account(6, name = "rfq_authority", desc = "RFQ authority PDA")]
+    #[account(7, name = "instructions_sysvar", desc = "Instructions sysvar")]
+    #[account(8, name = "system_program", desc = "System program")]
+    #[account(9, name = "token_program", desc = "Token program")]
+    #[account(10, name = "maker_mint", desc = "Maker mint")]
+    #[account(11, name = "taker_mint", desc = "Taker mint")]
+    #[account(12, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause and the trade guards")]
+    #[account(13, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(14, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    FillQuote { quote: Quote },
+
+    /// Price a fixed order from a Pyth price feed instead of `taker_amount`. The feed
+    /// quotes the maker mint in the taker mint; fills pay the oracle price plus `spread_bps`.
+    /// `CompleteSwap` then takes the price account as an extra trailing account.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    /// * [] Pyth `PriceUpdateV2` account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    #[account(2, name = "price_update", desc = "Pyth `PriceUpdateV2` account")]
+    SetPriceOracle { spread_bps: u16 },
+
+    /// Toggle whether the order only accepts fills of its full remaining size
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    SetAllOrNone { all_or_none: bool },
+
+    /// Fill the full order like `CompleteSwap`, then close the order account and its
+    /// maker-mint escrow so the rent returns to the maker in the same transaction
+    /// Accounts:
+    /// * [writable] Maker, or the order's rent payer if it was sponsored (rent receiver)
+    /// * ...`CompleteSwap` accounts, with the maker mint writable for Token-2022 mints
+    ///   with a transfer fee, and optionally the maker's order index PDA (writable) to
+    ///   drop the order from
+    #[account(0, writable, name = "maker", desc = "Maker, or the order's rent payer if it was sponsored (rent receiver)")]
+    CompleteSwapAndClose {
+        max_taker_amount: u64,
+        min_maker_amount: u64,
+    },
+
+    /// Fill a sequence of orders atomically, routing each order's maker tokens into the
+    /// next order's taker side (A→B, B→C). The taker pays at most `max_taker_amount` of
+    /// the first order's taker mint and receives at least `min_maker_amount` of the last
+    /// order's maker mint; surplus intermediate tokens stay with the taker. Oracle-priced
+    /// orders cannot be chained, nor can fee-paying hops while a fee mint is configured.
+    /// Accounts:
+    /// * [signer] Taker
+    /// * [] Token program of every mint in the chain
+    /// * [] Config PDA account (need not exist), checked for a protocol pause
+    /// * [writable] Taker's user stats PDA (need not exist)
+    /// * [writable] Protocol stats PDA (need not exist)
+    /// * For each hop:
+    ///   * [writable] Order PDA account
+    ///   * [writable] Maker's receiving token account
+    ///   * [writable] Taker's sending token account (the previous hop's receiving account)
+    ///   * [writable] Taker's receiving token account
+    ///   * [writable] Program's escrow token account
+    ///   * [writable] Treasury PDA of the hop's taker mint
+    ///   * [] The hop maker's fee-exemption PDA
+    ///   * [writable] The hop maker's user stats PDA (need not exist)
+    ///   * [] The hop's maker mint
+    ///   * [] The hop's taker mint
+    ///   * [] Blocked-mint PDAs of the hop's maker mint and taker mint (need not exist)
+    ///   * [writable] The hop's pair stats PDA (need not exist)
+    #[account(0, signer, name = "taker", desc = "Taker")]
+    #[account(1, name = "token_program", desc = "Token program of every mint in the chain")]
+    #[account(2, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause")]
+    #[account(3, writable, name = "taker_stats", desc = "Taker's user stats PDA (need not exist)")]
+    #[account(4, writable, name = "protocol_stats", desc = "Protocol stats PDA (need not exist)")]
+    CompleteSwapChain {
+        max_taker_amount: u64,
+        min_maker_amount: u64,
+    },
+
+    /// Settle three orders forming a cycle (A wants B, B wants C, C wants A) without any
+    /// party holding the counter-asset: each order's escrow pays the maker of the order
+    /// wanting its mint. Every order is filled in full and must receive at least its
+    /// `taker_amount`, less the protocol fee skimmed as for `ConfirmSwap`; each order must
+    /// be open or designate the maker it pays as taker. Orders that commit-reveal, gate
+    /// their taker by token, Merkle root, collection or group, and mints with a transfer
+    /// fee are refused, since no maker of the ring signs for the order it takes.
+    /// Accounts:
+    /// * [signer] Settler (anyone)
+    /// * [] Token program of every mint in the ring
+    /// * [] Config PDA account (need not exist), checked for a protocol pause and the
+    ///   trade guards
+    /// * For each order, in cycle order:
+    ///   * [writable] Order PDA account
+    ///   * [writable] Program's escrow token account
+    ///   * [writable] Maker's receiving token account (the order's taker mint)
+    ///   * [] The order's maker mint
+    ///   * [] Blocked-mint PDA of the order's maker mint (need not exist)
+    ///   * [writable] Treasury PDA of the order's taker mint (unused while no fee is set)
+    ///   * [] The order maker's fee-exemption PDA (need not exist)
+    ///   * [writable] The order maker's user stats PDA (need not exist)
+    /// * ...Transfer hook accounts of the ring's mints, as for `InitializeOrder`
+    /// * ...[writable, optional] Bookkeeping accounts of each order, looked up by key as
+    ///   for `CompleteSwap`: user stats PDAs, the protocol and pair stats PDAs, a maker or
+    ///   rent payer and its order index PDA (closing a completed order), registry pages,
+    ///   fill receipt PDAs (paid for by the settler, with the system program) and trade
+    ///   history PDAs
+    #[account(0, signer, name = "settler", desc = "Settler (anyone)")]
+    #[account(1, name = "token_program", desc = "Token program of every mint in the ring")]
+    #[account(2, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause and the trade guards")]
+    SettleRing,
+
+    /// Turn a fixed order into a hash-time-locked order for cross-chain swaps. The taker
+    /// can `ClaimHtlc` by revealing the preimage of `hashlock` until `timeout`; afterwards
+    /// the maker can `CloseOrder` to refund the escrow.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    StartHtlc { hashlock: [u8; 32], timeout: i64 },
+
+    /// Claim an HTLC order's escrow by revealing the hashlock preimage
+    /// Accounts:
+    /// * [signer] Taker (any signer if the order is open)
+    /// * [writable] Order PDA account
+    /// * [writable] Taker's receiving token account
+    /// * [writable] Program's escrow token account
+    /// * [] Token program
+    /// * [] Maker mint
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * ...[writable, optional] Bookkeeping accounts, looked up by key as for `CompleteSwap`:
+    ///   the maker's and taker's user stats PDAs, the protocol and pair stats PDAs, the
+    ///   maker or rent payer and the maker's order index PDA (closing a completed order),
+    ///   the registry page listing the order, the fill receipt PDA (paid for by the signer,
+    ///   with the system program) and the trade history PDA
+    #[account(0, signer, name = "taker", desc = "Taker (any signer if the order is open)")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "taker_receiving_token_account", desc = "Taker's receiving token account")]
+    #[account(3, writable, name = "escrow", desc = "Program's escrow token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "maker_mint", desc = "Maker mint")]
+    #[account(6, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(7, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    ClaimHtlc { preimage: [u8; 32] },
+
+    /// Add a vesting schedule: after a full `CompleteSwap` the maker's tokens stay in
+    /// escrow and vest linearly to the taker over `duration` seconds, with nothing
+    /// claimable before `cliff` seconds have passed.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    SetVesting { cliff: i64, duration: i64 },
+
+    /// Claim the maker tokens vested so far on a filled vesting order
+    /// Accounts:
+    /// * [signer] Taker who filled the order
+    /// * [writable] Order PDA account
+    /// * [writable] Taker's receiving token account
+    /// * [writable] Program's escrow token account
+    /// * [] Token program
+    /// * [] Maker mint
+    #[account(0, signer, name = "taker", desc = "Taker who filled the order")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "taker_receiving_token_account", desc = "Taker's receiving token account")]
+    #[account(3, writable, name = "escrow", desc = "Program's escrow token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "maker_mint", desc = "Maker mint")]
+    ClaimVested,
+
+    /// Settle a dual-escrow order in `tranches` equal parts, one every `interval` seconds
+    /// after the taker's `DepositTakerSide`, instead of through `CompleteSwap`
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    SetStreaming { tranches: u16, interval: i64 },
+
+    /// Release every tranche of a streaming order that has come due, moving the matching
+    /// share of both escrows to the counterparties. Callable by the maker or the taker.
+    /// Accounts:
+    /// * [signer] Maker or accepted taker
+    /// * [writable] Order PDA account
+    /// * [writable] Program's maker-mint escrow token account
+    /// * [writable] Program's taker-mint escrow token account
+    /// * [writable] Maker's receiving token account
+    /// * [writable] Taker's receiving token account
+    /// * [] Token program
+    /// * [] Maker mint
+    /// * [] Taker mint
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * ...[writable, optional] Bookkeeping accounts, looked up by key as for `CompleteSwap`:
+    ///   the maker's and taker's user stats PDAs, the protocol and pair stats PDAs, the
+    ///   maker or rent payer and the maker's order index PDA (closing a completed order),
+    ///   the registry page listing the order, the fill receipt PDA (paid for by the signer,
+    ///   with the system program) and the trade history PDA
+    #[account(0, signer, name = "maker_or_accepted_taker", desc = "Maker or accepted taker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "maker_escrow", desc = "Program's maker-mint escrow token account")]
+    #[account(3, writable, name = "taker_escrow", desc = "Program's taker-mint escrow token account")]
+    #[account(4, writable, name = "maker_receiving_token_account", desc = "Maker's receiving token account")]
+    #[account(5, writable, name = "taker_receiving_token_account", desc = "Taker's receiving token account")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    #[account(7, name = "maker_mint", desc = "Maker mint")]
+    #[account(8, name = "taker_mint", desc = "Taker mint")]
+    #[account(9, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(10, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    SettleTranche,
+
+    /// Make an order recur: after each full fill, `RenewOrder` can reset it to its current
+    /// amounts every `interval` seconds, up to `renewals` times. The maker funds renewals by
+    /// approving the order PDA as delegate on their maker-mint token account.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    SetRecurrence { interval: i64, renewals: u16 },
+
+    /// Permissionless crank that reopens a filled recurring order, pulling the next maker
+    /// amount from the maker's delegated token account into escrow
+    /// Accounts:
+    /// * [signer] Caller (anyone)
+    /// * [writable] Order PDA account
+    /// * [writable] Maker's token account (order PDA approved as delegate)
     /// * [writable] Program's escrow token account
     /// * [] Token program
-    /// * [] Token Authority PDA
-    CompleteSwap,
+    /// * [] Maker mint
+    #[account(0, signer, name = "caller", desc = "Caller (anyone)")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "maker_token_account", desc = "Maker's token account (order PDA approved as delegate)")]
+    #[account(3, writable, name = "escrow", desc = "Program's escrow token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "maker_mint", desc = "Maker mint")]
+    RenewOrder,
+
+    /// Create a new order with a fresh `order_id`, repeating an existing (e.g. completed)
+    /// order's terms: taker, mints, amounts, `requires_confirmation`, `all_or_none` and
+    /// memo. The amounts are those recorded on the source, which partial fills leave with
+    /// only what remained of them, so `maker_amount` and `taker_amount` can override
+    /// either. A UI-amount source's amounts are converted to what they are worth now, the
+    /// clone settling raw amounts. An order closed on its last fill is cloned from one of
+    /// its fill receipts instead, which records its maker, taker, mints and the amounts of
+    /// that fill but not its flags or memo, so those start unset; its clone can also be
+    /// left open to any taker.
+    /// The maker amount is moved into the new escrow.
+    /// Accounts:
+    /// * ...The first 12 `InitializeOrder` accounts for the new order
+    /// * [] Source order account, or a fill receipt PDA of it
+    /// * [] Blocked-mint PDAs, the maker's order index PDA and a registry page, as for
+    ///   `InitializeOrder`
+    CloneOrder {
+        order_id: u64,
+        maker_amount: Option<u64>,
+        taker_amount: Option<u64>,
+    },
+
+    /// Hand an open order, and the right to reclaim its escrow, to `new_maker`. The order
+    /// PDA keeps its original seeds; every later maker-only instruction checks `new_maker`.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    /// * [] Config PDA account (only to hand the order to its taker, or an order of a mint
+    ///   for itself, as allowed by `SetTradeGuards`)
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, optional, name = "config", desc = "Config PDA account (only to hand the order to its taker, or an order of a mint for itself, as allowed by `SetTradeGuards`)")]
+    TransferOrderAuthority { new_maker: Pubkey },
+
+    /// Let `delegate` call `ChangeOrderAmounts`, `ChangeTaker` and `CloseOrder` on the
+    /// maker's behalf. Refunds and rent still go to the maker. Passing the default pubkey
+    /// removes the delegate.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    SetDelegate { delegate: Pubkey },
+
+    /// Replace the order's allowed takers, who may fill it alongside the designated
+    /// `taker`. At most `MAX_ALLOWED_TAKERS`; an empty list clears them.
+    /// Accounts:
+    /// * [signer] Maker or delegate
+    /// * [writable] Order PDA account
+    /// * [] Config PDA account (only to allow the maker, or anyone on an order of a mint
+    ///   for itself, as allowed by `SetTradeGuards`)
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, optional, name = "config", desc = "Config PDA account (only to allow the maker, or anyone on an order of a mint for itself, as allowed by `SetTradeGuards`)")]
+    SetAllowedTakers { takers: Vec<Pubkey> },
+
+    /// Set the root of a Merkle allowlist of takers, who fill by passing a proof to
+    /// `CompleteSwap`. Build roots and proofs with `merkle::merkle_root` and
+    /// `merkle::merkle_proof`. An all-zero root removes the allowlist.
+    /// Accounts:
+    /// * [signer] Maker or delegate
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    SetTakerMerkleRoot { root: [u8; 32] },
+
+    /// Require takers to hold at least `min_balance` of `gate_mint` (e.g. a membership
+    /// NFT) to fill the order. The default pubkey removes the gate.
+    /// Accounts:
+    /// * [signer] Maker or delegate
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    SetTokenGate { gate_mint: Pubkey, min_balance: u64 },
 
-    /// Close order and reclaim rent
+    /// Require fills to be committed with `CommitFill` and revealed with `RevealFill`
+    /// in a later slot, so attractive orders cannot be sniped from the mempool.
     /// Accounts:
-    /// * [signer] Order authority (maker if incomplete, either party if complete)
+    /// * [signer] Maker or delegate
     /// * [writable] Order PDA account
-    /// * [writable] Rent receiver
-    /// * [writable, optional] Program's escrow token account
-    /// * [writable, optional] Maker's token account (refund)
-    /// * [optional] Token program
-    /// * [optional] Token Authority PDA
-    CloseOrder,
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    SetCommitReveal { enabled: bool },
+
+    /// Reserve a commit-reveal order for `COMMIT_REVEAL_WINDOW_SLOTS` with
+    /// `validation::fill_commitment(taker, salt)`
+    /// Accounts:
+    /// * [signer] Committer
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "committer", desc = "Committer")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    CommitFill { commitment: [u8; 32] },
+
+    /// Reveal the salt of a live commitment and complete the swap as in `CompleteSwap`
+    /// Accounts:
+    /// * ...`CompleteSwap` accounts, with the committed taker as signer
+    RevealFill {
+        salt: [u8; 32],
+        fill_amount: u64,
+        max_taker_amount: u64,
+        min_maker_amount: u64,
+    },
+
+    /// Turn a fixed order into a secret claim ("gift link"): the taker who presents the
+    /// preimage of `hashlock` with `ClaimSecret` receives the escrow, paying `taker_amount`
+    /// (0 = free) of the taker mint. The order needs a designated taker, typically a fresh
+    /// keypair handed over in the link with the preimage, since a revealed preimage alone
+    /// could be replayed by anyone. The maker can close the order at any time before it is
+    /// claimed.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    StartSecretClaim {
+        hashlock: [u8; 32],
+        taker_amount: u64,
+    },
+
+    /// Claim a secret-claim order by revealing the preimage of its hashlock, signed by the
+    /// order's taker.
+    /// Accounts:
+    /// * [signer] Claimer (the order's taker)
+    /// * [writable] Order PDA account
+    /// * [writable] Claimer's receiving token account
+    /// * [writable] Program's escrow token account
+    /// * [] Token program
+    /// * [] Maker mint
+    /// * [] Blocked-mint PDA of the maker mint (need not exist)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * [writable] Claimer's sending token account (if `taker_amount` is non-zero)
+    /// * [writable] Maker's receiving token account (if `taker_amount` is non-zero)
+    /// * [] Taker mint (if `taker_amount` is non-zero)
+    /// * ...[writable, optional] Bookkeeping accounts, looked up by key as for `CompleteSwap`:
+    ///   the maker's and taker's user stats PDAs, the protocol and pair stats PDAs, the
+    ///   maker or rent payer and the maker's order index PDA (closing a completed order),
+    ///   the registry page listing the order, the fill receipt PDA (paid for by the signer,
+    ///   with the system program) and the trade history PDA
+    #[account(0, signer, name = "claimer", desc = "Claimer (the order's taker)")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "claimer_receiving_token_account", desc = "Claimer's receiving token account")]
+    #[account(3, writable, name = "escrow", desc = "Program's escrow token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "maker_mint", desc = "Maker mint")]
+    #[account(6, name = "maker_mint_blocked", desc = "Blocked-mint PDA of the maker mint (need not exist)")]
+    #[account(7, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    #[account(8, writable, optional, name = "claimer_sending_token_account", desc = "Claimer's sending token account (if `taker_amount` is non-zero)")]
+    #[account(9, writable, optional, name = "maker_receiving_token_account", desc = "Maker's receiving token account (if `taker_amount` is non-zero)")]
+    #[account(10, optional, name = "taker_mint", desc = "Taker mint (if `taker_amount` is non-zero)")]
+    ClaimSecret { preimage: [u8; 32] },
+
+    /// Pay `referral_bps` (at most `MAX_REFERRAL_BPS`) of each fill's maker side to the
+    /// referrer named here, whose token account `CompleteSwap` then requires. Setting 0
+    /// clears the referrer.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    /// * [] Referrer wallet (if `referral_bps` is non-zero)
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, optional, name = "referrer", desc = "Referrer wallet (if `referral_bps` is non-zero)")]
+    SetReferralFee { referral_bps: u16 },
+
+    /// Create the program config PDA (`[b"config"]`) with the signer as admin. Only the
+    /// program's upgrade authority can initialize it.
+    /// Accounts:
+    /// * [signer, writable] Upgrade authority (admin and rent payer)
+    /// * [writable] Config PDA account
+    /// * [] Program data account
+    /// * [] System program
+    #[account(0, writable, signer, name = "upgrade_authority", desc = "Upgrade authority (admin and rent payer)")]
+    #[account(1, writable, name = "config", desc = "Config PDA account")]
+    #[account(2, name = "program_data_account", desc = "Program data account")]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitializeConfig { fee_bps: u16, fee_collector: Pubkey },
+
+    /// Change the protocol fee and the owner treasury withdrawals are paid to.
+    /// A config written before its later fields were added is read with them zeroed, and
+    /// grows to the current layout the first time the admin changes it, with the admin
+    /// (then writable) paying the extra rent through the system program.
+    /// Accounts:
+    /// * [signer] Admin
+    /// * [writable] Config PDA account
+    /// * [] System program (only to grow an older config)
+    #[account(0, signer, name = "admin", desc = "Admin")]
+    #[account(1, writable, name = "config", desc = "Config PDA account")]
+    UpdateConfig { fee_bps: u16, fee_collector: Pubkey },
+
+    /// Exempt `account` from protocol fees on orders it makes by creating its
+    /// `[b"fee_exempt", account]` PDA
+    /// Accounts:
+    /// * [signer, writable] Admin (rent payer)
+    /// * [] Config PDA account
+    /// * [writable] Fee-exemption PDA account
+    /// * [] System program
+    #[account(0, writable, signer, name = "admin", desc = "Admin (rent payer)")]
+    #[account(1, name = "config", desc = "Config PDA account")]
+    #[account(2, writable, name = "fee_exemption", desc = "Fee-exemption PDA account")]
+    #[account(3, name = "system_program", desc = "System program")]
+    AddFeeExemption { account: Pubkey },
+
+    /// Remove a fee exemption, returning its rent to the admin
+    /// Accounts:
+    /// * [signer, writable] Admin
+    /// * [] Config PDA account
+    /// * [writable] Fee-exemption PDA account
+    #[account(0, writable, signer, name = "admin", desc = "Admin")]
+    #[account(1, name = "config", desc = "Config PDA account")]
+    #[account(2, writable, name = "fee_exemption", desc = "Fee-exemption PDA account")]
+    RemoveFeeExemption { account: Pubkey },
+
+    /// Charge protocol fees in `fee_mint`, paid by the taker alongside each fill and
+    /// converted from the taker side through the taker mint's fee feed. The default
+    /// pubkey goes back to skimming the taker side.
+    /// Accounts:
+    /// * [signer] Admin
+    /// * [writable] Config PDA account
+    /// * [] Fee mint (unless `fee_mint` is the default pubkey)
+    /// * [] System program (only to grow an older config, as for `UpdateConfig`)
+    #[account(0, signer, name = "admin", desc = "Admin")]
+    #[account(1, writable, name = "config", desc = "Config PDA account")]
+    #[account(2, name = "fee_mint", desc = "Fee mint (unless `fee_mint` is the default pubkey)")]
+    SetFeeMint { fee_mint: Pubkey },
+
+    /// Register the Pyth feed pricing a traded mint in the fee mint, creating or updating
+    /// its `[b"fee_feed", mint]` PDA
+    /// Accounts:
+    /// * [signer, writable] Admin (rent payer)
+    /// * [] Config PDA account
+    /// * [writable] Fee feed PDA account
+    /// * [] Traded mint
+    /// * [] System program
+    #[account(0, writable, signer, name = "admin", desc = "Admin (rent payer)")]
+    #[account(1, name = "config", desc = "Config PDA account")]
+    #[account(2, writable, name = "fee_feed", desc = "Fee feed PDA account")]
+    #[account(3, name = "traded_mint", desc = "Traded mint")]
+    #[account(4, name = "system_program", desc = "System program")]
+    SetFeeFeed { price_feed: Pubkey },
+
+    /// Start tracking the fill volume of `user` in its `[b"user_stats", user]` PDA.
+    /// Tracked volume unlocks the config's fee tiers.
+    /// Accounts:
+    /// * [signer, writable] Rent payer
+    /// * [writable] User stats PDA account
+    /// * [] System program
+    #[account(0, writable, signer, name = "rent_payer", desc = "Rent payer")]
+    #[account(1, writable, name = "user_stats", desc = "User stats PDA account")]
+    #[account(2, name = "system_program", desc = "System program")]
+    InitializeUserStats { user: Pubkey },
+
+    /// Replace the volume-tiered fee schedule. Tiers must be ordered by strictly
+    /// ascending, non-zero `min_volume`; an empty list charges everyone `fee_bps`.
+    /// Accounts:
+    /// * [signer] Admin
+    /// * [writable] Config PDA account
+    /// * [] System program (only to grow an older config, as for `UpdateConfig`)
+    #[account(0, signer, name = "admin", desc = "Admin")]
+    #[account(1, writable, name = "config", desc = "Config PDA account")]
+    SetFeeTiers { tiers: Vec<FeeTier> },
+
+    /// Create the `[b"treasury", mint]` token account PDA that protocol fees in `mint`
+    /// accumulate in, with the config PDA as its owner
+    /// Accounts:
+    /// * [signer, writable] Admin (rent payer)
+    /// * [] Config PDA account
+    /// * [writable] Treasury PDA account
+    /// * [] Mint
+    /// * [] Token program
+    /// * [] System program
+    #[account(0, writable, signer, name = "admin", desc = "Admin (rent payer)")]
+    #[account(1, name = "config", desc = "Config PDA account")]
+    #[account(2, writable, name = "treasury", desc = "Treasury PDA account")]
+    #[account(3, name = "mint", desc = "Mint")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "system_program", desc = "System program")]
+    InitializeTreasury,
+
+    /// Pay `amount` (0 for everything) out of a treasury to the fee collector
+    /// Accounts:
+    /// * [signer] Admin
+    /// * [] Config PDA account
+    /// * [writable] Treasury PDA account
+    /// * [writable] Fee collector's token account
+    /// * [] Token program
+    /// * [] Mint of the treasury
+    #[account(0, signer, name = "admin", desc = "Admin")]
+    #[account(1, name = "config", desc = "Config PDA account")]
+    #[account(2, writable, name = "treasury", desc = "Treasury PDA account")]
+    #[account(3, writable, name = "fee_collector_token_account", desc = "Fee collector's token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "mint", desc = "Mint of the treasury")]
+    WithdrawFees { amount: u64 },
+
+    /// Replace the order's memo with new zero-padded UTF-8 text
+    /// Accounts:
+    /// * [signer] Maker or delegate
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    UpdateOrderMetadata { memo: [u8; 64] },
+
+    /// Categorize the order with a zero-padded tag for indexers
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order PDA account
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    SetOrderTag { tag: [u8; 8] },
+
+    /// Upgrade an order written with an older layout (or none) to `ORDER_VERSION`,
+    /// growing the account and topping up its rent as needed. Anyone can migrate.
+    /// Accounts:
+    /// * [signer, writable] Payer of any extra rent
+    /// * [writable] Order PDA account
+    /// * [] System program
+    #[account(0, writable, signer, name = "payer", desc = "Payer of any extra rent")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, name = "system_program", desc = "System program")]
+    MigrateOrder,
+
+    /// Move `amount` lamports into the owner's temporary `[b"wsol", owner]` token account,
+    /// creating it on first use, and sync its balance. Lets a transaction spend native SOL
+    /// wherever a wSOL token account is expected; see `wrap_sol_instructions`.
+    /// Accounts:
+    /// * [signer, writable] Owner (funds the account and its rent)
+    /// * [writable] wSOL PDA account
+    /// * [] Native mint
+    /// * [] Token program (SPL Token)
+    /// * [] System program
+    #[account(0, writable, signer, name = "owner", desc = "Owner (funds the account and its rent)")]
+    #[account(1, writable, name = "wsol_account", desc = "wSOL PDA account")]
+    #[account(2, name = "native_mint", desc = "Native mint")]
+    #[account(3, name = "token_program", desc = "Token program (SPL Token)")]
+    #[account(4, name = "system_program", desc = "System program")]
+    WrapSol { amount: u64 },
+
+    /// Close the owner's temporary wSOL account, returning its balance and rent as SOL
+    /// Accounts:
+    /// * [signer, writable] Owner
+    /// * [writable] wSOL PDA account
+    /// * [] Token program (SPL Token)
+    #[account(0, writable, signer, name = "owner", desc = "Owner")]
+    #[account(1, writable, name = "wsol_account", desc = "wSOL PDA account")]
+    #[account(2, name = "token_program", desc = "Token program (SPL Token)")]
+    UnwrapSol,
+
+    /// Switch a fixed-price order between raw and UI amounts. The current amounts are
+    /// converted at today's rate, so the order keeps its price; from then on fills of a
+    /// UI-amount order pay and release the raw amounts that are worth its UI amounts at
+    /// fill time. Meant for Token-2022 interest-bearing mints, whose UI amounts drift from
+    /// raw ones; other mints convert one to one. Escrowed tokens the fills leave behind go
+    /// back to the maker on `CloseOrder`. Only `CompleteSwap` converts, so orders that
+    /// settle any other way (confirmations, counter-offers, HTLCs, secret claims, English
+    /// auctions and rings) refuse UI amounts.
+    /// Accounts:
+    /// * [signer] Maker or delegate
+    /// * [writable] Order PDA account
+    /// * [] Maker mint
+    /// * [] Taker mint
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, name = "maker_mint", desc = "Maker mint")]
+    #[account(3, name = "taker_mint", desc = "Taker mint")]
+    SetUiAmounts { ui_amounts: bool },
+
+    /// Initialize an order selling a single NFT: a mint with 0 decimals and a supply of 1.
+    /// The maker side is always 1 and fills are all-or-none, so the order carries none of
+    /// the fungible options (dual escrow, UI amounts, vesting). The taker side is either
+    /// another NFT, in which case `taker_amount` must be 1, or any fungible amount.
+    /// Metaplex programmable NFTs, recognised by their metadata account, are moved through
+    /// Token Metadata's `TransferV1` with the NFT's token records and rule set. The escrow
+    /// is created ahead of the transfer and stays frozen under Token Metadata after the
+    /// order closes. See `metadata::transfer_v1`.
+    /// Accounts:
+    /// * ...The first 12 `InitializeOrder` accounts, with the maker's NFT account and the
+    ///   order PDA's NFT account as the two maker mint token accounts
+    /// * [writable, signer, optional] Rent payer, as for `InitializeOrder`
+    /// * ...Programmable NFT accounts (programmable NFTs only): the Token Metadata program,
+    ///   the NFT's metadata and master edition, the token records of the sending and
+    ///   receiving accounts, the instructions sysvar, the ATA program and, for NFTs with a
+    ///   rule set, the auth rules program and rule set
+    /// * [] Blocked-mint PDAs, the maker's order index PDA and a registry page, as for
+    ///   `InitializeOrder`
+    InitializeNftOrder { order_id: u64, taker_amount: u64 },
+
+    /// Initialize a bundle order at `[b"bundle", maker, bundle_id]`: the maker escrows
+    /// `maker_amounts` of up to `MAX_BUNDLE_LEGS` mints and asks for `taker_amounts` of up
+    /// to as many others. `CompleteSwap` fills a bundle whole and `CloseOrder` refunds it.
+    /// The maker pays any transfer fee so each escrow holds its full amount.
+    /// Accounts:
+    /// * [signer] Maker (pays rent)
+    /// * [writable] Bundle PDA account (to be created)
+    /// * [] Taker (or the default pubkey for an open bundle)
+    /// * [] System program
+    /// * [] Config PDA account (need not exist), checked for a protocol pause and the
+    ///   trade guards, as for `InitializeOrder`
+    /// * ...For each maker amount: [writable] maker's token account, [writable] the bundle's
+    ///   vault (or ATA) for the mint, created if missing, [] mint, [] token program
+    /// * ...For each taker amount: [] mint
+    /// * ...Blocked-mint PDAs of every maker and taker mint (need not exist)
+    /// * ...Transfer hook accounts of the maker mints, as for `InitializeOrder`
+    #[account(0, signer, name = "maker", desc = "Maker (pays rent)")]
+    #[account(1, writable, name = "bundle", desc = "Bundle PDA account (to be created)")]
+    #[account(2, name = "taker", desc = "Taker (or the default pubkey for an open bundle)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    #[account(4, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause and the trade guards, as for `InitializeOrder`")]
+    InitializeBundle {
+        bundle_id: u64,
+        maker_amounts: Vec<u64>,
+        taker_amounts: Vec<u64>,
+    },
+
+    /// Toggle whether the order's taker mint is a Metaplex collection mint, so that any
+    /// NFT verified as part of the collection fills the taker side. `taker_amount` must be
+    /// 1 and the order a plain fixed-price order without a taker escrow; the maker then
+    /// receives the NFT in its ATA for that NFT's mint.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    /// * [] Collection mint (the order's taker mint)
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    #[account(2, name = "collection_mint", desc = "Collection mint (the order's taker mint)")]
+    SetTakerCollection { enabled: bool },
+
+    /// Restrict the order to Token-2022 token groups; the default pubkey lifts either
+    /// restriction. A `maker_group` is checked against the maker mint's group member
+    /// extension right away and recorded for takers to filter on. With a `taker_group`,
+    /// any member mint of the group fills the taker side in place of the taker mint, on
+    /// the same terms as `SetTakerCollection`, except that `taker_amount` is free. Member
+    /// mints must share the taker mint's decimals.
+    /// Accounts:
+    /// * [signer] Maker
+    /// * [writable] Order account
+    /// * [] Maker mint
+    #[account(0, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "order", desc = "Order account")]
+    #[account(2, name = "maker_mint", desc = "Maker mint")]
+    SetTokenGroups {
+        maker_group: Pubkey,
+        taker_group: Pubkey,
+    },
+
+    /// Initialize an order at `[b"cnft_order", maker, order_id]` selling a Bubblegum
+    /// compressed NFT for `taker_amount` of the taker mint. The NFT's leaf is transferred
+    /// to the order PDA, so `root`, `data_hash`, `creator_hash`, `nonce` and `index` are
+    /// the leaf's as an indexer reports them, with the proof nodes as the trailing
+    /// accounts. The number of proof nodes is recorded for later transfers; see
+    /// `bubblegum::transfer`.
+    /// Accounts:
+    /// * [writable, signer] Maker (pays rent, owns the leaf)
+    /// * [writable] Compressed order PDA account (to be created)
+    /// * [] Taker (or the default pubkey for an open order)
+    /// * [] Taker mint
+    /// * [] System program
+    /// * [] Bubblegum tree config PDA of the tree
+    /// * [] Leaf delegate (the maker if none)
+    /// * [writable] Merkle tree
+    /// * [] SPL Noop program
+    /// * [] SPL Account Compression program
+    /// * [] Bubblegum program
+    /// * [] Config PDA account (need not exist), checked for a protocol pause
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * ...Proof nodes of the leaf, below the tree's canopy
+    #[account(0, writable, signer, name = "maker", desc = "Maker (pays rent, owns the leaf)")]
+    #[account(1, writable, name = "compressed_order", desc = "Compressed order PDA account (to be created)")]
+    #[account(2, name = "taker", desc = "Taker (or the default pubkey for an open order)")]
+    #[account(3, name = "taker_mint", desc = "Taker mint")]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(5, name = "tree_config", desc = "Bubblegum tree config PDA of the tree")]
+    #[account(6, name = "leaf_delegate", desc = "Leaf delegate (the maker if none)")]
+    #[account(7, writable, name = "merkle_tree", desc = "Merkle tree")]
+    #[account(8, name = "spl_noop_program", desc = "SPL Noop program")]
+    #[account(9, name = "spl_account_compression_program", desc = "SPL Account Compression program")]
+    #[account(10, name = "bubblegum_program", desc = "Bubblegum program")]
+    #[account(11, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause")]
+    #[account(12, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    InitializeCompressedOrder {
+        order_id: u64,
+        taker_amount: u64,
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+        nonce: u64,
+        index: u32,
+    },
+
+    /// Fill a compressed NFT order: the taker pays `taker_amount`, less the protocol fee
+    /// at the config's base rate, and the order PDA transfers the leaf to the taker.
+    /// `root` is the tree's current root for the leaf, now owned by the order PDA.
+    /// Accounts:
+    /// * [writable, signer] Taker
+    /// * [writable] Compressed order PDA account
+    /// * [writable] Taker's sending token account
+    /// * [writable] Maker's receiving token account
+    /// * [] Taker mint
+    /// * [] Taker mint's token program
+    /// * [] Config PDA account (need not exist), checked for a protocol pause
+    /// * [writable] Treasury PDA of the taker mint (unused while no fee is set)
+    /// * [] Blocked-mint PDA of the taker mint (need not exist)
+    /// * [] Bubblegum tree config PDA of the tree
+    /// * [writable] Merkle tree
+    /// * [] SPL Noop program
+    /// * [] SPL Account Compression program
+    /// * [] Bubblegum program
+    /// * [] System program
+    /// * ...Proof nodes of the leaf, as many as at `InitializeCompressedOrder`
+    /// * ...Transfer hook accounts of the taker mint, as for `InitializeOrder`
+    /// * [] SPL Memo program (if `memo` is set)
+    #[account(0, writable, signer, name = "taker", desc = "Taker")]
+    #[account(1, writable, name = "compressed_order", desc = "Compressed order PDA account")]
+    #[account(2, writable, name = "taker_sending_token_account", desc = "Taker's sending token account")]
+    #[account(3, writable, name = "maker_receiving_token_account", desc = "Maker's receiving token account")]
+    #[account(4, name = "taker_mint", desc = "Taker mint")]
+    #[account(5, name = "taker_mint_token_program", desc = "Taker mint's token program")]
+    #[account(6, name = "config", desc = "Config PDA account (need not exist), checked for a protocol pause")]
+    #[account(7, writable, name = "treasury", desc = "Treasury PDA of the taker mint (unused while no fee is set)")]
+    #[account(8, name = "taker_mint_blocked", desc = "Blocked-mint PDA of the taker mint (need not exist)")]
+    #[account(9, name = "tree_config", desc = "Bubblegum tree config PDA of the tree")]
+    #[account(10, writable, name = "merkle_tree", desc = "Merkle tree")]
+    #[account(11, name = "spl_noop_program", desc = "SPL Noop program")]
+    #[account(12, name = "spl_account_compression_program", desc = "SPL Account Compression program")]
+    #[account(13, name = "bubblegum_program", desc = "Bubblegum program")]
+    #[account(14, name = "system_program", desc = "System program")]
+    CompleteCompressedSwap {
+        root: [u8; 32],
+        max_taker_amount: u64,
+        memo: Option<String>,
+    },
+
+    /// Close a compressed NFT order and reclaim its rent, transferring the NFT back to
+    /// the maker first if the order was never filled. `root` is then the tree's current
+    /// root for the leaf and is ignored otherwise.
+    /// Accounts:
+    /// * [writable, signer] Maker
+    /// * [writable] Compressed order PDA account
+    /// * ...Open orders only: the tree accounts and proof nodes, as for
+    ///   `CompleteCompressedSwap`
+    #[account(0, writable, signer, name = "maker", desc = "Maker")]
+    #[account(1, writable, name = "compressed_order", desc = "Compressed order PDA account")]
+    CloseCompressedOrder { root: [u8; 32] },
+
+    /// Bring an order written with an older layout up to `ORDER_VERSION` like
+    /// `MigrateOrder`, reallocating it in place with the maker paying the extra rent, and
+    /// set the optional fields given. The escrow is untouched, so an order gains the
+    /// fields added since it was created without being recreated. `None` leaves a field
+    /// as is; `expires_at` follows `UpdateExpiry` and `memo` `UpdateOrderMetadata`.
+    /// Accounts:
+    /// * [writable, signer] Maker (pays any extra rent)
+    /// * [writable] Order PDA account
+    /// * [] System program
+    #[account(0, writable, signer, name = "maker", desc = "Maker (pays any extra rent)")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, name = "system_program", desc = "System program")]
+    ExtendOrder {
+        expires_at: Option<i64>,
+        memo: Option<[u8; 64]>,
+        taker_merkle_root: Option<[u8; 32]>,
+    },
+
+    /// Close an open order whose expiry has passed, on anyone's behalf: the escrow is
+    /// refunded to the maker and both accounts are closed. The caller keeps
+    /// `REAP_BOUNTY_LAMPORTS` of the order's rent for the cleanup and the rest goes back
+    /// to whoever paid it. Orders holding a taker deposit or a claimable HTLC are left to
+    /// their own flows.
+    /// Accounts:
+    /// * [writable, signer] Caller (receives the bounty)
+    /// * [writable] Order PDA account
+    /// * [writable] Program's escrow token account (order PDA for native SOL)
+    /// * [writable] Maker's token account (refund; maker's wallet for native SOL)
+    /// * [] Token program
+    /// * [writable] Maker mint (collects fees withheld in the escrow before it closes)
+    /// * [writable] Rent destination, as for `CloseOrder`
+    /// * ...Transfer hook accounts of the maker mint, as for `InitializeOrder`
+    /// * [writable, optional] Maker's order index PDA, which the order is dropped from
+    /// * [writable, optional] Registry page listing the order, which it is dropped from
+    #[account(0, writable, signer, name = "caller", desc = "Caller (receives the bounty)")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    #[account(2, writable, name = "escrow", desc = "Program's escrow token account (order PDA for native SOL)")]
+    #[account(3, writable, name = "maker_token_account", desc = "Maker's token account (refund; maker's wallet for native SOL)")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, writable, name = "maker_mint", desc = "Maker mint (collects fees withheld in the escrow before it closes)")]
+    #[account(6, writable, name = "rent_destination", desc = "Rent destination, as for `CloseOrder`")]
+    ReapExpiredOrder,
+
+    /// Create up to `MAX_BATCH_ORDERS` orders for the same maker in one instruction, each
+    /// exactly as `InitializeOrder` would with its entry of `orders`.
+    /// Accounts:
+    /// * ...For each order, in the order of `orders`: the first 12 `InitializeOrder`
+    ///   accounts, followed by the order's taker-mint vault or ATA if it is dual-escrow.
+    ///   The maker is the same in every group.
+    /// * ...Accounts shared by every order: rent payer, transfer hook accounts, the
+    ///   Associated Token Account program, the blocked-mint PDAs of every mint, the
+    ///   maker's order index PDA and a registry page of every mint pair, as for
+    ///   `InitializeOrder`
+    InitializeOrders { orders: Vec<OrderParams> },
+
+    /// Close `count` of the maker's orders in one instruction, each as `CloseOrder` would
+    /// when called by the maker, for winding down many stale quotes at once. Orders holding
+    /// a taker deposit have to be closed on their own.
+    /// Accounts:
+    /// * [writable, signer] Maker (rent destination of the orders it paid for)
+    /// * ...For each order: [writable] order PDA account, [writable] program's escrow
+    ///   token account, [writable] maker's token account (refund), [writable] maker mint,
+    ///   [] token program
+    /// * ...Accounts shared by every order: rent payers of sponsored orders, transfer hook
+    ///   accounts and the maker's order index PDA, as for `CloseOrder`
+    #[account(0, writable, signer, name = "maker", desc = "Maker (rent destination of the orders it paid for)")]
+    CloseOrders { count: u8 },
+
+    /// Settle up to `MAX_BATCH_ORDERS` independent fills in one instruction, each exactly
+    /// as `CompleteSwap` would with its entry of `fills`, no allowlist proof and no memo.
+    /// Orders that take positional optional accounts (oracle price, token gate, fee mint,
+    /// referrer) settle through `CompleteSwap` instead.
+    /// Accounts:
+    /// * ...For each fill, in the order of `fills`: the first 19 `CompleteSwap` accounts.
+    ///   The taker is the same in every group.
+    /// * ...Accounts shared by every fill: transfer hook accounts, the maker wallets,
+    ///   System program and Associated Token Account program, as for `CompleteSwap`
+    CompleteSwaps { fills: Vec<FillParams> },
+
+    /// Reprice, retarget and re-expire an order in one call, so a maker never leaves it
+    /// half-updated between separate instructions. `None` leaves a field as is; the
+    /// amounts follow `ChangeOrderAmounts` (a missing one keeps its current value),
+    /// `new_taker` `ChangeTaker` and `expires_at` `UpdateExpiry`, which only the maker
+    /// may change.
+    /// Accounts:
+    /// * [signer] Maker or delegate
+    /// * [writable] Order PDA account
+    /// * ...Only when an amount changes: the remaining `ChangeOrderAmounts` accounts,
+    ///   without the registry page
+    /// * [] Config PDA account (for a new taker `ChangeTaker` would need it for, or ahead
+    ///   of a registry page)
+    /// * [writable, optional] Registry page listing the order, as for `ChangeOrderAmounts`
+    #[account(0, signer, name = "authority", desc = "Maker or delegate")]
+    #[account(1, writable, name = "order", desc = "Order PDA account")]
+    UpdateOrder {
+        new_maker_amount: Option<u64>,
+        new_taker_amount: Option<u64>,
+        new_taker: Option<Pubkey>,
+        expires_at: Option<i64>,
+    },
+
+    /// Pause or unpause the protocol. While paused, the instructions that open orders or
+    /// fill them (see `SwapInstruction::is_pausable`) fail, so an issue can be contained
+    /// while makers still close their orders and takers still claim what they are owed.
+    /// Accounts:
+    /// * [signer] Admin
+    /// * [writable] Config PDA account
+    /// * [] System program (only to grow an older config, as for `UpdateConfig`)
+    #[account(0, signer, name = "admin", desc = "Admin")]
+    #[account(1, writable, name = "config", desc = "Config PDA account")]
+    SetProtocolPaused { paused: bool },
+
+    /// Block `mint` by creating its `[b"blocked_mint", mint]` PDA. Orders can no longer be
+    /// opened or filled in a blocked mint; open ones can still be closed.
+    /// Accounts:
+    /// * [signer, writable] Admin (rent payer)
+    /// * [] Config PDA account
+    /// * [writable] Blocked-mint PDA account
+    /// * [] System program
+    #[account(0, writable, signer, name = "admin", desc = "Admin (rent payer)")]
+    #[account(1, name = "config", desc = "Config PDA account")]
+    #[account(2, writable, name = "blocked_mint", desc = "Blocked-mint PDA account")]
+    #[account(3, name = "system_program", desc = "System program")]
+    BlockMint { mint: Pubkey },
+
+    /// Unblock a mint, returning its blocked-mint PDA's rent to the admin
+    /// Accounts:
+    /// * [signer, writable] Admin
+    /// * [] Config PDA account
+    /// * [writable] Blocked-mint PDA account
+    #[account(0, writable, signer, name = "admin", desc = "Admin")]
+    #[account(1, name = "config", desc = "Config PDA account")]
+    #[account(2, writable, name = "blocked_mint", desc = "Blocked-mint PDA account")]
+    UnblockMint { mint: Pubkey },
+
+    /// Start tracking protocol-wide fill activity in the `[b"protocol_stats"]` PDA
+    /// Accounts:
+    /// * [signer, writable] Rent payer
+    /// * [writable] Protocol stats PDA account
+    /// * [] System program
+    #[account(0, writable, signer, name = "rent_payer", desc = "Rent payer")]
+    #[account(1, writable, name = "protocol_stats", desc = "Protocol stats PDA account")]
+    #[account(2, name = "system_program", desc = "System program")]
+    InitializeProtocolStats,
+
+    /// Start tracking the fill activity of orders selling `maker_mint` for `taker_mint` in
+    /// their `[b"pair_stats", maker_mint, taker_mint]` PDA
+    /// Accounts:
+    /// * [signer, writable] Rent payer
+    /// * [writable] Pair stats PDA account
+    /// * [] System program
+    #[account(0, writable, signer, name = "rent_payer", desc = "Rent payer")]
+    #[account(1, writable, name = "pair_stats", desc = "Pair stats PDA account")]
+    #[account(2, name = "system_program", desc = "System program")]
+    InitializePairStats {
+        maker_mint: Pubkey,
+        taker_mint: Pubkey,
+    },
+
+    /// Create page `page` of the order registry of orders selling `maker_mint` for
+    /// `taker_mint`, at `[b"registry", maker_mint, taker_mint, page]`, once the pages
+    /// before it are full. Orders otherwise create page 0, and any later page when they
+    /// are passed it with the full page before it.
+    /// Accounts:
+    /// * [signer, writable] Rent payer
+    /// * [writable] Registry page PDA account
+    /// * [] System program
+    /// * [writable] The pair's previous registry page, linked to the new one (pages
+    ///   after the first only)
+    #[account(0, writable, signer, name = "rent_payer", desc = "Rent payer")]
+    #[account(1, writable, name = "registry_page", desc = "Registry page PDA account")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, optional, name = "previous_page", desc = "The pair's previous registry page, linked to the new one (pages after the first only)")]
+    InitializeRegistryPage {
+        maker_mint: Pubkey,
+        taker_mint: Pubkey,
+        page: u32,
+    },
+
+    /// Start keeping the recent fills of orders selling `maker_mint` for `taker_mint` in
+    /// their `[b"trade_history", maker_mint, taker_mint]` PDA
+    /// Accounts:
+    /// * [signer, writable] Rent payer
+    /// * [writable] Trade history PDA account
+    /// * [] System program
+    #[account(0, writable, signer, name = "rent_payer", desc = "Rent payer")]
+    #[account(1, writable, name = "trade_history", desc = "Trade history PDA account")]
+    #[account(2, name = "system_program", desc = "System program")]
+    InitializeTradeHistory {
+        maker_mint: Pubkey,
+        taker_mint: Pubkey,
+    },
+
+    /// Allow or refuse orders whose taker is their maker (`allow_self_trade`) and orders
+    /// trading a mint for itself (`allow_same_mint`). Both are refused by default, as
+    /// they are almost always a mistake, but can be allowed for testing or for moving
+    /// tokens between a maker's own accounts. The guards apply wherever an order's
+    /// maker, taker or mints are set: when it is created, cloned or given a new taker or
+    /// maker, for every order kind.
+    /// Accounts:
+    /// * [signer] Admin
+    /// * [writable] Config PDA account
+    /// * [] System program (only to grow an older config, as for `UpdateConfig`)
+    #[account(0, signer, name = "admin", desc = "Admin")]
+    #[account(1, writable, name = "config", desc = "Config PDA account")]
+    SetTradeGuards {
+        allow_self_trade: bool,
+        allow_same_mint: bool,
+    },
+}
+
+/// `sha256("global:<instruction_name>")[..8]` of every instruction, indexed by its
+/// variant. Variants are only ever appended, so an entry never moves once added.
+pub const INSTRUCTION_DISCRIMINATORS: [[u8; 8]; 82] = [
+    [133, 110, 74, 175, 112, 159, 245, 159],  // initialize_order
+    [83, 202, 124, 249, 26, 87, 151, 68],     // change_order_amounts
+    [41, 213, 54, 203, 165, 249, 37, 166],    // change_taker
+    [23, 139, 223, 154, 218, 76, 29, 200],    // complete_swap
+    [90, 103, 209, 28, 7, 63, 168, 4],        // close_order
+    [118, 157, 62, 39, 239, 234, 231, 193],   // accept_order
+    [183, 168, 179, 117, 86, 243, 166, 195],  // confirm_swap
+    [19, 21, 18, 92, 245, 56, 189, 140],      // cancel_acceptance
+    [61, 70, 107, 218, 131, 161, 227, 247],   // propose_counter_offer
+    [94, 106, 245, 253, 116, 96, 27, 165],    // accept_counter_offer
+    [139, 65, 163, 161, 137, 195, 197, 238],  // deposit_taker_side
+    [106, 85, 110, 248, 191, 190, 94, 70],    // decline_order
+    [19, 158, 36, 189, 195, 85, 13, 119],     // pause_order
+    [168, 250, 110, 147, 162, 181, 228, 30],  // resume_order
+    [137, 72, 245, 243, 100, 25, 17, 25],     // update_expiry
+    [51, 18, 177, 159, 48, 184, 54, 247],     // start_dutch_auction
+    [108, 178, 151, 12, 95, 78, 146, 93],     // start_english_auction
+    [238, 77, 148, 91, 200, 151, 92, 146],    // place_bid
+    [246, 196, 183, 98, 222, 139, 46, 133],   // settle_auction
+    [12, 116, 225, 132, 142, 74, 167, 253],   // fill_quote
+    [189, 75, 36, 144, 201, 68, 8, 248],      // set_price_oracle
+    [221, 221, 98, 127, 37, 48, 23, 200],     // set_all_or_none
+    [53, 233, 120, 199, 4, 65, 41, 179],      // complete_swap_and_close
+    [73, 79, 212, 127, 252, 155, 63, 242],    // complete_swap_chain
+    [71, 83, 246, 73, 133, 59, 27, 40],       // settle_ring
+    [230, 141, 174, 250, 28, 75, 108, 141],   // start_htlc
+    [202, 87, 174, 207, 197, 145, 190, 145],  // claim_htlc
+    [134, 163, 250, 45, 240, 193, 207, 114],  // set_vesting
+    [208, 190, 166, 114, 203, 225, 140, 208], // claim_vested
+    [12, 189, 210, 35, 108, 20, 40, 240],     // set_streaming
+    [96, 183, 52, 67, 242, 58, 168, 157],     // settle_tranche
+    [182, 43, 253, 22, 37, 156, 171, 12],     // set_recurrence
+    [216, 180, 12, 76, 71, 44, 165, 151],     // renew_order
+    [167, 149, 233, 215, 196, 89, 56, 34],    // clone_order
+    [94, 187, 23, 218, 209, 252, 97, 48],     // transfer_order_authority
+    [242, 30, 46, 76, 108, 235, 128, 181],    // set_delegate
+    [197, 209, 19, 42, 89, 57, 35, 141],      // set_allowed_takers
+    [70, 187, 198, 241, 97, 74, 124, 0],      // set_taker_merkle_root
+    [181, 246, 120, 133, 255, 105, 150, 113], // set_token_gate
+    [53, 8, 229, 223, 15, 231, 241, 132],     // set_commit_reveal
+    [23, 228, 52, 204, 87, 86, 134, 84],      // commit_fill
+    [122, 16, 234, 229, 34, 36, 33, 202],     // reveal_fill
+    [185, 81, 237, 239, 112, 50, 40, 232],    // start_secret_claim
+    [44, 152, 169, 14, 92, 126, 62, 91],      // claim_secret
+    [137, 113, 52, 190, 253, 2, 170, 109],    // set_referral_fee
+    [208, 127, 21, 1, 194, 190, 196, 70],     // initialize_config
+    [29, 158, 252, 191, 10, 83, 219, 99],     // update_config
+    [189, 238, 101, 182, 238, 47, 93, 30],    // add_fee_exemption
+    [158, 59, 24, 139, 29, 141, 63, 15],      // remove_fee_exemption
+    [139, 120, 154, 192, 246, 163, 89, 121],  // set_fee_mint
+    [177, 13, 237, 29, 114, 64, 52, 46],      // set_fee_feed
+    [254, 243, 72, 98, 251, 130, 168, 213],   // initialize_user_stats
+    [162, 35, 72, 250, 39, 183, 30, 7],       // set_fee_tiers
+    [124, 186, 211, 195, 85, 165, 129, 166],  // initialize_treasury
+    [198, 212, 171, 109, 144, 215, 174, 89],  // withdraw_fees
+    [55, 62, 46, 74, 13, 72, 225, 241],       // update_order_metadata
+    [44, 155, 28, 130, 15, 203, 101, 244],    // set_order_tag
+    [177, 81, 189, 119, 98, 91, 63, 189],     // migrate_order
+    [47, 62, 155, 172, 131, 205, 37, 201],    // wrap_sol
+    [99, 40, 14, 105, 45, 107, 172, 201],     // unwrap_sol
+    [244, 134, 44, 183, 115, 253, 156, 195],  // set_ui_amounts
+    [43, 246, 56, 76, 134, 29, 28, 213],      // initialize_nft_order
+    [93, 76, 148, 51, 99, 41, 179, 234],      // initialize_bundle
+    [41, 234, 130, 150, 244, 137, 198, 41],   // set_taker_collection
+    [120, 64, 143, 20, 215, 8, 97, 70],       // set_token_groups
+    [138, 93, 53, 108, 244, 62, 106, 112],    // initialize_compressed_order
+    [97, 244, 240, 62, 131, 217, 157, 73],    // complete_compressed_swap
+    [108, 213, 37, 252, 232, 28, 18, 117],    // close_compressed_order
+    [76, 238, 246, 145, 251, 43, 70, 84],     // extend_order
+    [42, 138, 86, 22, 149, 197, 77, 31],      // reap_expired_order
+    [49, 216, 217, 244, 196, 188, 211, 5],    // initialize_orders
+    [109, 157, 58, 113, 188, 114, 165, 104],  // close_orders
+    [194, 63, 250, 139, 40, 218, 30, 126],    // complete_swaps
+    [54, 8, 208, 207, 34, 134, 239, 168],     // update_order
+    [47, 62, 75, 69, 166, 0, 147, 157],       // set_protocol_paused
+    [134, 16, 218, 145, 101, 112, 87, 239],   // block_mint
+    [173, 89, 130, 208, 118, 212, 218, 224],  // unblock_mint
+    [103, 193, 97, 212, 182, 219, 67, 37],    // initialize_protocol_stats
+    [226, 196, 76, 238, 138, 7, 85, 208],     // initialize_pair_stats
+    [100, 247, 43, 95, 252, 238, 255, 239],   // initialize_registry_page
+    [68, 136, 30, 55, 94, 224, 173, 159],     // initialize_trade_history
+    [35, 67, 165, 179, 149, 56, 239, 184],    // set_trade_guards
+];
+
+impl SwapInstruction {
+    /// Returns true for the instructions `SetProtocolPaused` blocks: those opening new
+    /// orders or filling them. Each reads the config PDA at a fixed position among its
+    /// accounts to check the pause. Claims, refunds and cancellations are never paused,
+    /// so whoever is owed an escrow can still take it out.
+    pub fn is_pausable(&self) -> bool {
+        matches!(
+            self,
+            Self::InitializeOrder { .. }
+                | Self::InitializeOrders { .. }
+                | Self::InitializeNftOrder { .. }
+                | Self::InitializeBundle { .. }
+                | Self::InitializeCompressedOrder { .. }
+                | Self::CloneOrder { .. }
+                | Self::CompleteSwap { .. }
+                | Self::CompleteSwaps { .. }
+                | Self::CompleteSwapAndClose { .. }
+                | Self::CompleteSwapChain { .. }
+                | Self::CompleteCompressedSwap { .. }
+                | Self::RevealFill { .. }
+                | Self::ConfirmSwap
+                | Self::AcceptCounterOffer
+                | Self::SettleAuction
+                | Self::SettleRing
+                | Self::FillQuote { .. }
+        )
+    }
+
+    /// Encodes the instruction as its 8-byte discriminator followed by the Borsh
+    /// encoding of its fields
+    pub fn pack(&self) -> Vec<u8> {
+        // Writing to a `Vec` cannot fail
+        let mut data = borsh::to_vec(self).unwrap();
+        let discriminator = INSTRUCTION_DISCRIMINATORS[data[0] as usize];
+        data.splice(..1, discriminator);
+        data
+    }
+
+    /// Decodes instruction data written by `pack`. Unknown discriminators are rejected
+    /// rather than misread as another instruction, and bytes after the fields are
+    /// ignored so clients can append fields a later version reads.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let (discriminator, fields) = data
+            .split_first_chunk::<8>()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let variant = INSTRUCTION_DISCRIMINATORS
+            .iter()
+            .position(|known| known == discriminator)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        let mut tagged = Vec::with_capacity(1 + fields.len());
+        tagged.push(variant as u8);
+        tagged.extend_from_slice(fields);
+        Self::deserialize(&mut tagged.as_slice()).map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+/// Accounts of an `InitializeOrder`, in instruction order, ending with the blocked-mint,
+/// order index and registry page PDAs. Optional accounts (taker-mint escrow, rent payer,
+/// transfer hook accounts) are inserted after the first 12, which end with the config PDA.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InitializeOrderAccounts {
+    pub maker: Pubkey,
+    pub order: Pubkey,
+    pub maker_token_account: Pubkey,
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub maker_mint: Pubkey,
+    pub taker_mint: Pubkey,
+    pub maker_token_program: Pubkey,
+    pub taker_token_program: Pubkey,
+    pub config: Pubkey,
+    pub maker_mint_blocked: Pubkey,
+    pub taker_mint_blocked: Pubkey,
+    pub maker_index: Pubkey,
+    pub registry_page: Pubkey,
+}
+
+impl InitializeOrderAccounts {
+    /// Derives the accounts of order `order_id`, funded from the maker's ATA into the
+    /// order's vault and listed in registry page 0; when page 0 is full, replace it with
+    /// the pair's last page and append the page after it
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        program_id: &Pubkey,
+        maker: &Pubkey,
+        taker: &Pubkey,
+        maker_mint: &Pubkey,
+        taker_mint: &Pubkey,
+        maker_token_program: &Pubkey,
+        taker_token_program: &Pubkey,
+        order_id: u64,
+    ) -> Result<Self, ProgramError> {
+        let (order, _) = get_order_pda(program_id, maker, maker_mint, taker_mint, order_id)?;
+        Ok(Self {
+            maker: *maker,
+            order,
+            maker_token_account: get_associated_token_address_with_program_id(
+                maker,
+                maker_mint,
+                maker_token_program,
+            ),
+            escrow: get_vault_pda(program_id, &order, maker_mint).0,
+            taker: *taker,
+            maker_mint: *maker_mint,
+            taker_mint: *taker_mint,
+            maker_token_program: *maker_token_program,
+            taker_token_program: *taker_token_program,
+            config: get_config_pda(program_id).0,
+            maker_mint_blocked: get_blocked_mint_pda(program_id, maker_mint).0,
+            taker_mint_blocked: get_blocked_mint_pda(program_id, taker_mint).0,
+            maker_index: get_maker_index_pda(program_id, maker).0,
+            registry_page: get_registry_page_pda(program_id, maker_mint, taker_mint, 0).0,
+        })
+    }
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.maker, true),
+            AccountMeta::new(self.order, false),
+            AccountMeta::new(self.maker_token_account, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.taker, false),
+            AccountMeta::new_readonly(self.maker_mint, false),
+            AccountMeta::new_readonly(self.taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(self.maker_token_program, false),
+            AccountMeta::new_readonly(self.taker_token_program, false),
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new_readonly(self.maker_mint_blocked, false),
+            AccountMeta::new_readonly(self.taker_mint_blocked, false),
+            AccountMeta::new(self.maker_index, false),
+            AccountMeta::new(self.registry_page, false),
+        ]
+    }
+}
+
+/// Accounts of a `CompleteSwap`, in instruction order. Optional accounts (oracle, token
+/// gate, fee mint, referrer, transfer hook and receiving-ATA accounts) are appended to
+/// the built instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CompleteSwapAccounts {
+    pub taker: Pubkey,
+    pub order: Pubkey,
+    pub maker_receiving_token_account: Pubkey,
+    pub taker_sending_token_account: Pubkey,
+    pub taker_receiving_token_account: Pubkey,
+    pub escrow: Pubkey,
+    pub maker_token_program: Pubkey,
+    pub taker_token_program: Pubkey,
+    pub maker_mint: Pubkey,
+    pub taker_mint: Pubkey,
+    pub config: Pubkey,
+    pub treasury: Pubkey,
+    pub maker_fee_exemption: Pubkey,
+    pub maker_stats: Pubkey,
+    pub taker_stats: Pubkey,
+    pub maker_mint_blocked: Pubkey,
+    pub taker_mint_blocked: Pubkey,
+    pub protocol_stats: Pubkey,
+    pub pair_stats: Pubkey,
+}
+
+impl CompleteSwapAccounts {
+    /// Derives the accounts for `taker` filling `order` with its ATAs, for an order
+    /// escrowed in its vault. Orders escrowed in an ATA set `escrow` afterwards.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        program_id: &Pubkey,
+        order: &Pubkey,
+        maker: &Pubkey,
+        taker: &Pubkey,
+        maker_mint: &Pubkey,
+        taker_mint: &Pubkey,
+        maker_token_program: &Pubkey,
+        taker_token_program: &Pubkey,
+    ) -> Self {
+        Self {
+            taker: *taker,
+            order: *order,
+            maker_receiving_token_account: get_associated_token_address_with_program_id(
+                maker,
+                taker_mint,
+                taker_token_program,
+            ),
+            taker_sending_token_account: get_associated_token_address_with_program_id(
+                taker,
+                taker_mint,
+                taker_token_program,
+            ),
+            taker_receiving_token_account: get_associated_token_address_with_program_id(
+                taker,
+                maker_mint,
+                maker_token_program,
+            ),
+            escrow: get_vault_pda(program_id, order, maker_mint).0,
+            maker_token_program: *maker_token_program,
+            taker_token_program: *taker_token_program,
+            maker_mint: *maker_mint,
+            taker_mint: *taker_mint,
+            config: get_config_pda(program_id).0,
+            treasury: get_treasury_pda(program_id, taker_mint).0,
+            maker_fee_exemption: get_fee_exemption_pda(program_id, maker).0,
+            maker_stats: get_user_stats_pda(program_id, maker).0,
+            taker_stats: get_user_stats_pda(program_id, taker).0,
+            maker_mint_blocked: get_blocked_mint_pda(program_id, maker_mint).0,
+            taker_mint_blocked: get_blocked_mint_pda(program_id, taker_mint).0,
+            protocol_stats: get_protocol_stats_pda(program_id).0,
+            pair_stats: get_pair_stats_pda(program_id, maker_mint, taker_mint).0,
+        }
+    }
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.taker, true),
+            AccountMeta::new(self.order, false),
+            AccountMeta::new(self.maker_receiving_token_account, false),
+            AccountMeta::new(self.taker_sending_token_account, false),
+            AccountMeta::new(self.taker_receiving_token_account, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.maker_token_program, false),
+            AccountMeta::new_readonly(self.taker_token_program, false),
+            AccountMeta::new_readonly(self.maker_mint, false),
+            AccountMeta::new_readonly(self.taker_mint, false),
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.treasury, false),
+            AccountMeta::new_readonly(self.maker_fee_exemption, false),
+            AccountMeta::new(self.maker_stats, false),
+            AccountMeta::new(self.taker_stats, false),
+            AccountMeta::new_readonly(self.maker_mint_blocked, false),
+            AccountMeta::new_readonly(self.taker_mint_blocked, false),
+            AccountMeta::new(self.protocol_stats, false),
+            AccountMeta::new(self.pair_stats, false),
+        ]
+    }
+}
+
+/// Accounts of a `CloseOrder` on an order that was never accepted, in instruction order
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CloseOrderAccounts {
+    pub authority: Pubkey,
+    pub order: Pubkey,
+    pub escrow: Pubkey,
+    pub maker_token_account: Pubkey,
+    pub token_program: Pubkey,
+    pub maker_mint: Pubkey,
+    pub rent_destination: Pubkey,
+    pub maker_index: Pubkey,
+}
+
+impl CloseOrderAccounts {
+    /// Derives the accounts for `authority` closing `order`, escrowed in its vault, with
+    /// the refund going to the maker's ATA. Sponsored orders set `rent_destination` to
+    /// their rent payer afterwards, and orders handed over by `TransferOrderAuthority`
+    /// set `maker_index` to the index of the maker that created them.
+    pub fn new(
+        program_id: &Pubkey,
+        order: &Pubkey,
+        authority: &Pubkey,
+        maker: &Pubkey,
+        maker_mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Self {
+        Self {
+            authority: *authority,
+            order: *order,
+            escrow: get_vault_pda(program_id, order, maker_mint).0,
+            maker_token_account: get_associated_token_address_with_program_id(
+                maker,
+                maker_mint,
+                token_program,
+            ),
+            token_program: *token_program,
+            maker_mint: *maker_mint,
+            rent_destination: *maker,
+            maker_index: get_maker_index_pda(program_id, maker).0,
+        }
+    }
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.order, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new(self.maker_token_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new(self.maker_mint, false),
+            AccountMeta::new(self.rent_destination, false),
+            AccountMeta::new(self.maker_index, false),
+        ]
+    }
+}
+
+/// Builds an `InitializeOrder` instruction creating the order described by `params`
+pub fn initialize_order(
+    program_id: &Pubkey,
+    accounts: &InitializeOrderAccounts,
+    params: &OrderParams,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &SwapInstruction::InitializeOrder {
+            order_id: params.order_id,
+            maker_amount: params.maker_amount,
+            taker_amount: params.taker_amount,
+            requires_confirmation: params.requires_confirmation,
+            dual_escrow: params.dual_escrow,
+            all_or_none: params.all_or_none,
+            memo: params.memo,
+        }
+        .pack(),
+        accounts.to_account_metas(),
+    )
+}
+
+/// Builds a `CompleteSwap` instruction filling the order as described by `fill`, with no
+/// allowlist proof or memo
+pub fn complete_swap(
+    program_id: &Pubkey,
+    accounts: &CompleteSwapAccounts,
+    fill: &FillParams,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &SwapInstruction::CompleteSwap {
+            fill_amount: fill.fill_amount,
+            max_taker_amount: fill.max_taker_amount,
+            min_maker_amount: fill.min_maker_amount,
+            taker_proof: vec![],
+            memo: None,
+        }
+        .pack(),
+        accounts.to_account_metas(),
+    )
+}
+
+/// Builds a `CloseOrder` instruction refunding and closing the order
+pub fn close_order(
+    program_id: &Pubkey,
+    accounts: &CloseOrderAccounts,
+    memo: Option<String>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &SwapInstruction::CloseOrder { memo }.pack(),
+        accounts.to_account_metas(),
+    )
+}
+
+/// Builds a `WrapSol` instruction funding `owner`'s temporary wSOL account with `amount`
+pub fn wrap_sol(program_id: &Pubkey, owner: &Pubkey, amount: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &SwapInstruction::WrapSol { amount }.pack(),
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(get_wsol_pda(program_id, owner).0, false),
+            AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Builds an `UnwrapSol` instruction closing `owner`'s temporary wSOL account
+pub fn unwrap_sol(program_id: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &SwapInstruction::UnwrapSol.pack(),
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(get_wsol_pda(program_id, owner).0, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+/// Brackets `instructions` with `WrapSol` and `UnwrapSol` so they can spend `amount` of
+/// `owner`'s SOL through the temporary wSOL account at `get_wsol_pda`, which the caller
+/// passes wherever the owner's wSOL token account is expected. Any wSOL left over or
+/// received by that account is unwrapped back to the owner at the end.
+pub fn wrap_sol_instructions(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    instructions: &[Instruction],
+) -> Vec<Instruction> {
+    let mut wrapped = Vec::with_capacity(instructions.len() + 2);
+    wrapped.push(wrap_sol(program_id, owner, amount));
+    wrapped.extend_from_slice(instructions);
+    wrapped.push(unwrap_sol(program_id, owner));
+    wrapped
 }
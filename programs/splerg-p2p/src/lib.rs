@@ -1,7 +1,13 @@
 #![allow(unexpected_cfgs)]
 
+pub mod bubblegum;
 pub mod error;
+pub mod event;
 pub mod instruction;
+pub mod merkle;
+pub mod metadata;
+pub mod offsets;
+pub mod oracle;
 pub mod processor;
 pub mod state;
 pub mod validation;
@@ -10,14 +16,13 @@ pub mod splerg_p2p {
     use crate::processor::Processor;
 
     use solana_program::{
-        account_info::AccountInfo, declare_id, entrypoint, entrypoint::ProgramResult,
-        pubkey::Pubkey,
+        account_info::AccountInfo, declare_id, entrypoint::ProgramResult, pubkey::Pubkey,
     };
 
     declare_id!("GKTd9AGFpPGNKK28ncHeGGuT7rBJLzPxNjCUPKn8Yik8");
 
     #[cfg(not(feature = "no-entrypoint"))]
-    entrypoint!(process_instruction);
+    solana_program::entrypoint!(process_instruction);
 
     pub fn process_instruction(
         program_id: &Pubkey,
@@ -0,0 +1,49 @@
+use solana_program::pubkey::Pubkey;
+
+use crate::validation::{merkle_leaf, merkle_parent};
+
+/// Builds every level of the allowlist tree, leaves first. A node without a sibling
+/// is carried up to the next level unchanged.
+fn merkle_levels(takers: &[Pubkey]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![takers.iter().map(merkle_leaf).collect::<Vec<_>>()];
+    while levels.last().map_or(0, Vec::len) > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => merkle_parent(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Returns the root to store with `SetTakerMerkleRoot` for an allowlist of `takers`
+pub fn merkle_root(takers: &[Pubkey]) -> [u8; 32] {
+    merkle_levels(takers)
+        .last()
+        .and_then(|level| level.first().copied())
+        .unwrap_or([0; 32])
+}
+
+/// Returns the proof `takers[index]` passes to `CompleteSwap`, or `None` if `index`
+/// is out of range
+pub fn merkle_proof(takers: &[Pubkey], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= takers.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut index = index;
+    for level in merkle_levels(takers).iter().filter(|level| level.len() > 1) {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    Some(proof)
+}
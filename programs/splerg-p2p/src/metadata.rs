@@ -0,0 +1,191 @@
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+/// Metaplex Token Metadata program, which holds the freeze authority of programmable NFTs
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Metaplex Token Auth Rules program, which owns the rule sets of programmable NFTs
+pub const AUTH_RULES_PROGRAM_ID: Pubkey = pubkey!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+
+/// `Key::MetadataV1`, the first byte of every metadata account
+const METADATA_V1_KEY: u8 = 4;
+
+/// `TokenStandard::ProgrammableNonFungible`
+const PROGRAMMABLE_NON_FUNGIBLE: u8 = 4;
+
+/// `MetadataInstruction::Transfer`
+const TRANSFER_INSTRUCTION: u8 = 49;
+
+/// Returns the metadata PDA of `mint`
+pub fn find_metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Returns the master edition PDA of `mint`
+pub fn find_edition_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+            b"edition",
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Returns the PDA recording the delegate and lock state of a programmable NFT's
+/// `token` account
+pub fn find_token_record_address(mint: &Pubkey, token: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+            b"token_record",
+            token.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Returns true if `metadata_info` is a Token Metadata account describing a programmable
+/// NFT. Only the fields up to `token_standard` are read.
+pub fn is_programmable(metadata_info: &AccountInfo) -> Result<bool, ProgramError> {
+    if !is_metadata(metadata_info) {
+        return Ok(false);
+    }
+    let data = metadata_info.data.borrow();
+    let offset = token_standard_offset(&data)?;
+    Ok(read_u8(&data, offset)? == 1 && read_u8(&data, offset + 1)? == PROGRAMMABLE_NON_FUNGIBLE)
+}
+
+/// Returns the collection mint `metadata_info` belongs to, if the collection's update
+/// authority has verified the membership
+pub fn verified_collection(metadata_info: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+    if !is_metadata(metadata_info) {
+        return Ok(None);
+    }
+    let data = metadata_info.data.borrow();
+    let mut offset = token_standard_offset(&data)?;
+    if read_u8(&data, offset)? == 1 {
+        offset += 1;
+    }
+    offset += 1;
+
+    // collection: Option<Collection { verified: bool, key: Pubkey }>
+    if read_u8(&data, offset)? != 1 || read_u8(&data, offset + 1)? != 1 {
+        return Ok(None);
+    }
+    let key = data
+        .get(offset + 2..offset + 34)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(Some(Pubkey::new_from_array(key.try_into().unwrap())))
+}
+
+fn is_metadata(metadata_info: &AccountInfo) -> bool {
+    *metadata_info.owner == TOKEN_METADATA_PROGRAM_ID
+        && metadata_info.data.borrow().first() == Some(&METADATA_V1_KEY)
+}
+
+/// Returns the offset of the `Option<TokenStandard>` in a metadata account
+fn token_standard_offset(data: &[u8]) -> Result<usize, ProgramError> {
+    // Layout: key (1), update_authority (32), mint (32), name, symbol and uri (u32 length
+    // + bytes each), seller_fee_basis_points (2), creators (Option<Vec<34-byte creator>>),
+    // primary_sale_happened (1), is_mutable (1), edition_nonce (Option<u8>),
+    // token_standard (Option<u8>), collection (Option<Collection>), ...
+    let mut offset = 1 + 32 + 32;
+    for _ in 0..3 {
+        offset += 4 + read_u32(data, offset)? as usize;
+    }
+    offset += 2;
+    if read_u8(data, offset)? == 1 {
+        offset += 4 + 34 * read_u32(data, offset + 1)? as usize;
+    }
+    offset += 1 + 1 + 1;
+    if read_u8(data, offset)? == 1 {
+        offset += 1;
+    }
+    Ok(offset + 1)
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, ProgramError> {
+    data.get(offset)
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Builds a Token Metadata `TransferV1` moving `amount` of a programmable NFT from
+/// `token`, owned by `token_owner`, to `destination_owner`'s `destination_token`, which
+/// the transfer creates as an ATA if needed. `authorization_rules` is the NFT's rule set,
+/// if it has one. Missing optional accounts are passed as the Token Metadata program.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_v1(
+    token: &Pubkey,
+    token_owner: &Pubkey,
+    destination_token: &Pubkey,
+    destination_owner: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    token_program: &Pubkey,
+    authorization_rules: Option<&Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let (auth_rules_program, auth_rules) = match authorization_rules {
+        Some(rules) => (AUTH_RULES_PROGRAM_ID, *rules),
+        None => (TOKEN_METADATA_PROGRAM_ID, TOKEN_METADATA_PROGRAM_ID),
+    };
+    // `TransferArgs::V1 { amount, authorization_data: None }`
+    let mut data = vec![TRANSFER_INSTRUCTION, 0];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(0);
+
+    Instruction {
+        program_id: TOKEN_METADATA_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*token, false),
+            AccountMeta::new_readonly(*token_owner, false),
+            AccountMeta::new(*destination_token, false),
+            AccountMeta::new_readonly(*destination_owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_metadata_address(mint), false),
+            AccountMeta::new_readonly(find_edition_address(mint), false),
+            AccountMeta::new(find_token_record_address(mint, token), false),
+            AccountMeta::new(find_token_record_address(mint, destination_token), false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(auth_rules_program, false),
+            AccountMeta::new_readonly(auth_rules, false),
+        ],
+        data,
+    }
+}
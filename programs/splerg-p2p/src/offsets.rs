@@ -0,0 +1,81 @@
+//! Byte offsets of the `SwapOrder` fields within an order account, for `memcmp` filters
+//! over `getProgramAccounts`. Fields are only ever appended to the layout, so these
+//! offsets hold for every order at `ORDER_VERSION` and never change once published.
+//! See `SwapOrder` for what each field holds.
+
+use crate::state::SwapOrder;
+
+pub const DISCRIMINATOR: usize = 0;
+pub const VERSION: usize = 8;
+pub const MAKER: usize = 9;
+pub const TAKER: usize = 41;
+pub const MAKER_TOKEN_MINT: usize = 73;
+pub const TAKER_TOKEN_MINT: usize = 105;
+pub const MAKER_AMOUNT: usize = 137;
+pub const TAKER_AMOUNT: usize = 145;
+pub const BUMP: usize = 153;
+pub const ORDER_ID: usize = 154;
+pub const STATUS: usize = 162;
+pub const REQUIRES_CONFIRMATION: usize = 163;
+pub const ACCEPTED_TAKER: usize = 164;
+pub const COUNTER_MAKER_AMOUNT: usize = 196;
+pub const COUNTER_TAKER_AMOUNT: usize = 204;
+pub const DUAL_ESCROW: usize = 212;
+pub const PAUSED: usize = 213;
+pub const EXPIRES_AT: usize = 214;
+pub const ORDER_TYPE: usize = 222;
+pub const AUCTION_START_TS: usize = 223;
+pub const AUCTION_END_TS: usize = 231;
+pub const FLOOR_TAKER_AMOUNT: usize = 239;
+pub const BEST_BID: usize = 247;
+pub const MAKER_DECIMALS: usize = 255;
+pub const TAKER_DECIMALS: usize = 256;
+pub const PRICE_FEED: usize = 257;
+pub const SPREAD_BPS: usize = 289;
+pub const ALL_OR_NONE: usize = 291;
+pub const HASHLOCK: usize = 292;
+pub const HTLC_TIMEOUT: usize = 324;
+pub const VESTING_CLIFF: usize = 332;
+pub const VESTING_DURATION: usize = 340;
+pub const VESTING_START_TS: usize = 348;
+pub const VESTING_TOTAL: usize = 356;
+pub const VESTING_CLAIMED: usize = 364;
+pub const TRANCHE_COUNT: usize = 372;
+pub const TRANCHE_INTERVAL: usize = 374;
+pub const TRANCHES_SETTLED: usize = 382;
+pub const STREAM_START_TS: usize = 384;
+pub const RECURRENCE_INTERVAL: usize = 392;
+pub const RECURRENCE_MAKER_AMOUNT: usize = 400;
+pub const RECURRENCE_TAKER_AMOUNT: usize = 408;
+pub const RENEWALS_REMAINING: usize = 416;
+pub const NEXT_RENEWAL_TS: usize = 418;
+pub const SEED_MAKER: usize = 426;
+pub const DELEGATE: usize = 458;
+pub const ALLOWED_TAKERS: usize = 490;
+pub const TAKER_MERKLE_ROOT: usize = 618;
+pub const GATE_MINT: usize = 650;
+pub const GATE_MIN_BALANCE: usize = 682;
+pub const COMMIT_REVEAL: usize = 690;
+pub const FILL_COMMITMENT: usize = 691;
+pub const COMMIT_SLOT: usize = 723;
+pub const REVEALED_TAKER: usize = 731;
+pub const REFERRAL_BPS: usize = 763;
+pub const MEMO: usize = 765;
+pub const TAG: usize = 829;
+pub const MAKER_AMOUNT_GROSS: usize = 837;
+pub const TAKER_AMOUNT_NET: usize = 845;
+pub const UI_AMOUNTS: usize = 853;
+pub const MAKER_MINT_RISK: usize = 854;
+pub const TAKER_MINT_RISK: usize = 855;
+pub const NFT: usize = 856;
+pub const PROGRAMMABLE: usize = 857;
+pub const TAKER_COLLECTION: usize = 858;
+pub const MAKER_GROUP: usize = 859;
+pub const TAKER_GROUP: usize = 891;
+pub const RENT_PAYER: usize = 923;
+pub const FILL_COUNT: usize = 955;
+pub const FILLED_AMOUNT: usize = 963;
+pub const REFERRER: usize = 971;
+
+// The last field ends where the order does
+const _: () = assert!(REFERRER + 32 == SwapOrder::LEN && TAG == SwapOrder::TAG_OFFSET);
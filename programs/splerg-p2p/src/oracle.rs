@@ -0,0 +1,135 @@
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey, pubkey::Pubkey,
+};
+
+use crate::{error::SwapError, state::SwapOrder};
+
+/// Pyth pull-oracle receiver program that owns `PriceUpdateV2` accounts
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MHxZ2KfeMw4eUJxmEfVBiSt6SJsVFW1M7");
+
+/// Anchor discriminator of `PriceUpdateV2` accounts
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Oldest price, in seconds, accepted at fill time
+pub const MAX_PRICE_AGE: i64 = 60;
+
+/// Widest confidence interval, in bps of the price, accepted at fill time
+pub const MAX_CONFIDENCE_BPS: u64 = 200;
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Price read from a Pyth `PriceUpdateV2` account: `price * 10^exponent` units of the
+/// quote asset per unit of the base asset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PythPrice {
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+/// Reads the price from a fully verified Pyth `PriceUpdateV2` account
+pub fn load_pyth_price(price_info: &AccountInfo) -> Result<PythPrice, ProgramError> {
+    if *price_info.owner != PYTH_RECEIVER_PROGRAM_ID {
+        return Err(SwapError::InvalidOracle.into());
+    }
+
+    let data = price_info.data.borrow();
+    // Layout: discriminator (8), write_authority (32), verification_level (1-2),
+    // feed_id (32), price (8), conf (8), exponent (4), publish_time (8), ...
+    if data.len() < 41 || data[..8] != PRICE_UPDATE_V2_DISCRIMINATOR {
+        return Err(SwapError::InvalidOracle.into());
+    }
+    // Only `VerificationLevel::Full` is trusted; `Partial` carries an extra byte
+    if data[40] != 1 {
+        return Err(SwapError::InvalidOracle.into());
+    }
+    let message = data
+        .get(41 + 32..41 + 32 + 28)
+        .ok_or(SwapError::InvalidOracle)?;
+
+    Ok(PythPrice {
+        price: i64::from_le_bytes(message[0..8].try_into().unwrap()),
+        conf: u64::from_le_bytes(message[8..16].try_into().unwrap()),
+        exponent: i32::from_le_bytes(message[16..20].try_into().unwrap()),
+        publish_time: i64::from_le_bytes(message[20..28].try_into().unwrap()),
+    })
+}
+
+/// Computes the taker amount owed for `maker_amount` of an oracle-priced order at unix
+/// time `now`, rounding in the maker's favour. The feed prices the maker mint in the
+/// taker mint.
+pub fn oracle_taker_amount(
+    order: &SwapOrder,
+    maker_amount: u64,
+    price: &PythPrice,
+    now: i64,
+) -> Result<u64, ProgramError> {
+    convert_at_price(
+        maker_amount,
+        price,
+        order.maker_decimals,
+        order.taker_decimals,
+        order.spread_bps,
+        now,
+    )
+}
+
+/// Converts a protocol fee of `fee` base units of a traded mint into the fee mint at
+/// unix time `now`, rounding in the protocol's favour. The feed prices the traded mint
+/// in the fee mint.
+pub fn oracle_fee_amount(
+    fee: u64,
+    price: &PythPrice,
+    mint_decimals: u8,
+    fee_mint_decimals: u8,
+    now: i64,
+) -> Result<u64, ProgramError> {
+    convert_at_price(fee, price, mint_decimals, fee_mint_decimals, 0, now)
+}
+
+/// Converts `amount` of the feed's base asset into its quote asset, marked up by
+/// `spread_bps` and rounded up
+fn convert_at_price(
+    amount: u64,
+    price: &PythPrice,
+    base_decimals: u8,
+    quote_decimals: u8,
+    spread_bps: u16,
+    now: i64,
+) -> Result<u64, ProgramError> {
+    if now.saturating_sub(price.publish_time) > MAX_PRICE_AGE {
+        return Err(SwapError::StalePrice.into());
+    }
+    if price.price <= 0 {
+        return Err(SwapError::InvalidOracle.into());
+    }
+    let price_value = price.price as u128;
+    if (price.conf as u128) * BPS_DENOMINATOR > price_value * MAX_CONFIDENCE_BPS as u128 {
+        return Err(SwapError::PriceConfidenceTooWide.into());
+    }
+
+    // quote = base * price * 10^(exponent + quote_decimals - base_decimals) * (1 + spread)
+    let scale = price.exponent + quote_decimals as i32 - base_decimals as i32;
+    let pow = 10u128
+        .checked_pow(scale.unsigned_abs())
+        .ok_or(SwapError::InvalidAmount)?;
+    let mut numerator = (amount as u128)
+        .checked_mul(price_value)
+        .and_then(|n| n.checked_mul(BPS_DENOMINATOR + spread_bps as u128))
+        .ok_or(SwapError::InvalidAmount)?;
+    let mut denominator = BPS_DENOMINATOR;
+    if scale >= 0 {
+        numerator = numerator.checked_mul(pow).ok_or(SwapError::InvalidAmount)?;
+    } else {
+        denominator = denominator
+            .checked_mul(pow)
+            .ok_or(SwapError::InvalidAmount)?;
+    }
+
+    let amount = numerator.div_ceil(denominator);
+    if amount == 0 {
+        return Err(SwapError::InvalidAmount.into());
+    }
+    u64::try_from(amount).map_err(|_| SwapError::InvalidAmount.into())
+}
@@ -0,0 +1,567 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::{OrderStatus, OrderType, SwapOrder},
+    validation::{
+        validate_account_token_program, validate_authority, validate_init_amounts,
+        validate_not_expired, validate_not_paused, validate_order_open, validate_order_pda,
+        validate_pair_not_blocked, validate_protocol_not_paused, validate_signer, validate_taker,
+        validate_token_account, validate_trade_guards,
+    },
+};
+
+use super::{
+    escrowed_taker_fee,
+    token::{
+        gross_amount, memo_if_required, token_balance, transfer_tokens, transfer_tokens_with_hook,
+        transfer_with_fee,
+    },
+    Processor,
+};
+
+impl Processor {
+    pub(super) fn process_accept_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        Self::deposit_taker_side(program_id, accounts, false)
+    }
+
+    pub(super) fn process_deposit_taker_side(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        Self::deposit_taker_side(program_id, accounts, true)
+    }
+
+    /// Moves the taker amount into the order's taker-mint escrow. `AcceptOrder` and
+    /// `DepositTakerSide` share this path; the latter is only valid for dual-escrow orders.
+    fn deposit_taker_side(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        dual_escrow: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let taker_sending_ata = next_account_info(account_info_iter)?;
+        let order_taker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_taker(taker_info, &order)?;
+        validate_order_open(&order)?;
+        validate_not_paused(&order)?;
+        validate_not_expired(&order)?;
+        // Escrowed taker deposits are fixed-price and raw; auctions and UI amounts settle
+        // through CompleteSwap
+        if order.order_type != OrderType::Fixed
+            || (dual_escrow && !order.dual_escrow)
+            || order.ui_amounts
+        {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(taker_sending_ata, taker_info.key, &order.taker_token_mint)?;
+        validate_token_account(
+            order_taker_token_ata,
+            order_account_info.key,
+            &order.taker_token_mint,
+        )?;
+
+        // The taker covers the transfer fee so the escrow holds the full taker amount
+        transfer_tokens(
+            token_program,
+            taker_sending_ata,
+            taker_mint_info,
+            order_taker_token_ata,
+            taker_info,
+            gross_amount(taker_mint_info, order.taker_amount)?,
+            order.taker_decimals,
+            &[],
+        )?;
+
+        order.status = OrderStatus::Accepted;
+        order.accepted_taker = *taker_info.key;
+        if order.is_streaming() {
+            order.stream_start_ts = Clock::get()?.unix_timestamp;
+        }
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_confirm_swap(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let order_taker_token_ata = next_account_info(account_info_iter)?;
+        let maker_receiving_ata = next_account_info(account_info_iter)?;
+        let taker_receiving_ata = next_account_info(account_info_iter)?;
+        let maker_token_program = next_account_info(account_info_iter)?;
+        let taker_token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let fee_account = next_account_info(account_info_iter)?;
+        let fee_exemption_info = next_account_info(account_info_iter)?;
+        let maker_stats_info = next_account_info(account_info_iter)?;
+
+        validate_protocol_not_paused(program_id, config_info)?;
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            &order.maker_token_mint,
+            &order.taker_token_mint,
+        )?;
+        if order.status != OrderStatus::Accepted {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        validate_trade_guards(
+            program_id,
+            Some(config_info),
+            &{ order.maker },
+            &{ order.accepted_taker },
+            &{ order.maker_token_mint },
+            &{ order.taker_token_mint },
+        )?;
+        check_spl_token_program_account(maker_token_program.key)?;
+        check_spl_token_program_account(taker_token_program.key)?;
+        validate_token_account(
+            order_maker_token_ata,
+            order_account_info.key,
+            &order.maker_token_mint,
+        )?;
+        validate_account_token_program(order_maker_token_ata, maker_token_program.key)?;
+        validate_token_account(
+            order_taker_token_ata,
+            order_account_info.key,
+            &order.taker_token_mint,
+        )?;
+        validate_account_token_program(order_taker_token_ata, taker_token_program.key)?;
+        validate_token_account(maker_receiving_ata, &order.maker, &order.taker_token_mint)?;
+        validate_token_account(
+            taker_receiving_ata,
+            &order.accepted_taker,
+            &order.maker_token_mint,
+        )?;
+
+        if token_balance(order_maker_token_ata)? < order.maker_amount
+            || token_balance(order_taker_token_ata)? < order.taker_amount
+        {
+            return Err(SwapError::InsufficientFunds.into());
+        }
+        let (maker_amount, taker_amount) = (order.maker_amount, order.taker_amount);
+        let fee = escrowed_taker_fee(
+            program_id,
+            &order,
+            config_info,
+            fee_exemption_info,
+            maker_stats_info,
+            fee_account,
+            taker_amount,
+        )?;
+
+        let fill_index = order.record_final_fill(taker_amount)?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        let order_id = order.order_id.to_le_bytes();
+        let signer_seeds: &[&[u8]] = &[
+            b"order",
+            order.seed_maker.as_ref(),
+            order.maker_token_mint.as_ref(),
+            order.taker_token_mint.as_ref(),
+            &order_id,
+            &[order.bump],
+        ];
+        transfer_with_fee(
+            taker_token_program,
+            order_taker_token_ata,
+            taker_mint_info,
+            maker_receiving_ata,
+            fee_account,
+            order_account_info,
+            accounts,
+            taker_amount,
+            order.taker_decimals,
+            fee,
+            &[signer_seeds],
+        )?;
+        transfer_tokens_with_hook(
+            maker_token_program,
+            order_maker_token_ata,
+            maker_mint_info,
+            taker_receiving_ata,
+            order_account_info,
+            accounts,
+            maker_amount,
+            order.maker_decimals,
+            &[signer_seeds],
+        )?;
+
+        Self::book_fill(
+            program_id,
+            &order,
+            order_account_info,
+            order_maker_token_ata,
+            (maker_token_program, taker_token_program.key),
+            maker_mint_info,
+            maker_info,
+            &order.accepted_taker,
+            fill_index,
+            maker_amount,
+            taker_amount,
+            accounts,
+        )
+    }
+
+    pub(super) fn process_cancel_acceptance(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let order_taker_token_ata = next_account_info(account_info_iter)?;
+        let taker_refund_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_signer(taker_info)?;
+        if !order.status.holds_taker_deposit() || order.accepted_taker != *taker_info.key {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        // A partly settled stream can only be wound down by closing the order
+        if order.tranches_settled > 0 {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(
+            order_taker_token_ata,
+            order_account_info.key,
+            &order.taker_token_mint,
+        )?;
+        validate_token_account(taker_refund_ata, taker_info.key, &order.taker_token_mint)?;
+
+        Self::refund_acceptance(
+            &order,
+            order_account_info,
+            order_taker_token_ata,
+            taker_refund_ata,
+            token_program,
+            taker_mint_info,
+            accounts,
+            None,
+        )?;
+
+        order.status = OrderStatus::Open;
+        order.accepted_taker = Pubkey::default();
+        order.counter_maker_amount = 0;
+        order.counter_taker_amount = 0;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_propose_counter_offer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_maker_amount: u64,
+        new_taker_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let taker_sending_ata = next_account_info(account_info_iter)?;
+        let order_taker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        // Open orders have no counterparty to negotiate with
+        if order.accepts_any_taker() || order.order_type != OrderType::Fixed {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        // Counter amounts are escrowed and settled raw
+        if order.ui_amounts {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        validate_taker(taker_info, &order)?;
+        validate_order_open(&order)?;
+        validate_not_paused(&order)?;
+        validate_not_expired(&order)?;
+        validate_init_amounts(new_maker_amount, new_taker_amount)?;
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            &order.maker_token_mint,
+            &order.taker_token_mint,
+        )?;
+        validate_trade_guards(
+            program_id,
+            Some(config_info),
+            &{ order.maker },
+            taker_info.key,
+            &{ order.maker_token_mint },
+            &{ order.taker_token_mint },
+        )?;
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(taker_sending_ata, taker_info.key, &order.taker_token_mint)?;
+        validate_token_account(
+            order_taker_token_ata,
+            order_account_info.key,
+            &order.taker_token_mint,
+        )?;
+        validate_account_token_program(order_taker_token_ata, token_program.key)?;
+
+        // As for AcceptOrder, the taker covers the transfer fee
+        transfer_tokens(
+            token_program,
+            taker_sending_ata,
+            taker_mint_info,
+            order_taker_token_ata,
+            taker_info,
+            gross_amount(taker_mint_info, new_taker_amount)?,
+            order.taker_decimals,
+            &[],
+        )?;
+
+        order.status = OrderStatus::CounterOffered;
+        order.accepted_taker = *taker_info.key;
+        order.counter_maker_amount = new_maker_amount;
+        order.counter_taker_amount = new_taker_amount;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_accept_counter_offer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let order_taker_token_ata = next_account_info(account_info_iter)?;
+        let maker_token_ata = next_account_info(account_info_iter)?;
+        let maker_receiving_ata = next_account_info(account_info_iter)?;
+        let taker_receiving_ata = next_account_info(account_info_iter)?;
+        let maker_token_program = next_account_info(account_info_iter)?;
+        let taker_token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let fee_account = next_account_info(account_info_iter)?;
+        let fee_exemption_info = next_account_info(account_info_iter)?;
+        let maker_stats_info = next_account_info(account_info_iter)?;
+
+        validate_protocol_not_paused(program_id, config_info)?;
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            &order.maker_token_mint,
+            &order.taker_token_mint,
+        )?;
+        if order.status != OrderStatus::CounterOffered {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        validate_trade_guards(
+            program_id,
+            Some(config_info),
+            &{ order.maker },
+            &{ order.accepted_taker },
+            &{ order.maker_token_mint },
+            &{ order.taker_token_mint },
+        )?;
+        check_spl_token_program_account(maker_token_program.key)?;
+        check_spl_token_program_account(taker_token_program.key)?;
+        validate_token_account(
+            order_maker_token_ata,
+            order_account_info.key,
+            &order.maker_token_mint,
+        )?;
+        validate_account_token_program(order_maker_token_ata, maker_token_program.key)?;
+        validate_token_account(
+            order_taker_token_ata,
+            order_account_info.key,
+            &order.taker_token_mint,
+        )?;
+        validate_account_token_program(order_taker_token_ata, taker_token_program.key)?;
+        validate_token_account(maker_token_ata, &order.maker, &order.maker_token_mint)?;
+        validate_token_account(maker_receiving_ata, &order.maker, &order.taker_token_mint)?;
+        validate_token_account(
+            taker_receiving_ata,
+            &order.accepted_taker,
+            &order.maker_token_mint,
+        )?;
+
+        // The order settles at the counter-offer's terms
+        let (maker_amount, taker_amount) = (order.counter_maker_amount, order.counter_taker_amount);
+        order.maker_amount = maker_amount;
+        order.taker_amount = taker_amount;
+        order.counter_maker_amount = 0;
+        order.counter_taker_amount = 0;
+        let fee = escrowed_taker_fee(
+            program_id,
+            &order,
+            config_info,
+            fee_exemption_info,
+            maker_stats_info,
+            fee_account,
+            taker_amount,
+        )?;
+        let fill_index = order.record_final_fill(taker_amount)?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        let order_id = order.order_id.to_le_bytes();
+        let signer_seeds: &[&[u8]] = &[
+            b"order",
+            order.seed_maker.as_ref(),
+            order.maker_token_mint.as_ref(),
+            order.taker_token_mint.as_ref(),
+            &order_id,
+            &[order.bump],
+        ];
+
+        // Bring the maker escrow in line with the proposed maker amount, the maker
+        // covering the transfer fee of a top-up
+        let escrowed = token_balance(order_maker_token_ata)?;
+        match maker_amount.cmp(&escrowed) {
+            std::cmp::Ordering::Greater => transfer_tokens(
+                maker_token_program,
+                maker_token_ata,
+                maker_mint_info,
+                order_maker_token_ata,
+                maker_info,
+                gross_amount(maker_mint_info, maker_amount - escrowed)?,
+                order.maker_decimals,
+                &[],
+            )?,
+            std::cmp::Ordering::Less => transfer_tokens(
+                maker_token_program,
+                order_maker_token_ata,
+                maker_mint_info,
+                maker_token_ata,
+                order_account_info,
+                escrowed - maker_amount,
+                order.maker_decimals,
+                &[signer_seeds],
+            )?,
+            std::cmp::Ordering::Equal => {}
+        }
+
+        if token_balance(order_maker_token_ata)? < maker_amount
+            || token_balance(order_taker_token_ata)? < taker_amount
+        {
+            return Err(SwapError::InsufficientFunds.into());
+        }
+
+        transfer_with_fee(
+            taker_token_program,
+            order_taker_token_ata,
+            taker_mint_info,
+            maker_receiving_ata,
+            fee_account,
+            order_account_info,
+            accounts,
+            taker_amount,
+            order.taker_decimals,
+            fee,
+            &[signer_seeds],
+        )?;
+        transfer_tokens_with_hook(
+            maker_token_program,
+            order_maker_token_ata,
+            maker_mint_info,
+            taker_receiving_ata,
+            order_account_info,
+            accounts,
+            maker_amount,
+            order.maker_decimals,
+            &[signer_seeds],
+        )?;
+
+        Self::book_fill(
+            program_id,
+            &order,
+            order_account_info,
+            order_maker_token_ata,
+            (maker_token_program, taker_token_program.key),
+            maker_mint_info,
+            maker_info,
+            &order.accepted_taker,
+            fill_index,
+            maker_amount,
+            taker_amount,
+            accounts,
+        )
+    }
+
+    /// Returns the accepted taker's escrowed tokens
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn refund_acceptance<'a>(
+        order: &SwapOrder,
+        order_account_info: &AccountInfo<'a>,
+        order_taker_token_ata: &AccountInfo<'a>,
+        taker_refund_ata: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        taker_mint_info: &AccountInfo<'a>,
+        hook_accounts: &[AccountInfo<'a>],
+        memo: Option<&str>,
+    ) -> ProgramResult {
+        let amount = token_balance(order_taker_token_ata)?;
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let order_id = order.order_id.to_le_bytes();
+        memo_if_required(taker_refund_ata, memo, hook_accounts)?;
+        transfer_tokens_with_hook(
+            token_program,
+            order_taker_token_ata,
+            taker_mint_info,
+            taker_refund_ata,
+            order_account_info,
+            hook_accounts,
+            amount,
+            order.taker_decimals,
+            &[&[
+                b"order",
+                order.seed_maker.as_ref(),
+                order.maker_token_mint.as_ref(),
+                order.taker_token_mint.as_ref(),
+                &order_id,
+                &[order.bump],
+            ]],
+        )
+    }
+}
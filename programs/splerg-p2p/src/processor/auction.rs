@@ -0,0 +1,286 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::OrderType,
+    validation::{
+        validate_authority, validate_not_expired, validate_not_paused, validate_order_open,
+        validate_order_pda, validate_pair_not_blocked, validate_protocol_not_paused,
+        validate_signer, validate_taker, validate_token_account,
+    },
+};
+
+use super::{
+    token::{token_balance, transfer_tokens},
+    Processor,
+};
+
+impl Processor {
+    pub(super) fn process_start_dutch_auction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        floor_taker_amount: u64,
+        duration: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if order.order_type != OrderType::Fixed
+            || order.requires_confirmation
+            || order.dual_escrow
+            || order.takes_any_member()
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        if floor_taker_amount == 0 || floor_taker_amount >= order.taker_amount || duration <= 0 {
+            return Err(SwapError::InvalidAmount.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        order.order_type = OrderType::DutchAuction;
+        order.auction_start_ts = now;
+        order.auction_end_ts = now.checked_add(duration).ok_or(SwapError::InvalidAmount)?;
+        order.floor_taker_amount = floor_taker_amount;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_start_english_auction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        duration: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        // Bids are escrowed in raw amounts and settle outside CompleteSwap
+        if order.order_type != OrderType::Fixed
+            || order.requires_confirmation
+            || order.dual_escrow
+            || order.ui_amounts
+            || order.takes_any_member()
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        if duration <= 0 {
+            return Err(SwapError::InvalidAmount.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        order.order_type = OrderType::EnglishAuction;
+        order.auction_start_ts = now;
+        order.auction_end_ts = now.checked_add(duration).ok_or(SwapError::InvalidAmount)?;
+        order.best_bid = 0;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_place_bid(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let bidder_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let bidder_sending_ata = next_account_info(account_info_iter)?;
+        let order_taker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        if order.order_type != OrderType::EnglishAuction {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        validate_taker(bidder_info, &order)?;
+        validate_order_open(&order)?;
+        validate_not_paused(&order)?;
+        validate_not_expired(&order)?;
+        if Clock::get()?.unix_timestamp >= order.auction_end_ts {
+            return Err(SwapError::AuctionEnded.into());
+        }
+        if amount < order.taker_amount || amount <= order.best_bid {
+            return Err(SwapError::BidTooLow.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(bidder_sending_ata, bidder_info.key, &order.taker_token_mint)?;
+        validate_token_account(
+            order_taker_token_ata,
+            order_account_info.key,
+            &order.taker_token_mint,
+        )?;
+
+        transfer_tokens(
+            token_program,
+            bidder_sending_ata,
+            taker_mint_info,
+            order_taker_token_ata,
+            bidder_info,
+            amount,
+            order.taker_decimals,
+            &[],
+        )?;
+
+        if order.best_bid > 0 {
+            let previous_bidder_ata = next_account_info(account_info_iter)?;
+            validate_token_account(
+                previous_bidder_ata,
+                &order.accepted_taker,
+                &order.taker_token_mint,
+            )?;
+
+            let order_id = order.order_id.to_le_bytes();
+            transfer_tokens(
+                token_program,
+                order_taker_token_ata,
+                taker_mint_info,
+                previous_bidder_ata,
+                order_account_info,
+                order.best_bid,
+                order.taker_decimals,
+                &[&[
+                    b"order",
+                    order.seed_maker.as_ref(),
+                    order.maker_token_mint.as_ref(),
+                    order.taker_token_mint.as_ref(),
+                    &order_id,
+                    &[order.bump],
+                ]],
+            )?;
+        }
+
+        order.accepted_taker = *bidder_info.key;
+        order.best_bid = amount;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_settle_auction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let caller_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let order_taker_token_ata = next_account_info(account_info_iter)?;
+        let maker_receiving_ata = next_account_info(account_info_iter)?;
+        let winner_receiving_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+
+        validate_signer(caller_info)?;
+        validate_protocol_not_paused(program_id, config_info)?;
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            &order.maker_token_mint,
+            &order.taker_token_mint,
+        )?;
+        if order.order_type != OrderType::EnglishAuction || order.best_bid == 0 {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        validate_order_open(&order)?;
+        if Clock::get()?.unix_timestamp < order.auction_end_ts {
+            return Err(SwapError::AuctionNotEnded.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(
+            order_maker_token_ata,
+            order_account_info.key,
+            &order.maker_token_mint,
+        )?;
+        validate_token_account(
+            order_taker_token_ata,
+            order_account_info.key,
+            &order.taker_token_mint,
+        )?;
+        validate_token_account(maker_receiving_ata, &order.maker, &order.taker_token_mint)?;
+        validate_token_account(
+            winner_receiving_ata,
+            &order.accepted_taker,
+            &order.maker_token_mint,
+        )?;
+
+        if token_balance(order_maker_token_ata)? < order.maker_amount {
+            return Err(SwapError::InsufficientFunds.into());
+        }
+
+        // Record the clearing price as the executed taker amount
+        let (maker_amount, taker_amount) = (order.maker_amount, order.best_bid);
+        order.taker_amount = taker_amount;
+        order.best_bid = 0;
+        let fill_index = order.record_final_fill(taker_amount)?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        let order_id = order.order_id.to_le_bytes();
+        let signer_seeds: &[&[u8]] = &[
+            b"order",
+            order.seed_maker.as_ref(),
+            order.maker_token_mint.as_ref(),
+            order.taker_token_mint.as_ref(),
+            &order_id,
+            &[order.bump],
+        ];
+
+        transfer_tokens(
+            token_program,
+            order_taker_token_ata,
+            taker_mint_info,
+            maker_receiving_ata,
+            order_account_info,
+            taker_amount,
+            order.taker_decimals,
+            &[signer_seeds],
+        )?;
+        transfer_tokens(
+            token_program,
+            order_maker_token_ata,
+            maker_mint_info,
+            winner_receiving_ata,
+            order_account_info,
+            maker_amount,
+            order.maker_decimals,
+            &[signer_seeds],
+        )?;
+
+        Self::book_fill(
+            program_id,
+            &order,
+            order_account_info,
+            order_maker_token_ata,
+            (token_program, token_program.key),
+            maker_mint_info,
+            caller_info,
+            &order.accepted_taker,
+            fill_index,
+            maker_amount,
+            taker_amount,
+            accounts,
+        )
+    }
+}
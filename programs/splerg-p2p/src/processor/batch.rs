@@ -0,0 +1,155 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::SwapError,
+    state::{FillParams, OrderParams, MAX_BATCH_ORDERS},
+    validation::{validate_order_pda, validate_signer},
+};
+
+use super::Processor;
+
+/// Accounts `InitializeOrder` takes before its optional ones
+const INIT_ACCOUNTS: usize = 12;
+
+/// Accounts of each order closed by `CloseOrders`
+const CLOSE_ACCOUNTS: usize = 5;
+
+/// Accounts `CompleteSwap` takes before its optional ones
+const SWAP_ACCOUNTS: usize = 19;
+
+impl Processor {
+    pub(super) fn process_initialize_orders(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        orders: &[OrderParams],
+    ) -> ProgramResult {
+        if orders.is_empty() {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        if orders.len() > MAX_BATCH_ORDERS {
+            return Err(SwapError::TooManyOrders.into());
+        }
+
+        // Split the accounts into one group per order and the shared tail
+        let mut groups = Vec::with_capacity(orders.len());
+        let mut rest = accounts;
+        for params in orders {
+            let len = INIT_ACCOUNTS + usize::from(params.dual_escrow);
+            if rest.len() < len {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+            let (group, tail) = rest.split_at(len);
+            groups.push(group);
+            rest = tail;
+        }
+        let maker = groups[0][0].key;
+
+        for (params, group) in orders.iter().zip(groups) {
+            if group[0].key != maker {
+                return Err(SwapError::UnauthorizedSigner.into());
+            }
+            let mut order_accounts = group.to_vec();
+            order_accounts.extend_from_slice(rest);
+            Self::process_initialize_order(
+                program_id,
+                &order_accounts,
+                params.order_id,
+                params.maker_amount,
+                params.taker_amount,
+                params.requires_confirmation,
+                params.dual_escrow,
+                params.all_or_none,
+                params.memo,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn process_close_orders(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        count: u8,
+    ) -> ProgramResult {
+        let (maker_info, rest) = accounts
+            .split_first()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        validate_signer(maker_info)?;
+        let groups_len = usize::from(count) * CLOSE_ACCOUNTS;
+        if count == 0 || rest.len() < groups_len {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let (groups, shared) = rest.split_at(groups_len);
+
+        for group in groups.chunks(CLOSE_ACCOUNTS) {
+            let [order_account_info, escrow, refund, mint, token_program] = group else {
+                unreachable!()
+            };
+            let (order, _) = validate_order_pda(program_id, order_account_info)?;
+            if order.holds_taker_deposit() {
+                return Err(SwapError::InvalidOrderState.into());
+            }
+            // Sponsored orders return their rent to a payer passed among the shared accounts
+            let rent_refund_account = order.rent_refund_account();
+            let rent_receiver = accounts
+                .iter()
+                .find(|account| *account.key == rent_refund_account)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+            let mut close_accounts = vec![
+                maker_info.clone(),
+                order_account_info.clone(),
+                escrow.clone(),
+                refund.clone(),
+                token_program.clone(),
+                mint.clone(),
+                rent_receiver.clone(),
+            ];
+            close_accounts.extend_from_slice(shared);
+            Self::process_close_order(program_id, &close_accounts, None)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn process_complete_swaps(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fills: &[FillParams],
+    ) -> ProgramResult {
+        if fills.is_empty() {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        if fills.len() > MAX_BATCH_ORDERS {
+            return Err(SwapError::TooManyOrders.into());
+        }
+        let groups_len = fills.len() * SWAP_ACCOUNTS;
+        if accounts.len() < groups_len {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let (groups, shared) = accounts.split_at(groups_len);
+        let taker = groups[0].key;
+
+        for (fill, group) in fills.iter().zip(groups.chunks(SWAP_ACCOUNTS)) {
+            if group[0].key != taker {
+                return Err(SwapError::UnauthorizedSigner.into());
+            }
+            let mut swap_accounts = group.to_vec();
+            swap_accounts.extend_from_slice(shared);
+            Self::process_complete_swap(
+                program_id,
+                &swap_accounts,
+                fill.fill_amount,
+                fill.max_taker_amount,
+                fill.min_maker_amount,
+                &[],
+                None,
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::SwapError,
+    state::{BundleLeg, BundleOrder, Discriminator, OrderStatus, MAX_BUNDLE_LEGS},
+    validation::{
+        get_bundle_pda, get_treasury_pda, load_bundle, load_config, validate_mints_not_blocked,
+        validate_not_frozen, validate_protocol_not_paused, validate_signer,
+        validate_system_program, validate_token_account, validate_token_mint,
+        validate_token_program, validate_trade_guards,
+    },
+};
+
+use super::{
+    init::{create_pda_account, validate_uninitialized},
+    token::{
+        close_token_account, create_escrow_if_missing, gross_amount, harvest_withheld_fees,
+        memo_if_required, mint_decimals, token_balance, transfer_tokens_with_hook,
+        transfer_with_fee,
+    },
+    Processor,
+};
+
+impl Processor {
+    pub(super) fn process_initialize_bundle(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        bundle_id: u64,
+        maker_amounts: &[u64],
+        taker_amounts: &[u64],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let bundle_info = next_account_info(account_info_iter)?;
+        let taker_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        validate_signer(maker_info)?;
+        validate_protocol_not_paused(program_id, config_info)?;
+        validate_system_program(system_program_info.key)?;
+        if maker_amounts.len() > MAX_BUNDLE_LEGS || taker_amounts.len() > MAX_BUNDLE_LEGS {
+            return Err(SwapError::TooManyBundleLegs.into());
+        }
+        if maker_amounts.is_empty()
+            || taker_amounts.is_empty()
+            || maker_amounts.contains(&0)
+            || taker_amounts.contains(&0)
+        {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        let (bundle_pda, bump) = get_bundle_pda(program_id, maker_info.key, bundle_id);
+        if bundle_pda != *bundle_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        validate_uninitialized(bundle_info, ProgramError::AccountAlreadyInitialized)?;
+
+        create_pda_account(
+            maker_info,
+            bundle_info,
+            system_program_info,
+            BundleOrder::space(maker_amounts.len(), taker_amounts.len()),
+            program_id,
+            &[
+                b"bundle",
+                maker_info.key.as_ref(),
+                &bundle_id.to_le_bytes(),
+                &[bump],
+            ],
+        )?;
+
+        let mut maker_legs = Vec::with_capacity(maker_amounts.len());
+        for &amount in maker_amounts {
+            let maker_token_account = next_account_info(account_info_iter)?;
+            let escrow_info = next_account_info(account_info_iter)?;
+            let mint_info = next_account_info(account_info_iter)?;
+            let token_program = next_account_info(account_info_iter)?;
+
+            validate_token_mint(mint_info)?;
+            validate_mints_not_blocked(program_id, accounts, &[mint_info.key])?;
+            // Each maker mint has its own escrow
+            if maker_legs
+                .iter()
+                .any(|leg: &BundleLeg| leg.mint == *mint_info.key)
+            {
+                return Err(SwapError::InvalidMint.into());
+            }
+            validate_token_program(mint_info, token_program.key)?;
+            validate_token_account(maker_token_account, maker_info.key, mint_info.key)?;
+            create_escrow_if_missing(
+                program_id,
+                maker_info,
+                escrow_info,
+                bundle_info,
+                mint_info,
+                system_program_info,
+                token_program,
+                accounts,
+            )?;
+            validate_token_account(escrow_info, bundle_info.key, mint_info.key)?;
+            validate_not_frozen(escrow_info)?;
+            let decimals = mint_decimals(mint_info)?;
+
+            // The maker covers the transfer fee so the escrow holds the full amount
+            transfer_tokens_with_hook(
+                token_program,
+                maker_token_account,
+                mint_info,
+                escrow_info,
+                maker_info,
+                accounts,
+                gross_amount(mint_info, amount)?,
+                decimals,
+                &[],
+            )?;
+            if token_balance(escrow_info)? < amount {
+                return Err(SwapError::InsufficientFunds.into());
+            }
+            maker_legs.push(BundleLeg {
+                mint: *mint_info.key,
+                amount,
+                decimals,
+            });
+        }
+
+        let mut taker_legs = Vec::with_capacity(taker_amounts.len());
+        for &amount in taker_amounts {
+            let mint_info = next_account_info(account_info_iter)?;
+            validate_token_mint(mint_info)?;
+            validate_mints_not_blocked(program_id, accounts, &[mint_info.key])?;
+            for maker_leg in &maker_legs {
+                validate_trade_guards(
+                    program_id,
+                    Some(config_info),
+                    maker_info.key,
+                    taker_info.key,
+                    &maker_leg.mint,
+                    mint_info.key,
+                )?;
+            }
+            taker_legs.push(BundleLeg {
+                mint: *mint_info.key,
+                amount,
+                decimals: mint_decimals(mint_info)?,
+            });
+        }
+
+        let bundle = BundleOrder {
+            discriminator: BundleOrder::DISCRIMINATOR,
+            maker: *maker_info.key,
+            taker: *taker_info.key,
+            bundle_id,
+            bump,
+            status: OrderStatus::Open,
+            maker_legs,
+            taker_legs,
+        };
+        bundle.serialize(&mut *bundle_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_complete_bundle(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fill_amount: u64,
+        memo: Option<&str>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let bundle_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        validate_signer(taker_info)?;
+        validate_protocol_not_paused(program_id, config_info)?;
+        let mut bundle = load_bundle(program_id, bundle_info)?;
+        if bundle.status != OrderStatus::Open {
+            return Err(SwapError::OrderNotOpen.into());
+        }
+        if !bundle.is_allowed_taker(taker_info.key) {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        // Bundles only ever fill whole
+        if fill_amount != 0 {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        let mints = bundle
+            .maker_legs
+            .iter()
+            .chain(&bundle.taker_legs)
+            .map(|leg| &leg.mint)
+            .collect::<Vec<_>>();
+        validate_mints_not_blocked(program_id, accounts, &mints)?;
+        let config = load_config(program_id, config_info)?;
+
+        for leg in &bundle.taker_legs {
+            let taker_token_account = next_account_info(account_info_iter)?;
+            let maker_token_account = next_account_info(account_info_iter)?;
+            let fee_account = next_account_info(account_info_iter)?;
+            let mint_info = next_account_info(account_info_iter)?;
+            let token_program = next_account_info(account_info_iter)?;
+
+            if *mint_info.key != leg.mint {
+                return Err(SwapError::InvalidMint.into());
+            }
+            validate_token_program(mint_info, token_program.key)?;
+            validate_token_account(taker_token_account, taker_info.key, &leg.mint)?;
+            validate_token_account(maker_token_account, &bundle.maker, &leg.mint)?;
+            let fee = config
+                .as_ref()
+                .map_or(0, |config| config.fee(leg.amount, 0));
+            if fee > 0 && get_treasury_pda(program_id, &leg.mint).0 != *fee_account.key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            memo_if_required(maker_token_account, memo, accounts)?;
+            transfer_with_fee(
+                token_program,
+                taker_token_account,
+                mint_info,
+                maker_token_account,
+                fee_account,
+                taker_info,
+                accounts,
+                leg.amount,
+                leg.decimals,
+                fee,
+                &[],
+            )?;
+        }
+
+        let bundle_id = bundle.bundle_id.to_le_bytes();
+        let signer_seeds: &[&[u8]] =
+            &[b"bundle", bundle.maker.as_ref(), &bundle_id, &[bundle.bump]];
+        for leg in &bundle.maker_legs {
+            let escrow_info = next_account_info(account_info_iter)?;
+            let taker_token_account = next_account_info(account_info_iter)?;
+            let mint_info = next_account_info(account_info_iter)?;
+            let token_program = next_account_info(account_info_iter)?;
+
+            if *mint_info.key != leg.mint {
+                return Err(SwapError::InvalidMint.into());
+            }
+            validate_token_account(escrow_info, bundle_info.key, &leg.mint)?;
+            validate_token_account(taker_token_account, taker_info.key, &leg.mint)?;
+
+            memo_if_required(taker_token_account, memo, accounts)?;
+            transfer_tokens_with_hook(
+                token_program,
+                escrow_info,
+                mint_info,
+                taker_token_account,
+                bundle_info,
+                accounts,
+                leg.amount,
+                leg.decimals,
+                &[signer_seeds],
+            )?;
+        }
+
+        bundle.status = OrderStatus::Completed;
+        bundle.serialize(&mut *bundle_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_close_bundle(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        memo: Option<&str>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let bundle_info = next_account_info(account_info_iter)?;
+
+        validate_signer(maker_info)?;
+        let bundle = load_bundle(program_id, bundle_info)?;
+        if bundle.maker != *maker_info.key {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+
+        let bundle_id = bundle.bundle_id.to_le_bytes();
+        let signer_seeds: &[&[u8]] =
+            &[b"bundle", bundle.maker.as_ref(), &bundle_id, &[bundle.bump]];
+        for leg in &bundle.maker_legs {
+            let escrow_info = next_account_info(account_info_iter)?;
+            let maker_token_account = next_account_info(account_info_iter)?;
+            let mint_info = next_account_info(account_info_iter)?;
+            let token_program = next_account_info(account_info_iter)?;
+
+            if *mint_info.key != leg.mint {
+                return Err(SwapError::InvalidMint.into());
+            }
+            validate_token_account(escrow_info, bundle_info.key, &leg.mint)?;
+
+            let escrowed = token_balance(escrow_info)?;
+            if escrowed > 0 {
+                validate_token_account(maker_token_account, &bundle.maker, &leg.mint)?;
+                memo_if_required(maker_token_account, memo, accounts)?;
+                transfer_tokens_with_hook(
+                    token_program,
+                    escrow_info,
+                    mint_info,
+                    maker_token_account,
+                    bundle_info,
+                    accounts,
+                    escrowed,
+                    leg.decimals,
+                    &[signer_seeds],
+                )?;
+            }
+            harvest_withheld_fees(token_program, mint_info, escrow_info)?;
+            close_token_account(
+                token_program,
+                escrow_info,
+                maker_info,
+                bundle_info,
+                &[signer_seeds],
+            )?;
+        }
+
+        let rent_lamports = bundle_info.lamports();
+        **bundle_info.lamports.borrow_mut() = 0;
+        **maker_info.lamports.borrow_mut() += rent_lamports;
+
+        bundle_info.data.borrow_mut().fill(0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::{OrderType, SwapOrder},
+    validation::{
+        is_mint_blocked, validate_account_token_program, validate_max_taker_amount,
+        validate_not_expired, validate_not_paused, validate_order_open, validate_order_pda,
+        validate_protocol_not_paused, validate_signer, validate_token_account,
+        validate_trade_guards,
+    },
+};
+
+use super::{
+    escrowed_taker_fee,
+    token::{has_transfer_fee, token_balance, transfer_with_fee},
+    Processor,
+};
+
+/// Accounts supplied per order in a `CompleteSwapChain`
+const HOP_ACCOUNTS: usize = 13;
+
+/// Orders settled by a `SettleRing`
+const RING_SIZE: usize = 3;
+
+/// Accounts supplied per order in a `SettleRing`
+const RING_ACCOUNTS: usize = 8;
+
+impl Processor {
+    pub(super) fn process_complete_swap_chain(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_taker_amount: u64,
+        min_maker_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let taker_stats_info = next_account_info(account_info_iter)?;
+        let protocol_stats_info = next_account_info(account_info_iter)?;
+        let hops = account_info_iter.as_slice();
+
+        validate_signer(taker_info)?;
+        if hops.len() < 2 * HOP_ACCOUNTS || !hops.len().is_multiple_of(HOP_ACCOUNTS) {
+            return Err(SwapError::InvalidSwapChain.into());
+        }
+        // Each hop must pay with the account the previous hop delivered into
+        for (hop, next_hop) in hops
+            .chunks(HOP_ACCOUNTS)
+            .zip(hops.chunks(HOP_ACCOUNTS).skip(1))
+        {
+            if hop[3].key != next_hop[2].key {
+                return Err(SwapError::InvalidSwapChain.into());
+            }
+        }
+
+        let first_sending = &hops[2];
+        let last_receiving = &hops[hops.len() - HOP_ACCOUNTS + 3];
+        let sent_before = token_balance(first_sending)?;
+        let received_before = token_balance(last_receiving)?;
+
+        for hop in hops.chunks(HOP_ACCOUNTS) {
+            let hop_accounts = [
+                taker_info.clone(),
+                hop[0].clone(),
+                hop[1].clone(),
+                hop[2].clone(),
+                hop[3].clone(),
+                hop[4].clone(),
+                token_program.clone(),
+                token_program.clone(),
+                hop[8].clone(),
+                hop[9].clone(),
+                config_info.clone(),
+                hop[5].clone(),
+                hop[6].clone(),
+                hop[7].clone(),
+                taker_stats_info.clone(),
+                hop[10].clone(),
+                hop[11].clone(),
+                protocol_stats_info.clone(),
+                hop[12].clone(),
+            ];
+            Self::process_complete_swap(program_id, &hop_accounts, 0, u64::MAX, 0, &[], None)?;
+        }
+
+        let sent = sent_before
+            .checked_sub(token_balance(first_sending)?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let received = token_balance(last_receiving)?
+            .checked_sub(received_before)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        validate_max_taker_amount(sent, max_taker_amount)?;
+        if received < min_maker_amount {
+            return Err(SwapError::SlippageExceeded.into());
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn process_settle_ring(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let settler_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        // Bookkeeping accounts may follow the legs
+        let legs = account_info_iter
+            .as_slice()
+            .get(..RING_SIZE * RING_ACCOUNTS)
+            .ok_or(SwapError::InvalidSwapChain)?;
+
+        validate_signer(settler_info)?;
+        validate_protocol_not_paused(program_id, config_info)?;
+        check_spl_token_program_account(token_program.key)?;
+
+        let mut orders: Vec<SwapOrder> = Vec::with_capacity(RING_SIZE);
+        for (i, leg) in legs.chunks(RING_ACCOUNTS).enumerate() {
+            // An order may only appear once in the cycle
+            if legs[..i * RING_ACCOUNTS]
+                .chunks(RING_ACCOUNTS)
+                .any(|previous| previous[0].key == leg[0].key)
+            {
+                return Err(SwapError::InvalidSwapChain.into());
+            }
+            let (order, _) = validate_order_pda(program_id, &leg[0])?;
+            validate_order_open(&order)?;
+            validate_not_paused(&order)?;
+            validate_not_expired(&order)?;
+            // No maker of the ring signs for the order it takes, so orders gating their
+            // taker beyond a designated one or an allowlist are left to CompleteSwap
+            if order.order_type != OrderType::Fixed
+                || order.requires_confirmation
+                || order.dual_escrow
+                || order.has_vesting()
+                || order.ui_amounts
+                || order.commit_reveal
+                || order.is_token_gated()
+                || order.has_taker_merkle_root()
+                || order.taker_collection
+                || order.taker_group != Pubkey::default()
+            {
+                return Err(SwapError::IncompatibleOrderOptions.into());
+            }
+            validate_token_account(&leg[1], leg[0].key, &order.maker_token_mint)?;
+            validate_account_token_program(&leg[1], token_program.key)?;
+            validate_token_account(&leg[2], &order.maker, &order.taker_token_mint)?;
+            validate_account_token_program(&leg[2], token_program.key)?;
+            // Each order must receive its whole taker amount, which a transfer fee would cut
+            if has_transfer_fee(&leg[3])? {
+                return Err(SwapError::IncompatibleOrderOptions.into());
+            }
+            // Every mint of the ring is the maker mint of one of its orders
+            if is_mint_blocked(program_id, &leg[4], &order.maker_token_mint)? {
+                return Err(SwapError::MintBlocked.into());
+            }
+            orders.push(order);
+        }
+
+        // Order i wants what order i + 1 sells, and is paid from that order's escrow
+        for i in 0..RING_SIZE {
+            let (order, payer) = (&orders[i], &orders[(i + 1) % RING_SIZE]);
+            if order.taker_token_mint != payer.maker_token_mint
+                || payer.maker_amount < order.taker_amount
+            {
+                return Err(SwapError::InvalidSwapChain.into());
+            }
+            if !payer.is_allowed_taker(&order.maker) {
+                return Err(SwapError::UnauthorizedSigner.into());
+            }
+            validate_trade_guards(
+                program_id,
+                Some(config_info),
+                &{ payer.maker },
+                &{ order.maker },
+                &{ payer.maker_token_mint },
+                &{ payer.taker_token_mint },
+            )?;
+        }
+
+        // Order i fills for what order i + 1 sells, taken by the maker of order i - 1. The
+        // protocol fee is skimmed from what each maker receives, as for ConfirmSwap.
+        let mut fill_indices = [0; RING_SIZE];
+        let mut fees = [0; RING_SIZE];
+        for (i, leg) in legs.chunks(RING_ACCOUNTS).enumerate() {
+            let taker_amount = orders[(i + 1) % RING_SIZE].maker_amount;
+            fees[i] = escrowed_taker_fee(
+                program_id,
+                &orders[i],
+                config_info,
+                &leg[6],
+                &leg[7],
+                &leg[5],
+                taker_amount,
+            )?;
+            fill_indices[i] = orders[i].record_final_fill(taker_amount)?;
+            orders[i].pack(&mut leg[0].data.borrow_mut())?;
+        }
+
+        for i in 0..RING_SIZE {
+            let payer_index = (i + 1) % RING_SIZE;
+            let payer = &orders[payer_index];
+            let payer_leg = &legs[payer_index * RING_ACCOUNTS..];
+            let leg = &legs[i * RING_ACCOUNTS..];
+            let order_id = payer.order_id.to_le_bytes();
+            transfer_with_fee(
+                token_program,
+                &payer_leg[1],
+                &payer_leg[3],
+                &leg[2],
+                &leg[5],
+                &payer_leg[0],
+                accounts,
+                payer.maker_amount,
+                payer.maker_decimals,
+                fees[i],
+                &[&[
+                    b"order",
+                    payer.seed_maker.as_ref(),
+                    payer.maker_token_mint.as_ref(),
+                    payer.taker_token_mint.as_ref(),
+                    &order_id,
+                    &[payer.bump],
+                ]],
+            )?;
+        }
+
+        for (i, leg) in legs.chunks(RING_ACCOUNTS).enumerate() {
+            let order = &orders[i];
+            Self::book_fill(
+                program_id,
+                order,
+                &leg[0],
+                &leg[1],
+                (token_program, token_program.key),
+                &leg[3],
+                settler_info,
+                &orders[(i + RING_SIZE - 1) % RING_SIZE].maker,
+                fill_indices[i],
+                order.maker_amount,
+                orders[(i + 1) % RING_SIZE].maker_amount,
+                accounts,
+            )?;
+        }
+
+        Ok(())
+    }
+}
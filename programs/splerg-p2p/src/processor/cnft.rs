@@ -0,0 +1,299 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    bubblegum::{find_asset_id, transfer, BUBBLEGUM_PROGRAM_ID},
+    error::SwapError,
+    state::{CompressedOrder, Discriminator, OrderStatus},
+    validation::{
+        get_compressed_order_pda, get_treasury_pda, is_mint_blocked, load_compressed_order,
+        load_config, validate_max_taker_amount, validate_protocol_not_paused, validate_signer,
+        validate_system_program, validate_token_account, validate_token_mint,
+        validate_token_program, validate_trade_guards,
+    },
+};
+
+use super::{
+    token::{memo_if_required, mint_decimals, transfer_with_fee},
+    Processor,
+};
+
+/// Moves the order's leaf from the order PDA to `new_leaf_owner`. `tree_accounts` starts
+/// with the tree config, followed by the tree, the log wrapper, the compression program,
+/// the Bubblegum program, the system program and the proof nodes.
+fn release_leaf<'a>(
+    order: &CompressedOrder,
+    order_info: &AccountInfo<'a>,
+    new_leaf_owner: &AccountInfo<'a>,
+    root: [u8; 32],
+    tree_accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let [_, merkle_tree, _, _, bubblegum_program, _, proof @ ..] = tree_accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if *bubblegum_program.key != BUBBLEGUM_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if *merkle_tree.key != order.merkle_tree {
+        return Err(SwapError::InvalidOrderState.into());
+    }
+    let proof = proof
+        .get(..order.proof_len as usize)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let proof_keys = proof.iter().map(|node| *node.key).collect::<Vec<_>>();
+
+    // Bubblegum resets the delegate to the owner on every transfer, so the order PDA is
+    // both while it holds the leaf
+    let mut account_infos = vec![order_info.clone(), new_leaf_owner.clone()];
+    account_infos.extend_from_slice(tree_accounts);
+    let order_id = order.order_id.to_le_bytes();
+    invoke_signed(
+        &transfer(
+            &order.merkle_tree,
+            order_info.key,
+            order_info.key,
+            new_leaf_owner.key,
+            root,
+            order.data_hash,
+            order.creator_hash,
+            order.nonce,
+            order.leaf_index,
+            &proof_keys,
+        ),
+        &account_infos,
+        &[&[
+            b"cnft_order",
+            order.maker.as_ref(),
+            &order_id,
+            &[order.bump],
+        ]],
+    )
+}
+
+impl Processor {
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn process_initialize_compressed_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        order_id: u64,
+        taker_amount: u64,
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+        nonce: u64,
+        index: u32,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_info = next_account_info(account_info_iter)?;
+        let taker_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let _tree_config_info = next_account_info(account_info_iter)?;
+        let leaf_delegate_info = next_account_info(account_info_iter)?;
+        let merkle_tree_info = next_account_info(account_info_iter)?;
+        let _log_wrapper_info = next_account_info(account_info_iter)?;
+        let _compression_program_info = next_account_info(account_info_iter)?;
+        let bubblegum_program_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let proof = account_info_iter.as_slice();
+
+        validate_signer(maker_info)?;
+        validate_protocol_not_paused(program_id, config_info)?;
+        validate_system_program(system_program_info.key)?;
+        validate_token_mint(taker_mint_info)?;
+        if is_mint_blocked(program_id, taker_mint_blocked_info, taker_mint_info.key)? {
+            return Err(SwapError::MintBlocked.into());
+        }
+        if taker_amount == 0 {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        if *bubblegum_program_info.key != BUBBLEGUM_PROGRAM_ID {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let proof_len = u8::try_from(proof.len()).map_err(|_| SwapError::InvalidInstruction)?;
+        let (order_pda, bump) = get_compressed_order_pda(program_id, maker_info.key, order_id);
+        if order_pda != *order_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        // The asset ID stands for the maker mint
+        let asset_id = find_asset_id(merkle_tree_info.key, nonce);
+        validate_trade_guards(
+            program_id,
+            Some(config_info),
+            maker_info.key,
+            taker_info.key,
+            &asset_id,
+            taker_mint_info.key,
+        )?;
+
+        invoke_signed(
+            &system_instruction::create_account(
+                maker_info.key,
+                order_info.key,
+                Rent::get()?.minimum_balance(CompressedOrder::LEN),
+                CompressedOrder::LEN as u64,
+                program_id,
+            ),
+            &[
+                maker_info.clone(),
+                order_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[
+                b"cnft_order",
+                maker_info.key.as_ref(),
+                &order_id.to_le_bytes(),
+                &[bump],
+            ]],
+        )?;
+
+        // Bubblegum checks the proof and the leaf fields against the tree, so the order
+        // only records a leaf the maker actually owned
+        let proof_keys = proof.iter().map(|node| *node.key).collect::<Vec<_>>();
+        invoke(
+            &transfer(
+                merkle_tree_info.key,
+                maker_info.key,
+                leaf_delegate_info.key,
+                order_info.key,
+                root,
+                data_hash,
+                creator_hash,
+                nonce,
+                index,
+                &proof_keys,
+            ),
+            accounts,
+        )?;
+
+        let order = CompressedOrder {
+            discriminator: CompressedOrder::DISCRIMINATOR,
+            maker: *maker_info.key,
+            taker: *taker_info.key,
+            order_id,
+            bump,
+            status: OrderStatus::Open,
+            merkle_tree: *merkle_tree_info.key,
+            asset_id,
+            nonce,
+            leaf_index: index,
+            data_hash,
+            creator_hash,
+            proof_len,
+            taker_token_mint: *taker_mint_info.key,
+            taker_amount,
+            taker_decimals: mint_decimals(taker_mint_info)?,
+        };
+        order.serialize(&mut *order_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_complete_compressed_swap(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        root: [u8; 32],
+        max_taker_amount: u64,
+        memo: Option<&str>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_info = next_account_info(account_info_iter)?;
+        let taker_sending_account = next_account_info(account_info_iter)?;
+        let maker_receiving_account = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let fee_account = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let tree_accounts = account_info_iter.as_slice();
+
+        validate_signer(taker_info)?;
+        validate_protocol_not_paused(program_id, config_info)?;
+        let mut order = load_compressed_order(program_id, order_info)?;
+        if order.status != OrderStatus::Open {
+            return Err(SwapError::OrderNotOpen.into());
+        }
+        if !order.is_allowed_taker(taker_info.key) {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        if *taker_mint_info.key != order.taker_token_mint {
+            return Err(SwapError::InvalidMint.into());
+        }
+        if is_mint_blocked(program_id, taker_mint_blocked_info, taker_mint_info.key)? {
+            return Err(SwapError::MintBlocked.into());
+        }
+        validate_max_taker_amount(order.taker_amount, max_taker_amount)?;
+        validate_token_program(taker_mint_info, token_program.key)?;
+        validate_token_account(taker_sending_account, taker_info.key, taker_mint_info.key)?;
+        validate_token_account(maker_receiving_account, &order.maker, taker_mint_info.key)?;
+
+        // Charged at the config's base rate, as for bundles
+        let fee = load_config(program_id, config_info)?
+            .map_or(0, |config| config.fee(order.taker_amount, 0));
+        if fee > 0 && get_treasury_pda(program_id, taker_mint_info.key).0 != *fee_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        memo_if_required(maker_receiving_account, memo, accounts)?;
+        transfer_with_fee(
+            token_program,
+            taker_sending_account,
+            taker_mint_info,
+            maker_receiving_account,
+            fee_account,
+            taker_info,
+            accounts,
+            order.taker_amount,
+            order.taker_decimals,
+            fee,
+            &[],
+        )?;
+
+        release_leaf(&order, order_info, taker_info, root, tree_accounts)?;
+
+        order.status = OrderStatus::Completed;
+        order.serialize(&mut *order_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_close_compressed_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        root: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_info = next_account_info(account_info_iter)?;
+        let tree_accounts = account_info_iter.as_slice();
+
+        validate_signer(maker_info)?;
+        let order = load_compressed_order(program_id, order_info)?;
+        if order.maker != *maker_info.key {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        // An unfilled order hands the NFT back before its account goes
+        if order.status == OrderStatus::Open {
+            release_leaf(&order, order_info, maker_info, root, tree_accounts)?;
+        }
+
+        let rent_lamports = order_info.lamports();
+        **order_info.lamports.borrow_mut() = 0;
+        **maker_info.lamports.borrow_mut() += rent_lamports;
+
+        order_info.data.borrow_mut().fill(0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::SwapError,
+    validation::{
+        fill_commitment, validate_manager, validate_order_open, validate_order_pda, validate_signer,
+    },
+};
+
+use super::Processor;
+
+impl Processor {
+    pub(super) fn process_set_commit_reveal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        enabled: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_manager(maker_info, &order)?;
+        validate_order_open(&order)?;
+
+        order.commit_reveal = enabled;
+        order.fill_commitment = [0; 32];
+        order.commit_slot = 0;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_commit_fill(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        commitment: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let committer_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        validate_signer(committer_info)?;
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_order_open(&order)?;
+        if !order.commit_reveal {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        let slot = Clock::get()?.slot;
+        if order.has_live_commitment(slot) {
+            return Err(SwapError::CommitmentPending.into());
+        }
+
+        order.fill_commitment = commitment;
+        order.commit_slot = slot;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_reveal_fill(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        salt: [u8; 32],
+        fill_amount: u64,
+        max_taker_amount: u64,
+        min_maker_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        validate_signer(taker_info)?;
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        if !order.commit_reveal {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        // The reveal must land in a later slot than its commitment, before it lapses
+        let slot = Clock::get()?.slot;
+        if slot <= order.commit_slot
+            || !order.has_live_commitment(slot)
+            || fill_commitment(taker_info.key, &salt) != order.fill_commitment
+        {
+            return Err(SwapError::InvalidCommitment.into());
+        }
+
+        order.fill_commitment = [0; 32];
+        order.commit_slot = 0;
+        order.revealed_taker = *taker_info.key;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Self::process_complete_swap(
+            program_id,
+            accounts,
+            fill_amount,
+            max_taker_amount,
+            min_maker_amount,
+            &[],
+            None,
+        )
+    }
+}
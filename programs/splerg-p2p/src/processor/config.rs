@@ -0,0 +1,639 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::SwapError,
+    state::{
+        Config, FeeFeed, FeeTier, PairStats, ProtocolStats, UserStats, MAX_FEE_BPS, MAX_FEE_TIERS,
+    },
+    validation::{
+        get_blocked_mint_pda, get_config_pda, get_fee_exemption_pda, get_fee_feed_pda,
+        get_pair_stats_pda, get_protocol_stats_pda, get_treasury_pda, get_user_stats_pda,
+        is_fee_exempt, is_mint_blocked, unpack_token_account, validate_admin,
+        validate_system_program, validate_token_account, validate_token_mint,
+        validate_token_program, validate_upgrade_authority,
+    },
+};
+
+use super::{
+    grow_account,
+    token::{is_native_mint, mint_decimals, token_account_len, token_balance, transfer_tokens},
+    Processor,
+};
+
+/// Writes `config` back to `config_info`, first growing a config written before its later
+/// fields were added, with the admin paying the extra rent through the system program
+/// passed among `accounts`
+fn save_config<'a>(
+    config: &Config,
+    config_info: &AccountInfo<'a>,
+    admin_info: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    if config_info.data_len() < Config::LEN {
+        let system_program_info = accounts
+            .iter()
+            .find(|account| system_program::check_id(account.key))
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        grow_account(config_info, admin_info, system_program_info, Config::LEN)?;
+    }
+    config.serialize(&mut *config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+impl Processor {
+    pub(super) fn process_initialize_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fee_bps: u16,
+        fee_collector: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let program_data_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        // Only the upgrade authority can claim the admin role
+        validate_upgrade_authority(program_id, program_data_info, admin_info)?;
+        validate_system_program(system_program_info.key)?;
+        if fee_bps > MAX_FEE_BPS {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        let (config_pda, bump) = get_config_pda(program_id);
+        if config_pda != *config_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if !config_info.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_info.key,
+                config_info.key,
+                Rent::get()?.minimum_balance(Config::LEN),
+                Config::LEN as u64,
+                program_id,
+            ),
+            &[
+                admin_info.clone(),
+                config_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"config", &[bump]]],
+        )?;
+
+        let config = Config {
+            admin: *admin_info.key,
+            fee_bps,
+            fee_collector,
+            bump,
+            fee_mint: Pubkey::default(),
+            fee_mint_decimals: 0,
+            fee_tiers: [FeeTier::default(); MAX_FEE_TIERS],
+            paused: false,
+            allow_self_trade: false,
+            allow_same_mint: false,
+        };
+        config.serialize(&mut *config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_update_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fee_bps: u16,
+        fee_collector: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        let mut config = validate_admin(program_id, config_info, admin_info)?;
+        if fee_bps > MAX_FEE_BPS {
+            return Err(SwapError::InvalidAmount.into());
+        }
+
+        config.fee_bps = fee_bps;
+        config.fee_collector = fee_collector;
+        save_config(&config, config_info, admin_info, accounts)?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_set_protocol_paused(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        paused: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        let mut config = validate_admin(program_id, config_info, admin_info)?;
+        config.paused = paused;
+        save_config(&config, config_info, admin_info, accounts)?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_set_trade_guards(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        allow_self_trade: bool,
+        allow_same_mint: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        let mut config = validate_admin(program_id, config_info, admin_info)?;
+        config.allow_self_trade = allow_self_trade;
+        config.allow_same_mint = allow_same_mint;
+        save_config(&config, config_info, admin_info, accounts)?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_add_fee_exemption(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        account: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let exemption_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_admin(program_id, config_info, admin_info)?;
+        validate_system_program(system_program_info.key)?;
+        let (exemption_pda, bump) = get_fee_exemption_pda(program_id, &account);
+        if exemption_pda != *exemption_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        // The exemption is the account's existence; it holds no data
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_info.key,
+                exemption_info.key,
+                Rent::get()?.minimum_balance(0),
+                0,
+                program_id,
+            ),
+            &[
+                admin_info.clone(),
+                exemption_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"fee_exempt", account.as_ref(), &[bump]]],
+        )?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_remove_fee_exemption(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        account: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let exemption_info = next_account_info(account_info_iter)?;
+
+        validate_admin(program_id, config_info, admin_info)?;
+        if !is_fee_exempt(program_id, exemption_info, &account)? {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        let lamports = exemption_info.lamports();
+        **exemption_info.lamports.borrow_mut() = 0;
+        **admin_info.lamports.borrow_mut() += lamports;
+
+        Ok(())
+    }
+
+    pub(super) fn process_block_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        mint: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let blocked_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_admin(program_id, config_info, admin_info)?;
+        validate_system_program(system_program_info.key)?;
+        let (blocked_pda, bump) = get_blocked_mint_pda(program_id, &mint);
+        if blocked_pda != *blocked_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        // As with fee exemptions, the block is the account's existence
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_info.key,
+                blocked_info.key,
+                Rent::get()?.minimum_balance(0),
+                0,
+                program_id,
+            ),
+            &[
+                admin_info.clone(),
+                blocked_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"blocked_mint", mint.as_ref(), &[bump]]],
+        )?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_unblock_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        mint: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let blocked_info = next_account_info(account_info_iter)?;
+
+        validate_admin(program_id, config_info, admin_info)?;
+        if !is_mint_blocked(program_id, blocked_info, &mint)? {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        let lamports = blocked_info.lamports();
+        **blocked_info.lamports.borrow_mut() = 0;
+        **admin_info.lamports.borrow_mut() += lamports;
+
+        Ok(())
+    }
+
+    pub(super) fn process_set_fee_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fee_mint: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        let mut config = validate_admin(program_id, config_info, admin_info)?;
+        config.fee_mint_decimals = if fee_mint == Pubkey::default() {
+            0
+        } else {
+            let fee_mint_info = next_account_info(account_info_iter)?;
+            if *fee_mint_info.key != fee_mint {
+                return Err(SwapError::InvalidMint.into());
+            }
+            validate_token_mint(fee_mint_info)?;
+            mint_decimals(fee_mint_info)?
+        };
+        config.fee_mint = fee_mint;
+        save_config(&config, config_info, admin_info, accounts)?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_set_fee_feed(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        price_feed: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let fee_feed_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_admin(program_id, config_info, admin_info)?;
+        validate_system_program(system_program_info.key)?;
+        validate_token_mint(mint_info)?;
+        let (fee_feed_pda, bump) = get_fee_feed_pda(program_id, mint_info.key);
+        if fee_feed_pda != *fee_feed_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if fee_feed_info.data_is_empty() {
+            invoke_signed(
+                &system_instruction::create_account(
+                    admin_info.key,
+                    fee_feed_info.key,
+                    Rent::get()?.minimum_balance(FeeFeed::LEN),
+                    FeeFeed::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    admin_info.clone(),
+                    fee_feed_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[b"fee_feed", mint_info.key.as_ref(), &[bump]]],
+            )?;
+        }
+
+        let fee_feed = FeeFeed {
+            mint: *mint_info.key,
+            price_feed,
+            mint_decimals: mint_decimals(mint_info)?,
+            bump,
+        };
+        fee_feed.serialize(&mut *fee_feed_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_initialize_user_stats(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        user: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let stats_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_system_program(system_program_info.key)?;
+        let (stats_pda, bump) = get_user_stats_pda(program_id, &user);
+        if stats_pda != *stats_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if !stats_info.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                stats_info.key,
+                Rent::get()?.minimum_balance(UserStats::LEN),
+                UserStats::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                stats_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"user_stats", user.as_ref(), &[bump]]],
+        )?;
+
+        let stats = UserStats {
+            user,
+            volume: 0,
+            bump,
+        };
+        stats.serialize(&mut *stats_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_initialize_protocol_stats(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let stats_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_system_program(system_program_info.key)?;
+        let (stats_pda, bump) = get_protocol_stats_pda(program_id);
+        if stats_pda != *stats_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if !stats_info.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                stats_info.key,
+                Rent::get()?.minimum_balance(ProtocolStats::LEN),
+                ProtocolStats::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                stats_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"protocol_stats", &[bump]]],
+        )?;
+
+        let stats = ProtocolStats {
+            fills: 0,
+            token_fills: 0,
+            token_2022_fills: 0,
+            maker_volume: 0,
+            taker_volume: 0,
+            bump,
+        };
+        stats.serialize(&mut *stats_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_initialize_pair_stats(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        maker_mint: Pubkey,
+        taker_mint: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let stats_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_system_program(system_program_info.key)?;
+        let (stats_pda, bump) = get_pair_stats_pda(program_id, &maker_mint, &taker_mint);
+        if stats_pda != *stats_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if !stats_info.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                stats_info.key,
+                Rent::get()?.minimum_balance(PairStats::LEN),
+                PairStats::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                stats_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[
+                b"pair_stats",
+                maker_mint.as_ref(),
+                taker_mint.as_ref(),
+                &[bump],
+            ]],
+        )?;
+
+        let stats = PairStats {
+            maker_mint,
+            taker_mint,
+            fills: 0,
+            maker_volume: 0,
+            taker_volume: 0,
+            bump,
+        };
+        stats.serialize(&mut *stats_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_set_fee_tiers(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        tiers: Vec<FeeTier>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        let mut config = validate_admin(program_id, config_info, admin_info)?;
+        if tiers.len() > MAX_FEE_TIERS {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        let mut previous_volume = 0;
+        for tier in &tiers {
+            if tier.min_volume <= previous_volume || tier.fee_bps > MAX_FEE_BPS {
+                return Err(SwapError::InvalidAmount.into());
+            }
+            previous_volume = tier.min_volume;
+        }
+
+        config.fee_tiers = [FeeTier::default(); MAX_FEE_TIERS];
+        config.fee_tiers[..tiers.len()].copy_from_slice(&tiers);
+        save_config(&config, config_info, admin_info, accounts)?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_initialize_treasury(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let treasury_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_admin(program_id, config_info, admin_info)?;
+        validate_system_program(system_program_info.key)?;
+        validate_token_mint(mint_info)?;
+        validate_token_program(mint_info, token_program.key)?;
+        let (treasury_pda, bump) = get_treasury_pda(program_id, mint_info.key);
+        if treasury_pda != *treasury_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let account_len = token_account_len(mint_info, token_program.key)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_info.key,
+                treasury_info.key,
+                Rent::get()?.minimum_balance(account_len),
+                account_len as u64,
+                token_program.key,
+            ),
+            &[
+                admin_info.clone(),
+                treasury_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"treasury", mint_info.key.as_ref(), &[bump]]],
+        )?;
+        // Both token programs share the `InitializeAccount3` encoding
+        invoke_signed(
+            &spl_token_2022::instruction::initialize_account3(
+                token_program.key,
+                treasury_info.key,
+                mint_info.key,
+                config_info.key,
+            )?,
+            &[
+                treasury_info.clone(),
+                mint_info.clone(),
+                token_program.clone(),
+            ],
+            &[],
+        )?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_withdraw_fees(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let treasury_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let config = validate_admin(program_id, config_info, admin_info)?;
+        let mint = unpack_token_account(treasury_info)?.mint;
+        if get_treasury_pda(program_id, &mint).0 != *treasury_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        validate_token_account(destination_info, &config.fee_collector, &mint)?;
+        if is_native_mint(&mint) {
+            // Native SOL fees arrive as bare lamports
+            invoke(
+                &spl_token::instruction::sync_native(token_program.key, treasury_info.key)?,
+                &[treasury_info.clone(), token_program.clone()],
+            )?;
+        }
+
+        let amount = if amount == 0 {
+            token_balance(treasury_info)?
+        } else {
+            amount
+        };
+        transfer_tokens(
+            token_program,
+            treasury_info,
+            mint_info,
+            destination_info,
+            config_info,
+            amount,
+            mint_decimals(mint_info)?,
+            &[&[b"config", &[config.bump]]],
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::SwapError,
+    state::OrderType,
+    validation::{
+        validate_authority, validate_group_member, validate_order_open, validate_order_pda,
+    },
+};
+
+use super::Processor;
+
+impl Processor {
+    pub(super) fn process_set_token_groups(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        maker_group: Pubkey,
+        taker_group: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if *maker_mint_info.key != order.maker_token_mint {
+            return Err(SwapError::InvalidMint.into());
+        }
+        if maker_group != Pubkey::default() {
+            validate_group_member(maker_mint_info, &maker_group)?;
+        }
+        // As for collection orders, everything but a plain fill settles in the order's
+        // own taker mint
+        if taker_group != Pubkey::default()
+            && (order.order_type != OrderType::Fixed
+                || order.dual_escrow
+                || order.requires_confirmation
+                || order.ui_amounts
+                || order.taker_is_native()
+                || order.taker_collection)
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+
+        order.maker_group = maker_group;
+        order.taker_group = taker_group;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
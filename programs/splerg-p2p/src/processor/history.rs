@@ -0,0 +1,110 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::SwapError,
+    state::{fill_price, Discriminator, SwapOrder, TradeHistory, TradePrint, TRADE_HISTORY_LEN},
+    validation::{get_trade_history_pda, validate_system_program},
+};
+
+use super::Processor;
+
+impl Processor {
+    pub(super) fn process_initialize_trade_history(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        maker_mint: Pubkey,
+        taker_mint: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let history_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_system_program(system_program_info.key)?;
+        let (history_pda, bump) = get_trade_history_pda(program_id, &maker_mint, &taker_mint);
+        if history_pda != *history_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if !history_info.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                history_info.key,
+                Rent::get()?.minimum_balance(TradeHistory::LEN),
+                TradeHistory::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                history_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[
+                b"trade_history",
+                maker_mint.as_ref(),
+                taker_mint.as_ref(),
+                &[bump],
+            ]],
+        )?;
+
+        let history = TradeHistory {
+            discriminator: TradeHistory::DISCRIMINATOR,
+            maker_mint,
+            taker_mint,
+            bump,
+            total: 0,
+            prints: [TradePrint::default(); TRADE_HISTORY_LEN],
+        };
+        history.serialize(&mut *history_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Records a fill of `maker_amount` for `taker_amount` in the trade history of the order's
+/// mint pair, if that is passed among `accounts` and initialized
+pub(super) fn record_trade(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    order: &SwapOrder,
+    maker_amount: u64,
+    taker_amount: u64,
+) -> ProgramResult {
+    let history_pda =
+        get_trade_history_pda(program_id, &order.maker_token_mint, &order.taker_token_mint).0;
+    let Some(history_info) = accounts.iter().find(|account| {
+        *account.key == history_pda && account.is_writable && !account.data_is_empty()
+    }) else {
+        return Ok(());
+    };
+    if history_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    if !TradeHistory::has_discriminator(&history_info.data.borrow()) {
+        return Err(SwapError::InvalidDiscriminator.into());
+    }
+
+    let mut history = TradeHistory::try_from_slice(&history_info.data.borrow())?;
+    history.record(TradePrint {
+        price: fill_price(maker_amount, taker_amount, order.maker_decimals),
+        size: maker_amount,
+        slot: Clock::get()?.slot,
+    });
+    history.serialize(&mut *history_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    hash::hash,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::OrderType,
+    validation::{
+        validate_authority, validate_not_expired, validate_not_paused, validate_order_open,
+        validate_order_pda, validate_pair_not_blocked, validate_taker, validate_token_account,
+    },
+};
+
+use super::{token::transfer_tokens, Processor};
+
+impl Processor {
+    pub(super) fn process_start_htlc(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        hashlock: [u8; 32],
+        timeout: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        // The claim releases the escrow as it stands, without converting UI amounts
+        if order.order_type != OrderType::Fixed
+            || order.requires_confirmation
+            || order.dual_escrow
+            || order.ui_amounts
+            || order.takes_any_member()
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        if timeout <= Clock::get()?.unix_timestamp {
+            return Err(SwapError::InvalidExpiry.into());
+        }
+
+        order.order_type = OrderType::Htlc;
+        order.hashlock = hashlock;
+        order.htlc_timeout = timeout;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_claim_htlc(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        preimage: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let taker_receiving_ata = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_taker(taker_info, &order)?;
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            &order.maker_token_mint,
+            &order.taker_token_mint,
+        )?;
+        validate_order_open(&order)?;
+        if order.order_type != OrderType::Htlc {
+            return Err(SwapError::UnsupportedOrderType.into());
+        }
+        if Clock::get()?.unix_timestamp >= order.htlc_timeout {
+            return Err(SwapError::HtlcTimedOut.into());
+        }
+        if hash(&preimage).to_bytes() != order.hashlock {
+            return Err(SwapError::InvalidPreimage.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(taker_receiving_ata, taker_info.key, &order.maker_token_mint)?;
+        validate_token_account(
+            order_maker_token_ata,
+            order_account_info.key,
+            &order.maker_token_mint,
+        )?;
+
+        // The taker side of the swap is paid on the other chain
+        let (maker_amount, taker_amount) = (order.maker_amount, order.taker_amount);
+        let fill_index = order.record_final_fill(taker_amount)?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        let order_id = order.order_id.to_le_bytes();
+        transfer_tokens(
+            token_program,
+            order_maker_token_ata,
+            maker_mint_info,
+            taker_receiving_ata,
+            order_account_info,
+            maker_amount,
+            order.maker_decimals,
+            &[&[
+                b"order",
+                order.seed_maker.as_ref(),
+                order.maker_token_mint.as_ref(),
+                order.taker_token_mint.as_ref(),
+                &order_id,
+                &[order.bump],
+            ]],
+        )?;
+
+        Self::book_fill(
+            program_id,
+            &order,
+            order_account_info,
+            order_maker_token_ata,
+            (token_program, token_program.key),
+            maker_mint_info,
+            taker_info,
+            taker_info.key,
+            fill_index,
+            maker_amount,
+            taker_amount,
+            accounts,
+        )
+    }
+
+    pub(super) fn process_start_secret_claim(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        hashlock: [u8; 32],
+        taker_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if order.order_type != OrderType::Fixed
+            || order.requires_confirmation
+            || order.dual_escrow
+            || order.has_vesting()
+            || order.ui_amounts
+            || order.takes_any_member()
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        // The preimage is public once sent, so the claim is bound to the designated
+        // taker's signature: the link carries that taker's key along with the secret
+        if order.accepts_any_taker() {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+
+        order.order_type = OrderType::SecretClaim;
+        order.hashlock = hashlock;
+        order.taker_amount = taker_amount;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_claim_secret(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        preimage: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let claimer_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let claimer_receiving_ata = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        // Anyone who saw the preimage could claim an open order ahead of its holder
+        if order.accepts_any_taker() {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        validate_taker(claimer_info, &order)?;
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            &order.maker_token_mint,
+            &order.taker_token_mint,
+        )?;
+        validate_order_open(&order)?;
+        validate_not_paused(&order)?;
+        validate_not_expired(&order)?;
+        if order.order_type != OrderType::SecretClaim {
+            return Err(SwapError::UnsupportedOrderType.into());
+        }
+        if hash(&preimage).to_bytes() != order.hashlock {
+            return Err(SwapError::InvalidPreimage.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(
+            claimer_receiving_ata,
+            claimer_info.key,
+            &order.maker_token_mint,
+        )?;
+        validate_token_account(
+            order_maker_token_ata,
+            order_account_info.key,
+            &order.maker_token_mint,
+        )?;
+
+        let (maker_amount, taker_amount) = (order.maker_amount, order.taker_amount);
+        order.accepted_taker = *claimer_info.key;
+        let fill_index = order.record_final_fill(taker_amount)?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        if taker_amount > 0 {
+            let claimer_sending_ata = next_account_info(account_info_iter)?;
+            let maker_receiving_ata = next_account_info(account_info_iter)?;
+            let taker_mint_info = next_account_info(account_info_iter)?;
+            validate_token_account(
+                claimer_sending_ata,
+                claimer_info.key,
+                &order.taker_token_mint,
+            )?;
+            validate_token_account(maker_receiving_ata, &order.maker, &order.taker_token_mint)?;
+            transfer_tokens(
+                token_program,
+                claimer_sending_ata,
+                taker_mint_info,
+                maker_receiving_ata,
+                claimer_info,
+                taker_amount,
+                order.taker_decimals,
+                &[],
+            )?;
+        }
+
+        let order_id = order.order_id.to_le_bytes();
+        transfer_tokens(
+            token_program,
+            order_maker_token_ata,
+            maker_mint_info,
+            claimer_receiving_ata,
+            order_account_info,
+            maker_amount,
+            order.maker_decimals,
+            &[&[
+                b"order",
+                order.seed_maker.as_ref(),
+                order.maker_token_mint.as_ref(),
+                order.taker_token_mint.as_ref(),
+                &order_id,
+                &[order.bump],
+            ]],
+        )?;
+
+        Self::book_fill(
+            program_id,
+            &order,
+            order_account_info,
+            order_maker_token_ata,
+            (token_program, token_program.key),
+            maker_mint_info,
+            claimer_info,
+            claimer_info.key,
+            fill_index,
+            maker_amount,
+            taker_amount,
+            accounts,
+        )
+    }
+}
@@ -0,0 +1,93 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::invoke_signed, pubkey::Pubkey,
+    rent::Rent, system_instruction, sysvar::Sysvar,
+};
+
+use crate::{
+    state::MakerIndex,
+    validation::{get_maker_index_pda, load_maker_index},
+};
+
+use super::grow_account;
+
+/// Returns `maker`'s order index if it is passed, writable, among `accounts`
+pub(super) fn find_maker_index<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    maker: &Pubkey,
+) -> Option<&'a AccountInfo<'info>> {
+    let index_pda = get_maker_index_pda(program_id, maker).0;
+    accounts
+        .iter()
+        .find(|account| *account.key == index_pda && account.is_writable)
+}
+
+/// Adds `order` to `maker`'s order index, creating the index on first use. `payer_info`
+/// covers the rent of the extra entry.
+pub(super) fn index_order<'a>(
+    program_id: &Pubkey,
+    index_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    maker: &Pubkey,
+    order: &Pubkey,
+) -> ProgramResult {
+    let index = match load_maker_index(program_id, index_info, maker)? {
+        Some(mut index) => {
+            index.orders.push(*order);
+            index
+        }
+        None => MakerIndex {
+            maker: *maker,
+            bump: get_maker_index_pda(program_id, maker).1,
+            orders: vec![*order],
+        },
+    };
+    let space = MakerIndex::space(index.orders.len());
+
+    if index_info.data_is_empty() {
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                index_info.key,
+                Rent::get()?.minimum_balance(space),
+                space as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                index_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"maker_index", maker.as_ref(), &[index.bump]]],
+        )?;
+    } else {
+        grow_account(index_info, payer_info, system_program_info, space)?;
+    }
+    index.serialize(&mut *index_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Removes `order` from `maker`'s order index if it is listed there. The index shrinks
+/// to fit and keeps the freed rent towards its next entry.
+pub(super) fn unindex_order(
+    program_id: &Pubkey,
+    index_info: &AccountInfo,
+    maker: &Pubkey,
+    order: &Pubkey,
+) -> ProgramResult {
+    let Some(mut index) = load_maker_index(program_id, index_info, maker)? else {
+        return Ok(());
+    };
+    let Some(position) = index.orders.iter().position(|key| key == order) else {
+        return Ok(());
+    };
+    index.orders.remove(position);
+
+    index_info.realloc(MakerIndex::space(index.orders.len()), false)?;
+    index.serialize(&mut *index_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! Initialization safety. Accounts an instruction initializes are checked to be unclaimed
+//! before anything is moved into them, so an initialize call against an account already
+//! in use fails up front instead of overwriting it. Lamports sent to an address before
+//! it is initialized do not block it: anyone can send them, so they do not claim it.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+
+/// Fails with `error` unless `account_info` is unclaimed: owned by the system program and
+/// without data. It may hold lamports.
+pub(super) fn validate_uninitialized(
+    account_info: &AccountInfo,
+    error: impl Into<ProgramError>,
+) -> ProgramResult {
+    if *account_info.owner != system_program::id() || !account_info.data_is_empty() {
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+/// Creates the PDA `account_info`, signed for by `seeds`, with `space` bytes owned by
+/// `owner` and rent-exempt at `payer_info`'s expense. An account that already holds
+/// lamports is topped up, allocated and assigned instead, as `create_account` refuses it.
+pub(super) fn create_pda_account<'a>(
+    payer_info: &AccountInfo<'a>,
+    account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_lamports = Rent::get()?.minimum_balance(space);
+    if account_info.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                account_info.key,
+                rent_lamports,
+                space as u64,
+                owner,
+            ),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[seeds],
+        );
+    }
+
+    let shortfall = rent_lamports.saturating_sub(account_info.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, account_info.key, shortfall),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account_info.key, space as u64),
+        &[account_info.clone(), system_program_info.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account_info.key, owner),
+        &[account_info.clone(), system_program_info.clone()],
+        &[seeds],
+    )
+}
@@ -0,0 +1,133 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::SwapError,
+    state::{Discriminator, OrderType, SwapOrder, MEMO_LEN, ORDER_VERSION},
+    validation::{
+        load_order, validate_authority, validate_expiry, validate_memo, validate_order_open,
+        validate_order_pda, validate_signer, validate_system_program,
+    },
+};
+
+use super::Processor;
+
+impl Processor {
+    pub(super) fn process_migrate_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_signer(payer_info)?;
+        validate_system_program(system_program_info.key)?;
+        if order_account_info.owner != program_id {
+            return Err(SwapError::WrongAccountOwner.into());
+        }
+
+        // Everything after the discriminator and version byte carries over
+        let (version, fields) = {
+            let data = order_account_info.data.borrow();
+            if SwapOrder::has_discriminator(&data) {
+                if data.len() < 9 || data[8] >= ORDER_VERSION {
+                    return Err(SwapError::InvalidOrderState.into());
+                }
+                (data[8], data[9..].to_vec())
+            } else if data.len() == SwapOrder::VERSION_1_LEN && data[0] == 1 {
+                (1, data[1..].to_vec())
+            } else if data.len() == SwapOrder::LEGACY_LEN {
+                (0, data.to_vec())
+            } else {
+                return Err(SwapError::InvalidDiscriminator.into());
+            }
+        };
+
+        let rent_due = Rent::get()?
+            .minimum_balance(SwapOrder::LEN)
+            .saturating_sub(order_account_info.lamports());
+        if rent_due > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, order_account_info.key, rent_due),
+                &[
+                    payer_info.clone(),
+                    order_account_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+        order_account_info.realloc(SwapOrder::LEN, true)?;
+        {
+            let mut data = order_account_info.data.borrow_mut();
+            data[..8].copy_from_slice(&SwapOrder::DISCRIMINATOR);
+            data[8] = ORDER_VERSION;
+            data[9..9 + fields.len()].copy_from_slice(&fields);
+        }
+
+        // Fields added since default to their zero value, apart from the transfer fee
+        // amounts added in version 3, which start out fee-less. A referral fee set before
+        // version 13 named no referrer, so it lapses until the maker sets it again. The
+        // migrated order has to load and still live at its PDA.
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        if version < 3 {
+            order.maker_amount_gross = order.maker_amount;
+            order.taker_amount_net = order.taker_amount;
+        }
+        if version < 13 {
+            order.referral_bps = 0;
+        }
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_extend_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        expires_at: Option<i64>,
+        memo: Option<[u8; MEMO_LEN]>,
+        taker_merkle_root: Option<[u8; 32]>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        // An order on an older layout grows in place first, with the maker topping up
+        // the rent, so the escrow stays where it is
+        let outdated: ProgramError = SwapError::OutdatedOrder.into();
+        if load_order(order_account_info).err() == Some(outdated) {
+            Self::process_migrate_order(program_id, accounts)?;
+        }
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if let Some(expires_at) = expires_at {
+            validate_expiry(expires_at)?;
+            order.expires_at = expires_at;
+        }
+        if let Some(memo) = memo {
+            validate_memo(&memo)?;
+            order.memo = memo;
+        }
+        if let Some(root) = taker_merkle_root {
+            if order.order_type == OrderType::Htlc {
+                return Err(SwapError::HtlcLocked.into());
+            }
+            order.taker_merkle_root = root;
+        }
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
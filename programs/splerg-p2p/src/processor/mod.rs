@@ -1,13 +1,52 @@
 use spl_token_2022::check_spl_token_program_account;
 
+mod acceptance;
+mod auction;
+mod batch;
+mod bundle;
+mod chain;
+mod cnft;
+mod commit;
+mod config;
+mod group;
+mod history;
+mod htlc;
+mod index;
+mod init;
+mod migrate;
+mod nft;
+mod oracle;
+mod reap;
+mod receipt;
+mod recurrence;
+mod registry;
+mod rfq;
+mod streaming;
+mod token;
+mod vesting;
+mod wsol;
+
+use history::record_trade;
+use index::{find_maker_index, index_order, unindex_order};
+use init::{create_pda_account, validate_uninitialized};
+use receipt::write_fill_receipt;
+use registry::{register_order, sync_registry_entry, unregister_order};
+use token::{
+    amount_to_ui_amount, close_token_account, create_escrow_if_missing,
+    create_receiving_ata_if_missing, escrowed_lamports, gross_amount, harvest_withheld_fees,
+    has_transfer_fee, is_native_mint, memo_if_required, mint_decimals, token_balance, transfer_fee,
+    transfer_lamports, transfer_programmable_nft, transfer_sol, transfer_tokens,
+    transfer_tokens_with_hook, transfer_with_fee, ui_amount_to_amount, update_transfer_fee_amounts,
+};
+
 use {
-    borsh::{BorshDeserialize, BorshSerialize},
+    borsh::BorshSerialize,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
+        clock::Clock,
         entrypoint::ProgramResult,
-        program::{invoke, invoke_signed},
+        program::invoke,
         program_error::ProgramError,
-        program_pack::Pack,
         pubkey::Pubkey,
         system_instruction,
         sysvar::rent::Rent,
@@ -17,12 +56,25 @@ use {
 
 use crate::{
     error::SwapError,
+    event::{Event, OrderAmountsChanged, OrderClosed, OrderCreated, OrderFilled},
     instruction::SwapInstruction,
-    state::SwapOrder,
+    oracle::{load_pyth_price, oracle_fee_amount, oracle_taker_amount},
+    state::{
+        Config, Discriminator, FillReceipt, OrderStatus, OrderType, SwapOrder, MAX_ALLOWED_TAKERS,
+        MAX_REFERRAL_BPS, MEMO_LEN, TAG_LEN,
+    },
     validation::{
-        get_order_pda, validate_authority, validate_init_amounts, validate_order_pda,
+        get_order_pda, get_pair_stats_pda, get_protocol_stats_pda, get_treasury_pda,
+        get_user_stats_pda, is_bundle, is_fee_exempt, load_config, load_fee_feed,
+        load_fill_receipt, load_pair_stats, load_protocol_stats, load_user_stats, mint_risk,
+        validate_account_token_program, validate_authority, validate_collection_member,
+        validate_expiry, validate_fill_amount, validate_group_member, validate_init_amounts,
+        validate_manager, validate_max_taker_amount, validate_memo, validate_mints_not_blocked,
+        validate_not_expired, validate_not_frozen, validate_not_paused, validate_order_open,
+        validate_order_pda, validate_pair_not_blocked, validate_protocol_not_paused,
         validate_rent_sysvar, validate_signer, validate_system_program, validate_taker,
-        validate_token_account, validate_token_mint, validate_token_program,
+        validate_taker_proof, validate_token_account, validate_token_gate, validate_token_mint,
+        validate_token_program, validate_trade_guards,
     },
 };
 
@@ -34,13 +86,36 @@ impl Processor {
         accounts: &[AccountInfo],
         instruction_data: &[u8],
     ) -> ProgramResult {
-        let instruction = SwapInstruction::try_from_slice(instruction_data)?;
+        // An event the program sent itself, see `Event::emit`. It only has to land in the
+        // transaction, so there is nothing to do beyond checking who sent it.
+        #[cfg(feature = "event-cpi")]
+        if instruction_data.starts_with(&crate::event::EVENT_IX_TAG) {
+            let authority_info = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            return crate::validation::validate_event_authority(program_id, authority_info);
+        }
+
+        let instruction = SwapInstruction::unpack(instruction_data)?;
 
         match instruction {
             SwapInstruction::InitializeOrder {
+                order_id,
+                maker_amount,
+                taker_amount,
+                requires_confirmation,
+                dual_escrow,
+                all_or_none,
+                memo,
+            } => Self::process_initialize_order(
+                program_id,
+                accounts,
+                order_id,
                 maker_amount,
                 taker_amount,
-            } => Self::process_initialize_order(program_id, accounts, maker_amount, taker_amount),
+                requires_confirmation,
+                dual_escrow,
+                all_or_none,
+                memo,
+            ),
             SwapInstruction::ChangeOrderAmounts {
                 new_maker_amount,
                 new_taker_amount,
@@ -51,18 +126,367 @@ impl Processor {
                 new_taker_amount,
             ),
             SwapInstruction::ChangeTaker { new_taker } => {
-                Self::process_change_taker(accounts, new_taker)
+                Self::process_change_taker(program_id, accounts, new_taker)
+            }
+            SwapInstruction::CompleteSwap {
+                fill_amount, memo, ..
+            } if accounts.get(1).is_some_and(is_bundle) => {
+                Self::process_complete_bundle(program_id, accounts, fill_amount, memo.as_deref())
+            }
+            SwapInstruction::CompleteSwap {
+                fill_amount,
+                max_taker_amount,
+                min_maker_amount,
+                taker_proof,
+                memo,
+            } => Self::process_complete_swap(
+                program_id,
+                accounts,
+                fill_amount,
+                max_taker_amount,
+                min_maker_amount,
+                &taker_proof,
+                memo.as_deref(),
+            ),
+            SwapInstruction::CloseOrder { memo } if accounts.get(1).is_some_and(is_bundle) => {
+                Self::process_close_bundle(program_id, accounts, memo.as_deref())
+            }
+            SwapInstruction::CloseOrder { memo } => {
+                Self::process_close_order(program_id, accounts, memo.as_deref())
+            }
+            SwapInstruction::AcceptOrder => Self::process_accept_order(program_id, accounts),
+            SwapInstruction::ConfirmSwap => Self::process_confirm_swap(program_id, accounts),
+            SwapInstruction::CancelAcceptance => {
+                Self::process_cancel_acceptance(program_id, accounts)
+            }
+            SwapInstruction::ProposeCounterOffer {
+                new_maker_amount,
+                new_taker_amount,
+            } => Self::process_propose_counter_offer(
+                program_id,
+                accounts,
+                new_maker_amount,
+                new_taker_amount,
+            ),
+            SwapInstruction::AcceptCounterOffer => {
+                Self::process_accept_counter_offer(program_id, accounts)
+            }
+            SwapInstruction::DepositTakerSide => {
+                Self::process_deposit_taker_side(program_id, accounts)
+            }
+            SwapInstruction::DeclineOrder => Self::process_decline_order(program_id, accounts),
+            SwapInstruction::PauseOrder => Self::process_set_paused(program_id, accounts, true),
+            SwapInstruction::ResumeOrder => Self::process_set_paused(program_id, accounts, false),
+            SwapInstruction::UpdateExpiry { new_expires_at } => {
+                Self::process_update_expiry(program_id, accounts, new_expires_at)
+            }
+            SwapInstruction::StartDutchAuction {
+                floor_taker_amount,
+                duration,
+            } => Self::process_start_dutch_auction(
+                program_id,
+                accounts,
+                floor_taker_amount,
+                duration,
+            ),
+            SwapInstruction::StartEnglishAuction { duration } => {
+                Self::process_start_english_auction(program_id, accounts, duration)
+            }
+            SwapInstruction::PlaceBid { amount } => {
+                Self::process_place_bid(program_id, accounts, amount)
+            }
+            SwapInstruction::SettleAuction => Self::process_settle_auction(program_id, accounts),
+            SwapInstruction::FillQuote { quote } => {
+                Self::process_fill_quote(program_id, accounts, quote)
+            }
+            SwapInstruction::SetPriceOracle { spread_bps } => {
+                Self::process_set_price_oracle(program_id, accounts, spread_bps)
+            }
+            SwapInstruction::SetAllOrNone { all_or_none } => {
+                Self::process_set_all_or_none(program_id, accounts, all_or_none)
+            }
+            SwapInstruction::CompleteSwapAndClose {
+                max_taker_amount,
+                min_maker_amount,
+            } => Self::process_complete_swap_and_close(
+                program_id,
+                accounts,
+                max_taker_amount,
+                min_maker_amount,
+            ),
+            SwapInstruction::CompleteSwapChain {
+                max_taker_amount,
+                min_maker_amount,
+            } => Self::process_complete_swap_chain(
+                program_id,
+                accounts,
+                max_taker_amount,
+                min_maker_amount,
+            ),
+            SwapInstruction::SettleRing => Self::process_settle_ring(program_id, accounts),
+            SwapInstruction::StartHtlc { hashlock, timeout } => {
+                Self::process_start_htlc(program_id, accounts, hashlock, timeout)
+            }
+            SwapInstruction::ClaimHtlc { preimage } => {
+                Self::process_claim_htlc(program_id, accounts, preimage)
+            }
+            SwapInstruction::StartSecretClaim {
+                hashlock,
+                taker_amount,
+            } => Self::process_start_secret_claim(program_id, accounts, hashlock, taker_amount),
+            SwapInstruction::ClaimSecret { preimage } => {
+                Self::process_claim_secret(program_id, accounts, preimage)
+            }
+            SwapInstruction::SetReferralFee { referral_bps } => {
+                Self::process_set_referral_fee(program_id, accounts, referral_bps)
+            }
+            SwapInstruction::InitializeConfig {
+                fee_bps,
+                fee_collector,
+            } => Self::process_initialize_config(program_id, accounts, fee_bps, fee_collector),
+            SwapInstruction::UpdateConfig {
+                fee_bps,
+                fee_collector,
+            } => Self::process_update_config(program_id, accounts, fee_bps, fee_collector),
+            SwapInstruction::SetProtocolPaused { paused } => {
+                Self::process_set_protocol_paused(program_id, accounts, paused)
+            }
+            SwapInstruction::SetTradeGuards {
+                allow_self_trade,
+                allow_same_mint,
+            } => Self::process_set_trade_guards(
+                program_id,
+                accounts,
+                allow_self_trade,
+                allow_same_mint,
+            ),
+            SwapInstruction::AddFeeExemption { account } => {
+                Self::process_add_fee_exemption(program_id, accounts, account)
+            }
+            SwapInstruction::RemoveFeeExemption { account } => {
+                Self::process_remove_fee_exemption(program_id, accounts, account)
+            }
+            SwapInstruction::BlockMint { mint } => {
+                Self::process_block_mint(program_id, accounts, mint)
+            }
+            SwapInstruction::UnblockMint { mint } => {
+                Self::process_unblock_mint(program_id, accounts, mint)
+            }
+            SwapInstruction::SetFeeMint { fee_mint } => {
+                Self::process_set_fee_mint(program_id, accounts, fee_mint)
+            }
+            SwapInstruction::SetFeeFeed { price_feed } => {
+                Self::process_set_fee_feed(program_id, accounts, price_feed)
+            }
+            SwapInstruction::InitializeUserStats { user } => {
+                Self::process_initialize_user_stats(program_id, accounts, user)
+            }
+            SwapInstruction::InitializeProtocolStats => {
+                Self::process_initialize_protocol_stats(program_id, accounts)
+            }
+            SwapInstruction::InitializePairStats {
+                maker_mint,
+                taker_mint,
+            } => Self::process_initialize_pair_stats(program_id, accounts, maker_mint, taker_mint),
+            SwapInstruction::InitializeRegistryPage {
+                maker_mint,
+                taker_mint,
+                page,
+            } => Self::process_initialize_registry_page(
+                program_id, accounts, maker_mint, taker_mint, page,
+            ),
+            SwapInstruction::InitializeTradeHistory {
+                maker_mint,
+                taker_mint,
+            } => {
+                Self::process_initialize_trade_history(program_id, accounts, maker_mint, taker_mint)
+            }
+            SwapInstruction::SetFeeTiers { tiers } => {
+                Self::process_set_fee_tiers(program_id, accounts, tiers)
+            }
+            SwapInstruction::InitializeTreasury => {
+                Self::process_initialize_treasury(program_id, accounts)
+            }
+            SwapInstruction::WithdrawFees { amount } => {
+                Self::process_withdraw_fees(program_id, accounts, amount)
             }
-            SwapInstruction::CompleteSwap => Self::process_complete_swap(program_id, accounts),
-            SwapInstruction::CloseOrder => Self::process_close_order(program_id, accounts),
+            SwapInstruction::UpdateOrderMetadata { memo } => {
+                Self::process_update_order_metadata(program_id, accounts, memo)
+            }
+            SwapInstruction::SetOrderTag { tag } => {
+                Self::process_set_order_tag(program_id, accounts, tag)
+            }
+            SwapInstruction::MigrateOrder => Self::process_migrate_order(program_id, accounts),
+            SwapInstruction::WrapSol { amount } => {
+                Self::process_wrap_sol(program_id, accounts, amount)
+            }
+            SwapInstruction::UnwrapSol => Self::process_unwrap_sol(program_id, accounts),
+            SwapInstruction::SetUiAmounts { ui_amounts } => {
+                Self::process_set_ui_amounts(program_id, accounts, ui_amounts)
+            }
+            SwapInstruction::InitializeNftOrder {
+                order_id,
+                taker_amount,
+            } => Self::process_initialize_nft_order(program_id, accounts, order_id, taker_amount),
+            SwapInstruction::InitializeBundle {
+                bundle_id,
+                maker_amounts,
+                taker_amounts,
+            } => Self::process_initialize_bundle(
+                program_id,
+                accounts,
+                bundle_id,
+                &maker_amounts,
+                &taker_amounts,
+            ),
+            SwapInstruction::SetTakerCollection { enabled } => {
+                Self::process_set_taker_collection(program_id, accounts, enabled)
+            }
+            SwapInstruction::SetTokenGroups {
+                maker_group,
+                taker_group,
+            } => Self::process_set_token_groups(program_id, accounts, maker_group, taker_group),
+            SwapInstruction::InitializeCompressedOrder {
+                order_id,
+                taker_amount,
+                root,
+                data_hash,
+                creator_hash,
+                nonce,
+                index,
+            } => Self::process_initialize_compressed_order(
+                program_id,
+                accounts,
+                order_id,
+                taker_amount,
+                root,
+                data_hash,
+                creator_hash,
+                nonce,
+                index,
+            ),
+            SwapInstruction::CompleteCompressedSwap {
+                root,
+                max_taker_amount,
+                memo,
+            } => Self::process_complete_compressed_swap(
+                program_id,
+                accounts,
+                root,
+                max_taker_amount,
+                memo.as_deref(),
+            ),
+            SwapInstruction::CloseCompressedOrder { root } => {
+                Self::process_close_compressed_order(program_id, accounts, root)
+            }
+            SwapInstruction::InitializeOrders { orders } => {
+                Self::process_initialize_orders(program_id, accounts, &orders)
+            }
+            SwapInstruction::CloseOrders { count } => {
+                Self::process_close_orders(program_id, accounts, count)
+            }
+            SwapInstruction::CompleteSwaps { fills } => {
+                Self::process_complete_swaps(program_id, accounts, &fills)
+            }
+            SwapInstruction::UpdateOrder {
+                new_maker_amount,
+                new_taker_amount,
+                new_taker,
+                expires_at,
+            } => Self::process_update_order(
+                program_id,
+                accounts,
+                new_maker_amount,
+                new_taker_amount,
+                new_taker,
+                expires_at,
+            ),
+            SwapInstruction::ReapExpiredOrder => {
+                Self::process_reap_expired_order(program_id, accounts)
+            }
+            SwapInstruction::ExtendOrder {
+                expires_at,
+                memo,
+                taker_merkle_root,
+            } => Self::process_extend_order(
+                program_id,
+                accounts,
+                expires_at,
+                memo,
+                taker_merkle_root,
+            ),
+            SwapInstruction::SetVesting { cliff, duration } => {
+                Self::process_set_vesting(program_id, accounts, cliff, duration)
+            }
+            SwapInstruction::ClaimVested => Self::process_claim_vested(program_id, accounts),
+            SwapInstruction::SetStreaming { tranches, interval } => {
+                Self::process_set_streaming(program_id, accounts, tranches, interval)
+            }
+            SwapInstruction::SettleTranche => Self::process_settle_tranche(program_id, accounts),
+            SwapInstruction::SetRecurrence { interval, renewals } => {
+                Self::process_set_recurrence(program_id, accounts, interval, renewals)
+            }
+            SwapInstruction::RenewOrder => Self::process_renew_order(program_id, accounts),
+            SwapInstruction::CloneOrder {
+                order_id,
+                maker_amount,
+                taker_amount,
+            } => Self::process_clone_order(
+                program_id,
+                accounts,
+                order_id,
+                maker_amount,
+                taker_amount,
+            ),
+            SwapInstruction::TransferOrderAuthority { new_maker } => {
+                Self::process_transfer_order_authority(program_id, accounts, new_maker)
+            }
+            SwapInstruction::SetDelegate { delegate } => {
+                Self::process_set_delegate(program_id, accounts, delegate)
+            }
+            SwapInstruction::SetAllowedTakers { takers } => {
+                Self::process_set_allowed_takers(program_id, accounts, takers)
+            }
+            SwapInstruction::SetTakerMerkleRoot { root } => {
+                Self::process_set_taker_merkle_root(program_id, accounts, root)
+            }
+            SwapInstruction::SetTokenGate {
+                gate_mint,
+                min_balance,
+            } => Self::process_set_token_gate(program_id, accounts, gate_mint, min_balance),
+            SwapInstruction::SetCommitReveal { enabled } => {
+                Self::process_set_commit_reveal(program_id, accounts, enabled)
+            }
+            SwapInstruction::CommitFill { commitment } => {
+                Self::process_commit_fill(program_id, accounts, commitment)
+            }
+            SwapInstruction::RevealFill {
+                salt,
+                fill_amount,
+                max_taker_amount,
+                min_maker_amount,
+            } => Self::process_reveal_fill(
+                program_id,
+                accounts,
+                salt,
+                fill_amount,
+                max_taker_amount,
+                min_maker_amount,
+            ),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn process_initialize_order(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
+        order_id: u64,
         maker_amount: u64,
         taker_amount: u64,
+        requires_confirmation: bool,
+        dual_escrow: bool,
+        all_or_none: bool,
+        memo: [u8; MEMO_LEN],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let maker_info = next_account_info(account_info_iter)?;
@@ -74,90 +498,126 @@ impl Processor {
         let taker_mint_info = next_account_info(account_info_iter)?;
         let system_program_info = next_account_info(account_info_iter)?;
         let rent_info = next_account_info(account_info_iter)?;
-        let token_program = next_account_info(account_info_iter)?;
+        let maker_token_program = next_account_info(account_info_iter)?;
+        let taker_token_program = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
 
         validate_signer(maker_info)?;
-        validate_init_amounts(maker_amount, taker_amount)?;
-        validate_token_mint(maker_mint_info)?;
-        validate_token_mint(taker_mint_info)?;
-        check_spl_token_program_account(token_program.key)?;
-        validate_token_program(maker_mint_info, token_program.key)?;
-        validate_token_account(maker_mint_ata_info, maker_info.key, maker_mint_info.key)?;
-        validate_system_program(system_program_info.key)?;
-        validate_rent_sysvar(rent_info.key)?;
-        validate_token_account(
-            order_maker_mint_ata_info,
-            order_account_info.key,
+        validate_protocol_not_paused(program_id, config_info)?;
+        // Before anything moves into the escrow: initializing over a live order would
+        // overwrite its terms
+        let (order_pda, bump) = get_order_pda(
+            program_id,
+            maker_info.key,
             maker_mint_info.key,
+            taker_mint_info.key,
+            order_id,
         )?;
-
-        let (_, bump) = get_order_pda(
+        if order_pda != *order_account_info.key {
+            return Err(SwapError::InvalidOrderAddress.into());
+        }
+        validate_uninitialized(order_account_info, SwapError::OrderAlreadyInitialized)?;
+        validate_init_amounts(maker_amount, taker_amount)?;
+        validate_memo(&memo)?;
+        validate_mints_not_blocked(
             program_id,
+            accounts,
+            &[maker_mint_info.key, taker_mint_info.key],
+        )?;
+        validate_trade_guards(
+            program_id,
+            Some(config_info),
             maker_info.key,
+            taker_info.key,
             maker_mint_info.key,
             taker_mint_info.key,
         )?;
-
-        let rent = Rent::from_account_info(rent_info)?;
-        let space = SwapOrder::LEN;
-        let rent_lamports = rent.minimum_balance(space);
-
-        invoke_signed(
-            &system_instruction::create_account(
-                maker_info.key,
+        // The rent payer follows the taker-mint escrow of a dual-escrow order
+        let payer_info = rent_payer(accounts, 12 + usize::from(dual_escrow), maker_info);
+        let maker_is_native = is_native_mint(maker_mint_info.key);
+        let taker_is_native = is_native_mint(taker_mint_info.key);
+        if !maker_is_native {
+            validate_token_mint(maker_mint_info)?;
+        }
+        if !taker_is_native {
+            validate_token_mint(taker_mint_info)?;
+        }
+        check_spl_token_program_account(maker_token_program.key)?;
+        check_spl_token_program_account(taker_token_program.key)?;
+        if !taker_is_native {
+            validate_token_program(taker_mint_info, taker_token_program.key)?;
+        }
+        validate_system_program(system_program_info.key)?;
+        validate_rent_sysvar(rent_info.key)?;
+        if maker_is_native {
+            // Native SOL is paid from the maker's wallet into the order PDA itself
+            if maker_mint_ata_info.key != maker_info.key
+                || order_maker_mint_ata_info.key != order_account_info.key
+            {
+                return Err(SwapError::InvalidTokenAccount.into());
+            }
+        } else {
+            validate_token_program(maker_mint_info, maker_token_program.key)?;
+            validate_token_account(maker_mint_ata_info, maker_info.key, maker_mint_info.key)?;
+            create_escrow_if_missing(
+                program_id,
+                payer_info,
+                order_maker_mint_ata_info,
+                order_account_info,
+                maker_mint_info,
+                system_program_info,
+                maker_token_program,
+                accounts,
+            )?;
+            validate_token_account(
+                order_maker_mint_ata_info,
                 order_account_info.key,
-                rent_lamports,
-                space as u64,
+                maker_mint_info.key,
+            )?;
+        }
+        if (maker_is_native && taker_is_native)
+            || (dual_escrow && (maker_is_native || taker_is_native))
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        if dual_escrow {
+            let order_taker_mint_ata_info = next_account_info(account_info_iter)?;
+            create_escrow_if_missing(
                 program_id,
-            ),
+                payer_info,
+                order_taker_mint_ata_info,
+                order_account_info,
+                taker_mint_info,
+                system_program_info,
+                taker_token_program,
+                accounts,
+            )?;
+            validate_token_account(
+                order_taker_mint_ata_info,
+                order_account_info.key,
+                taker_mint_info.key,
+            )?;
+            validate_account_token_program(order_taker_mint_ata_info, taker_token_program.key)?;
+            validate_not_frozen(order_taker_mint_ata_info)?;
+        }
+
+        create_pda_account(
+            payer_info,
+            order_account_info,
+            system_program_info,
+            SwapOrder::LEN,
+            program_id,
             &[
-                maker_info.clone(),
-                order_account_info.clone(),
-                system_program_info.clone(),
-            ],
-            &[&[
                 b"order",
                 maker_info.key.as_ref(),
                 maker_mint_info.key.as_ref(),
                 taker_mint_info.key.as_ref(),
+                &order_id.to_le_bytes(),
                 &[bump],
-            ]],
-        )?;
-
-        let transfer_instruction = if *token_program.key == spl_token::id() {
-            spl_token::instruction::transfer(
-                token_program.key,
-                maker_mint_ata_info.key,
-                order_maker_mint_ata_info.key,
-                maker_info.key,
-                &[],
-                maker_amount,
-            )?
-        } else {
-            let account_data = spl_token_2022::state::Mint::unpack(&maker_mint_info.data.borrow())?;
-            spl_token_2022::instruction::transfer_checked(
-                token_program.key,
-                maker_mint_ata_info.key,
-                maker_mint_info.key,
-                order_maker_mint_ata_info.key,
-                maker_info.key,
-                &[],
-                maker_amount,
-                account_data.decimals,
-            )?
-        };
-
-        invoke(
-            &transfer_instruction,
-            &[
-                maker_mint_ata_info.clone(),
-                order_maker_mint_ata_info.clone(),
-                maker_info.clone(),
-                token_program.clone(),
             ],
         )?;
 
-        let order = SwapOrder::new(
+        let mut order = SwapOrder::new(
             *maker_info.key,
             *taker_info.key,
             *maker_mint_info.key,
@@ -165,13 +625,187 @@ impl Processor {
             maker_amount,
             taker_amount,
             bump,
+            order_id,
         );
+        order.requires_confirmation = requires_confirmation;
+        order.dual_escrow = dual_escrow;
+        order.all_or_none = all_or_none;
+        order.memo = memo;
+        if payer_info.key != maker_info.key {
+            order.rent_payer = *payer_info.key;
+        }
+        order.maker_decimals = mint_decimals(maker_mint_info)?;
+        order.taker_decimals = mint_decimals(taker_mint_info)?;
+        order.maker_mint_risk = mint_risk(maker_mint_info)?;
+        order.taker_mint_risk = mint_risk(taker_mint_info)?;
+        update_transfer_fee_amounts(&mut order, maker_mint_info, taker_mint_info)?;
+
+        if maker_is_native {
+            transfer_sol(
+                system_program_info,
+                maker_info,
+                order_account_info,
+                maker_amount,
+            )?;
+        } else {
+            // Makers whose account has CPI guard enabled cannot be pulled from, so they
+            // pre-fund the escrow themselves and it is reconciled as is. A pre-funded
+            // escrow may be frozen, as programmable NFT accounts are.
+            if token_balance(order_maker_mint_ata_info)? < maker_amount {
+                validate_not_frozen(maker_mint_ata_info)?;
+                validate_not_frozen(order_maker_mint_ata_info)?;
+                // The maker covers the transfer fee so the escrow holds the full maker amount
+                transfer_tokens_with_hook(
+                    maker_token_program,
+                    maker_mint_ata_info,
+                    maker_mint_info,
+                    order_maker_mint_ata_info,
+                    maker_info,
+                    accounts,
+                    order.maker_amount_gross,
+                    order.maker_decimals,
+                    &[],
+                )?;
+            }
+            if token_balance(order_maker_mint_ata_info)? < maker_amount {
+                return Err(SwapError::InsufficientFunds.into());
+            }
+        }
+
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        let index_info = find_maker_index(program_id, accounts, maker_info.key)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        index_order(
+            program_id,
+            index_info,
+            payer_info,
+            system_program_info,
+            maker_info.key,
+            order_account_info.key,
+        )?;
+        register_order(
+            program_id,
+            accounts,
+            payer_info,
+            system_program_info,
+            order_account_info.key,
+            &order,
+        )?;
 
-        order.serialize(&mut *order_account_info.data.borrow_mut())?;
+        OrderCreated {
+            order: *order_account_info.key,
+            maker: *maker_info.key,
+            taker: *taker_info.key,
+            maker_mint: *maker_mint_info.key,
+            taker_mint: *taker_mint_info.key,
+            order_id,
+            maker_amount,
+            taker_amount,
+        }
+        .emit(program_id, accounts)?;
 
         Ok(())
     }
 
+    fn process_clone_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        order_id: u64,
+        maker_amount: Option<u64>,
+        taker_amount: Option<u64>,
+    ) -> ProgramResult {
+        // The new order takes the `InitializeOrder` accounts, followed by the source order
+        const INIT_ACCOUNTS: usize = 12;
+        if accounts.len() <= INIT_ACCOUNTS {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let (init_accounts, source_accounts) = accounts.split_at(INIT_ACCOUNTS);
+        let maker_info = &init_accounts[0];
+        let taker_info = &init_accounts[4];
+        let maker_mint_info = &init_accounts[5];
+        let taker_mint_info = &init_accounts[6];
+
+        let source_info = &source_accounts[0];
+        let (
+            maker,
+            taker,
+            maker_mint,
+            taker_mint,
+            (recorded_maker_amount, recorded_taker_amount),
+            requires_confirmation,
+            all_or_none,
+            memo,
+        ) = if FillReceipt::has_discriminator(&source_info.data.borrow()) {
+            let receipt = load_fill_receipt(program_id, source_info)?;
+            // A receipt names whoever filled the order, open or not
+            let taker = if *taker_info.key == Pubkey::default() {
+                Pubkey::default()
+            } else {
+                receipt.taker
+            };
+            let (maker_mint, taker_mint) = (receipt.maker_mint, receipt.taker_mint);
+            (
+                receipt.maker,
+                taker,
+                maker_mint,
+                taker_mint,
+                (receipt.maker_amount, receipt.taker_amount),
+                false,
+                false,
+                [0; MEMO_LEN],
+            )
+        } else {
+            let (source, _) = validate_order_pda(program_id, source_info)?;
+            // A UI-amount source is cloned at the raw amounts its terms are worth now;
+            // the mints are checked against the source below
+            let amounts = if source.ui_amounts {
+                let now = Clock::get()?.unix_timestamp;
+                (
+                    ui_amount_to_amount(maker_mint_info, source.maker_amount, now)?,
+                    ui_amount_to_amount(taker_mint_info, source.taker_amount, now)?,
+                )
+            } else {
+                (source.maker_amount, source.taker_amount)
+            };
+            (
+                source.maker,
+                source.taker,
+                source.maker_token_mint,
+                source.taker_token_mint,
+                amounts,
+                source.requires_confirmation,
+                source.all_or_none,
+                source.memo,
+            )
+        };
+        validate_signer(maker_info)?;
+        if *maker_info.key != maker {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        if *taker_info.key != taker
+            || *maker_mint_info.key != maker_mint
+            || *taker_mint_info.key != taker_mint
+        {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+
+        // The PDAs `InitializeOrder` looks up by key follow the source order
+        let mut order_accounts = init_accounts.to_vec();
+        order_accounts.extend_from_slice(&source_accounts[1..]);
+        Self::process_initialize_order(
+            program_id,
+            &order_accounts,
+            order_id,
+            maker_amount.unwrap_or(recorded_maker_amount),
+            taker_amount.unwrap_or(recorded_taker_amount),
+            requires_confirmation,
+            false,
+            all_or_none,
+            memo,
+        )
+    }
+
     fn process_change_order_amounts(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
@@ -184,396 +818,1484 @@ impl Processor {
         let order_token_account = next_account_info(account_info_iter)?;
         let maker_token_account = next_account_info(account_info_iter)?;
         let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
 
         let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
 
-        validate_authority(maker_info, &order)?;
+        validate_manager(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if order.order_type == OrderType::DutchAuction
+            && new_taker_amount <= order.floor_taker_amount
+        {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        // Terms are locked once an auction has attracted a bid
+        if order.has_live_bid() {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        if order.order_type == OrderType::Htlc {
+            return Err(SwapError::HtlcLocked.into());
+        }
+        if order.order_type == OrderType::SecretClaim {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        // Top-ups and refunds are measured in raw amounts
+        if order.ui_amounts {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        // Only the price of an NFT can change
+        if order.nft && new_maker_amount != 1 {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        // A collection order asks for exactly one NFT
+        if order.taker_collection && new_taker_amount != 1 {
+            return Err(SwapError::InvalidAmount.into());
+        }
         check_spl_token_program_account(token_program.key)?;
         validate_token_account(
             order_token_account,
             order_account_info.key,
             &order.maker_token_mint,
         )?;
-        validate_token_account(
-            order_token_account,
-            order_account_info.key,
-            &order.maker_token_mint,
-        )?;
+        // Refunds only ever go back to the maker, even when a delegate signs
+        validate_token_account(maker_token_account, &order.maker, &order.maker_token_mint)?;
 
         // Get current escrow balance
-        let escrow_token_data =
-            spl_token::state::Account::unpack(&order_token_account.data.borrow())?;
-        let current_escrow_amount = escrow_token_data.amount;
+        let current_escrow_amount = token_balance(order_token_account)?;
 
         match new_maker_amount.cmp(&current_escrow_amount) {
             std::cmp::Ordering::Greater => {
-                // Need to transfer additional tokens to escrow
-                let additional_amount = new_maker_amount - current_escrow_amount;
-
-                if *token_program.key == spl_token::id() {
-                    invoke(
-                        &spl_token::instruction::transfer(
-                            token_program.key,
-                            maker_token_account.key,
-                            order_token_account.key,
-                            maker_info.key,
-                            &[],
-                            additional_amount,
-                        )?,
-                        &[
-                            maker_token_account.clone(),
-                            order_token_account.clone(),
-                            maker_info.clone(),
-                            token_program.clone(),
-                        ],
-                    )?;
-                } else {
-                    invoke(
-                        &spl_token_2022::instruction::transfer(
-                            token_program.key,
-                            maker_token_account.key,
-                            order_token_account.key,
-                            maker_info.key,
-                            &[],
-                            additional_amount,
-                        )?,
-                        &[
-                            maker_token_account.clone(),
-                            order_token_account.clone(),
-                            maker_info.clone(),
-                            token_program.clone(),
-                        ],
-                    )?;
-                }
+                // Need to transfer additional tokens to escrow, plus any transfer fee
+                transfer_tokens(
+                    token_program,
+                    maker_token_account,
+                    maker_mint_info,
+                    order_token_account,
+                    maker_info,
+                    gross_amount(maker_mint_info, new_maker_amount - current_escrow_amount)?,
+                    order.maker_decimals,
+                    &[],
+                )?;
             }
             std::cmp::Ordering::Less => {
                 // Need to refund tokens to maker
-                let refund_amount = current_escrow_amount - new_maker_amount;
-
-                if *token_program.key == spl_token::id() {
-                    invoke_signed(
-                        &spl_token::instruction::transfer(
-                            token_program.key,
-                            order_token_account.key,
-                            maker_token_account.key,
-                            order_account_info.key,
-                            &[],
-                            refund_amount,
-                        )?,
-                        &[
-                            order_token_account.clone(),
-                            maker_token_account.clone(),
-                            order_account_info.clone(),
-                            token_program.clone(),
-                        ],
-                        &[&[
-                            b"order",
-                            maker_info.key.as_ref(),
-                            &order.maker_token_mint.to_bytes(),
-                            &order.taker_token_mint.to_bytes(),
-                            &[order.bump],
-                        ]],
-                    )?;
-                } else {
-                    invoke_signed(
-                        &spl_token_2022::instruction::transfer(
-                            token_program.key,
-                            order_token_account.key,
-                            maker_token_account.key,
-                            order_account_info.key,
-                            &[],
-                            refund_amount,
-                        )?,
-                        &[
-                            order_token_account.clone(),
-                            maker_token_account.clone(),
-                            order_account_info.clone(),
-                            token_program.clone(),
-                        ],
-                        &[&[
-                            b"order",
-                            maker_info.key.as_ref(),
-                            &order.maker_token_mint.to_bytes(),
-                            &order.taker_token_mint.to_bytes(),
-                            &[order.bump],
-                        ]],
-                    )?;
-                }
+                let order_id = order.order_id.to_le_bytes();
+                transfer_tokens(
+                    token_program,
+                    order_token_account,
+                    maker_mint_info,
+                    maker_token_account,
+                    order_account_info,
+                    current_escrow_amount - new_maker_amount,
+                    order.maker_decimals,
+                    &[&[
+                        b"order",
+                        order.seed_maker.as_ref(),
+                        order.maker_token_mint.as_ref(),
+                        order.taker_token_mint.as_ref(),
+                        &order_id,
+                        &[order.bump],
+                    ]],
+                )?;
             }
             std::cmp::Ordering::Equal => {} // No token transfer needed
         }
 
         order.maker_amount = new_maker_amount;
         order.taker_amount = new_taker_amount;
-        order.serialize(&mut *order_account_info.data.borrow_mut())?;
+        update_transfer_fee_amounts(&mut order, maker_mint_info, taker_mint_info)?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+        sync_registry_entry(program_id, accounts, order_account_info.key, &order)?;
+
+        OrderAmountsChanged {
+            order: *order_account_info.key,
+            maker_amount: new_maker_amount,
+            taker_amount: new_taker_amount,
+        }
+        .emit(program_id, accounts)?;
 
         Ok(())
     }
 
-    fn process_change_taker(accounts: &[AccountInfo], new_taker: [u8; 32]) -> ProgramResult {
+    fn process_change_taker(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_taker: [u8; 32],
+    ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let maker_info = next_account_info(account_info_iter)?;
         let order_account_info = next_account_info(account_info_iter)?;
         let new_taker_info = next_account_info(account_info_iter)?;
+        let config_info = account_info_iter.next();
 
-        let mut order = SwapOrder::try_from_slice(&order_account_info.data.borrow())?;
-        validate_authority(maker_info, &order)?;
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_manager(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if order.order_type == OrderType::Htlc {
+            return Err(SwapError::HtlcLocked.into());
+        }
+        if order.has_live_bid() {
+            return Err(SwapError::InvalidOrderState.into());
+        }
 
         if Pubkey::new_from_array(new_taker) != *new_taker_info.key {
             return Err(ProgramError::InvalidArgument);
         }
+        validate_trade_guards(
+            program_id,
+            config_info,
+            &{ order.maker },
+            new_taker_info.key,
+            &{ order.maker_token_mint },
+            &{ order.taker_token_mint },
+        )?;
 
         order.taker = Pubkey::new_from_array(new_taker);
-        order.serialize(&mut *order_account_info.data.borrow_mut())?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
 
         Ok(())
     }
 
-    fn process_complete_swap(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let taker_info = next_account_info(account_info_iter)?;
-        let order_account_info = next_account_info(account_info_iter)?;
-        let maker_taker_mint_ata = next_account_info(account_info_iter)?;
-        let taker_sending_ata = next_account_info(account_info_iter)?;
-        let taker_maker_mint_ata = next_account_info(account_info_iter)?;
-        let order_maker_token_ata = next_account_info(account_info_iter)?;
-        let token_program = next_account_info(account_info_iter)?;
+    fn process_update_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_maker_amount: Option<u64>,
+        new_taker_amount: Option<u64>,
+        new_taker: Option<Pubkey>,
+        expires_at: Option<i64>,
+    ) -> ProgramResult {
+        let [maker_info, order_account_info, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let changes_amounts = new_maker_amount.is_some() || new_taker_amount.is_some();
+        // The config follows the `ChangeOrderAmounts` accounts when those are passed
+        let config_info = accounts.get(if changes_amounts { 7 } else { 2 });
 
         let (order, _) = validate_order_pda(program_id, order_account_info)?;
-        validate_taker(taker_info, &order)?;
-        check_spl_token_program_account(token_program.key)?;
-        validate_token_account(maker_taker_mint_ata, &order.maker, &order.taker_token_mint)?;
-        validate_token_account(
-            taker_maker_mint_ata,
-            taker_info.key,
-            &order.maker_token_mint,
+        if expires_at.is_some() {
+            validate_authority(maker_info, &order)?;
+        }
+        if changes_amounts {
+            Self::process_change_order_amounts(
+                program_id,
+                accounts,
+                new_maker_amount.unwrap_or(order.maker_amount),
+                new_taker_amount.unwrap_or(order.taker_amount),
+            )?;
+        }
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_manager(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if let Some(new_taker) = new_taker {
+            if order.order_type == OrderType::Htlc {
+                return Err(SwapError::HtlcLocked.into());
+            }
+            if order.has_live_bid() {
+                return Err(SwapError::InvalidOrderState.into());
+            }
+            validate_trade_guards(
+                program_id,
+                config_info,
+                &{ order.maker },
+                &new_taker,
+                &{ order.maker_token_mint },
+                &{ order.taker_token_mint },
+            )?;
+            order.taker = new_taker;
+        }
+        if let Some(expires_at) = expires_at {
+            validate_expiry(expires_at)?;
+            order.expires_at = expires_at;
+        }
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_transfer_order_authority(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_maker: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let config_info = account_info_iter.next();
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if new_maker == Pubkey::default() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        validate_trade_guards(
+            program_id,
+            config_info,
+            &new_maker,
+            &{ order.taker },
+            &{ order.maker_token_mint },
+            &{ order.taker_token_mint },
         )?;
-        validate_token_account(taker_sending_ata, taker_info.key, &order.taker_token_mint)?;
-        validate_token_account(
-            order_maker_token_ata,
-            order_account_info.key,
-            &order.maker_token_mint,
+
+        order.maker = new_maker;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_delegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        delegate: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+
+        order.delegate = delegate;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_allowed_takers(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        takers: Vec<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let config_info = account_info_iter.next();
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_manager(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if order.order_type == OrderType::Htlc {
+            return Err(SwapError::HtlcLocked.into());
+        }
+        if takers.len() > MAX_ALLOWED_TAKERS {
+            return Err(SwapError::TooManyTakers.into());
+        }
+        for taker in &takers {
+            validate_trade_guards(
+                program_id,
+                config_info,
+                &{ order.maker },
+                taker,
+                &{ order.maker_token_mint },
+                &{ order.taker_token_mint },
+            )?;
+        }
+
+        order.allowed_takers = [Pubkey::default(); MAX_ALLOWED_TAKERS];
+        order.allowed_takers[..takers.len()].copy_from_slice(&takers);
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_taker_merkle_root(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        root: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_manager(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if order.order_type == OrderType::Htlc {
+            return Err(SwapError::HtlcLocked.into());
+        }
+
+        order.taker_merkle_root = root;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_token_gate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        gate_mint: Pubkey,
+        min_balance: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_manager(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if gate_mint != Pubkey::default() && min_balance == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        order.gate_mint = gate_mint;
+        order.gate_min_balance = min_balance;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_referral_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        referral_bps: u16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if referral_bps > MAX_REFERRAL_BPS {
+            return Err(SwapError::InvalidAmount.into());
+        }
+
+        // Fills pay the cut to the referrer named here and nobody else
+        order.referrer = if referral_bps > 0 {
+            *next_account_info(account_info_iter)?.key
+        } else {
+            Pubkey::default()
+        };
+        order.referral_bps = referral_bps;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_update_order_metadata(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        memo: [u8; MEMO_LEN],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let manager_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_manager(manager_info, &order)?;
+        validate_memo(&memo)?;
+
+        order.memo = memo;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_order_tag(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        tag: [u8; TAG_LEN],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+
+        order.tag = tag;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_ui_amounts(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        ui_amounts: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_manager(maker_info, &order)?;
+        validate_order_open(&order)?;
+        // Only CompleteSwap converts UI amounts; escrowed taker deposits, confirmed swaps
+        // and other pricing schemes stay in raw amounts
+        if order.order_type != OrderType::Fixed
+            || order.dual_escrow
+            || order.requires_confirmation
+            || order.has_vesting()
+            || order.nft
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        if *maker_mint_info.key != order.maker_token_mint
+            || *taker_mint_info.key != order.taker_token_mint
+        {
+            return Err(SwapError::InvalidMint.into());
+        }
+        if order.ui_amounts == ui_amounts {
+            return Ok(());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let convert = if ui_amounts {
+            amount_to_ui_amount
+        } else {
+            ui_amount_to_amount
+        };
+        order.maker_amount = convert(maker_mint_info, order.maker_amount, now)?;
+        order.taker_amount = convert(taker_mint_info, order.taker_amount, now)?;
+        validate_init_amounts(order.maker_amount, order.taker_amount)?;
+        order.ui_amounts = ui_amounts;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_decline_order(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        // Only a designated taker can decline; open orders have nobody to decline them
+        if order.accepts_any_taker() {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        validate_taker(taker_info, &order)?;
+        validate_order_open(&order)?;
+
+        order.status = OrderStatus::Declined;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_paused(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        paused: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if order.paused == paused {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+
+        order.paused = paused;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_set_all_or_none(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        all_or_none: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+
+        order.all_or_none = all_or_none;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_update_expiry(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_expires_at: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        validate_expiry(new_expires_at)?;
+
+        order.expires_at = new_expires_at;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn process_complete_swap(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fill_amount: u64,
+        max_taker_amount: u64,
+        min_maker_amount: u64,
+        taker_proof: &[[u8; 32]],
+        memo: Option<&str>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let maker_taker_mint_ata = next_account_info(account_info_iter)?;
+        let taker_sending_ata = next_account_info(account_info_iter)?;
+        let taker_maker_mint_ata = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let maker_token_program = next_account_info(account_info_iter)?;
+        let taker_token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let fee_account = next_account_info(account_info_iter)?;
+        let fee_exemption_info = next_account_info(account_info_iter)?;
+        let maker_stats_info = next_account_info(account_info_iter)?;
+        let taker_stats_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let protocol_stats_info = next_account_info(account_info_iter)?;
+        let pair_stats_info = next_account_info(account_info_iter)?;
+
+        validate_protocol_not_paused(program_id, config_info)?;
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_taker_proof(taker_info, &order, taker_proof)?;
+        if *maker_mint_info.key != order.maker_token_mint {
+            return Err(SwapError::InvalidMint.into());
+        }
+        // Collection and taker-group orders take any member mint as the taker side
+        if order.taker_collection {
+            validate_collection_member(taker_mint_info, &order.taker_token_mint, accounts)?;
+        } else if order.taker_group != Pubkey::default() {
+            validate_group_member(taker_mint_info, &order.taker_group)?;
+        } else if *taker_mint_info.key != order.taker_token_mint {
+            return Err(SwapError::InvalidMint.into());
+        }
+        // Orders opened before a mint was blocked can no longer be filled in it
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            maker_mint_info.key,
+            taker_mint_info.key,
         )?;
+        if order.commit_reveal && order.revealed_taker != *taker_info.key {
+            return Err(SwapError::CommitmentRequired.into());
+        }
+        validate_not_paused(&order)?;
+        validate_not_expired(&order)?;
+        if order.requires_confirmation {
+            return Err(SwapError::ConfirmationRequired.into());
+        }
+        if matches!(
+            order.order_type,
+            OrderType::EnglishAuction | OrderType::Htlc | OrderType::SecretClaim
+        ) {
+            return Err(SwapError::UnsupportedOrderType.into());
+        }
+        if order.dual_escrow {
+            // The taker side must already sit in the order's taker-mint escrow
+            if order.status != OrderStatus::Accepted || order.accepted_taker != *taker_info.key {
+                return Err(SwapError::TakerDepositRequired.into());
+            }
+            if order.is_streaming() {
+                return Err(SwapError::InvalidOrderState.into());
+            }
+        } else {
+            validate_order_open(&order)?;
+        }
+        let fill_amount = if fill_amount == 0 {
+            order.remaining()
+        } else {
+            fill_amount
+        };
+        validate_fill_amount(&order, fill_amount)?;
+        // UI-amount orders settle the raw amounts their UI amounts are worth right now
+        let now = Clock::get()?.unix_timestamp;
+        let maker_fill = if order.ui_amounts {
+            ui_amount_to_amount(maker_mint_info, fill_amount, now)?
+        } else {
+            fill_amount
+        };
+        check_spl_token_program_account(maker_token_program.key)?;
+        check_spl_token_program_account(taker_token_program.key)?;
+        if order.taker_is_native() {
+            if *maker_taker_mint_ata.key != order.maker {
+                return Err(SwapError::InvalidTokenAccount.into());
+            }
+        } else {
+            // A maker without a receiving account gets one, paid by the taker
+            create_receiving_ata_if_missing(
+                taker_info,
+                maker_taker_mint_ata,
+                &order.maker,
+                taker_mint_info,
+                taker_token_program,
+                accounts,
+            )?;
+            validate_token_account(maker_taker_mint_ata, &order.maker, taker_mint_info.key)?;
+            validate_account_token_program(maker_taker_mint_ata, taker_token_program.key)?;
+        }
 
         // Verify we have enough tokens in escrow
-        let escrow_token_data =
-            spl_token::state::Account::unpack(&order_maker_token_ata.data.borrow())?;
-        if escrow_token_data.amount < order.maker_amount {
+        let escrowed = if order.maker_is_native() {
+            // Native SOL sits in the order PDA and goes straight to the taker's wallet
+            if order_maker_token_ata.key != order_account_info.key
+                || taker_maker_mint_ata.key != taker_info.key
+            {
+                return Err(SwapError::InvalidTokenAccount.into());
+            }
+            if order.has_vesting() {
+                return Err(SwapError::InvalidOrderState.into());
+            }
+            escrowed_lamports(order_account_info)?
+        } else {
+            // Token Metadata creates and checks the taker's account for a programmable NFT
+            if !order.programmable {
+                create_receiving_ata_if_missing(
+                    taker_info,
+                    taker_maker_mint_ata,
+                    taker_info.key,
+                    maker_mint_info,
+                    maker_token_program,
+                    accounts,
+                )?;
+                validate_token_account(
+                    taker_maker_mint_ata,
+                    taker_info.key,
+                    &order.maker_token_mint,
+                )?;
+            }
+            validate_token_account(
+                order_maker_token_ata,
+                order_account_info.key,
+                &order.maker_token_mint,
+            )?;
+            validate_account_token_program(order_maker_token_ata, maker_token_program.key)?;
+            token_balance(order_maker_token_ata)?
+        };
+        if escrowed < maker_fill {
             return Err(SwapError::InsufficientFunds.into());
         }
 
-        if *token_program.key == spl_token::id() {
-            invoke(
-                &spl_token::instruction::transfer(
-                    token_program.key,
-                    taker_sending_ata.key,
-                    maker_taker_mint_ata.key,
-                    taker_info.key,
-                    &[],
-                    order.taker_amount,
-                )?,
-                &[
-                    taker_sending_ata.clone(),
-                    maker_taker_mint_ata.clone(),
-                    taker_info.clone(),
-                    token_program.clone(),
-                ],
+        let config = load_fee_config(program_id, config_info, fee_exemption_info, &order.maker)?;
+        let maker_stats = load_user_stats(program_id, maker_stats_info, &order.maker)?;
+        let taker_stats = load_user_stats(program_id, taker_stats_info, taker_info.key)?;
+        // Checked here, updated by `book_fill` once the fill settles
+        load_protocol_stats(program_id, protocol_stats_info)?;
+        load_pair_stats(
+            program_id,
+            pair_stats_info,
+            &order.maker_token_mint,
+            &order.taker_token_mint,
+        )?;
+        // The fee is charged at the tier of whoever pays it
+        let fee_payer_volume = match config.as_ref() {
+            Some(config) if config.has_fee_mint() => taker_stats.as_ref(),
+            _ => maker_stats.as_ref(),
+        }
+        .map_or(0, |stats| stats.volume);
+        if let Some(config) = config.as_ref() {
+            let fee_mint = if config.has_fee_mint() {
+                &config.fee_mint
+            } else {
+                taker_mint_info.key
+            };
+            // Treasuries are only ever created as token accounts of their mint
+            if get_treasury_pda(program_id, fee_mint).0 != *fee_account.key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+        }
+        // With a fee mint configured the taker side reaches the maker in full
+        let protocol_fee = |taker_amount| {
+            config
+                .as_ref()
+                .filter(|config| !config.has_fee_mint())
+                .map_or(0, |config| config.fee(taker_amount, fee_payer_volume))
+        };
+
+        let (taker_amount, quoted_taker_amount) = if order.dual_escrow {
+            validate_max_taker_amount(order.taker_amount, max_taker_amount)?;
+            (order.taker_amount, order.taker_amount)
+        } else {
+            // Auction and oracle orders are priced at the moment of the fill
+            let quoted_taker_amount = if order.order_type == OrderType::Oracle {
+                let price_info = next_account_info(account_info_iter)?;
+                if *price_info.key != order.price_feed {
+                    return Err(SwapError::InvalidOracle.into());
+                }
+                oracle_taker_amount(&order, fill_amount, &load_pyth_price(price_info)?, now)?
+            } else {
+                order.taker_amount_for(fill_amount, now)
+            };
+            let taker_amount = if order.ui_amounts {
+                ui_amount_to_amount(taker_mint_info, quoted_taker_amount, now)?
+            } else {
+                quoted_taker_amount
+            };
+            validate_max_taker_amount(taker_amount, max_taker_amount)?;
+            (taker_amount, quoted_taker_amount)
+        };
+
+        // The fill is settled on the order before any tokens move, so another fill of the
+        // same tokens, in this transaction or a later one, finds nothing left to take
+        order.record_fill(fill_amount, quoted_taker_amount)?;
+        if order.status != OrderStatus::Completed {
+            update_transfer_fee_amounts(&mut order, maker_mint_info, taker_mint_info)?;
+        }
+        order.revealed_taker = Pubkey::default();
+        let fill_index = order.fill_count;
+        order.fill_count += 1;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        if order.dual_escrow {
+            validate_token_account(
+                taker_sending_ata,
+                order_account_info.key,
+                &order.taker_token_mint,
             )?;
+            let order_id = order.order_id.to_le_bytes();
+            memo_if_required(maker_taker_mint_ata, memo, accounts)?;
+            transfer_with_fee(
+                taker_token_program,
+                taker_sending_ata,
+                taker_mint_info,
+                maker_taker_mint_ata,
+                fee_account,
+                order_account_info,
+                accounts,
+                taker_amount,
+                order.taker_decimals,
+                protocol_fee(taker_amount),
+                &[&[
+                    b"order",
+                    order.seed_maker.as_ref(),
+                    order.maker_token_mint.as_ref(),
+                    order.taker_token_mint.as_ref(),
+                    &order_id,
+                    &[order.bump],
+                ]],
+            )?;
+        } else if order.taker_is_native() {
+            // Native SOL takers pass the system program in place of a sending account
+            let fee = protocol_fee(taker_amount);
+            transfer_sol(
+                taker_sending_ata,
+                taker_info,
+                maker_taker_mint_ata,
+                taker_amount - fee,
+            )?;
+            if fee > 0 {
+                // The wSOL treasury picks the lamports up on its next `SyncNative`
+                transfer_sol(taker_sending_ata, taker_info, fee_account, fee)?;
+            }
         } else {
-            invoke(
-                &spl_token_2022::instruction::transfer(
-                    token_program.key,
-                    taker_sending_ata.key,
-                    maker_taker_mint_ata.key,
-                    taker_info.key,
-                    &[],
-                    order.taker_amount,
-                )?,
-                &[
-                    taker_sending_ata.clone(),
-                    maker_taker_mint_ata.clone(),
-                    taker_info.clone(),
-                    token_program.clone(),
-                ],
+            validate_token_account(taker_sending_ata, taker_info.key, taker_mint_info.key)?;
+            memo_if_required(maker_taker_mint_ata, memo, accounts)?;
+            transfer_with_fee(
+                taker_token_program,
+                taker_sending_ata,
+                taker_mint_info,
+                maker_taker_mint_ata,
+                fee_account,
+                taker_info,
+                accounts,
+                taker_amount,
+                order.taker_decimals,
+                protocol_fee(taker_amount),
+                &[],
             )?;
         }
+        if order.is_token_gated() {
+            let gate_account = next_account_info(account_info_iter)?;
+            validate_token_gate(gate_account, taker_info.key, &order)?;
+        }
+        if let Some(config) = config.as_ref().filter(|config| config.has_fee_mint()) {
+            let fee_feed_info = next_account_info(account_info_iter)?;
+            let price_info = next_account_info(account_info_iter)?;
+            let taker_fee_ata = next_account_info(account_info_iter)?;
+            let fee_mint_info = next_account_info(account_info_iter)?;
 
-        if *token_program.key == spl_token::id() {
-            invoke_signed(
-                &spl_token::instruction::transfer(
-                    token_program.key,
-                    order_maker_token_ata.key,
-                    taker_maker_mint_ata.key,
-                    order_account_info.key,
+            let fee_feed = load_fee_feed(program_id, fee_feed_info, taker_mint_info.key)?;
+            if *price_info.key != fee_feed.price_feed {
+                return Err(SwapError::InvalidOracle.into());
+            }
+            validate_token_account(taker_fee_ata, taker_info.key, &config.fee_mint)?;
+            let fee = config.fee(taker_amount, fee_payer_volume);
+            if fee > 0 {
+                let fee = oracle_fee_amount(
+                    fee,
+                    &load_pyth_price(price_info)?,
+                    fee_feed.mint_decimals,
+                    config.fee_mint_decimals,
+                    now,
+                )?;
+                // The fee mint may live under either of the swap's token programs
+                let fee_token_program = if taker_fee_ata.owner == maker_token_program.key {
+                    maker_token_program
+                } else {
+                    taker_token_program
+                };
+                transfer_tokens_with_hook(
+                    fee_token_program,
+                    taker_fee_ata,
+                    fee_mint_info,
+                    fee_account,
+                    taker_info,
+                    accounts,
+                    fee,
+                    config.fee_mint_decimals,
                     &[],
-                    order.maker_amount,
-                )?,
-                &[
-                    order_maker_token_ata.clone(),
-                    taker_maker_mint_ata.clone(),
-                    order_account_info.clone(),
-                    token_program.clone(),
-                ],
+                )?;
+            }
+        }
+
+        // The referrer the maker recorded takes its cut of the maker side first; a whole
+        // NFT leaves no cut to take
+        let referral_fee = if order.referral_bps == 0 || order.nft {
+            0
+        } else if order.maker_is_native() {
+            let referrer = next_account_info(account_info_iter)?;
+            if *referrer.key != order.referrer {
+                return Err(SwapError::InvalidReferrer.into());
+            }
+            let referral_fee = order.referral_fee(maker_fill);
+            transfer_lamports(order_account_info, referrer, referral_fee)?;
+            referral_fee
+        } else {
+            let referrer_ata = next_account_info(account_info_iter)?;
+            validate_token_account(referrer_ata, &order.referrer, &order.maker_token_mint)?;
+            let referral_fee = order.referral_fee(maker_fill);
+            let order_id = order.order_id.to_le_bytes();
+            memo_if_required(referrer_ata, memo, accounts)?;
+            transfer_tokens_with_hook(
+                maker_token_program,
+                order_maker_token_ata,
+                maker_mint_info,
+                referrer_ata,
+                order_account_info,
+                accounts,
+                referral_fee,
+                order.maker_decimals,
+                &[&[
+                    b"order",
+                    order.seed_maker.as_ref(),
+                    order.maker_token_mint.as_ref(),
+                    order.taker_token_mint.as_ref(),
+                    &order_id,
+                    &[order.bump],
+                ]],
+            )?;
+            referral_fee
+        };
+        let taker_receives = maker_fill - referral_fee;
+        // Slippage is checked against what reaches the taker after any transfer fee
+        if taker_receives - transfer_fee(maker_mint_info, taker_receives)? < min_maker_amount {
+            return Err(SwapError::SlippageExceeded.into());
+        }
+
+        if order.has_vesting() {
+            // The maker tokens stay in escrow and vest to the taker from now on
+            order.accepted_taker = *taker_info.key;
+            order.vesting_start_ts = now;
+            order.vesting_total = taker_receives;
+            order.pack(&mut order_account_info.data.borrow_mut())?;
+        } else if order.maker_is_native() {
+            transfer_lamports(order_account_info, taker_maker_mint_ata, taker_receives)?;
+        } else if order.programmable {
+            let order_id = order.order_id.to_le_bytes();
+            transfer_programmable_nft(
+                maker_token_program,
+                order_maker_token_ata,
+                order_account_info,
+                maker_mint_info,
+                taker_maker_mint_ata,
+                taker_info,
+                accounts,
+                taker_receives,
                 &[&[
                     b"order",
-                    &order.maker.to_bytes(),
-                    &order.maker_token_mint.to_bytes(),
-                    &order.taker_token_mint.to_bytes(),
+                    order.seed_maker.as_ref(),
+                    order.maker_token_mint.as_ref(),
+                    order.taker_token_mint.as_ref(),
+                    &order_id,
                     &[order.bump],
                 ]],
             )?;
         } else {
-            invoke_signed(
-                &spl_token_2022::instruction::transfer(
-                    token_program.key,
-                    order_maker_token_ata.key,
-                    taker_maker_mint_ata.key,
-                    order_account_info.key,
-                    &[],
-                    order.maker_amount,
-                )?,
-                &[
-                    order_maker_token_ata.clone(),
-                    taker_maker_mint_ata.clone(),
-                    order_account_info.clone(),
-                    token_program.clone(),
-                ],
+            let order_id = order.order_id.to_le_bytes();
+            memo_if_required(taker_maker_mint_ata, memo, accounts)?;
+            transfer_tokens_with_hook(
+                maker_token_program,
+                order_maker_token_ata,
+                maker_mint_info,
+                taker_maker_mint_ata,
+                order_account_info,
+                accounts,
+                taker_receives,
+                order.maker_decimals,
                 &[&[
                     b"order",
-                    &order.maker.to_bytes(),
-                    &order.maker_token_mint.to_bytes(),
-                    &order.taker_token_mint.to_bytes(),
+                    order.seed_maker.as_ref(),
+                    order.maker_token_mint.as_ref(),
+                    order.taker_token_mint.as_ref(),
+                    &order_id,
                     &[order.bump],
                 ]],
             )?;
         }
 
+        Self::book_fill(
+            program_id,
+            &order,
+            order_account_info,
+            order_maker_token_ata,
+            (maker_token_program, taker_token_program.key),
+            maker_mint_info,
+            taker_info,
+            taker_info.key,
+            fill_index,
+            maker_fill,
+            taker_amount,
+            accounts,
+        )
+    }
+
+    /// Books fill `fill_index` of `maker_amount` for `taker_amount`, already recorded on the
+    /// order and settled, by `taker`: the registry entry, the maker's and taker's user
+    /// stats, the protocol and pair stats, the fill receipt paid for by `payer_info` and the
+    /// trade history are updated wherever they are passed among `accounts`, which are
+    /// searched by key, `OrderFilled` is emitted and an order that closes on the fill is
+    /// closed. Every path that settles an order books its fills here.
+    #[allow(clippy::too_many_arguments)]
+    fn book_fill<'a>(
+        program_id: &Pubkey,
+        order: &SwapOrder,
+        order_account_info: &AccountInfo<'a>,
+        order_maker_token_ata: &AccountInfo<'a>,
+        (maker_token_program, taker_token_program): (&AccountInfo<'a>, &Pubkey),
+        maker_mint_info: &AccountInfo<'a>,
+        payer_info: &AccountInfo<'a>,
+        taker: &Pubkey,
+        fill_index: u64,
+        maker_amount: u64,
+        taker_amount: u64,
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        let find_writable = |key: Pubkey| {
+            accounts
+                .iter()
+                .find(move |account| *account.key == key && account.is_writable)
+        };
+
+        sync_registry_entry(program_id, accounts, order_account_info.key, order)?;
+
+        // A self-fill only counts once
+        let users = if order.maker == *taker {
+            vec![order.maker]
+        } else {
+            vec![order.maker, *taker]
+        };
+        for user in users {
+            let Some(stats_info) = find_writable(get_user_stats_pda(program_id, &user).0) else {
+                continue;
+            };
+            if let Some(mut stats) = load_user_stats(program_id, stats_info, &user)? {
+                stats.volume = stats.volume.saturating_add(taker_amount);
+                stats.serialize(&mut *stats_info.data.borrow_mut())?;
+            }
+        }
+        if let Some(stats_info) = find_writable(get_protocol_stats_pda(program_id).0) {
+            if let Some(mut stats) = load_protocol_stats(program_id, stats_info)? {
+                stats.record_fill(
+                    maker_token_program.key,
+                    taker_token_program,
+                    maker_amount,
+                    taker_amount,
+                );
+                stats.serialize(&mut *stats_info.data.borrow_mut())?;
+            }
+        }
+        let pair_stats_pda =
+            get_pair_stats_pda(program_id, &order.maker_token_mint, &order.taker_token_mint).0;
+        if let Some(stats_info) = find_writable(pair_stats_pda) {
+            if let Some(mut stats) = load_pair_stats(
+                program_id,
+                stats_info,
+                &order.maker_token_mint,
+                &order.taker_token_mint,
+            )? {
+                stats.record_fill(maker_amount, taker_amount);
+                stats.serialize(&mut *stats_info.data.borrow_mut())?;
+            }
+        }
+        write_fill_receipt(
+            program_id,
+            accounts,
+            payer_info,
+            taker,
+            order_account_info.key,
+            order,
+            fill_index,
+            maker_amount,
+            taker_amount,
+        )?;
+        record_trade(program_id, accounts, order, maker_amount, taker_amount)?;
+
+        let completed = order.status == OrderStatus::Completed;
+        OrderFilled {
+            order: *order_account_info.key,
+            taker: *taker,
+            maker_amount,
+            taker_amount,
+            remaining_maker_amount: if completed { 0 } else { order.maker_amount },
+            status: order.status,
+        }
+        .emit(program_id, accounts)?;
+
+        if order.closes_on_fill() {
+            Self::close_settled_order(
+                program_id,
+                order,
+                order_account_info,
+                order_maker_token_ata,
+                maker_token_program,
+                maker_mint_info,
+                accounts,
+            )?;
+        }
+
         Ok(())
     }
 
-    fn process_close_order(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    /// Closes a filled order and its emptied escrow, returning their rent to the account
+    /// that paid it, or to the maker if only the maker is passed. That account is looked
+    /// up in `accounts` by key; without it, or if the escrow cannot close yet, the order is
+    /// left for `CloseOrder`. The order is dropped from the maker's order index when that
+    /// is passed too.
+    fn close_settled_order<'a>(
+        program_id: &Pubkey,
+        order: &SwapOrder,
+        order_account_info: &AccountInfo<'a>,
+        order_maker_token_ata: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        maker_mint_info: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        let find_writable = |key: Pubkey| {
+            accounts
+                .iter()
+                .find(move |account| *account.key == key && account.is_writable)
+        };
+        let Some(rent_receiver) =
+            find_writable(order.rent_refund_account()).or_else(|| find_writable(order.maker))
+        else {
+            return Ok(());
+        };
+
+        if order.maker_is_native() {
+            if escrowed_lamports(order_account_info)? > 0 {
+                return Ok(());
+            }
+        } else {
+            // Withheld transfer fees can only be harvested into a writable mint
+            if token_balance(order_maker_token_ata)? > 0
+                || validate_not_frozen(order_maker_token_ata).is_err()
+                || (!maker_mint_info.is_writable && has_transfer_fee(maker_mint_info)?)
+            {
+                return Ok(());
+            }
+        }
+
+        Self::close_order_accounts(
+            program_id,
+            order,
+            order_account_info,
+            (!order.maker_is_native()).then_some(order_maker_token_ata),
+            token_program,
+            maker_mint_info,
+            rent_receiver,
+            accounts,
+        )
+    }
+
+    fn process_complete_swap_and_close(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_taker_amount: u64,
+        min_maker_amount: u64,
+    ) -> ProgramResult {
+        let (maker_info, swap_accounts) = accounts
+            .split_first()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        Self::process_complete_swap(
+            program_id,
+            swap_accounts,
+            0,
+            max_taker_amount,
+            min_maker_amount,
+            &[],
+            None,
+        )?;
+
+        let account_info_iter = &mut swap_accounts.iter();
+        let _taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let _maker_taker_mint_ata = next_account_info(account_info_iter)?;
+        let _taker_sending_ata = next_account_info(account_info_iter)?;
+        let _taker_maker_mint_ata = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let _taker_token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        // The fill already closed the order if the maker was among the swap accounts
+        if order_account_info.lamports() == 0 {
+            return Ok(());
+        }
+
+        let (order, _) = validate_order_pda(program_id, order_account_info)?;
+        if !order.is_rent_destination(maker_info.key) {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        if order.is_vesting_locked() {
+            return Err(SwapError::VestingLocked.into());
+        }
+        if order.status != OrderStatus::Completed {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+
+        // Token Metadata owns the freeze state of a programmable NFT's escrow, so the
+        // program leaves that account in place
+        let escrow =
+            (!order.maker_is_native() && !order.programmable).then_some(order_maker_token_ata);
+        Self::close_order_accounts(
+            program_id,
+            &order,
+            order_account_info,
+            escrow,
+            token_program,
+            maker_mint_info,
+            maker_info,
+            accounts,
+        )
+    }
+
+    /// Closes an order whose escrows are settled, along with its emptied maker-mint
+    /// `escrow` if one is passed, sending their rent to `rent_receiver`. The order is
+    /// dropped from the maker's order index when that is among `accounts` and from its
+    /// registry page, then zeroed, and `OrderClosed` is emitted.
+    #[allow(clippy::too_many_arguments)]
+    fn close_order_accounts<'a>(
+        program_id: &Pubkey,
+        order: &SwapOrder,
+        order_account_info: &AccountInfo<'a>,
+        escrow: Option<&AccountInfo<'a>>,
+        token_program: &AccountInfo<'a>,
+        maker_mint_info: &AccountInfo<'a>,
+        rent_receiver: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        if let Some(escrow) = escrow {
+            harvest_withheld_fees(token_program, maker_mint_info, escrow)?;
+            let order_id = order.order_id.to_le_bytes();
+            close_token_account(
+                token_program,
+                escrow,
+                rent_receiver,
+                order_account_info,
+                &[&[
+                    b"order",
+                    order.seed_maker.as_ref(),
+                    order.maker_token_mint.as_ref(),
+                    order.taker_token_mint.as_ref(),
+                    &order_id,
+                    &[order.bump],
+                ]],
+            )?;
+        }
+
+        if let Some(index_info) = find_maker_index(program_id, accounts, &order.seed_maker) {
+            unindex_order(
+                program_id,
+                index_info,
+                &order.seed_maker,
+                order_account_info.key,
+            )?;
+        }
+        unregister_order(program_id, accounts, order_account_info.key, order)?;
+
+        let rent_lamports = order_account_info.lamports();
+        **order_account_info.lamports.borrow_mut() = 0;
+        **rent_receiver.lamports.borrow_mut() += rent_lamports;
+
+        order_account_info.data.borrow_mut().fill(0);
+
+        OrderClosed {
+            order: *order_account_info.key,
+            maker: order.maker,
+            status: order.status,
+        }
+        .emit(program_id, accounts)?;
+
+        Ok(())
+    }
+
+    fn process_close_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        memo: Option<&str>,
+    ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let authority_info = next_account_info(account_info_iter)?;
         let order_account_info = next_account_info(account_info_iter)?;
         let order_token_ata = next_account_info(account_info_iter)?;
         let maker_token_ata = next_account_info(account_info_iter)?;
         let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
 
         let (order, _) = validate_order_pda(program_id, order_account_info)?;
-        validate_authority(authority_info, &order)?;
+        if order.is_htlc_locked(Clock::get()?.unix_timestamp) {
+            return Err(SwapError::HtlcLocked.into());
+        }
+        if order.is_vesting_locked() {
+            return Err(SwapError::VestingLocked.into());
+        }
+        // A leading bid binds the maker until the auction settles
+        if order.has_live_bid() {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        let rent_receiver = next_account_info(account_info_iter)?;
+        // Declined orders may be closed by anyone and any order by its delegate; tokens
+        // still go back to the maker
+        if order.maker != *authority_info.key
+            && (order.status == OrderStatus::Declined || order.is_delegate(authority_info.key))
+        {
+            validate_signer(authority_info)?;
+        } else {
+            validate_authority(authority_info, &order)?;
+        }
+        // Rent goes back to the maker or whoever paid it, whichever party closes
+        if !order.is_rent_destination(rent_receiver.key) {
+            return Err(SwapError::InvalidRentDestination.into());
+        }
         check_spl_token_program_account(token_program.key)?;
+        if order.maker_is_native() {
+            // Escrowed SOL goes back to the maker's wallet before the rent is released
+            if order_token_ata.key != order_account_info.key || *maker_token_ata.key != order.maker
+            {
+                return Err(SwapError::InvalidTokenAccount.into());
+            }
+            transfer_lamports(
+                order_account_info,
+                maker_token_ata,
+                escrowed_lamports(order_account_info)?,
+            )?;
+        } else {
+            Self::refund_escrow(
+                &order,
+                order_account_info,
+                order_token_ata,
+                maker_token_ata,
+                token_program,
+                maker_mint_info,
+                accounts,
+                memo,
+            )?;
+        }
+
+        if order.holds_taker_deposit() {
+            let order_taker_token_ata = next_account_info(account_info_iter)?;
+            let taker_refund_ata = next_account_info(account_info_iter)?;
+            let taker_mint_info = next_account_info(account_info_iter)?;
+            validate_token_account(
+                order_taker_token_ata,
+                order_account_info.key,
+                &order.taker_token_mint,
+            )?;
+            validate_token_account(
+                taker_refund_ata,
+                &order.accepted_taker,
+                &order.taker_token_mint,
+            )?;
+
+            Self::refund_acceptance(
+                &order,
+                order_account_info,
+                order_taker_token_ata,
+                taker_refund_ata,
+                token_program,
+                taker_mint_info,
+                accounts,
+                memo,
+            )?;
+        }
+
+        // Token Metadata keeps a programmable NFT's escrow frozen, so it is not closed
+        let escrow = (!order.maker_is_native() && !order.programmable).then_some(order_token_ata);
+        Self::close_order_accounts(
+            program_id,
+            &order,
+            order_account_info,
+            escrow,
+            token_program,
+            maker_mint_info,
+            rent_receiver,
+            accounts,
+        )
+    }
+
+    /// Returns a token escrow's balance to the maker, leaving the emptied escrow to be
+    /// closed with the order
+    #[allow(clippy::too_many_arguments)]
+    fn refund_escrow<'a>(
+        order: &SwapOrder,
+        order_account_info: &AccountInfo<'a>,
+        order_token_ata: &AccountInfo<'a>,
+        maker_token_ata: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        maker_mint_info: &AccountInfo<'a>,
+        hook_accounts: &[AccountInfo<'a>],
+        memo: Option<&str>,
+    ) -> ProgramResult {
         validate_token_account(
             order_token_ata,
             order_account_info.key,
             &order.maker_token_mint,
         )?;
-        validate_token_account(maker_token_ata, &order.maker, &order.maker_token_mint)?;
-
-        let token_data = spl_token::state::Account::unpack(&order_token_ata.data.borrow())?;
-        if token_data.amount > 0 {
-            if *token_program.key == spl_token::id() {
-                invoke_signed(
-                    &spl_token::instruction::transfer(
-                        token_program.key,
-                        order_token_ata.key,
-                        maker_token_ata.key,
-                        order_account_info.key,
-                        &[],
-                        token_data.amount,
-                    )?,
-                    &[
-                        order_token_ata.clone(),
-                        maker_token_ata.clone(),
-                        order_account_info.clone(),
-                        token_program.clone(),
-                    ],
-                    &[&[
-                        b"order",
-                        &order.maker.to_bytes(),
-                        &order.maker_token_mint.to_bytes(),
-                        &order.taker_token_mint.to_bytes(),
-                        &[order.bump],
-                    ]],
-                )?;
-
-                invoke_signed(
-                    &spl_token::instruction::close_account(
-                        token_program.key,
-                        order_token_ata.key,
-                        authority_info.key,
-                        order_account_info.key,
-                        &[],
-                    )?,
-                    &[
-                        order_token_ata.clone(),
-                        authority_info.clone(),
-                        order_account_info.clone(),
-                        token_program.clone(),
-                    ],
-                    &[&[
-                        b"order",
-                        &order.maker.to_bytes(),
-                        &order.maker_token_mint.to_bytes(),
-                        &order.taker_token_mint.to_bytes(),
-                        &[order.bump],
-                    ]],
-                )?;
-            } else {
-                invoke_signed(
-                    &spl_token_2022::instruction::transfer(
-                        token_program.key,
-                        order_token_ata.key,
-                        maker_token_ata.key,
-                        order_account_info.key,
-                        &[],
-                        token_data.amount,
-                    )?,
-                    &[
-                        order_token_ata.clone(),
-                        maker_token_ata.clone(),
-                        order_account_info.clone(),
-                        token_program.clone(),
-                    ],
-                    &[&[
-                        b"order",
-                        &order.maker.to_bytes(),
-                        &order.maker_token_mint.to_bytes(),
-                        &order.taker_token_mint.to_bytes(),
-                        &[order.bump],
-                    ]],
-                )?;
+        let escrowed = token_balance(order_token_ata)?;
+        if escrowed == 0 {
+            return Ok(());
+        }
 
-                invoke_signed(
-                    &spl_token_2022::instruction::close_account(
-                        token_program.key,
-                        order_token_ata.key,
-                        authority_info.key,
-                        order_account_info.key,
-                        &[],
-                    )?,
-                    &[
-                        order_token_ata.clone(),
-                        authority_info.clone(),
-                        order_account_info.clone(),
-                        token_program.clone(),
-                    ],
-                    &[&[
-                        b"order",
-                        &order.maker.to_bytes(),
-                        &order.maker_token_mint.to_bytes(),
-                        &order.taker_token_mint.to_bytes(),
-                        &[order.bump],
-                    ]],
-                )?;
-            }
+        let order_id = order.order_id.to_le_bytes();
+        let signer_seeds: &[&[u8]] = &[
+            b"order",
+            order.seed_maker.as_ref(),
+            order.maker_token_mint.as_ref(),
+            order.taker_token_mint.as_ref(),
+            &order_id,
+            &[order.bump],
+        ];
+        if order.programmable {
+            // Token Metadata creates and checks the maker's account
+            let maker_info = hook_accounts
+                .iter()
+                .find(|account| *account.key == order.maker)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            return transfer_programmable_nft(
+                token_program,
+                order_token_ata,
+                order_account_info,
+                maker_mint_info,
+                maker_token_ata,
+                maker_info,
+                hook_accounts,
+                escrowed,
+                &[signer_seeds],
+            );
         }
+        validate_token_account(maker_token_ata, &order.maker, &order.maker_token_mint)?;
+        memo_if_required(maker_token_ata, memo, hook_accounts)?;
+        transfer_tokens_with_hook(
+            token_program,
+            order_token_ata,
+            maker_mint_info,
+            maker_token_ata,
+            order_account_info,
+            hook_accounts,
+            escrowed,
+            order.maker_decimals,
+            &[signer_seeds],
+        )
+    }
+}
 
-        let rent_lamports = order_account_info.lamports();
-        **order_account_info.lamports.borrow_mut() = 0;
-        **authority_info.lamports.borrow_mut() += rent_lamports;
+/// Returns the account paying an order's rent: a platform sponsoring the order signs as
+/// the writable account at `position`, right after the accounts the instruction always
+/// takes; otherwise the maker pays. Signers anywhere else never pay.
+fn rent_payer<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    position: usize,
+    maker_info: &'a AccountInfo<'info>,
+) -> &'a AccountInfo<'info> {
+    accounts
+        .get(position)
+        .filter(|account| account.is_signer && account.is_writable)
+        .unwrap_or(maker_info)
+}
 
-        order_account_info.data.borrow_mut().fill(0);
+/// Loads the config a fill of an order by `maker` is charged under, or `None` if no fee is
+/// set. Fee-exempt makers are charged as if no config existed.
+fn load_fee_config(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    fee_exemption_info: &AccountInfo,
+    maker: &Pubkey,
+) -> Result<Option<Config>, ProgramError> {
+    let fee_exempt = is_fee_exempt(program_id, fee_exemption_info, maker)?;
+    Ok(load_config(program_id, config_info)?
+        .filter(|config| !fee_exempt && (config.fee_bps > 0 || !config.fee_tiers[0].is_unused())))
+}
 
-        Ok(())
+/// Returns the protocol fee skimmed from `taker_amount` of an escrowed taker side the
+/// maker settles, as `CompleteSwap` charges a dual-escrow fill: the config's rate at the
+/// maker's volume tier. It stays in the taker mint even with a fee mint configured, as the
+/// taker does not sign these settlements. `fee_account` must be the taker mint's treasury
+/// whenever a fee is due.
+fn escrowed_taker_fee(
+    program_id: &Pubkey,
+    order: &SwapOrder,
+    config_info: &AccountInfo,
+    fee_exemption_info: &AccountInfo,
+    maker_stats_info: &AccountInfo,
+    fee_account: &AccountInfo,
+    taker_amount: u64,
+) -> Result<u64, ProgramError> {
+    let Some(config) = load_fee_config(program_id, config_info, fee_exemption_info, &order.maker)?
+    else {
+        return Ok(0);
+    };
+    let maker_volume = load_user_stats(program_id, maker_stats_info, &order.maker)?
+        .map_or(0, |stats| stats.volume);
+    let fee = config.fee(taker_amount, maker_volume);
+    if fee > 0 && get_treasury_pda(program_id, &order.taker_token_mint).0 != *fee_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(fee)
+}
+
+/// Grows a program account to `len` bytes, with `payer_info` topping up the rent the
+/// larger size needs
+fn grow_account<'a>(
+    account_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    len: usize,
+) -> ProgramResult {
+    let rent_due = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(account_info.lamports());
+    if rent_due > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, account_info.key, rent_due),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
     }
+    account_info.realloc(len, false)
 }
@@ -0,0 +1,131 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::SwapError,
+    metadata::{find_metadata_address, is_programmable},
+    state::{OrderType, MEMO_LEN},
+    validation::{is_nft_mint, validate_authority, validate_order_open, validate_order_pda},
+};
+
+use super::{
+    rent_payer,
+    token::{create_escrow_if_missing, transfer_programmable_nft},
+    Processor,
+};
+
+impl Processor {
+    pub(super) fn process_initialize_nft_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        order_id: u64,
+        taker_amount: u64,
+    ) -> ProgramResult {
+        let [maker_info, order_account_info, maker_nft_account, escrow, _, maker_mint_info, taker_mint_info, system_program, _, token_program, ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        // Token Metadata keeps programmable NFTs frozen, so one is moved into the escrow
+        // through it first and the order takes the escrow as pre-funded
+        let metadata = find_metadata_address(maker_mint_info.key);
+        let programmable = match accounts.iter().find(|account| *account.key == metadata) {
+            Some(metadata_info) => is_programmable(metadata_info)?,
+            None => false,
+        };
+        if programmable {
+            create_escrow_if_missing(
+                program_id,
+                rent_payer(accounts, 12, maker_info),
+                escrow,
+                order_account_info,
+                maker_mint_info,
+                system_program,
+                token_program,
+                accounts,
+            )?;
+            transfer_programmable_nft(
+                token_program,
+                maker_nft_account,
+                maker_info,
+                maker_mint_info,
+                escrow,
+                order_account_info,
+                accounts,
+                1,
+                &[],
+            )?;
+        }
+
+        // An NFT moves whole, so the order is a plain all-or-none order for one token
+        Self::process_initialize_order(
+            program_id,
+            accounts,
+            order_id,
+            1,
+            taker_amount,
+            false,
+            false,
+            true,
+            [0; MEMO_LEN],
+        )?;
+
+        // The mints were validated as token mints above
+        if !is_nft_mint(maker_mint_info)? {
+            return Err(SwapError::InvalidNftMint.into());
+        }
+        if is_nft_mint(taker_mint_info)? && taker_amount != 1 {
+            return Err(SwapError::InvalidAmount.into());
+        }
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        order.nft = true;
+        order.programmable = programmable;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_set_taker_collection(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        enabled: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let collection_mint_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if *collection_mint_info.key != order.taker_token_mint {
+            return Err(SwapError::InvalidMint.into());
+        }
+        // The other order types and the taker escrow all settle in the order's own
+        // taker mint
+        if enabled
+            && (order.order_type != OrderType::Fixed
+                || order.dual_escrow
+                || order.requires_confirmation
+                || order.ui_amounts
+                || order.taker_is_native()
+                || order.taker_group != Pubkey::default())
+        {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        if enabled && (order.taker_amount != 1 || !is_nft_mint(collection_mint_info)?) {
+            return Err(SwapError::InvalidNftMint.into());
+        }
+
+        order.taker_collection = enabled;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
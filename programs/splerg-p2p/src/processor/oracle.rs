@@ -0,0 +1,47 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::SwapError,
+    oracle::load_pyth_price,
+    state::OrderType,
+    validation::{validate_authority, validate_order_open, validate_order_pda},
+};
+
+use super::Processor;
+
+impl Processor {
+    pub(super) fn process_set_price_oracle(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        spread_bps: u16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let price_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        if order.order_type != OrderType::Fixed
+            || order.requires_confirmation
+            || order.dual_escrow
+            || order.takes_any_member()
+        {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        // Staleness is checked at fill time; here the account only has to be a price feed
+        load_pyth_price(price_info)?;
+
+        order.order_type = OrderType::Oracle;
+        order.price_feed = *price_info.key;
+        order.spread_bps = spread_bps;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
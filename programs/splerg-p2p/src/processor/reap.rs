@@ -0,0 +1,89 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::REAP_BOUNTY_LAMPORTS,
+    validation::{validate_order_pda, validate_signer},
+};
+
+use super::{
+    token::{escrowed_lamports, transfer_lamports},
+    Processor,
+};
+
+impl Processor {
+    pub(super) fn process_reap_expired_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let caller_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let order_token_ata = next_account_info(account_info_iter)?;
+        let maker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let rent_receiver = next_account_info(account_info_iter)?;
+
+        validate_signer(caller_info)?;
+        let (order, _) = validate_order_pda(program_id, order_account_info)?;
+        let now = Clock::get()?.unix_timestamp;
+        if !order.is_expired(now) {
+            return Err(SwapError::OrderNotExpired.into());
+        }
+        // Only untouched orders are reaped; anything holding a taker's tokens or owing
+        // them a claim goes through its own flow
+        if !order.is_open() || order.holds_taker_deposit() || order.is_htlc_locked(now) {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        if !order.is_rent_destination(rent_receiver.key) {
+            return Err(SwapError::InvalidRentDestination.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        if order.maker_is_native() {
+            if order_token_ata.key != order_account_info.key || *maker_token_ata.key != order.maker
+            {
+                return Err(SwapError::InvalidTokenAccount.into());
+            }
+            transfer_lamports(
+                order_account_info,
+                maker_token_ata,
+                escrowed_lamports(order_account_info)?,
+            )?;
+        } else {
+            Self::refund_escrow(
+                &order,
+                order_account_info,
+                order_token_ata,
+                maker_token_ata,
+                token_program,
+                maker_mint_info,
+                accounts,
+                None,
+            )?;
+        }
+
+        // The caller's bounty comes out of the order's rent, the rest goes to its payer
+        let bounty = order_account_info.lamports().min(REAP_BOUNTY_LAMPORTS);
+        **order_account_info.lamports.borrow_mut() -= bounty;
+        **caller_info.lamports.borrow_mut() += bounty;
+
+        Self::close_order_accounts(
+            program_id,
+            &order,
+            order_account_info,
+            (!order.maker_is_native() && !order.programmable).then_some(order_token_ata),
+            token_program,
+            maker_mint_info,
+            rent_receiver,
+            accounts,
+        )
+    }
+}
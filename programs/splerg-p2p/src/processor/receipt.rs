@@ -0,0 +1,85 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, program::invoke_signed,
+    program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, system_program,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    state::{fill_price, Discriminator, FillReceipt, SwapOrder},
+    validation::get_fill_receipt_pda,
+};
+
+/// Records fill `fill_index` of the order by `taker` in its receipt PDA, if that is passed
+/// among `accounts`. `payer_info`, the signer settling the fill, covers the rent; the
+/// system program is looked up in `accounts`.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn write_fill_receipt<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    payer_info: &AccountInfo<'a>,
+    taker: &Pubkey,
+    order_key: &Pubkey,
+    order: &SwapOrder,
+    fill_index: u64,
+    maker_amount: u64,
+    taker_amount: u64,
+) -> ProgramResult {
+    let (receipt_pda, bump) = get_fill_receipt_pda(program_id, order_key, fill_index);
+    let Some(receipt_info) = accounts
+        .iter()
+        .find(|account| *account.key == receipt_pda && account.is_writable)
+    else {
+        return Ok(());
+    };
+    // Receipts are never closed, so one already there belongs to an earlier order at the
+    // same PDA
+    if !receipt_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let system_program_info = accounts
+        .iter()
+        .find(|account| *account.key == system_program::id())
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            receipt_info.key,
+            Rent::get()?.minimum_balance(FillReceipt::LEN),
+            FillReceipt::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer_info.clone(),
+            receipt_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            b"receipt",
+            order_key.as_ref(),
+            &fill_index.to_le_bytes(),
+            &[bump],
+        ]],
+    )?;
+
+    let clock = Clock::get()?;
+    let receipt = FillReceipt {
+        discriminator: FillReceipt::DISCRIMINATOR,
+        order: *order_key,
+        fill_index,
+        maker: order.maker,
+        taker: *taker,
+        maker_mint: order.maker_token_mint,
+        taker_mint: order.taker_token_mint,
+        maker_amount,
+        taker_amount,
+        price: fill_price(maker_amount, taker_amount, order.maker_decimals),
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+        bump,
+    };
+    receipt.serialize(&mut *receipt_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::{OrderStatus, OrderType},
+    validation::{
+        validate_authority, validate_order_open, validate_order_pda, validate_signer,
+        validate_token_account,
+    },
+};
+
+use super::{token::transfer_tokens, Processor};
+
+impl Processor {
+    pub(super) fn process_set_recurrence(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        interval: i64,
+        renewals: u16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        // Renewals refill only the maker escrow, so the order must settle from it alone
+        if order.order_type != OrderType::Fixed
+            || order.dual_escrow
+            || order.has_vesting()
+            || order.is_streaming()
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        if interval <= 0 || renewals == 0 {
+            return Err(SwapError::InvalidAmount.into());
+        }
+
+        order.recurrence_interval = interval;
+        order.recurrence_maker_amount = order.maker_amount;
+        order.recurrence_taker_amount = order.taker_amount;
+        order.renewals_remaining = renewals;
+        order.next_renewal_ts = Clock::get()?
+            .unix_timestamp
+            .checked_add(interval)
+            .ok_or(SwapError::InvalidAmount)?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_renew_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let caller_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let maker_source_ata = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_signer(caller_info)?;
+        if order.recurrence_interval == 0
+            || order.renewals_remaining == 0
+            || order.status != OrderStatus::Completed
+        {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if now < order.next_renewal_ts {
+            return Err(SwapError::RenewalNotDue.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(maker_source_ata, &order.maker, &order.maker_token_mint)?;
+        validate_token_account(
+            order_maker_token_ata,
+            order_account_info.key,
+            &order.maker_token_mint,
+        )?;
+
+        let order_id = order.order_id.to_le_bytes();
+        transfer_tokens(
+            token_program,
+            maker_source_ata,
+            maker_mint_info,
+            order_maker_token_ata,
+            order_account_info,
+            order.recurrence_maker_amount,
+            order.maker_decimals,
+            &[&[
+                b"order",
+                order.seed_maker.as_ref(),
+                order.maker_token_mint.as_ref(),
+                order.taker_token_mint.as_ref(),
+                &order_id,
+                &[order.bump],
+            ]],
+        )?;
+
+        order.maker_amount = order.recurrence_maker_amount;
+        order.taker_amount = order.recurrence_taker_amount;
+        order.status = OrderStatus::Open;
+        order.accepted_taker = Pubkey::default();
+        order.renewals_remaining -= 1;
+        order.next_renewal_ts = now
+            .checked_add(order.recurrence_interval)
+            .ok_or(SwapError::InvalidAmount)?;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    state::{Discriminator, RegistryEntry, RegistryPage, SwapOrder, REGISTRY_PAGE_ENTRIES},
+    validation::{
+        get_registry_page_pda, is_registry_page, load_registry_page, validate_signer,
+        validate_system_program,
+    },
+};
+
+use super::{grow_account, Processor};
+
+impl Processor {
+    pub(super) fn process_initialize_registry_page(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        maker_mint: Pubkey,
+        taker_mint: Pubkey,
+        page: u32,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let page_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_signer(payer_info)?;
+        validate_system_program(system_program_info.key)?;
+
+        // Every later page hangs off the one before it, so the pair's pages form one chain
+        if page > 0 {
+            let previous_info = next_account_info(account_info_iter)?;
+            let mut previous = load_registry_page(program_id, previous_info)?;
+            if previous.maker_mint != maker_mint
+                || previous.taker_mint != taker_mint
+                || previous.page != page - 1
+            {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            previous.next = *page_info.key;
+            previous.serialize(&mut *previous_info.data.borrow_mut())?;
+        }
+
+        create_registry_page(
+            program_id,
+            page_info,
+            payer_info,
+            system_program_info,
+            maker_mint,
+            taker_mint,
+            page,
+            Vec::new(),
+        )
+    }
+}
+
+/// Creates page `page` of the `maker_mint`/`taker_mint` registry listing `entries`
+#[allow(clippy::too_many_arguments)]
+fn create_registry_page<'a>(
+    program_id: &Pubkey,
+    page_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    maker_mint: Pubkey,
+    taker_mint: Pubkey,
+    page: u32,
+    entries: Vec<RegistryEntry>,
+) -> ProgramResult {
+    let (page_pda, bump) = get_registry_page_pda(program_id, &maker_mint, &taker_mint, page);
+    if page_pda != *page_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !page_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let space = RegistryPage::space(entries.len());
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            page_info.key,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            program_id,
+        ),
+        &[
+            payer_info.clone(),
+            page_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            b"registry",
+            maker_mint.as_ref(),
+            taker_mint.as_ref(),
+            &page.to_le_bytes(),
+            &[bump],
+        ]],
+    )?;
+
+    let registry_page = RegistryPage {
+        discriminator: RegistryPage::DISCRIMINATOR,
+        maker_mint,
+        taker_mint,
+        page,
+        bump,
+        next: Pubkey::default(),
+        entries,
+    };
+    registry_page.serialize(&mut *page_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Returns the writable registry pages of the order's mint pair among `accounts`
+fn registry_pages<'a, 'info>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    order: &'a SwapOrder,
+) -> impl Iterator<Item = Result<(&'a AccountInfo<'info>, RegistryPage), ProgramError>> + 'a {
+    accounts
+        .iter()
+        .filter(|account| account.is_writable && is_registry_page(program_id, account))
+        .map(|page_info| {
+            let page = RegistryPage::try_from_slice(&page_info.data.borrow())?;
+            Ok((page_info, page))
+        })
+        .filter(|result| {
+            result.as_ref().map_or(true, |(_, page)| {
+                page.maker_mint == order.maker_token_mint
+                    && page.taker_mint == order.taker_token_mint
+            })
+        })
+}
+
+/// Lists a new order in the first page of its pair among `accounts` with room, or else
+/// in a newly created page: page 0 if the pair has none yet, or the page after a full
+/// last page, which is linked to it. `payer_info` covers the rent of the extra entry.
+/// Listing is best-effort like the stats accounts: without such a page among `accounts`
+/// the order is not listed.
+pub(super) fn register_order<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    order_key: &Pubkey,
+    order: &SwapOrder,
+) -> ProgramResult {
+    let entry = RegistryEntry {
+        order: *order_key,
+        maker: order.maker,
+        maker_amount: order.maker_amount,
+        taker_amount: order.taker_amount,
+    };
+
+    let mut last_page = None;
+    for result in registry_pages(program_id, accounts, order) {
+        let (page_info, mut page) = result?;
+        if page.entries.len() >= REGISTRY_PAGE_ENTRIES {
+            if page.next == Pubkey::default() {
+                last_page = Some((page_info, page));
+            }
+            continue;
+        }
+        page.entries.push(entry);
+        let space = RegistryPage::space(page.entries.len());
+        grow_account(page_info, payer_info, system_program_info, space)?;
+        page.serialize(&mut *page_info.data.borrow_mut())?;
+        return Ok(());
+    }
+
+    let new_page = last_page.as_ref().map_or(0, |(_, page)| page.page + 1);
+    let (new_page_pda, _) = get_registry_page_pda(
+        program_id,
+        &order.maker_token_mint,
+        &order.taker_token_mint,
+        new_page,
+    );
+    let Some(page_info) = accounts
+        .iter()
+        .find(|account| *account.key == new_page_pda && account.data_is_empty())
+    else {
+        return Ok(());
+    };
+    create_registry_page(
+        program_id,
+        page_info,
+        payer_info,
+        system_program_info,
+        order.maker_token_mint,
+        order.taker_token_mint,
+        new_page,
+        vec![entry],
+    )?;
+    if let Some((last_page_info, mut last_page)) = last_page {
+        last_page.next = *page_info.key;
+        last_page.serialize(&mut *last_page_info.data.borrow_mut())?;
+    }
+
+    Ok(())
+}
+
+/// Brings the order's registry entry in line with the order: an open order's remaining
+/// amounts are updated, anything else is dropped from the registry. Does nothing unless
+/// the page listing the order is among `accounts`.
+pub(super) fn sync_registry_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    order_key: &Pubkey,
+    order: &SwapOrder,
+) -> ProgramResult {
+    update_registry_entry(program_id, accounts, order_key, order, order.is_open())
+}
+
+/// Drops a closing order from the registry, if the page listing it is among `accounts`
+pub(super) fn unregister_order(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    order_key: &Pubkey,
+    order: &SwapOrder,
+) -> ProgramResult {
+    update_registry_entry(program_id, accounts, order_key, order, false)
+}
+
+fn update_registry_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    order_key: &Pubkey,
+    order: &SwapOrder,
+    listed: bool,
+) -> ProgramResult {
+    for result in registry_pages(program_id, accounts, order) {
+        let (page_info, mut page) = result?;
+        let Some(position) = page
+            .entries
+            .iter()
+            .position(|entry| entry.order == *order_key)
+        else {
+            continue;
+        };
+
+        if listed {
+            let entry = &mut page.entries[position];
+            entry.maker = order.maker;
+            entry.maker_amount = order.maker_amount;
+            entry.taker_amount = order.taker_amount;
+        } else {
+            // The page shrinks to fit and keeps the freed rent towards its next entry
+            page.entries.remove(position);
+            page_info.realloc(RegistryPage::space(page.entries.len()), false)?;
+        }
+        page.serialize(&mut *page_info.data.borrow_mut())?;
+        return Ok(());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use borsh::to_vec;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::Quote,
+    validation::{
+        get_quote_nonce_pda, get_rfq_authority_pda, validate_ed25519_signature,
+        validate_init_amounts, validate_pair_not_blocked, validate_protocol_not_paused,
+        validate_signer, validate_system_program, validate_token_account, validate_trade_guards,
+    },
+};
+
+use super::{
+    token::{mint_decimals, transfer_tokens},
+    Processor,
+};
+
+impl Processor {
+    pub(super) fn process_fill_quote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        quote: Quote,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let nonce_account_info = next_account_info(account_info_iter)?;
+        let maker_sending_ata = next_account_info(account_info_iter)?;
+        let maker_receiving_ata = next_account_info(account_info_iter)?;
+        let taker_sending_ata = next_account_info(account_info_iter)?;
+        let taker_receiving_ata = next_account_info(account_info_iter)?;
+        let rfq_authority_info = next_account_info(account_info_iter)?;
+        let instructions_sysvar = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+
+        validate_signer(taker_info)?;
+        validate_protocol_not_paused(program_id, config_info)?;
+        validate_trade_guards(
+            program_id,
+            Some(config_info),
+            &quote.maker,
+            taker_info.key,
+            &quote.maker_token_mint,
+            &quote.taker_token_mint,
+        )?;
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            &quote.maker_token_mint,
+            &quote.taker_token_mint,
+        )?;
+        if quote.taker != Pubkey::default() && quote.taker != *taker_info.key {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        validate_init_amounts(quote.maker_amount, quote.taker_amount)?;
+        if quote.expires_at <= Clock::get()?.unix_timestamp {
+            return Err(SwapError::QuoteExpired.into());
+        }
+        validate_ed25519_signature(instructions_sysvar, &quote.maker, &to_vec(&quote)?)?;
+        validate_system_program(system_program_info.key)?;
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(maker_sending_ata, &quote.maker, &quote.maker_token_mint)?;
+        validate_token_account(maker_receiving_ata, &quote.maker, &quote.taker_token_mint)?;
+        validate_token_account(taker_sending_ata, taker_info.key, &quote.taker_token_mint)?;
+        validate_token_account(taker_receiving_ata, taker_info.key, &quote.maker_token_mint)?;
+
+        let (rfq_authority, rfq_bump) = get_rfq_authority_pda(program_id, &quote.maker);
+        if rfq_authority != *rfq_authority_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        // Creating the nonce marker fails if the quote was already filled
+        let (nonce_pda, nonce_bump) = get_quote_nonce_pda(program_id, &quote.maker, quote.nonce);
+        if nonce_pda != *nonce_account_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        invoke_signed(
+            &system_instruction::create_account(
+                taker_info.key,
+                nonce_account_info.key,
+                Rent::get()?.minimum_balance(0),
+                0,
+                program_id,
+            ),
+            &[
+                taker_info.clone(),
+                nonce_account_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[
+                b"quote",
+                quote.maker.as_ref(),
+                &quote.nonce.to_le_bytes(),
+                &[nonce_bump],
+            ]],
+        )?;
+
+        transfer_tokens(
+            token_program,
+            taker_sending_ata,
+            taker_mint_info,
+            maker_receiving_ata,
+            taker_info,
+            quote.taker_amount,
+            mint_decimals(taker_mint_info)?,
+            &[],
+        )?;
+        transfer_tokens(
+            token_program,
+            maker_sending_ata,
+            maker_mint_info,
+            taker_receiving_ata,
+            rfq_authority_info,
+            quote.maker_amount,
+            mint_decimals(maker_mint_info)?,
+            &[&[b"rfq", quote.maker.as_ref(), &[rfq_bump]]],
+        )?;
+
+        Ok(())
+    }
+}
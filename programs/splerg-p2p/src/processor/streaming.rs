@@ -0,0 +1,173 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::{OrderStatus, OrderType},
+    validation::{
+        validate_authority, validate_order_open, validate_order_pda, validate_pair_not_blocked,
+        validate_signer, validate_token_account,
+    },
+};
+
+use super::{token::transfer_tokens, Processor};
+
+impl Processor {
+    pub(super) fn process_set_streaming(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        tranches: u16,
+        interval: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        // Both sides must sit in escrow for tranches to release proportionally
+        if order.order_type != OrderType::Fixed
+            || !order.dual_escrow
+            || order.requires_confirmation
+            || order.has_vesting()
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        if tranches < 2 || interval <= 0 {
+            return Err(SwapError::InvalidAmount.into());
+        }
+
+        order.tranche_count = tranches;
+        order.tranche_interval = interval;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_settle_tranche(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let caller_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let order_taker_token_ata = next_account_info(account_info_iter)?;
+        let maker_receiving_ata = next_account_info(account_info_iter)?;
+        let taker_receiving_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+        let taker_mint_info = next_account_info(account_info_iter)?;
+        let maker_mint_blocked_info = next_account_info(account_info_iter)?;
+        let taker_mint_blocked_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_signer(caller_info)?;
+        validate_pair_not_blocked(
+            program_id,
+            maker_mint_blocked_info,
+            taker_mint_blocked_info,
+            &order.maker_token_mint,
+            &order.taker_token_mint,
+        )?;
+        if *caller_info.key != order.maker && *caller_info.key != order.accepted_taker {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        if !order.is_streaming() || order.status != OrderStatus::Accepted {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(
+            order_maker_token_ata,
+            order_account_info.key,
+            &order.maker_token_mint,
+        )?;
+        validate_token_account(
+            order_taker_token_ata,
+            order_account_info.key,
+            &order.taker_token_mint,
+        )?;
+        validate_token_account(maker_receiving_ata, &order.maker, &order.taker_token_mint)?;
+        validate_token_account(
+            taker_receiving_ata,
+            &order.accepted_taker,
+            &order.maker_token_mint,
+        )?;
+
+        let due = order.tranches_due_at(Clock::get()?.unix_timestamp);
+        if due <= order.tranches_settled {
+            return Err(SwapError::TrancheNotDue.into());
+        }
+        let maker_release = order.tranche_share(order.maker_amount, due)
+            - order.tranche_share(order.maker_amount, order.tranches_settled);
+        let taker_release = order.tranche_share(order.taker_amount, due)
+            - order.tranche_share(order.taker_amount, order.tranches_settled);
+
+        // Each settlement is a fill of the tranches it releases, while the order keeps the
+        // full amounts the shares are taken of
+        order.filled_amount = order
+            .filled_amount
+            .checked_add(maker_release)
+            .ok_or(SwapError::InvalidAmount)?;
+        order.tranches_settled = due;
+        if due == order.tranche_count {
+            order.status = OrderStatus::Completed;
+        }
+        let fill_index = order.fill_count;
+        order.fill_count += 1;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        let order_id = order.order_id.to_le_bytes();
+        let signer_seeds: &[&[u8]] = &[
+            b"order",
+            order.seed_maker.as_ref(),
+            order.maker_token_mint.as_ref(),
+            order.taker_token_mint.as_ref(),
+            &order_id,
+            &[order.bump],
+        ];
+
+        transfer_tokens(
+            token_program,
+            order_taker_token_ata,
+            taker_mint_info,
+            maker_receiving_ata,
+            order_account_info,
+            taker_release,
+            order.taker_decimals,
+            &[signer_seeds],
+        )?;
+        transfer_tokens(
+            token_program,
+            order_maker_token_ata,
+            maker_mint_info,
+            taker_receiving_ata,
+            order_account_info,
+            maker_release,
+            order.maker_decimals,
+            &[signer_seeds],
+        )?;
+
+        Self::book_fill(
+            program_id,
+            &order,
+            order_account_info,
+            order_maker_token_ata,
+            (token_program, token_program.key),
+            maker_mint_info,
+            caller_info,
+            &order.accepted_taker,
+            fill_index,
+            maker_release,
+            taker_release,
+            accounts,
+        )
+    }
+}
@@ -0,0 +1,729 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::{
+    cpi_guard::CpiGuard,
+    interest_bearing_mint::InterestBearingConfig,
+    memo_transfer::memo_required,
+    transfer_fee::{self, TransferFeeConfig},
+    transfer_hook, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use spl_transfer_hook_interface::onchain::add_extra_accounts_for_execute_cpi;
+
+use crate::{
+    error::SwapError,
+    metadata::{transfer_v1, AUTH_RULES_PROGRAM_ID, TOKEN_METADATA_PROGRAM_ID},
+    state::SwapOrder,
+    validation::{get_vault_pda, unpack_token_account, validate_system_program},
+};
+
+/// Transfers `amount` tokens from `source` to `destination` with `TransferChecked`,
+/// which both token programs accept and Token-2022 requires for some mints. `signer_seeds`
+/// is empty when `authority` signed the transaction and holds the order PDA seeds when
+/// the escrow is the source.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_tokens<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    transfer_tokens_with_hook(
+        token_program,
+        source,
+        mint,
+        destination,
+        authority,
+        &[],
+        amount,
+        decimals,
+        signer_seeds,
+    )
+}
+
+/// Transfers like `transfer_tokens`, resolving the extra accounts of a Token-2022
+/// transfer hook from `hook_accounts`. Callers pass every account of their instruction,
+/// so the hook program, its `ExtraAccountMetaList` and the accounts it lists may be
+/// appended anywhere after the instruction's own accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_tokens_with_hook<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    hook_accounts: &[AccountInfo<'a>],
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    check_cpi_guard(source, authority)?;
+    // Both token programs share the `TransferChecked` encoding
+    let mut transfer_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        source.key,
+        mint.key,
+        destination.key,
+        authority.key,
+        &[],
+        amount,
+        decimals,
+    )?;
+    let mut account_infos = vec![
+        source.clone(),
+        mint.clone(),
+        destination.clone(),
+        authority.clone(),
+    ];
+    if let Some(hook_program_id) = transfer_hook_program_id(mint)? {
+        add_extra_accounts_for_execute_cpi(
+            &mut transfer_instruction,
+            &mut account_infos,
+            &hook_program_id,
+            source.clone(),
+            mint.clone(),
+            destination.clone(),
+            authority.clone(),
+            amount,
+            hook_accounts,
+        )?;
+    }
+    account_infos.push(token_program.clone());
+
+    invoke_signed(&transfer_instruction, &account_infos, signer_seeds)
+}
+
+/// Moves `amount` of a programmable NFT from `source`, owned by `source_owner`, to
+/// `destination_owner`'s `destination` through Token Metadata, which thaws and refreezes
+/// the accounts around the transfer. The Token Metadata, ATA and system programs, the
+/// NFT's metadata, master edition and both token records, the instructions sysvar and,
+/// for NFTs with a rule set, the auth rules program and rule set are looked up in
+/// `accounts`, whose first signer pays for the destination's token record.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_programmable_nft<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    source_owner: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    destination_owner: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let payer = accounts
+        .iter()
+        .find(|account| account.is_signer)
+        .ok_or(ProgramError::MissingRequiredSignature)?;
+    let authorization_rules = accounts
+        .iter()
+        .find(|account| *account.owner == AUTH_RULES_PROGRAM_ID)
+        .map(|account| account.key);
+    let instruction = transfer_v1(
+        source.key,
+        source_owner.key,
+        destination.key,
+        destination_owner.key,
+        mint.key,
+        source_owner.key,
+        payer.key,
+        token_program.key,
+        authorization_rules,
+        amount,
+    );
+
+    let known = [
+        source,
+        source_owner,
+        mint,
+        destination,
+        destination_owner,
+        token_program,
+    ];
+    let mut account_infos = instruction
+        .accounts
+        .iter()
+        .map(|meta| {
+            known
+                .into_iter()
+                .chain(accounts)
+                .find(|account| *account.key == meta.pubkey)
+                .cloned()
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let metadata_program = accounts
+        .iter()
+        .find(|account| *account.key == TOKEN_METADATA_PROGRAM_ID)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    account_infos.push(metadata_program.clone());
+
+    invoke_signed(&instruction, &account_infos, signer_seeds)
+}
+
+/// Fails with a descriptive error instead of the token program's generic one when
+/// `source` is a Token-2022 account whose CPI guard refuses its owner `authority`
+/// transferring through this program. Delegated transfers are still allowed.
+fn check_cpi_guard(source: &AccountInfo, authority: &AccountInfo) -> ProgramResult {
+    if *source.owner != spl_token_2022::id() {
+        return Ok(());
+    }
+    let data = source.data.borrow();
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    let locked = account
+        .get_extension::<CpiGuard>()
+        .is_ok_and(|extension| extension.lock_cpi.into());
+    if locked && account.base.owner == *authority.key {
+        return Err(SwapError::CpiGuardEnabled.into());
+    }
+    Ok(())
+}
+
+/// Transfers `amount` like `transfer_tokens_with_hook`, diverting `fee` of it to
+/// `fee_account`
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_with_fee<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    fee_account: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    hook_accounts: &[AccountInfo<'a>],
+    amount: u64,
+    decimals: u8,
+    fee: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    transfer_tokens_with_hook(
+        token_program,
+        source,
+        mint,
+        destination,
+        authority,
+        hook_accounts,
+        amount - fee,
+        decimals,
+        signer_seeds,
+    )?;
+    if fee > 0 {
+        transfer_tokens_with_hook(
+            token_program,
+            source,
+            mint,
+            fee_account,
+            authority,
+            hook_accounts,
+            fee,
+            decimals,
+            signer_seeds,
+        )?;
+    }
+    Ok(())
+}
+
+/// Logs `memo` through the SPL Memo program right before a transfer into `destination`
+/// if it is a Token-2022 account that requires incoming transfer memos. The memo program
+/// has to be among `accounts`.
+pub fn memo_if_required<'a>(
+    destination: &AccountInfo<'a>,
+    memo: Option<&str>,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    if *destination.owner != spl_token_2022::id() {
+        return Ok(());
+    }
+    let required = {
+        let data = destination.data.borrow();
+        memo_required(&StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?)
+    };
+    if !required {
+        return Ok(());
+    }
+
+    let memo = memo.ok_or(SwapError::MemoRequired)?;
+    let memo_program = accounts
+        .iter()
+        .find(|account| *account.key == spl_memo::id())
+        .ok_or(SwapError::MemoRequired)?;
+    invoke(
+        &spl_memo::build_memo(memo.as_bytes(), &[]),
+        std::slice::from_ref(memo_program),
+    )
+}
+
+/// Creates the escrow holding `mint` for the order (or bundle) PDA `owner` if it does not
+/// exist yet, paid by `payer`. New escrows are vaults at `get_vault_pda`; the owner's ATA
+/// is still accepted for escrows set up outside the program, such as pre-funded ones.
+/// Any other address is refused.
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow_if_missing<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    escrow: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let (vault, bump) = get_vault_pda(program_id, owner.key, mint.key);
+    if *escrow.key == vault {
+        create_vault_if_missing(
+            payer,
+            escrow,
+            owner,
+            mint,
+            system_program,
+            token_program,
+            &[b"vault", owner.key.as_ref(), mint.key.as_ref(), &[bump]],
+        )
+    } else if *escrow.key
+        == get_associated_token_address_with_program_id(owner.key, mint.key, token_program.key)
+    {
+        create_ata_if_missing(
+            payer,
+            escrow,
+            owner,
+            mint,
+            system_program,
+            token_program,
+            accounts,
+        )
+    } else {
+        Err(SwapError::InvalidTokenAccount.into())
+    }
+}
+
+/// Creates and initializes the token account `vault` for `mint`, owned by `owner`, at
+/// the PDA `vault_seeds` lead to, unless it already exists. Token-2022 vaults are sized
+/// for the account extensions the mint requires.
+fn create_vault_if_missing<'a>(
+    payer: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    vault_seeds: &[&[u8]],
+) -> ProgramResult {
+    if vault.owner != system_program.key || vault.lamports() > 0 {
+        return Ok(());
+    }
+    validate_system_program(system_program.key)?;
+    let space = token_account_len(mint, token_program.key)?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            vault.key,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            token_program.key,
+        ),
+        &[payer.clone(), vault.clone(), system_program.clone()],
+        &[vault_seeds],
+    )?;
+    invoke(
+        &spl_token_2022::instruction::initialize_account3(
+            token_program.key,
+            vault.key,
+            mint.key,
+            owner.key,
+        )?,
+        &[vault.clone(), mint.clone(), token_program.clone()],
+    )
+}
+
+/// Returns the size of a token account of `mint` under `token_program`: Token-2022
+/// accounts carry the account extensions the mint's extensions require
+pub fn token_account_len(
+    mint: &AccountInfo,
+    token_program: &Pubkey,
+) -> Result<usize, ProgramError> {
+    if *token_program != spl_token_2022::id() {
+        return Ok(spl_token::state::Account::LEN);
+    }
+    let data = mint.data.borrow();
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+        &ExtensionType::get_required_init_account_extensions(&mint_state.get_extension_types()?),
+    )
+}
+
+/// Creates `wallet`'s associated token account for `mint` at `ata`, paid by `payer`,
+/// unless the account already exists. The ATA program has to be among `accounts` when
+/// the account is missing.
+#[allow(clippy::too_many_arguments)]
+pub fn create_ata_if_missing<'a>(
+    payer: &AccountInfo<'a>,
+    ata: &AccountInfo<'a>,
+    wallet: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    if ata.owner != system_program.key || ata.lamports() > 0 {
+        return Ok(());
+    }
+    validate_system_program(system_program.key)?;
+    let ata_program = accounts
+        .iter()
+        .find(|account| *account.key == spl_associated_token_account::id())
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    invoke(
+        &spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            payer.key,
+            wallet.key,
+            mint.key,
+            token_program.key,
+        ),
+        &[
+            payer.clone(),
+            ata.clone(),
+            wallet.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            ata_program.clone(),
+        ],
+    )
+}
+
+/// Creates `wallet`'s associated token account for `mint` at `ata` during a fill, paid by
+/// `payer`, unless the account already exists. The wallet, the system program and the
+/// ATA program are looked up in `accounts` when the account is missing.
+pub fn create_receiving_ata_if_missing<'a>(
+    payer: &AccountInfo<'a>,
+    ata: &AccountInfo<'a>,
+    wallet: &Pubkey,
+    mint: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    if *ata.owner != system_program::id() || ata.lamports() > 0 {
+        return Ok(());
+    }
+    let find = |key: &Pubkey| {
+        accounts
+            .iter()
+            .find(|account| account.key == key)
+            .ok_or(ProgramError::NotEnoughAccountKeys)
+    };
+    create_ata_if_missing(
+        payer,
+        ata,
+        find(wallet)?,
+        mint,
+        find(&system_program::id())?,
+        token_program,
+        accounts,
+    )
+}
+
+/// Closes an empty escrow token account owned by the order PDA, sending its rent
+/// to `destination`
+pub fn close_token_account<'a>(
+    token_program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let close_instruction = if *token_program.key == spl_token::id() {
+        spl_token::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?
+    } else {
+        spl_token_2022::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?
+    };
+
+    invoke_signed(
+        &close_instruction,
+        &[
+            account.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Moves transfer fees withheld in an escrow to its mint, which Token-2022 requires
+/// before the escrow can be closed. Anyone may harvest, so no authority is needed.
+pub fn harvest_withheld_fees<'a>(
+    token_program: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+) -> ProgramResult {
+    if transfer_fee_config(mint)?.is_none() {
+        return Ok(());
+    }
+    invoke(
+        &transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+            token_program.key,
+            mint.key,
+            &[account.key],
+        )?,
+        &[mint.clone(), account.clone(), token_program.clone()],
+    )
+}
+
+/// Reads the decimals of an SPL Token or Token-2022 mint, or of native SOL
+pub fn mint_decimals(mint_info: &AccountInfo) -> Result<u8, ProgramError> {
+    if is_native_mint(mint_info.key) {
+        return Ok(spl_token::native_mint::DECIMALS);
+    }
+    let data = mint_info.data.borrow();
+    Ok(
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?
+            .base
+            .decimals,
+    )
+}
+
+/// Returns the transfer fee config of a Token-2022 mint that has one
+fn transfer_fee_config(mint_info: &AccountInfo) -> Result<Option<TransferFeeConfig>, ProgramError> {
+    if *mint_info.owner != spl_token_2022::id() {
+        return Ok(None);
+    }
+    let data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint.get_extension::<TransferFeeConfig>().ok().copied())
+}
+
+/// Returns the transfer hook program of a Token-2022 mint that has one
+fn transfer_hook_program_id(mint_info: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+    if *mint_info.owner != spl_token_2022::id() {
+        return Ok(None);
+    }
+    let data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(transfer_hook::get_program_id(&mint))
+}
+
+/// Returns true if the mint is a Token-2022 mint with a transfer fee
+pub fn has_transfer_fee(mint_info: &AccountInfo) -> Result<bool, ProgramError> {
+    Ok(transfer_fee_config(mint_info)?.is_some())
+}
+
+/// Returns the fee the mint withholds from a transfer of `amount` this epoch, so the
+/// recipient gets `amount` minus the fee
+pub fn transfer_fee(mint_info: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    match transfer_fee_config(mint_info)? {
+        Some(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ProgramError::ArithmeticOverflow),
+        None => Ok(0),
+    }
+}
+
+/// Returns the fee to add on top of a transfer so that exactly `net_amount` arrives
+pub fn inverse_transfer_fee(mint_info: &AccountInfo, net_amount: u64) -> Result<u64, ProgramError> {
+    match transfer_fee_config(mint_info)? {
+        Some(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, net_amount)
+            .ok_or(ProgramError::ArithmeticOverflow),
+        None => Ok(0),
+    }
+}
+
+/// Returns what has to be sent for exactly `net_amount` to arrive after the mint's
+/// transfer fee
+pub fn gross_amount(mint_info: &AccountInfo, net_amount: u64) -> Result<u64, ProgramError> {
+    net_amount
+        .checked_add(inverse_transfer_fee(mint_info, net_amount)?)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+/// Recomputes the order's fee-adjusted amounts after `maker_amount` or `taker_amount`
+/// changed
+pub fn update_transfer_fee_amounts(
+    order: &mut SwapOrder,
+    maker_mint_info: &AccountInfo,
+    taker_mint_info: &AccountInfo,
+) -> ProgramResult {
+    if *maker_mint_info.key != order.maker_token_mint
+        || *taker_mint_info.key != order.taker_token_mint
+    {
+        return Err(SwapError::InvalidMint.into());
+    }
+    order.maker_amount_gross = gross_amount(maker_mint_info, order.maker_amount)?;
+    order.taker_amount_net =
+        order.taker_amount - transfer_fee(taker_mint_info, order.taker_amount)?;
+    Ok(())
+}
+
+/// Returns the interest config of a Token-2022 interest-bearing mint
+fn interest_bearing_config(
+    mint_info: &AccountInfo,
+) -> Result<Option<InterestBearingConfig>, ProgramError> {
+    if *mint_info.owner != spl_token_2022::id() {
+        return Ok(None);
+    }
+    let data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint.get_extension::<InterestBearingConfig>().ok().copied())
+}
+
+/// Fixed-point unit of the interest math: `WAD` stands for 1
+const WAD: i128 = 1_000_000_000_000_000_000;
+/// ln 2 in `WAD` units
+const LN_2_WAD: i128 = 693_147_180_559_945_309;
+/// The year interest-bearing rates are quoted per, 365.24 days as Token-2022 counts it
+const SECONDS_PER_YEAR: i128 = 31_556_736;
+/// Interest-bearing rates are in bps
+const ONE_IN_BASIS_POINTS: i128 = 10_000;
+
+/// Returns `e^x` for `x` in `WAD` units, in `WAD` units, or `None` if it overflows.
+/// `x` is split into `k ln 2 + r` with `|r| <= ln 2 / 2`, so the Taylor series of `e^r`
+/// converges in a few dozen terms and `2^k` is a shift.
+fn exp_wad(x: i128) -> Option<u128> {
+    let k = x.checked_add(x.signum() * LN_2_WAD / 2)? / LN_2_WAD;
+    let r = x - k * LN_2_WAD;
+    let (mut sum, mut term) = (WAD, WAD);
+    for n in 1..40 {
+        term = term * r / WAD / n;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+    // `e^r` is at least `e^(-ln 2 / 2)`, so the sum is positive
+    let sum = u128::try_from(sum).ok()?;
+    if k >= 0 {
+        sum.checked_mul(1u128.checked_shl(u32::try_from(k).ok()?)?)
+    } else {
+        Some(sum.checked_shr(u32::try_from(-k).ok()?).unwrap_or(0))
+    }
+}
+
+/// Returns how much a raw amount of an interest-bearing mint is worth in UI base units
+/// at unix time `now`, in `WAD` units: `e^(rate × years)` compounded continuously at
+/// the average rate until the last rate update and at the current rate since
+fn interest_scale(config: &InterestBearingConfig, now: i64) -> Result<u128, ProgramError> {
+    let initialized = i128::from(i64::from(config.initialization_timestamp));
+    let last_update = i128::from(i64::from(config.last_update_timestamp));
+    let pre_update_rate = i128::from(i16::from(config.pre_update_average_rate));
+    let current_rate = i128::from(i16::from(config.current_rate));
+    // Each product fits: a 16-bit rate times a 65-bit timespan
+    let rate_seconds = pre_update_rate * (last_update - initialized)
+        + current_rate * (i128::from(now) - last_update);
+    rate_seconds
+        .checked_mul(WAD)
+        .map(|exponent| exponent / (SECONDS_PER_YEAR * ONE_IN_BASIS_POINTS))
+        .and_then(exp_wad)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+/// Returns `amount × numerator / denominator`, rounded to the nearest unit
+fn mul_div_round(amount: u64, numerator: u128, denominator: u128) -> Result<u64, ProgramError> {
+    u128::from(amount)
+        .checked_mul(numerator)
+        .and_then(|product| product.checked_add(denominator / 2))
+        .and_then(|product| product.checked_div(denominator))
+        .and_then(|result| u64::try_from(result).ok())
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+/// Converts a raw amount to its UI amount at unix time `now`, in base units (the UI
+/// amount times `10^decimals`). Mints without accrued interest convert one to one.
+pub fn amount_to_ui_amount(
+    mint_info: &AccountInfo,
+    amount: u64,
+    now: i64,
+) -> Result<u64, ProgramError> {
+    let Some(config) = interest_bearing_config(mint_info)? else {
+        return Ok(amount);
+    };
+    mul_div_round(amount, interest_scale(&config, now)?, WAD as u128)
+}
+
+/// Converts a UI amount in base units, as returned by `amount_to_ui_amount`, to the raw
+/// amount worth that much at unix time `now`
+pub fn ui_amount_to_amount(
+    mint_info: &AccountInfo,
+    ui_amount: u64,
+    now: i64,
+) -> Result<u64, ProgramError> {
+    let Some(config) = interest_bearing_config(mint_info)? else {
+        return Ok(ui_amount);
+    };
+    mul_div_round(ui_amount, WAD as u128, interest_scale(&config, now)?)
+}
+
+/// Returns true for the native mint, which orders use to trade SOL without wrapping it
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::id()
+}
+
+/// Pays `amount` lamports from a signer's wallet through the system program
+pub fn transfer_sol<'a>(
+    system_program: &AccountInfo<'a>,
+    from: &AccountInfo<'a>,
+    to: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    validate_system_program(system_program.key)?;
+    invoke(
+        &system_instruction::transfer(from.key, to.key, amount),
+        &[from.clone(), to.clone(), system_program.clone()],
+    )
+}
+
+/// Moves `amount` lamports out of an account owned by this program
+pub fn transfer_lamports(
+    source: &AccountInfo,
+    destination: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    let source_lamports = source
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(SwapError::InsufficientFunds)?;
+    let destination_lamports = destination
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **source.try_borrow_mut_lamports()? = source_lamports;
+    **destination.try_borrow_mut_lamports()? = destination_lamports;
+    Ok(())
+}
+
+/// Returns the native SOL escrowed in an order PDA: its lamports above rent exemption
+pub fn escrowed_lamports(order_info: &AccountInfo) -> Result<u64, ProgramError> {
+    let rent = Rent::get()?.minimum_balance(order_info.data_len());
+    Ok(order_info.lamports().saturating_sub(rent))
+}
+
+/// Reads the token balance of an escrow account
+pub fn token_balance(account: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(unpack_token_account(account)?.amount)
+}
@@ -0,0 +1,114 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::check_spl_token_program_account;
+
+use crate::{
+    error::SwapError,
+    state::{OrderStatus, OrderType},
+    validation::{
+        validate_authority, validate_order_open, validate_order_pda, validate_signer,
+        validate_token_account,
+    },
+};
+
+use super::{token::transfer_tokens, Processor};
+
+impl Processor {
+    pub(super) fn process_set_vesting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        cliff: i64,
+        duration: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_authority(maker_info, &order)?;
+        validate_order_open(&order)?;
+        // Only orders settled by `CompleteSwap` can hold the maker side back
+        if matches!(
+            order.order_type,
+            OrderType::EnglishAuction | OrderType::Htlc | OrderType::SecretClaim
+        ) || order.requires_confirmation
+            || order.nft
+        {
+            return Err(SwapError::IncompatibleOrderOptions.into());
+        }
+        if duration <= 0 || cliff < 0 || cliff > duration {
+            return Err(SwapError::InvalidAmount.into());
+        }
+
+        order.vesting_cliff = cliff;
+        order.vesting_duration = duration;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub(super) fn process_claim_vested(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_info = next_account_info(account_info_iter)?;
+        let order_account_info = next_account_info(account_info_iter)?;
+        let taker_receiving_ata = next_account_info(account_info_iter)?;
+        let order_maker_token_ata = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let maker_mint_info = next_account_info(account_info_iter)?;
+
+        let (mut order, _) = validate_order_pda(program_id, order_account_info)?;
+        validate_signer(taker_info)?;
+        if !order.has_vesting() || order.status != OrderStatus::Completed {
+            return Err(SwapError::InvalidOrderState.into());
+        }
+        if order.accepted_taker != *taker_info.key {
+            return Err(SwapError::UnauthorizedSigner.into());
+        }
+        check_spl_token_program_account(token_program.key)?;
+        validate_token_account(taker_receiving_ata, taker_info.key, &order.maker_token_mint)?;
+        validate_token_account(
+            order_maker_token_ata,
+            order_account_info.key,
+            &order.maker_token_mint,
+        )?;
+
+        let claimable = order
+            .vested_amount_at(Clock::get()?.unix_timestamp)
+            .saturating_sub(order.vesting_claimed);
+        if claimable == 0 {
+            return Err(SwapError::VestingLocked.into());
+        }
+
+        let order_id = order.order_id.to_le_bytes();
+        transfer_tokens(
+            token_program,
+            order_maker_token_ata,
+            maker_mint_info,
+            taker_receiving_ata,
+            order_account_info,
+            claimable,
+            order.maker_decimals,
+            &[&[
+                b"order",
+                order.seed_maker.as_ref(),
+                order.maker_token_mint.as_ref(),
+                order.taker_token_mint.as_ref(),
+                &order_id,
+                &[order.bump],
+            ]],
+        )?;
+
+        order.vesting_claimed += claimable;
+        order.pack(&mut order_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::validation::{get_wsol_pda, validate_signer, validate_system_program};
+
+use super::{
+    token::{close_token_account, is_native_mint, transfer_sol},
+    Processor,
+};
+
+impl Processor {
+    pub(super) fn process_wrap_sol(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let owner_info = next_account_info(account_info_iter)?;
+        let wsol_info = next_account_info(account_info_iter)?;
+        let native_mint_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        validate_signer(owner_info)?;
+        validate_system_program(system_program_info.key)?;
+        if !is_native_mint(native_mint_info.key) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if *token_program.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let (wsol_pda, bump) = get_wsol_pda(program_id, owner_info.key);
+        if wsol_pda != *wsol_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        // The temporary account is created on first use and owned by the user, so it
+        // works as their taker-side wSOL account for any instruction in the transaction
+        if wsol_info.data_is_empty() {
+            let account_len = spl_token::state::Account::LEN;
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner_info.key,
+                    wsol_info.key,
+                    Rent::get()?.minimum_balance(account_len),
+                    account_len as u64,
+                    token_program.key,
+                ),
+                &[
+                    owner_info.clone(),
+                    wsol_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[b"wsol", owner_info.key.as_ref(), &[bump]]],
+            )?;
+            invoke(
+                &spl_token::instruction::initialize_account3(
+                    token_program.key,
+                    wsol_info.key,
+                    native_mint_info.key,
+                    owner_info.key,
+                )?,
+                &[
+                    wsol_info.clone(),
+                    native_mint_info.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        }
+
+        if amount > 0 {
+            transfer_sol(system_program_info, owner_info, wsol_info, amount)?;
+        }
+        invoke(
+            &spl_token::instruction::sync_native(token_program.key, wsol_info.key)?,
+            &[wsol_info.clone(), token_program.clone()],
+        )
+    }
+
+    pub(super) fn process_unwrap_sol(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let owner_info = next_account_info(account_info_iter)?;
+        let wsol_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        validate_signer(owner_info)?;
+        if *token_program.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let (wsol_pda, _) = get_wsol_pda(program_id, owner_info.key);
+        if wsol_pda != *wsol_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        // Closing a native account pays out its whole balance, rent included
+        close_token_account(token_program, wsol_info, owner_info, owner_info, &[])
+    }
+}
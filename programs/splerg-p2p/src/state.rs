@@ -1,8 +1,136 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use bytemuck::{CheckedBitPattern, NoUninit};
+use shank::ShankAccount;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use std::mem::offset_of;
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+use crate::error::SwapError;
+
+/// Lifecycle of an order. Only `Open` orders can be modified or filled.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    CheckedBitPattern,
+    NoUninit,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum OrderStatus {
+    #[default]
+    Open,
+    Completed,
+    Cancelled,
+    Expired,
+    /// A taker has escrowed their side, awaiting `ConfirmSwap` or a dual-escrow `CompleteSwap`
+    Accepted,
+    /// The designated taker has escrowed a counter-offer awaiting the maker's decision
+    CounterOffered,
+    /// The designated taker refused the order; anyone may close it back to the maker
+    Declined,
+}
+
+impl OrderStatus {
+    /// Returns true if the order's taker-mint escrow holds a deposit from `accepted_taker`
+    pub fn holds_taker_deposit(&self) -> bool {
+        matches!(self, OrderStatus::Accepted | OrderStatus::CounterOffered)
+    }
+}
+
+/// How the taker side of an order is priced at fill time
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    CheckedBitPattern,
+    NoUninit,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum OrderType {
+    /// The taker pays exactly `taker_amount`
+    #[default]
+    Fixed,
+    /// The taker amount decays linearly from `taker_amount` to `floor_taker_amount`
+    /// between `auction_start_ts` and `auction_end_ts`
+    DutchAuction,
+    /// Takers bid at least `taker_amount` until `auction_end_ts`; the highest bid,
+    /// held in the taker-mint escrow, wins at `SettleAuction`
+    EnglishAuction,
+    /// The taker amount is derived from the `price_feed` Pyth price at fill time,
+    /// marked up by `spread_bps`
+    Oracle,
+    /// Hash-time-locked: the taker claims the escrow by revealing the SHA-256 preimage
+    /// of `hashlock` before `htlc_timeout`, after which the maker can close and refund.
+    /// The taker side is paid on another chain.
+    Htlc,
+    /// Secret claim ("gift link"): whoever presents the SHA-256 preimage of `hashlock`
+    /// claims the escrow, paying `taker_amount` only when it is non-zero
+    SecretClaim,
+}
+
+/// Number of takers an order can allow in addition to its designated `taker`
+pub const MAX_ALLOWED_TAKERS: usize = 4;
+
+/// Slots a fill commitment stays valid for, after which it can be revealed no more and
+/// another taker may commit (about a minute)
+pub const COMMIT_REVEAL_WINDOW_SLOTS: u64 = 150;
+
+/// Largest referral cut, in bps of the maker side, a maker can configure
+pub const MAX_REFERRAL_BPS: u16 = 1_000;
+
+/// Lamports of an expired order's rent paid to whoever reaps it with `ReapExpiredOrder`
+pub const REAP_BOUNTY_LAMPORTS: u64 = 10_000;
+
+/// Size of the zero-padded UTF-8 memo stored on each order
+pub const MEMO_LEN: usize = 64;
+
+/// Size of the maker-chosen category tag stored on each order
+pub const TAG_LEN: usize = 8;
+/// `SwapOrder::maker_mint_risk`/`taker_mint_risk` bit: the mint has a permanent delegate
+/// that can move or burn tokens out of any account, escrows included
+pub const MINT_RISK_PERMANENT_DELEGATE: u8 = 1 << 0;
+
+/// `SwapOrder::maker_mint_risk`/`taker_mint_risk` bit: the mint has a freeze authority
+/// that can freeze the escrow or either party's account
+pub const MINT_RISK_FREEZE_AUTHORITY: u8 = 1 << 1;
+
+/// Layout version written by this program; older orders must go through `MigrateOrder`
+pub const ORDER_VERSION: u8 = 13;
+
+/// Program accounts that open with a fixed 8-byte type tag
+pub trait Discriminator {
+    /// `sha256("account:<TypeName>")[..8]`
+    const DISCRIMINATOR: [u8; 8];
+
+    /// Returns true if `data` opens with this type's discriminator
+    fn has_discriminator(data: &[u8]) -> bool {
+        data.starts_with(&Self::DISCRIMINATOR)
+    }
+}
+
+/// An order account, read and written in place as a packed plain-old-data layout: every
+/// field sits at a fixed byte offset, which RPC `memcmp` filters can rely on, and loading
+/// or storing an order is a copy rather than a field-by-field decode. Booleans are one
+/// byte and the enums their one-byte discriminant, so the bytes are the same as the Borsh
+/// encoding orders were written with before.
+#[derive(NoUninit, Clone, Copy, Debug, PartialEq, ShankAccount)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(C, packed)]
 pub struct SwapOrder {
+    /// Always `SwapOrder::DISCRIMINATOR`
+    pub discriminator: [u8; 8],
+    /// Layout version; fields are only ever appended, so migrating an order means
+    /// zero-extending it and setting the new fields' defaults
+    pub version: u8,
     pub maker: Pubkey,
     pub taker: Pubkey,
     pub maker_token_mint: Pubkey,
@@ -10,17 +138,265 @@ pub struct SwapOrder {
     pub maker_amount: u64,
     pub taker_amount: u64,
     pub bump: u8,
+    pub order_id: u64,
+    pub status: OrderStatus,
+    pub requires_confirmation: bool,
+    pub accepted_taker: Pubkey,
+    pub counter_maker_amount: u64,
+    pub counter_taker_amount: u64,
+    pub dual_escrow: bool,
+    pub paused: bool,
+    /// Unix timestamp after which the order can no longer be filled (0 = never)
+    pub expires_at: i64,
+    pub order_type: OrderType,
+    pub auction_start_ts: i64,
+    pub auction_end_ts: i64,
+    pub floor_taker_amount: u64,
+    /// Highest escrowed bid of an English auction, placed by `accepted_taker`
+    pub best_bid: u64,
+    pub maker_decimals: u8,
+    pub taker_decimals: u8,
+    /// Pyth price account of an oracle-priced order (`Pubkey::default()` if unset)
+    pub price_feed: Pubkey,
+    /// Markup over the oracle price charged to the taker, in bps
+    pub spread_bps: u16,
+    /// Rejects partial fills; `CompleteSwap` must take the full remaining size
+    pub all_or_none: bool,
+    /// SHA-256 hash of the secret that unlocks an HTLC or secret-claim order
+    pub hashlock: [u8; 32],
+    /// Unix timestamp after which an HTLC order can no longer be claimed
+    pub htlc_timeout: i64,
+    /// Seconds after the fill before any maker tokens vest to the taker
+    pub vesting_cliff: i64,
+    /// Seconds over which maker tokens vest linearly after the fill (0 = paid at fill)
+    pub vesting_duration: i64,
+    pub vesting_start_ts: i64,
+    /// Maker tokens held in escrow for `accepted_taker` when the order was filled
+    pub vesting_total: u64,
+    pub vesting_claimed: u64,
+    /// Number of equal tranches a streaming order settles in (0 = settles at once)
+    pub tranche_count: u16,
+    /// Seconds between tranches of a streaming order
+    pub tranche_interval: i64,
+    pub tranches_settled: u16,
+    /// Unix timestamp of the taker deposit that started the stream
+    pub stream_start_ts: i64,
+    /// Seconds between renewals of a recurring order (0 = not recurring)
+    pub recurrence_interval: i64,
+    /// Amounts a recurring order is reset to on each renewal
+    pub recurrence_maker_amount: u64,
+    pub recurrence_taker_amount: u64,
+    pub renewals_remaining: u16,
+    /// Earliest unix timestamp at which a filled recurring order can be renewed
+    pub next_renewal_ts: i64,
+    /// Maker the order PDA was derived from. `maker` is the current owner and can be
+    /// moved with `TransferOrderAuthority`; the PDA seeds always use this original key.
+    pub seed_maker: Pubkey,
+    /// Wallet allowed to change and close the order on the maker's behalf
+    /// (`Pubkey::default()` = none)
+    pub delegate: Pubkey,
+    /// Further takers allowed to fill the order; unused slots hold `Pubkey::default()`
+    pub allowed_takers: [Pubkey; 4],
+    /// Root of a Merkle tree of further allowed takers, proven at fill time
+    /// (all zeros = none). See `merkle` for building roots and proofs.
+    pub taker_merkle_root: [u8; 32],
+    /// Mint the taker must hold at least `gate_min_balance` of to fill
+    /// (`Pubkey::default()` = not gated)
+    pub gate_mint: Pubkey,
+    pub gate_min_balance: u64,
+    /// Fills must be committed with `CommitFill` and revealed in a later slot
+    pub commit_reveal: bool,
+    /// `validation::fill_commitment` of the committed taker and salt (all zeros = none)
+    pub fill_commitment: [u8; 32],
+    pub commit_slot: u64,
+    /// Taker whose reveal is being filled; only set within a `RevealFill`
+    pub revealed_taker: Pubkey,
+    /// Cut of each fill's maker side, in bps, paid to `referrer` by `CompleteSwap`
+    pub referral_bps: u16,
+    /// Zero-padded UTF-8 note, e.g. an OTC deal ID or terms hash
+    pub memo: [u8; 64],
+    /// Maker-chosen category (e.g. `b"OTC"`, zero-padded) at `SwapOrder::TAG_OFFSET`
+    pub tag: [u8; 8],
+    /// What the maker pays into escrow so that `maker_amount` arrives after the maker
+    /// mint's Token-2022 transfer fee; equals `maker_amount` for fee-less mints
+    pub maker_amount_gross: u64,
+    /// What the maker receives for `taker_amount` once the taker mint's transfer fee
+    /// is withheld; equals `taker_amount` for fee-less mints
+    pub taker_amount_net: u64,
+    /// `maker_amount` and `taker_amount` are UI amounts, converted to raw amounts at fill
+    /// time so interest-bearing mints keep their quoted price
+    pub ui_amounts: bool,
+    /// `MINT_RISK_*` bits of the maker mint, recorded at creation for clients to warn about
+    pub maker_mint_risk: u8,
+    /// `MINT_RISK_*` bits of the taker mint
+    pub taker_mint_risk: u8,
+    /// The maker side is a single NFT (decimals 0, supply 1) that only trades whole
+    pub nft: bool,
+    /// The NFT is a Metaplex programmable NFT, moved through Token Metadata
+    pub programmable: bool,
+    /// `taker_token_mint` is a Metaplex collection mint and any verified NFT of the
+    /// collection fills the taker side
+    pub taker_collection: bool,
+    /// Token-2022 token group the maker mint was verified to be a member of, or the
+    /// default pubkey
+    pub maker_group: Pubkey,
+    /// Token-2022 token group whose member mints fill the taker side in place of
+    /// `taker_token_mint`, or the default pubkey
+    pub taker_group: Pubkey,
+    /// Account that paid the order's rent in place of the maker and gets it back on
+    /// close, or the default pubkey when the maker paid
+    pub rent_payer: Pubkey,
+    /// Fills the order has taken, by `CompleteSwap` or any other settlement, which number
+    /// its fill receipts
+    pub fill_count: u64,
+    /// Maker tokens settled over all fills, counted from version 12 for
+    /// migrated orders. Recorded before any tokens move, along with the shrunk
+    /// `maker_amount` or completed status, so a repeated fill finds nothing left to take.
+    pub filled_amount: u64,
+    /// Wallet the maker named with `SetReferralFee` to receive the referral cut, or the
+    /// default pubkey while no referral fee is set
+    pub referrer: Pubkey,
+}
+
+// The derived impl takes references to the fields of its bits type, which are unaligned
+// in a packed struct, so the byte patterns are checked by offset instead.
+// SAFETY: every field of `SwapOrder` is valid for any bit pattern except the bools and
+// enums checked here, and `[u8; SwapOrder::LEN]` matches its size and 1-byte alignment.
+unsafe impl CheckedBitPattern for SwapOrder {
+    type Bits = [u8; SwapOrder::LEN];
+
+    fn is_valid_bit_pattern(bits: &Self::Bits) -> bool {
+        let bools = [
+            offset_of!(SwapOrder, requires_confirmation),
+            offset_of!(SwapOrder, dual_escrow),
+            offset_of!(SwapOrder, paused),
+            offset_of!(SwapOrder, all_or_none),
+            offset_of!(SwapOrder, commit_reveal),
+            offset_of!(SwapOrder, ui_amounts),
+            offset_of!(SwapOrder, nft),
+            offset_of!(SwapOrder, programmable),
+            offset_of!(SwapOrder, taker_collection),
+        ];
+        OrderStatus::is_valid_bit_pattern(&bits[offset_of!(SwapOrder, status)])
+            && OrderType::is_valid_bit_pattern(&bits[offset_of!(SwapOrder, order_type)])
+            && bools.iter().all(|&offset| bits[offset] <= 1)
+    }
 }
 
 impl SwapOrder {
-    pub const LEN: usize = 32 + // maker
+    /// Byte offset of `tag`, for `memcmp` filters over order accounts
+    pub const TAG_OFFSET: usize = 8 + // discriminator
+        1 + // version
+        32 + // maker
         32 + // taker
         32 + // maker_token_mint
         32 + // taker_token_mint
         8 + // maker_amount
         8 + // taker_amount
-        1; // bump
+        1 + // bump
+        8 + // order_id
+        1 + // status
+        1 + // requires_confirmation
+        32 + // accepted_taker
+        8 + // counter_maker_amount
+        8 + // counter_taker_amount
+        1 + // dual_escrow
+        1 + // paused
+        8 + // expires_at
+        1 + // order_type
+        8 + // auction_start_ts
+        8 + // auction_end_ts
+        8 + // floor_taker_amount
+        8 + // best_bid
+        1 + // maker_decimals
+        1 + // taker_decimals
+        32 + // price_feed
+        2 + // spread_bps
+        1 + // all_or_none
+        32 + // hashlock
+        8 + // htlc_timeout
+        8 + // vesting_cliff
+        8 + // vesting_duration
+        8 + // vesting_start_ts
+        8 + // vesting_total
+        8 + // vesting_claimed
+        2 + // tranche_count
+        8 + // tranche_interval
+        2 + // tranches_settled
+        8 + // stream_start_ts
+        8 + // recurrence_interval
+        8 + // recurrence_maker_amount
+        8 + // recurrence_taker_amount
+        2 + // renewals_remaining
+        8 + // next_renewal_ts
+        32 + // seed_maker
+        32 + // delegate
+        32 * MAX_ALLOWED_TAKERS + // allowed_takers
+        32 + // taker_merkle_root
+        32 + // gate_mint
+        8 + // gate_min_balance
+        1 + // commit_reveal
+        32 + // fill_commitment
+        8 + // commit_slot
+        32 + // revealed_taker
+        2 + // referral_bps
+        MEMO_LEN; // memo
+
+    pub const LEN: usize = Self::TAG_OFFSET +
+        TAG_LEN + // tag
+        8 + // maker_amount_gross
+        8 + // taker_amount_net
+        1 + // ui_amounts
+        1 + // maker_mint_risk
+        1 + // taker_mint_risk
+        1 + // nft
+        1 + // programmable
+        1 + // taker_collection
+        32 + // maker_group
+        32 + // taker_group
+        32 + // rent_payer
+        8 + // fill_count
+        8 + // filled_amount
+        32; // referrer
+
+    /// Size of orders created before the version byte
+    pub const LEGACY_LEN: usize = 764;
+
+    /// Size of version 1 orders: the legacy layout behind a version byte, without a
+    /// discriminator
+    pub const VERSION_1_LEN: usize = Self::LEGACY_LEN + 1;
+
+    /// Borrows the order stored at the start of `data` in place. Only the byte patterns of
+    /// the bools and enums are checked; the discriminator and version are not.
+    pub fn view(data: &[u8]) -> Result<&Self, ProgramError> {
+        let data = data
+            .get(..Self::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        bytemuck::checked::try_from_bytes(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Mutably borrows the order stored at the start of `data` in place
+    pub fn view_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        let data = data
+            .get_mut(..Self::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        bytemuck::checked::try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Copies the order stored at the start of `data` out of it
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        Self::view(data).copied()
+    }
+
+    /// Writes the order to the start of `data`
+    pub fn pack(&self, data: &mut [u8]) -> ProgramResult {
+        data.get_mut(..Self::LEN)
+            .ok_or(ProgramError::AccountDataTooSmall)?
+            .copy_from_slice(bytemuck::bytes_of(self));
+        Ok(())
+    }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         maker: Pubkey,
         taker: Pubkey,
@@ -29,8 +405,11 @@ impl SwapOrder {
         maker_amount: u64,
         taker_amount: u64,
         bump: u8,
+        order_id: u64,
     ) -> Self {
         Self {
+            discriminator: Self::DISCRIMINATOR,
+            version: ORDER_VERSION,
             maker,
             taker,
             maker_token_mint,
@@ -38,6 +417,940 @@ impl SwapOrder {
             maker_amount,
             taker_amount,
             bump,
+            order_id,
+            status: OrderStatus::Open,
+            requires_confirmation: false,
+            accepted_taker: Pubkey::default(),
+            counter_maker_amount: 0,
+            counter_taker_amount: 0,
+            dual_escrow: false,
+            paused: false,
+            expires_at: 0,
+            order_type: OrderType::Fixed,
+            auction_start_ts: 0,
+            auction_end_ts: 0,
+            floor_taker_amount: 0,
+            best_bid: 0,
+            maker_decimals: 0,
+            taker_decimals: 0,
+            price_feed: Pubkey::default(),
+            spread_bps: 0,
+            all_or_none: false,
+            hashlock: [0; 32],
+            htlc_timeout: 0,
+            vesting_cliff: 0,
+            vesting_duration: 0,
+            vesting_start_ts: 0,
+            vesting_total: 0,
+            vesting_claimed: 0,
+            tranche_count: 0,
+            tranche_interval: 0,
+            tranches_settled: 0,
+            stream_start_ts: 0,
+            recurrence_interval: 0,
+            recurrence_maker_amount: 0,
+            recurrence_taker_amount: 0,
+            renewals_remaining: 0,
+            next_renewal_ts: 0,
+            seed_maker: maker,
+            delegate: Pubkey::default(),
+            allowed_takers: [Pubkey::default(); MAX_ALLOWED_TAKERS],
+            taker_merkle_root: [0; 32],
+            gate_mint: Pubkey::default(),
+            gate_min_balance: 0,
+            commit_reveal: false,
+            fill_commitment: [0; 32],
+            commit_slot: 0,
+            revealed_taker: Pubkey::default(),
+            referral_bps: 0,
+            memo: [0; MEMO_LEN],
+            tag: [0; TAG_LEN],
+            maker_amount_gross: maker_amount,
+            taker_amount_net: taker_amount,
+            ui_amounts: false,
+            maker_mint_risk: 0,
+            taker_mint_risk: 0,
+            nft: false,
+            programmable: false,
+            taker_collection: false,
+            maker_group: Pubkey::default(),
+            taker_group: Pubkey::default(),
+            rent_payer: Pubkey::default(),
+            fill_count: 0,
+            filled_amount: 0,
+            referrer: Pubkey::default(),
+        }
+    }
+
+    /// Returns the amount a taker owes when filling at unix time `now`. Oracle orders
+    /// are priced from their feed with `oracle::oracle_taker_amount` instead.
+    pub fn taker_amount_at(&self, now: i64) -> u64 {
+        match self.order_type {
+            OrderType::Fixed
+            | OrderType::EnglishAuction
+            | OrderType::Oracle
+            | OrderType::Htlc
+            | OrderType::SecretClaim => self.taker_amount,
+            OrderType::DutchAuction => {
+                if now <= self.auction_start_ts || self.floor_taker_amount >= self.taker_amount {
+                    return self.taker_amount;
+                }
+                if now >= self.auction_end_ts {
+                    return self.floor_taker_amount;
+                }
+
+                let elapsed = (now - self.auction_start_ts) as u128;
+                let duration = (self.auction_end_ts - self.auction_start_ts) as u128;
+                let range = (self.taker_amount - self.floor_taker_amount) as u128;
+                self.taker_amount - (range * elapsed / duration) as u64
+            }
+        }
+    }
+
+    /// Returns the amount a taker owes for `fill_amount` of the remaining maker size at
+    /// unix time `now`, rounded up in the maker's favour
+    pub fn taker_amount_for(&self, fill_amount: u64, now: i64) -> u64 {
+        let full_amount = self.taker_amount_at(now);
+        if fill_amount >= self.maker_amount {
+            return full_amount;
+        }
+        Price {
+            taker_amount: full_amount,
+            maker_amount: self.maker_amount,
+        }
+        .taker_amount_for(fill_amount)
+    }
+
+    /// Returns the order's listed price. Dutch auctions fill at `taker_amount_at` and
+    /// oracle orders at their feed's price instead.
+    pub fn price(&self) -> Price {
+        Price {
+            taker_amount: self.taker_amount,
+            maker_amount: self.maker_amount,
+        }
+    }
+
+    /// Returns true if the order takes fills and amount changes
+    pub fn is_open(&self) -> bool {
+        self.status == OrderStatus::Open
+    }
+
+    /// Returns the maker tokens still to be filled: `maker_amount` while the order can
+    /// fill, open or holding a taker's deposit, and 0 once it completed or was
+    /// cancelled, expired or declined
+    pub fn remaining(&self) -> u64 {
+        if self.is_open() || self.status.holds_taker_deposit() {
+            self.maker_amount
+        } else {
+            0
+        }
+    }
+
+    /// Settles a fill of `fill_amount` of the remaining maker size, for
+    /// `quoted_taker_amount` of the taker side: adds it to `filled_amount` and completes
+    /// the order, or shrinks it to what is left with a fixed order keeping its price.
+    /// Fails for a fill the remaining size no longer covers.
+    pub fn record_fill(
+        &mut self,
+        fill_amount: u64,
+        quoted_taker_amount: u64,
+    ) -> Result<(), ProgramError> {
+        let remaining = self.remaining();
+        if fill_amount == 0 || fill_amount > remaining {
+            return Err(SwapError::InvalidAmount.into());
+        }
+        self.filled_amount = self
+            .filled_amount
+            .checked_add(fill_amount)
+            .ok_or(SwapError::InvalidAmount)?;
+        if fill_amount == remaining {
+            // A completed order keeps the terms of its final fill
+            self.status = OrderStatus::Completed;
+        } else {
+            self.maker_amount -= fill_amount;
+            if self.order_type == OrderType::Fixed {
+                // The rest of a partially filled order keeps its original price
+                self.taker_amount = self
+                    .taker_amount
+                    .checked_sub(quoted_taker_amount)
+                    .filter(|remaining| *remaining > 0)
+                    .ok_or(SwapError::InvalidAmount)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Settles everything that remains of the order as one fill for `taker_amount`, as
+    /// `record_fill` does, and returns that fill's index
+    pub fn record_final_fill(&mut self, taker_amount: u64) -> Result<u64, ProgramError> {
+        self.record_fill(self.remaining(), taker_amount)?;
+        let fill_index = self.fill_count;
+        self.fill_count += 1;
+        Ok(fill_index)
+    }
+
+    /// Returns the order's address under `program_id`, derived with its stored bump
+    pub fn pda(&self, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+        Ok(Pubkey::create_program_address(
+            &[
+                b"order",
+                self.seed_maker.as_ref(),
+                self.maker_token_mint.as_ref(),
+                self.taker_token_mint.as_ref(),
+                &self.order_id.to_le_bytes(),
+                &[self.bump],
+            ],
+            program_id,
+        )?)
+    }
+
+    /// Returns true if `CompleteSwap` may take less than the full remaining size. Escrowed
+    /// taker deposits and auctions are priced for the whole order and always fill atomically.
+    pub fn allows_partial_fill(&self) -> bool {
+        !self.all_or_none
+            && !self.nft
+            && !self.dual_escrow
+            && !self.has_vesting()
+            && matches!(self.order_type, OrderType::Fixed | OrderType::Oracle)
+    }
+
+    /// Returns true if `accepted_taker` has tokens sitting in the taker-mint escrow,
+    /// either as an acceptance/counter-offer or as the leading auction bid
+    pub fn holds_taker_deposit(&self) -> bool {
+        self.status.holds_taker_deposit() || self.has_live_bid()
+    }
+
+    /// Returns true if an English auction holds a bid, which locks its terms until the
+    /// bidder is refunded by a higher bid or paid out by `SettleAuction`
+    pub fn has_live_bid(&self) -> bool {
+        self.order_type == OrderType::EnglishAuction && self.best_bid > 0
+    }
+
+    /// Returns true if the escrow of an HTLC order is still claimable by the taker at `now`,
+    /// which keeps the maker from withdrawing it
+    pub fn is_htlc_locked(&self, now: i64) -> bool {
+        self.order_type == OrderType::Htlc && self.is_open() && now < self.htlc_timeout
+    }
+
+    /// Returns true if a completed order has nothing left to vest, renew or refund, so it
+    /// and its escrow can close as soon as it fills
+    pub fn closes_on_fill(&self) -> bool {
+        self.status == OrderStatus::Completed
+            && !self.has_vesting()
+            && !self.programmable
+            && !self.dual_escrow
+            && (self.recurrence_interval == 0 || self.renewals_remaining == 0)
+    }
+
+    /// Returns true if the maker's tokens unlock to the taker over time after the fill
+    pub fn has_vesting(&self) -> bool {
+        self.vesting_duration > 0
+    }
+
+    /// Returns the maker tokens vested to the taker at unix time `now`, claimed or not
+    pub fn vested_amount_at(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.vesting_start_ts);
+        if elapsed < self.vesting_cliff {
+            return 0;
+        }
+        if elapsed >= self.vesting_duration {
+            return self.vesting_total;
+        }
+        (self.vesting_total as u128 * elapsed as u128 / self.vesting_duration as u128) as u64
+    }
+
+    /// Returns true if a filled order's escrow still owes vesting tokens to the taker
+    pub fn is_vesting_locked(&self) -> bool {
+        self.has_vesting()
+            && self.status == OrderStatus::Completed
+            && self.vesting_claimed < self.vesting_total
+    }
+
+    /// Returns true if the order settles in tranches through `SettleTranche`
+    pub fn is_streaming(&self) -> bool {
+        self.tranche_count > 0
+    }
+
+    /// Returns the number of tranches of a streaming order that are due at unix time `now`
+    pub fn tranches_due_at(&self, now: i64) -> u16 {
+        if self.tranche_interval <= 0 {
+            return 0;
+        }
+        let elapsed = now.saturating_sub(self.stream_start_ts).max(0);
+        (elapsed / self.tranche_interval).min(self.tranche_count as i64) as u16
+    }
+
+    /// Returns the share of `amount` released once `tranches` tranches have settled
+    pub fn tranche_share(&self, amount: u64, tranches: u16) -> u64 {
+        (amount as u128 * tranches as u128 / self.tranche_count as u128) as u64
+    }
+
+    /// Returns true if the order has an expiry that has passed at `now`
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+
+    /// Returns true if the order has no designated or allowed takers and can be filled
+    /// by anyone
+    pub fn accepts_any_taker(&self) -> bool {
+        self.taker == Pubkey::default()
+            && !self.has_taker_merkle_root()
+            && self
+                .allowed_takers
+                .iter()
+                .all(|taker| *taker == Pubkey::default())
+    }
+
+    /// Returns the referrer's cut of a fill of `fill_amount` maker tokens, rounded down
+    pub fn referral_fee(&self, fill_amount: u64) -> u64 {
+        (fill_amount as u128 * self.referral_bps as u128 / 10_000) as u64
+    }
+
+    /// Returns true if a fill commitment made at `commit_slot` can still be revealed
+    /// at `slot`
+    pub fn has_live_commitment(&self, slot: u64) -> bool {
+        self.fill_commitment != [0; 32]
+            && slot <= self.commit_slot.saturating_add(COMMIT_REVEAL_WINDOW_SLOTS)
+    }
+
+    /// Returns true if takers must hold a balance of `gate_mint` to fill
+    pub fn is_token_gated(&self) -> bool {
+        self.gate_mint != Pubkey::default()
+    }
+
+    /// Returns true if takers can prove membership in a Merkle allowlist
+    pub fn has_taker_merkle_root(&self) -> bool {
+        self.taker_merkle_root != [0; 32]
+    }
+
+    /// Returns true if `key` may take the order: anyone on open orders, otherwise the
+    /// designated taker or one of the allowed takers. Merkle allowlist members are
+    /// checked separately with `validation::validate_taker_proof`.
+    pub fn is_allowed_taker(&self, key: &Pubkey) -> bool {
+        self.accepts_any_taker()
+            || (*key != Pubkey::default()
+                && (self.taker == *key || self.allowed_takers.contains(key)))
+    }
+
+    /// Returns true if the maker side is native SOL escrowed in the order PDA itself
+    pub fn maker_is_native(&self) -> bool {
+        self.maker_token_mint == spl_token::native_mint::id()
+    }
+
+    /// Returns true if the taker side is paid in any mint of a collection or token group
+    /// rather than in `taker_token_mint` itself
+    pub fn takes_any_member(&self) -> bool {
+        self.taker_collection || self.taker_group != Pubkey::default()
+    }
+
+    /// Returns the account the order's rent is refunded to
+    pub fn rent_refund_account(&self) -> Pubkey {
+        if self.rent_payer == Pubkey::default() {
+            self.maker
+        } else {
+            self.rent_payer
         }
     }
+
+    /// Returns true if `key` may receive the order's rent when it closes: the maker, or
+    /// the account that paid it
+    pub fn is_rent_destination(&self, key: &Pubkey) -> bool {
+        *key == self.maker || *key == self.rent_refund_account()
+    }
+
+    /// Returns true if the taker side is paid in native SOL
+    pub fn taker_is_native(&self) -> bool {
+        self.taker_token_mint == spl_token::native_mint::id()
+    }
+
+    /// Returns the memo without its zero padding
+    pub fn memo_str(&self) -> &str {
+        let len = self.memo.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        std::str::from_utf8(&self.memo[..len]).unwrap_or_default()
+    }
+
+    /// Returns true if `key` is the order's management delegate
+    pub fn is_delegate(&self, key: &Pubkey) -> bool {
+        self.delegate != Pubkey::default() && self.delegate == *key
+    }
+}
+
+impl Discriminator for SwapOrder {
+    const DISCRIMINATOR: [u8; 8] = [120, 0, 228, 80, 167, 248, 73, 201];
+}
+
+// `LEN` lists the fields one by one, so it has to agree with the packed struct
+const _: () = assert!(std::mem::size_of::<SwapOrder>() == SwapOrder::LEN);
+
+/// Most mints a bundle order can escrow, and separately ask for, at once
+pub const MAX_BUNDLE_LEGS: usize = 4;
+
+/// One mint of a bundle order and the amount of it that changes hands
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BundleLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl BundleLeg {
+    pub const LEN: usize = 32 + // mint
+        8 + // amount
+        1; // decimals
+}
+
+/// Order trading several maker mints for several taker mints in a single fill, stored
+/// at `[b"bundle", maker, bundle_id]` and sized to its legs when created
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct BundleOrder {
+    /// Always `BundleOrder::DISCRIMINATOR`
+    pub discriminator: [u8; 8],
+    pub maker: Pubkey,
+    /// Taker allowed to fill the bundle (`Pubkey::default()` for anyone)
+    pub taker: Pubkey,
+    pub bundle_id: u64,
+    pub bump: u8,
+    pub status: OrderStatus,
+    /// Amounts held in the bundle's escrows and released to the taker
+    pub maker_legs: Vec<BundleLeg>,
+    /// Amounts the taker pays the maker
+    pub taker_legs: Vec<BundleLeg>,
+}
+
+impl BundleOrder {
+    /// Returns the account size of a bundle with the given numbers of legs
+    pub fn space(maker_legs: usize, taker_legs: usize) -> usize {
+        8 + // discriminator
+        32 + // maker
+        32 + // taker
+        8 + // bundle_id
+        1 + // bump
+        1 + // status
+        4 + BundleLeg::LEN * maker_legs + // maker_legs
+        4 + BundleLeg::LEN * taker_legs // taker_legs
+    }
+
+    /// Returns true if `key` may fill the bundle
+    pub fn is_allowed_taker(&self, key: &Pubkey) -> bool {
+        self.taker == Pubkey::default() || self.taker == *key
+    }
+}
+
+impl Discriminator for BundleOrder {
+    const DISCRIMINATOR: [u8; 8] = [164, 40, 152, 17, 95, 140, 229, 251];
+}
+
+/// Order selling a Bubblegum compressed NFT for tokens, stored at
+/// `[b"cnft_order", maker, order_id]`. The order PDA owns the NFT's leaf while the order
+/// is open; the leaf fields below are what Bubblegum needs to move it again.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct CompressedOrder {
+    /// Always `CompressedOrder::DISCRIMINATOR`
+    pub discriminator: [u8; 8],
+    pub maker: Pubkey,
+    /// Taker allowed to fill the order (`Pubkey::default()` for anyone)
+    pub taker: Pubkey,
+    pub order_id: u64,
+    pub bump: u8,
+    pub status: OrderStatus,
+    pub merkle_tree: Pubkey,
+    /// Bubblegum asset id of the NFT, derived from the tree and `nonce`
+    pub asset_id: Pubkey,
+    pub nonce: u64,
+    pub leaf_index: u32,
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    /// Number of proof nodes the tree needs below its canopy
+    pub proof_len: u8,
+    pub taker_token_mint: Pubkey,
+    pub taker_amount: u64,
+    pub taker_decimals: u8,
+}
+
+impl CompressedOrder {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // maker
+        32 + // taker
+        8 + // order_id
+        1 + // bump
+        1 + // status
+        32 + // merkle_tree
+        32 + // asset_id
+        8 + // nonce
+        4 + // leaf_index
+        32 + // data_hash
+        32 + // creator_hash
+        1 + // proof_len
+        32 + // taker_token_mint
+        8 + // taker_amount
+        1; // taker_decimals
+
+    /// Returns true if `key` may fill the order
+    pub fn is_allowed_taker(&self, key: &Pubkey) -> bool {
+        self.taker == Pubkey::default() || self.taker == *key
+    }
+}
+
+impl Discriminator for CompressedOrder {
+    const DISCRIMINATOR: [u8; 8] = [105, 139, 163, 206, 92, 185, 130, 99];
+}
+
+/// Largest protocol fee, in bps of the taker side, the config admin can set
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Most volume tiers the config fee schedule can hold
+pub const MAX_FEE_TIERS: usize = 4;
+
+// Shank only reads literal array lengths, so the account and instruction fields sized
+// by these constants spell them out
+const _: () =
+    assert!(MEMO_LEN == 64 && TAG_LEN == 8 && MAX_ALLOWED_TAKERS == 4 && MAX_FEE_TIERS == 4);
+
+/// Discounted protocol fee for users whose tracked volume reaches `min_volume`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FeeTier {
+    pub min_volume: u64,
+    pub fee_bps: u16,
+}
+
+impl FeeTier {
+    pub const LEN: usize = 8 + // min_volume
+        2; // fee_bps
+
+    /// Returns true for the zeroed slots after the configured tiers
+    pub fn is_unused(&self) -> bool {
+        self.min_volume == 0
+    }
+}
+
+/// Program-wide settings, stored in the `[b"config"]` PDA
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct Config {
+    /// Authority allowed to `UpdateConfig`
+    pub admin: Pubkey,
+    /// Protocol fee, in bps of the taker side of each `CompleteSwap`
+    pub fee_bps: u16,
+    /// Owner of the token accounts `WithdrawFees` pays treasury balances out to
+    pub fee_collector: Pubkey,
+    pub bump: u8,
+    /// Mint the taker pays protocol fees in (`Pubkey::default()` to skim the taker side)
+    pub fee_mint: Pubkey,
+    pub fee_mint_decimals: u8,
+    /// Volume tiers by ascending `min_volume`, replacing `fee_bps` for users whose
+    /// `UserStats` volume reaches them
+    pub fee_tiers: [FeeTier; 4],
+    /// Set by the admin with `SetProtocolPaused` to stop new orders and fills protocol-wide
+    pub paused: bool,
+    /// Set by the admin with `SetTradeGuards` to allow orders whose taker is their maker
+    pub allow_self_trade: bool,
+    /// Set by the admin with `SetTradeGuards` to allow orders trading a mint for itself
+    pub allow_same_mint: bool,
+}
+
+impl Config {
+    pub const LEN: usize = 32 + // admin
+        2 + // fee_bps
+        32 + // fee_collector
+        1 + // bump
+        32 + // fee_mint
+        1 + // fee_mint_decimals
+        FeeTier::LEN * MAX_FEE_TIERS + // fee_tiers
+        1 + // paused
+        1 + // allow_self_trade
+        1; // allow_same_mint
+
+    /// Returns the fee rate of a user with tracked `volume`: the highest tier it
+    /// reaches, or `fee_bps` below the first tier
+    pub fn fee_bps_for(&self, volume: u64) -> u16 {
+        self.fee_tiers
+            .iter()
+            .take_while(|tier| !tier.is_unused())
+            .filter(|tier| volume >= tier.min_volume)
+            .last()
+            .map_or(self.fee_bps, |tier| tier.fee_bps)
+    }
+
+    /// Returns the protocol fee on a taker-side payment of `taker_amount` by a user
+    /// with tracked `volume`, rounded down
+    pub fn fee(&self, taker_amount: u64, volume: u64) -> u64 {
+        (taker_amount as u128 * self.fee_bps_for(volume) as u128 / 10_000) as u64
+    }
+
+    /// Returns true if fees are paid separately in `fee_mint` rather than skimmed
+    pub fn has_fee_mint(&self) -> bool {
+        self.fee_mint != Pubkey::default()
+    }
+}
+
+/// Cumulative fill volume of a maker or taker, stored at `[b"user_stats", user]` and
+/// counted in taker-side base units of every fill it takes part in
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct UserStats {
+    pub user: Pubkey,
+    pub volume: u64,
+    pub bump: u8,
+}
+
+impl UserStats {
+    pub const LEN: usize = 32 + // user
+        8 + // volume
+        1; // bump
+}
+
+/// Protocol-wide fill activity, stored in the `[b"protocol_stats"]` PDA and updated by
+/// every fill once initialized. Volumes are raw base units summed over every
+/// mint, so they only mean something next to the per-pair totals.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct ProtocolStats {
+    pub fills: u64,
+    /// Fill sides settled through SPL Token and through Token-2022; each fill counts once
+    /// for its maker side and once for its taker side
+    pub token_fills: u64,
+    pub token_2022_fills: u64,
+    pub maker_volume: u128,
+    pub taker_volume: u128,
+    pub bump: u8,
+}
+
+impl ProtocolStats {
+    pub const LEN: usize = 8 + // fills
+        8 + // token_fills
+        8 + // token_2022_fills
+        16 + // maker_volume
+        16 + // taker_volume
+        1; // bump
+
+    /// Counts a fill of `maker_amount` for `taker_amount` settled through the given
+    /// token programs
+    pub fn record_fill(
+        &mut self,
+        maker_token_program: &Pubkey,
+        taker_token_program: &Pubkey,
+        maker_amount: u64,
+        taker_amount: u64,
+    ) {
+        self.fills = self.fills.saturating_add(1);
+        for token_program in [maker_token_program, taker_token_program] {
+            if *token_program == spl_token_2022::id() {
+                self.token_2022_fills = self.token_2022_fills.saturating_add(1);
+            } else {
+                self.token_fills = self.token_fills.saturating_add(1);
+            }
+        }
+        self.maker_volume = self.maker_volume.saturating_add(maker_amount as u128);
+        self.taker_volume = self.taker_volume.saturating_add(taker_amount as u128);
+    }
+}
+
+/// Fill activity of the orders selling `maker_mint` for `taker_mint`, stored at
+/// `[b"pair_stats", maker_mint, taker_mint]` and updated by every fill of such an
+/// order once initialized
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct PairStats {
+    pub maker_mint: Pubkey,
+    pub taker_mint: Pubkey,
+    pub fills: u64,
+    pub maker_volume: u128,
+    pub taker_volume: u128,
+    pub bump: u8,
+}
+
+impl PairStats {
+    pub const LEN: usize = 32 + // maker_mint
+        32 + // taker_mint
+        8 + // fills
+        16 + // maker_volume
+        16 + // taker_volume
+        1; // bump
+
+    /// Counts a fill of `maker_amount` for `taker_amount`
+    pub fn record_fill(&mut self, maker_amount: u64, taker_amount: u64) {
+        self.fills = self.fills.saturating_add(1);
+        self.maker_volume = self.maker_volume.saturating_add(maker_amount as u128);
+        self.taker_volume = self.taker_volume.saturating_add(taker_amount as u128);
+    }
+}
+
+/// The open orders of a maker, stored at `[b"maker_index", maker]` so a wallet can list
+/// them with one account fetch. `InitializeOrder` adds each order and `CloseOrder`
+/// removes it; the account grows and shrinks by one key at a time. Entries are keyed by
+/// the maker the order PDA was derived from, so they stay put across
+/// `TransferOrderAuthority`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct MakerIndex {
+    pub maker: Pubkey,
+    pub bump: u8,
+    pub orders: Vec<Pubkey>,
+}
+
+impl MakerIndex {
+    /// Returns the account size of an index listing `orders` orders
+    pub fn space(orders: usize) -> usize {
+        32 + // maker
+        1 + // bump
+        4 + 32 * orders // orders
+    }
+}
+
+/// Most orders one registry page lists
+pub const REGISTRY_PAGE_ENTRIES: usize = 32;
+
+/// An open order as listed in a registry page
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RegistryEntry {
+    pub order: Pubkey,
+    pub maker: Pubkey,
+    /// Remaining amounts, as of the order's last fill or amount change
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+}
+
+impl RegistryEntry {
+    pub const LEN: usize = 32 + // order
+        32 + // maker
+        8 + // maker_amount
+        8; // taker_amount
+}
+
+/// One page of the on-chain order book of the orders selling `maker_mint` for
+/// `taker_mint`, stored at `[b"registry", maker_mint, taker_mint, page]`. Page 0 is
+/// created with the pair's first order and each later page by `InitializeRegistryPage`,
+/// which links it from the one before, so the book is walked from page 0 along `next`.
+/// `InitializeOrder` lists each new order in a page with room, and fills, amount changes
+/// and closes passed the page keep its entry up to date.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct RegistryPage {
+    pub discriminator: [u8; 8],
+    pub maker_mint: Pubkey,
+    pub taker_mint: Pubkey,
+    pub page: u32,
+    pub bump: u8,
+    /// The pair's next page, or the default pubkey for the last one
+    pub next: Pubkey,
+    pub entries: Vec<RegistryEntry>,
+}
+
+impl RegistryPage {
+    /// Returns the account size of a page listing `entries` orders
+    pub fn space(entries: usize) -> usize {
+        8 + // discriminator
+        32 + // maker_mint
+        32 + // taker_mint
+        4 + // page
+        1 + // bump
+        32 + // next
+        4 + RegistryEntry::LEN * entries // entries
+    }
+}
+
+impl Discriminator for RegistryPage {
+    const DISCRIMINATOR: [u8; 8] = [190, 151, 207, 163, 226, 253, 16, 250];
+}
+
+/// An exact price: `taker_amount` raw taker tokens for `maker_amount` raw maker tokens
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Price {
+    pub taker_amount: u64,
+    pub maker_amount: u64,
+}
+
+impl Price {
+    /// Returns the taker tokens owed for `maker_amount` at this price, rounded up in the
+    /// maker's favour
+    pub fn taker_amount_for(&self, maker_amount: u64) -> u64 {
+        if self.maker_amount == 0 {
+            return 0;
+        }
+        (self.taker_amount as u128 * maker_amount as u128).div_ceil(self.maker_amount as u128)
+            as u64
+    }
+
+    /// Returns the taker mint's raw units per whole maker token of `maker_decimals`,
+    /// rounded down and saturating
+    pub fn per_maker_token(&self, maker_decimals: u8) -> u64 {
+        if self.maker_amount == 0 {
+            return 0;
+        }
+        let price = self.taker_amount as u128 * 10u128.pow(maker_decimals as u32)
+            / self.maker_amount as u128;
+        price.min(u64::MAX as u128) as u64
+    }
+}
+
+/// Returns the price of a fill of `maker_amount` for `taker_amount`: the taker mint's raw
+/// units paid per whole maker token of `maker_decimals`, saturating
+pub fn fill_price(maker_amount: u64, taker_amount: u64, maker_decimals: u8) -> u64 {
+    Price {
+        taker_amount,
+        maker_amount,
+    }
+    .per_maker_token(maker_decimals)
+}
+
+/// Durable record of one fill, stored at `[b"receipt", order, fill_index]` when the
+/// settling signer passes it. It outlives the order and
+/// the transaction's logs, and is never closed.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct FillReceipt {
+    pub discriminator: [u8; 8],
+    pub order: Pubkey,
+    /// The order's `fill_count` before this fill
+    pub fill_index: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub maker_mint: Pubkey,
+    pub taker_mint: Pubkey,
+    /// Maker tokens that left escrow, referral cut included
+    pub maker_amount: u64,
+    /// Taker tokens paid, protocol fee included
+    pub taker_amount: u64,
+    /// See `fill_price`
+    pub price: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl FillReceipt {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // order
+        8 + // fill_index
+        32 + // maker
+        32 + // taker
+        32 + // maker_mint
+        32 + // taker_mint
+        8 + // maker_amount
+        8 + // taker_amount
+        8 + // price
+        8 + // slot
+        8 + // timestamp
+        1; // bump
+}
+
+impl Discriminator for FillReceipt {
+    const DISCRIMINATOR: [u8; 8] = [8, 10, 169, 84, 163, 19, 167, 139];
+}
+
+/// Fills one trade history keeps
+pub const TRADE_HISTORY_LEN: usize = 64;
+
+/// One fill as kept in a trade history
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct TradePrint {
+    /// See `fill_price`
+    pub price: u64,
+    /// Maker tokens that left escrow
+    pub size: u64,
+    pub slot: u64,
+}
+
+impl TradePrint {
+    pub const LEN: usize = 8 + // price
+        8 + // size
+        8; // slot
+}
+
+/// The latest `TRADE_HISTORY_LEN` fills of the orders selling `maker_mint` for
+/// `taker_mint`, stored at `[b"trade_history", maker_mint, taker_mint]` and written by
+/// every fill passed it once initialized. `prints` is a ring buffer: fill `n`
+/// lands at `n % TRADE_HISTORY_LEN`, overwriting the oldest.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct TradeHistory {
+    pub discriminator: [u8; 8],
+    pub maker_mint: Pubkey,
+    pub taker_mint: Pubkey,
+    pub bump: u8,
+    /// Fills recorded since the history was initialized
+    pub total: u64,
+    pub prints: [TradePrint; 64],
+}
+
+impl TradeHistory {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // maker_mint
+        32 + // taker_mint
+        1 + // bump
+        8 + // total
+        TradePrint::LEN * TRADE_HISTORY_LEN; // prints
+
+    /// Records a fill, overwriting the oldest once the buffer is full
+    pub fn record(&mut self, print: TradePrint) {
+        self.prints[(self.total % TRADE_HISTORY_LEN as u64) as usize] = print;
+        self.total = self.total.saturating_add(1);
+    }
+
+    /// Returns the recorded fills, newest first
+    pub fn recent(&self) -> impl Iterator<Item = &TradePrint> {
+        let len = self.total.min(TRADE_HISTORY_LEN as u64) as usize;
+        let next = (self.total % TRADE_HISTORY_LEN as u64) as usize;
+        (1..=len)
+            .map(move |back| &self.prints[(next + TRADE_HISTORY_LEN - back) % TRADE_HISTORY_LEN])
+    }
+}
+
+impl Discriminator for TradeHistory {
+    const DISCRIMINATOR: [u8; 8] = [190, 117, 218, 114, 66, 112, 56, 41];
+}
+
+// Shank only reads literal array lengths, so `TradeHistory::prints` spells this out
+const _: () = assert!(TRADE_HISTORY_LEN == 64);
+
+/// Pyth feed pricing a traded mint in the config's fee mint, stored at
+/// `[b"fee_feed", mint]`
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, ShankAccount)]
+pub struct FeeFeed {
+    pub mint: Pubkey,
+    pub price_feed: Pubkey,
+    pub mint_decimals: u8,
+    pub bump: u8,
+}
+
+impl FeeFeed {
+    pub const LEN: usize = 32 + // mint
+        32 + // price_feed
+        1 + // mint_decimals
+        1; // bump
+}
+
+/// Most orders one `InitializeOrders` instruction can create
+pub const MAX_BATCH_ORDERS: usize = 8;
+
+/// Parameters of one order created by `InitializeOrders`, as for `InitializeOrder`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct OrderParams {
+    pub order_id: u64,
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+    pub requires_confirmation: bool,
+    pub dual_escrow: bool,
+    pub all_or_none: bool,
+    pub memo: [u8; 64],
+}
+
+/// Amounts of one fill settled by `CompleteSwaps`, as for `CompleteSwap`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FillParams {
+    pub fill_amount: u64,
+    pub max_taker_amount: u64,
+    pub min_maker_amount: u64,
+}
+
+/// Off-chain quote signed by a maker with ed25519 and settled by `FillQuote`.
+/// The signed message is the Borsh encoding of the quote.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Quote {
+    pub maker: Pubkey,
+    /// Taker allowed to fill the quote (`Pubkey::default()` for anyone)
+    pub taker: Pubkey,
+    pub maker_token_mint: Pubkey,
+    pub taker_token_mint: Pubkey,
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+    pub expires_at: i64,
+    /// Maker-chosen nonce; each (maker, nonce) pair can be filled once
+    pub nonce: u64,
 }
@@ -1,27 +1,111 @@
 use borsh::BorshDeserialize;
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
-    program_pack::Pack, pubkey::Pubkey, sysvar::rent::Rent,
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    hash::hashv,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{instructions, rent::Rent, Sysvar},
 };
 
-use crate::{error::SwapError, state::SwapOrder};
+use spl_token_2022::extension::{
+    non_transferable::NonTransferable, permanent_delegate::PermanentDelegate,
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use spl_token_group_interface::state::TokenGroupMember;
+
+use crate::{
+    error::SwapError,
+    metadata::{find_metadata_address, verified_collection},
+    state::{
+        BundleOrder, CompressedOrder, Config, Discriminator, FeeFeed, FillReceipt, MakerIndex,
+        PairStats, ProtocolStats, RegistryPage, SwapOrder, UserStats, MEMO_LEN,
+        MINT_RISK_FREEZE_AUTHORITY, MINT_RISK_PERMANENT_DELEGATE, ORDER_VERSION,
+    },
+};
 
-/// Validates that a mint account is a valid SPL Token or Token-2022 mint
+/// Validates that a mint account is a valid SPL Token or Token-2022 mint whose tokens can
+/// be escrowed. Non-transferable mints are refused: nothing could ever leave the escrow.
 pub fn validate_token_mint(mint_info: &AccountInfo) -> ProgramResult {
-    let owner = mint_info.owner;
-    if *owner != spl_token::id() && *owner != spl_token_2022::id() {
+    if !is_token_program(mint_info.owner) {
         return Err(SwapError::InvalidMint.into());
     }
+    let data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    if mint.get_extension::<NonTransferable>().is_ok() {
+        return Err(SwapError::NonTransferableMint.into());
+    }
+
+    Ok(())
+}
+
+/// Returns the `MINT_RISK_*` bits of a mint: whether some authority other than the
+/// holder can freeze or take tokens out of the escrow. Native SOL carries no risk.
+pub fn mint_risk(mint_info: &AccountInfo) -> Result<u8, ProgramError> {
+    let mut risk = 0;
+    if !is_token_program(mint_info.owner) {
+        return Ok(risk);
+    }
+    let data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    if mint.base.freeze_authority.is_some() {
+        risk |= MINT_RISK_FREEZE_AUTHORITY;
+    }
+    if mint
+        .get_extension::<PermanentDelegate>()
+        .is_ok_and(|extension| Option::<Pubkey>::from(extension.delegate).is_some())
+    {
+        risk |= MINT_RISK_PERMANENT_DELEGATE;
+    }
+    Ok(risk)
+}
 
-    if *owner == spl_token_2022::id() {
-        spl_token_2022::state::Mint::unpack(&mint_info.data.borrow())?;
-    } else {
-        spl_token::state::Mint::unpack(&mint_info.data.borrow())?;
+/// Returns true if the mint is an NFT: no decimals and a supply of exactly one
+pub fn is_nft_mint(mint_info: &AccountInfo) -> Result<bool, ProgramError> {
+    let data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?.base;
+    Ok(mint.decimals == 0 && mint.supply == 1)
+}
+
+/// Validates that `mint_info` is an NFT verified as part of the Metaplex collection
+/// `collection`, reading the NFT's metadata account from `accounts`
+pub fn validate_collection_member(
+    mint_info: &AccountInfo,
+    collection: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if !is_nft_mint(mint_info)? {
+        return Err(SwapError::InvalidNftMint.into());
+    }
+    let metadata = find_metadata_address(mint_info.key);
+    let metadata_info = accounts
+        .iter()
+        .find(|account| *account.key == metadata)
+        .ok_or(SwapError::NotInCollection)?;
+    if verified_collection(metadata_info)? != Some(*collection) {
+        return Err(SwapError::NotInCollection.into());
     }
 
     Ok(())
 }
 
+/// Validates that `mint_info` is a Token-2022 mint recorded as a member of the token group
+/// `group`. Only a member extension in the mint itself counts, as Token-2022 only writes
+/// one there with the group's update authority signing.
+pub fn validate_group_member(mint_info: &AccountInfo, group: &Pubkey) -> ProgramResult {
+    if *mint_info.owner != spl_token_2022::id() {
+        return Err(SwapError::NotInGroup.into());
+    }
+    let data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    match mint.get_extension::<TokenGroupMember>() {
+        Ok(member) if member.mint == *mint_info.key && member.group == *group => Ok(()),
+        _ => Err(SwapError::NotInGroup.into()),
+    }
+}
+
 /// Validates that a mint account's owning program matches the expected program
 pub fn validate_token_program(mint_info: &AccountInfo, token_program: &Pubkey) -> ProgramResult {
     if *token_program != spl_token::id() && *token_program != spl_token_2022::id() {
@@ -35,33 +119,75 @@ pub fn validate_token_program(mint_info: &AccountInfo, token_program: &Pubkey) -
     Ok(())
 }
 
+/// Returns true if `program` is SPL Token or Token-2022
+pub fn is_token_program(program: &Pubkey) -> bool {
+    *program == spl_token::id() || *program == spl_token_2022::id()
+}
+
+/// Unpacks the base state of an SPL Token or Token-2022 account, with or without
+/// extensions. Both programs share the base layout, so Token-2022's state reads either.
+pub fn unpack_token_account(
+    account: &AccountInfo,
+) -> Result<spl_token_2022::state::Account, ProgramError> {
+    if !is_token_program(account.owner) {
+        return Err(SwapError::InvalidTokenAccount.into());
+    }
+    let data = account.data.borrow();
+    Ok(StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?.base)
+}
+
 /// Validates a token account's owner and mint
 pub fn validate_token_account(
     account: &AccountInfo,
     expected_owner: &Pubkey,
     expected_mint: &Pubkey,
 ) -> ProgramResult {
-    if account.owner == &spl_token::id() {
-        let account_data = spl_token::state::Account::unpack(&account.data.borrow())?;
-        if account_data.owner != *expected_owner || account_data.mint != *expected_mint {
-            return Err(SwapError::InvalidTokenAccount.into());
-        }
-    } else if account.owner == &spl_token_2022::id() {
-        let account_data = spl_token_2022::state::Account::unpack(&account.data.borrow())?;
-        if account_data.owner != *expected_owner || account_data.mint != *expected_mint {
-            return Err(SwapError::InvalidTokenAccount.into());
-        }
-    } else {
-        return Err(SwapError::InvalidTokenAccount.into());
+    let account_data = unpack_token_account(account)?;
+    if account_data.mint != *expected_mint {
+        return Err(SwapError::WrongTokenAccountMint.into());
+    }
+    if account_data.owner != *expected_owner {
+        return Err(SwapError::WrongTokenAccountOwner.into());
+    }
+
+    Ok(())
+}
+
+/// Validates that a token account is not frozen. Token-2022 mints with a frozen default
+/// account state create every account frozen until their freeze authority thaws it.
+pub fn validate_not_frozen(account: &AccountInfo) -> ProgramResult {
+    if unpack_token_account(account)?.is_frozen() {
+        return Err(SwapError::TokenAccountFrozen.into());
+    }
+
+    Ok(())
+}
+
+/// Validates that a token account is held by `token_program`
+pub fn validate_account_token_program(
+    account: &AccountInfo,
+    token_program: &Pubkey,
+) -> ProgramResult {
+    if account.owner != token_program {
+        return Err(SwapError::InvalidTokenProgram.into());
     }
 
     Ok(())
 }
 
+/// Validates that a memo holds UTF-8 text followed only by zero padding
+pub fn validate_memo(memo: &[u8; MEMO_LEN]) -> ProgramResult {
+    let len = memo.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    if std::str::from_utf8(&memo[..len]).is_err() {
+        return Err(SwapError::InvalidMemo.into());
+    }
+    Ok(())
+}
+
 /// Validates that an account is a signer
 pub fn validate_signer(account: &AccountInfo) -> ProgramResult {
     if !account.is_signer {
-        return Err(SwapError::UnauthorizedSigner.into());
+        return Err(SwapError::MissingSignature.into());
     }
     Ok(())
 }
@@ -75,15 +201,122 @@ pub fn validate_authority(authority: &AccountInfo, order: &SwapOrder) -> Program
     Ok(())
 }
 
+/// Validates that the account is the maker or the order's delegate
+pub fn validate_manager(manager: &AccountInfo, order: &SwapOrder) -> ProgramResult {
+    validate_signer(manager)?;
+    if order.maker != *manager.key && !order.is_delegate(manager.key) {
+        return Err(SwapError::UnauthorizedSigner.into());
+    }
+    Ok(())
+}
+
+/// Validates that the account may fill the order. Orders without a designated
+/// taker (`Pubkey::default()`) or allowed takers accept any signer.
 pub fn validate_taker(taker: &AccountInfo, order: &SwapOrder) -> ProgramResult {
     validate_signer(taker)?;
-    if order.taker != *taker.key {
+    if !order.is_allowed_taker(taker.key) {
+        return Err(SwapError::UnauthorizedSigner.into());
+    }
+
+    Ok(())
+}
+
+/// Validates that the account may fill the order, either as a listed taker or by
+/// proving membership in the order's Merkle allowlist with `proof`
+pub fn validate_taker_proof(
+    taker: &AccountInfo,
+    order: &SwapOrder,
+    proof: &[[u8; 32]],
+) -> ProgramResult {
+    validate_signer(taker)?;
+    if order.is_allowed_taker(taker.key) {
+        return Ok(());
+    }
+    if !order.has_taker_merkle_root()
+        || !verify_merkle_proof(&order.taker_merkle_root, merkle_leaf(taker.key), proof)
+    {
         return Err(SwapError::UnauthorizedSigner.into());
     }
 
     Ok(())
 }
 
+/// Returns the commitment a taker submits with `CommitFill` before revealing `salt`
+pub fn fill_commitment(taker: &Pubkey, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[taker.as_ref(), salt]).to_bytes()
+}
+
+/// Hashes a taker into a Merkle allowlist leaf. Leaves and inner nodes are
+/// domain-separated so an inner node can never be passed off as a leaf.
+pub fn merkle_leaf(taker: &Pubkey) -> [u8; 32] {
+    hashv(&[&[0], taker.as_ref()]).to_bytes()
+}
+
+/// Hashes two Merkle nodes into their parent. The pair is sorted first, so proofs
+/// need no left/right flags.
+pub fn merkle_parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[1], left, right]).to_bytes()
+}
+
+/// Returns true if `proof` links `leaf` to `root`
+pub fn verify_merkle_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| merkle_parent(&node, sibling))
+        == *root
+}
+
+/// Validates that `gate_account` is a `taker` token account holding at least the
+/// order's minimum balance of its gating mint
+pub fn validate_token_gate(
+    gate_account: &AccountInfo,
+    taker: &Pubkey,
+    order: &SwapOrder,
+) -> ProgramResult {
+    let account = unpack_token_account(gate_account)?;
+    if account.owner != *taker || account.mint != order.gate_mint {
+        return Err(SwapError::InvalidTokenAccount.into());
+    }
+    if account.amount < order.gate_min_balance {
+        return Err(SwapError::TokenGateNotMet.into());
+    }
+
+    Ok(())
+}
+
+/// Validates that the order is still open for changes and fills
+pub fn validate_order_open(order: &SwapOrder) -> ProgramResult {
+    if !order.is_open() {
+        return Err(SwapError::OrderNotOpen.into());
+    }
+    Ok(())
+}
+
+/// Validates that the maker has not paused fills on the order
+pub fn validate_not_paused(order: &SwapOrder) -> ProgramResult {
+    if order.paused {
+        return Err(SwapError::OrderPaused.into());
+    }
+    Ok(())
+}
+
+/// Validates that the order's expiry, if any, has not passed
+pub fn validate_not_expired(order: &SwapOrder) -> ProgramResult {
+    if order.expires_at != 0 && order.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(SwapError::OrderExpired.into());
+    }
+    Ok(())
+}
+
+/// Validates that a new expiry is either 0 (no expiry) or in the future
+pub fn validate_expiry(expires_at: i64) -> ProgramResult {
+    if expires_at != 0 && expires_at <= Clock::get()?.unix_timestamp {
+        return Err(SwapError::InvalidExpiry.into());
+    }
+    Ok(())
+}
+
 /// Validates non-zero amounts for the initialization ix
 pub fn validate_init_amounts(maker_amount: u64, taker_amount: u64) -> ProgramResult {
     if maker_amount == 0 || taker_amount == 0 {
@@ -92,10 +325,30 @@ pub fn validate_init_amounts(maker_amount: u64, taker_amount: u64) -> ProgramRes
     Ok(())
 }
 
+/// Validates that a fill does not cost the taker more than they agreed to pay
+pub fn validate_max_taker_amount(taker_amount: u64, max_taker_amount: u64) -> ProgramResult {
+    if taker_amount > max_taker_amount {
+        return Err(SwapError::SlippageExceeded.into());
+    }
+    Ok(())
+}
+
+/// Validates the maker-side size of a fill against the order's remaining size
+pub fn validate_fill_amount(order: &SwapOrder, fill_amount: u64) -> ProgramResult {
+    let remaining = order.remaining();
+    if fill_amount == 0 || fill_amount > remaining {
+        return Err(SwapError::InvalidAmount.into());
+    }
+    if fill_amount < remaining && !order.allows_partial_fill() {
+        return Err(SwapError::PartialFillNotAllowed.into());
+    }
+    Ok(())
+}
+
 /// Validates that an account is rent-exempt
 pub fn validate_rent_exempt(rent: &Rent, account: &AccountInfo) -> ProgramResult {
     if !rent.is_exempt(account.lamports(), account.data_len()) {
-        return Err(ProgramError::AccountNotRentExempt);
+        return Err(SwapError::NotRentExempt.into());
     }
     Ok(())
 }
@@ -122,6 +375,7 @@ pub fn get_order_pda(
     maker: &Pubkey,
     maker_mint: &Pubkey,
     taker_mint: &Pubkey,
+    order_id: u64,
 ) -> Result<(Pubkey, u8), ProgramError> {
     let (pda, bump) = Pubkey::find_program_address(
         &[
@@ -129,28 +383,613 @@ pub fn get_order_pda(
             maker.as_ref(),
             maker_mint.as_ref(),
             taker_mint.as_ref(),
+            &order_id.to_le_bytes(),
         ],
         program_id,
     );
     Ok((pda, bump))
 }
 
-/// Validate order PDA
+/// Get the PDA of the vault token account holding `mint` for the order (or bundle) at
+/// `order`
+pub fn get_vault_pda(program_id: &Pubkey, order: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", order.as_ref(), mint.as_ref()], program_id)
+}
+
+/// Loads the order in `account_info` and validates that the program owns it at its PDA
 pub fn validate_order_pda(
     program_id: &Pubkey,
     account_info: &AccountInfo,
 ) -> Result<(SwapOrder, u8), ProgramError> {
-    let order = SwapOrder::try_from_slice(&account_info.data.borrow())?;
-    let (pda, bump) = get_order_pda(
+    if account_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    let order = load_order(account_info)?;
+    // The program only ever stores the canonical bump, so the stored one derives the
+    // same address without searching for it
+    if order.pda(program_id)? != *account_info.key {
+        return Err(SwapError::InvalidOrderAddress.into());
+    }
+
+    Ok((order, order.bump))
+}
+
+/// Deserializes an order, rejecting other account types and layouts older than
+/// `ORDER_VERSION`
+pub fn load_order(account_info: &AccountInfo) -> Result<SwapOrder, ProgramError> {
+    let data = account_info.data.borrow();
+    if !SwapOrder::has_discriminator(&data) {
+        // Orders from before the discriminator are recognized by their size alone
+        return Err(
+            if [SwapOrder::LEGACY_LEN, SwapOrder::VERSION_1_LEN].contains(&data.len()) {
+                SwapError::OutdatedOrder
+            } else {
+                SwapError::InvalidDiscriminator
+            }
+            .into(),
+        );
+    }
+    if data.len() != SwapOrder::LEN || data[8] != ORDER_VERSION {
+        return Err(SwapError::OutdatedOrder.into());
+    }
+    SwapOrder::unpack(&data)
+}
+
+/// Get the PDA of a maker's bundle order
+pub fn get_bundle_pda(program_id: &Pubkey, maker: &Pubkey, bundle_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"bundle", maker.as_ref(), &bundle_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Returns true if the account holds a bundle order rather than a `SwapOrder`
+pub fn is_bundle(account_info: &AccountInfo) -> bool {
+    BundleOrder::has_discriminator(&account_info.data.borrow())
+}
+
+/// Deserializes a bundle order owned by the program and checks it lives at its PDA
+pub fn load_bundle(
+    program_id: &Pubkey,
+    account_info: &AccountInfo,
+) -> Result<BundleOrder, ProgramError> {
+    if account_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    if !is_bundle(account_info) {
+        return Err(SwapError::InvalidDiscriminator.into());
+    }
+    let bundle = BundleOrder::try_from_slice(&account_info.data.borrow())?;
+    let (pda, bump) = get_bundle_pda(program_id, &bundle.maker, bundle.bundle_id);
+    if pda != *account_info.key || bundle.bump != bump {
+        return Err(SwapError::InvalidOrderState.into());
+    }
+    Ok(bundle)
+}
+
+/// Get the PDA of a maker's compressed NFT order
+pub fn get_compressed_order_pda(
+    program_id: &Pubkey,
+    maker: &Pubkey,
+    order_id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"cnft_order", maker.as_ref(), &order_id.to_le_bytes()],
         program_id,
-        &order.maker,
-        &order.maker_token_mint,
-        &order.taker_token_mint,
-    )?;
+    )
+}
 
+/// Deserializes a compressed NFT order owned by the program and checks it lives at its
+/// PDA
+pub fn load_compressed_order(
+    program_id: &Pubkey,
+    account_info: &AccountInfo,
+) -> Result<CompressedOrder, ProgramError> {
+    if account_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    if !CompressedOrder::has_discriminator(&account_info.data.borrow()) {
+        return Err(SwapError::InvalidDiscriminator.into());
+    }
+    let order = CompressedOrder::try_from_slice(&account_info.data.borrow())?;
+    let (pda, bump) = get_compressed_order_pda(program_id, &order.maker, order.order_id);
     if pda != *account_info.key || order.bump != bump {
         return Err(SwapError::InvalidOrderState.into());
     }
+    Ok(order)
+}
+
+/// Get the PDA that makers approve as delegate for quote settlement
+pub fn get_rfq_authority_pda(program_id: &Pubkey, maker: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"rfq", maker.as_ref()], program_id)
+}
+
+/// Get the PDA that signs the program's event self-CPIs
+pub fn get_event_authority_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"__event_authority"], program_id)
+}
+
+/// Checks that `authority_info` is the event authority, signing. Only the program can
+/// sign for it, so an event self-CPI cannot be forged by anyone else.
+pub fn validate_event_authority(
+    program_id: &Pubkey,
+    authority_info: &AccountInfo,
+) -> ProgramResult {
+    if get_event_authority_pda(program_id).0 != *authority_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    validate_signer(authority_info)
+}
+
+/// Get the program config PDA
+pub fn get_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], program_id)
+}
+
+/// Loads the program config, or `None` if it has not been initialized yet. An initialized
+/// config's address is checked with the bump it stores rather than searched for, as
+/// every pausable instruction loads it.
+pub fn load_config(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+) -> Result<Option<Config>, ProgramError> {
+    if config_info.data_is_empty() {
+        if get_config_pda(program_id).0 != *config_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        return Ok(None);
+    }
+    if config_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    // Fields appended to the config since it was written read as zero
+    let mut data = config_info.data.borrow().to_vec();
+    data.resize(data.len().max(Config::LEN), 0);
+    let config = Config::try_from_slice(&data)?;
+    if Pubkey::create_program_address(&[b"config", &[config.bump]], program_id)
+        != Ok(*config_info.key)
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(Some(config))
+}
 
-    Ok((order, bump))
+/// Validates that the admin has not paused the protocol. `config_info` is the config PDA
+/// a pausable instruction takes last; nothing is paused before it is initialized.
+pub fn validate_protocol_not_paused(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+) -> ProgramResult {
+    if load_config(program_id, config_info)?.is_some_and(|config| config.paused) {
+        return Err(SwapError::ProtocolPaused.into());
+    }
+    Ok(())
+}
+
+/// Validates that an order of `maker` for `taker` neither trades with itself nor trades
+/// `maker_mint` for itself, unless the admin allowed that with `SetTradeGuards` in the
+/// config at `config_info`. Without an initialized config, both are refused.
+pub fn validate_trade_guards(
+    program_id: &Pubkey,
+    config_info: Option<&AccountInfo>,
+    maker: &Pubkey,
+    taker: &Pubkey,
+    maker_mint: &Pubkey,
+    taker_mint: &Pubkey,
+) -> ProgramResult {
+    let self_trade = maker == taker;
+    let same_mint = maker_mint == taker_mint;
+    if !self_trade && !same_mint {
+        return Ok(());
+    }
+    let config = match config_info {
+        Some(config_info) => load_config(program_id, config_info)?,
+        None => None,
+    };
+    if self_trade
+        && !config
+            .as_ref()
+            .is_some_and(|config| config.allow_self_trade)
+    {
+        return Err(SwapError::SelfTrade.into());
+    }
+    if same_mint && !config.as_ref().is_some_and(|config| config.allow_same_mint) {
+        return Err(SwapError::SameMint.into());
+    }
+    Ok(())
+}
+
+/// Loads the program config and validates that `admin` signed as its admin
+pub fn validate_admin(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    admin: &AccountInfo,
+) -> Result<Config, ProgramError> {
+    validate_signer(admin)?;
+    let config = load_config(program_id, config_info)?.ok_or(ProgramError::UninitializedAccount)?;
+    if config.admin != *admin.key {
+        return Err(SwapError::UnauthorizedSigner.into());
+    }
+    Ok(config)
+}
+
+/// Get the PDA holding the fee-mint price feed of `mint`
+pub fn get_fee_feed_pda(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fee_feed", mint.as_ref()], program_id)
+}
+
+/// Loads the fee-mint price feed registered for `mint`
+pub fn load_fee_feed(
+    program_id: &Pubkey,
+    fee_feed_info: &AccountInfo,
+    mint: &Pubkey,
+) -> Result<FeeFeed, ProgramError> {
+    if get_fee_feed_pda(program_id, mint).0 != *fee_feed_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if fee_feed_info.owner != program_id {
+        return Err(SwapError::InvalidOracle.into());
+    }
+    Ok(FeeFeed::try_from_slice(&fee_feed_info.data.borrow())?)
+}
+
+/// Get the PDA tracking the fill volume of `user`
+pub fn get_user_stats_pda(program_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"user_stats", user.as_ref()], program_id)
+}
+
+/// Loads the volume stats of `user`, or `None` if they have not been initialized
+pub fn load_user_stats(
+    program_id: &Pubkey,
+    stats_info: &AccountInfo,
+    user: &Pubkey,
+) -> Result<Option<UserStats>, ProgramError> {
+    if get_user_stats_pda(program_id, user).0 != *stats_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if stats_info.data_is_empty() {
+        return Ok(None);
+    }
+    if stats_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    Ok(Some(UserStats::try_from_slice(&stats_info.data.borrow())?))
+}
+
+/// Get the PDA tracking protocol-wide fill activity
+pub fn get_protocol_stats_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"protocol_stats"], program_id)
+}
+
+/// Loads the protocol-wide stats, or `None` if they have not been initialized
+pub fn load_protocol_stats(
+    program_id: &Pubkey,
+    stats_info: &AccountInfo,
+) -> Result<Option<ProtocolStats>, ProgramError> {
+    if get_protocol_stats_pda(program_id).0 != *stats_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if stats_info.data_is_empty() {
+        return Ok(None);
+    }
+    if stats_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    Ok(Some(ProtocolStats::try_from_slice(
+        &stats_info.data.borrow(),
+    )?))
+}
+
+/// Get the PDA tracking the fill activity of orders selling `maker_mint` for `taker_mint`
+pub fn get_pair_stats_pda(
+    program_id: &Pubkey,
+    maker_mint: &Pubkey,
+    taker_mint: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pair_stats", maker_mint.as_ref(), taker_mint.as_ref()],
+        program_id,
+    )
+}
+
+/// Loads the stats of the `maker_mint`/`taker_mint` pair, or `None` if they have not been
+/// initialized
+pub fn load_pair_stats(
+    program_id: &Pubkey,
+    stats_info: &AccountInfo,
+    maker_mint: &Pubkey,
+    taker_mint: &Pubkey,
+) -> Result<Option<PairStats>, ProgramError> {
+    if get_pair_stats_pda(program_id, maker_mint, taker_mint).0 != *stats_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if stats_info.data_is_empty() {
+        return Ok(None);
+    }
+    if stats_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    Ok(Some(PairStats::try_from_slice(&stats_info.data.borrow())?))
+}
+
+/// Get the PDA listing the open orders of `maker`
+pub fn get_maker_index_pda(program_id: &Pubkey, maker: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"maker_index", maker.as_ref()], program_id)
+}
+
+/// Loads the open-order index of `maker`, or `None` if it has not been created yet
+pub fn load_maker_index(
+    program_id: &Pubkey,
+    index_info: &AccountInfo,
+    maker: &Pubkey,
+) -> Result<Option<MakerIndex>, ProgramError> {
+    if get_maker_index_pda(program_id, maker).0 != *index_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if index_info.data_is_empty() {
+        return Ok(None);
+    }
+    if index_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    Ok(Some(MakerIndex::try_from_slice(&index_info.data.borrow())?))
+}
+
+/// Get page `page` of the order registry of the `maker_mint`/`taker_mint` pair
+pub fn get_registry_page_pda(
+    program_id: &Pubkey,
+    maker_mint: &Pubkey,
+    taker_mint: &Pubkey,
+    page: u32,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"registry",
+            maker_mint.as_ref(),
+            taker_mint.as_ref(),
+            &page.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Returns true if the account holds a registry page
+pub fn is_registry_page(program_id: &Pubkey, account_info: &AccountInfo) -> bool {
+    account_info.owner == program_id && RegistryPage::has_discriminator(&account_info.data.borrow())
+}
+
+/// Deserializes a registry page owned by the program and checks it lives at its PDA
+pub fn load_registry_page(
+    program_id: &Pubkey,
+    account_info: &AccountInfo,
+) -> Result<RegistryPage, ProgramError> {
+    if account_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    if !is_registry_page(program_id, account_info) {
+        return Err(SwapError::InvalidDiscriminator.into());
+    }
+    let page = RegistryPage::try_from_slice(&account_info.data.borrow())?;
+    let (pda, bump) =
+        get_registry_page_pda(program_id, &page.maker_mint, &page.taker_mint, page.page);
+    if pda != *account_info.key || page.bump != bump {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(page)
+}
+
+/// Get the PDA keeping the recent fills of orders selling `maker_mint` for `taker_mint`
+pub fn get_trade_history_pda(
+    program_id: &Pubkey,
+    maker_mint: &Pubkey,
+    taker_mint: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"trade_history", maker_mint.as_ref(), taker_mint.as_ref()],
+        program_id,
+    )
+}
+
+/// Get the PDA recording fill `fill_index` of `order`
+pub fn get_fill_receipt_pda(program_id: &Pubkey, order: &Pubkey, fill_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"receipt", order.as_ref(), &fill_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Loads a fill receipt, checking it sits at the PDA of the fill it records
+pub fn load_fill_receipt(
+    program_id: &Pubkey,
+    account_info: &AccountInfo,
+) -> Result<FillReceipt, ProgramError> {
+    if account_info.owner != program_id {
+        return Err(SwapError::WrongAccountOwner.into());
+    }
+    if !FillReceipt::has_discriminator(&account_info.data.borrow()) {
+        return Err(SwapError::InvalidDiscriminator.into());
+    }
+    let receipt = FillReceipt::try_from_slice(&account_info.data.borrow())?;
+    let seeds: &[&[u8]] = &[
+        b"receipt",
+        receipt.order.as_ref(),
+        &receipt.fill_index.to_le_bytes(),
+        &[receipt.bump],
+    ];
+    if Pubkey::create_program_address(seeds, program_id) != Ok(*account_info.key) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(receipt)
+}
+
+/// Get the token account PDA that collects protocol fees paid in `mint`
+pub fn get_treasury_pda(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"treasury", mint.as_ref()], program_id)
+}
+
+/// Get the temporary wSOL token account PDA that `WrapSol` creates for `owner`
+pub fn get_wsol_pda(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"wsol", owner.as_ref()], program_id)
+}
+
+/// Get the PDA whose existence exempts `account` from protocol fees
+pub fn get_fee_exemption_pda(program_id: &Pubkey, account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fee_exempt", account.as_ref()], program_id)
+}
+
+/// Returns true if `exemption_info` is the live fee-exemption PDA of `account`
+pub fn is_fee_exempt(
+    program_id: &Pubkey,
+    exemption_info: &AccountInfo,
+    account: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if get_fee_exemption_pda(program_id, account).0 != *exemption_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(exemption_info.owner == program_id && exemption_info.lamports() > 0)
+}
+
+/// Get the PDA whose existence keeps `mint` out of new orders and fills
+pub fn get_blocked_mint_pda(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"blocked_mint", mint.as_ref()], program_id)
+}
+
+/// Returns true if `blocked_info` is the live blocklist PDA of `mint`
+pub fn is_mint_blocked(
+    program_id: &Pubkey,
+    blocked_info: &AccountInfo,
+    mint: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if get_blocked_mint_pda(program_id, mint).0 != *blocked_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(blocked_info.owner == program_id && blocked_info.lamports() > 0)
+}
+
+/// Validates that the admin has blocked neither mint of a trade, given the blocklist PDAs
+/// of its maker and taker mints
+pub fn validate_pair_not_blocked(
+    program_id: &Pubkey,
+    maker_mint_blocked_info: &AccountInfo,
+    taker_mint_blocked_info: &AccountInfo,
+    maker_mint: &Pubkey,
+    taker_mint: &Pubkey,
+) -> ProgramResult {
+    if is_mint_blocked(program_id, maker_mint_blocked_info, maker_mint)?
+        || is_mint_blocked(program_id, taker_mint_blocked_info, taker_mint)?
+    {
+        return Err(SwapError::MintBlocked.into());
+    }
+    Ok(())
+}
+
+/// Validates that the admin has not blocked any of `mints`, reading their blocklist PDAs
+/// from wherever they are among `accounts`
+pub fn validate_mints_not_blocked(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mints: &[&Pubkey],
+) -> ProgramResult {
+    for mint in mints {
+        let blocked_pda = get_blocked_mint_pda(program_id, mint).0;
+        let blocked_info = accounts
+            .iter()
+            .find(|account| *account.key == blocked_pda)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if is_mint_blocked(program_id, blocked_info, mint)? {
+            return Err(SwapError::MintBlocked.into());
+        }
+    }
+    Ok(())
+}
+
+/// Validates that `authority` signed and is the upgrade authority recorded in the
+/// program's `ProgramData` account
+pub fn validate_upgrade_authority(
+    program_id: &Pubkey,
+    program_data_info: &AccountInfo,
+    authority: &AccountInfo,
+) -> ProgramResult {
+    validate_signer(authority)?;
+    let (program_data, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if program_data != *program_data_info.key
+        || *program_data_info.owner != bpf_loader_upgradeable::id()
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // `UpgradeableLoaderState::ProgramData`: u32 variant (3), u64 slot, Option<Pubkey>
+    let data = program_data_info.data.borrow();
+    if data.len() < 45 || data[..4] != 3u32.to_le_bytes() || data[12] != 1 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if data[13..45] != authority.key.to_bytes() {
+        return Err(SwapError::UnauthorizedSigner.into());
+    }
+
+    Ok(())
+}
+
+/// Get the PDA marking a quote nonce as used
+pub fn get_quote_nonce_pda(program_id: &Pubkey, maker: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"quote", maker.as_ref(), &nonce.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Validates that the instruction preceding the current one is an Ed25519 program
+/// instruction verifying exactly one signature by `signer` over `message`
+pub fn validate_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> ProgramResult {
+    if !instructions::check_id(instructions_sysvar.key) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let current_index = instructions::load_current_index_checked(instructions_sysvar)?;
+    if current_index == 0 {
+        return Err(SwapError::InvalidQuoteSignature.into());
+    }
+    let ed25519_ix =
+        instructions::load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+    if !solana_program::ed25519_program::check_id(&ed25519_ix.program_id) {
+        return Err(SwapError::InvalidQuoteSignature.into());
+    }
+
+    // Layout: [num_signatures: u8, padding: u8, offsets: 7 x u16]
+    let data = &ed25519_ix.data;
+    if data.len() < 16 || data[0] != 1 {
+        return Err(SwapError::InvalidQuoteSignature.into());
+    }
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_ix_index = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_ix_index = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_ix_index = read_u16(14);
+
+    // Signature, key and message must all live in the Ed25519 instruction itself
+    if signature_ix_index != u16::MAX
+        || public_key_ix_index != u16::MAX
+        || message_ix_index != u16::MAX
+    {
+        return Err(SwapError::InvalidQuoteSignature.into());
+    }
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(SwapError::InvalidQuoteSignature)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(SwapError::InvalidQuoteSignature)?;
+    if public_key != signer.as_ref() || signed_message != message {
+        return Err(SwapError::InvalidQuoteSignature.into());
+    }
+
+    Ok(())
 }
@@ -37,7 +37,7 @@ pub fn setup_mint(
     svm.send_transaction(Transaction::new_signed_with_payer(
         &[create_acc_ins, init_mint_ins],
         Some(&payer.pubkey()),
-        &[&payer, &mint],
+        &[payer, &mint],
         svm.latest_blockhash(),
     ))
     .unwrap();
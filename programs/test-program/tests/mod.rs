@@ -1,13 +1,42 @@
+// `TransactionResult` is litesvm's, and resending an instruction reads best as a clone
+#![allow(clippy::result_large_err, clippy::cloned_ref_to_slice_refs)]
+
+use arbitrary::{Arbitrary, Unstructured};
+use base64::{prelude::BASE64_STANDARD, Engine};
 use borsh::BorshDeserialize;
-use litesvm::LiteSVM;
+use litesvm::{types::TransactionResult, LiteSVM};
 use mints::{mint_to_ata, setup_mint};
-use solana_program::{pubkey::Pubkey, system_program};
+use proptest::{collection, prelude::any, proptest};
+use solana_program::{instruction::Instruction, pubkey::Pubkey, system_program};
 use solana_sdk::{
-    instruction::AccountMeta, program_pack::Pack, rent::sysvar, signature::Keypair, signer::Signer,
-    transaction::Transaction,
+    clock::Clock, instruction::AccountMeta, program_pack::Pack, rent::sysvar, signature::Keypair,
+    signer::Signer, transaction::Transaction,
 };
 use spl_associated_token_account::get_associated_token_address;
-use splerg_p2p::state::SwapOrder;
+use spl_p2p_client::{self as client, filters::RpcFilterType};
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+#[cfg(feature = "event-cpi")]
+use splerg_p2p::event::{Event, EVENT_IX_TAG};
+use splerg_p2p::{
+    bubblegum::{self, find_tree_config_address, BUBBLEGUM_PROGRAM_ID},
+    error::SwapError,
+    event::{OrderAmountsChanged, OrderClosed, OrderCreated, OrderFilled, SwapEvent},
+    instruction::{
+        close_order, complete_swap, initialize_order, unwrap_sol, wrap_sol, wrap_sol_instructions,
+        CloseOrderAccounts, CompleteSwapAccounts, InitializeOrderAccounts, SwapInstruction,
+        INSTRUCTION_DISCRIMINATORS,
+    },
+    merkle::{merkle_proof, merkle_root},
+    metadata::{find_metadata_address, TOKEN_METADATA_PROGRAM_ID},
+    state::{
+        BundleOrder, Config, Discriminator, FeeTier, FillParams, FillReceipt, MakerIndex,
+        OrderParams, OrderStatus, PairStats, Price, ProtocolStats, Quote, RegistryEntry,
+        RegistryPage, SwapOrder, TradeHistory, TradePrint, UserStats, MAX_ALLOWED_TAKERS,
+        MAX_REFERRAL_BPS, MEMO_LEN, MINT_RISK_FREEZE_AUTHORITY, MINT_RISK_PERMANENT_DELEGATE,
+        REAP_BOUNTY_LAMPORTS, REGISTRY_PAGE_ENTRIES, TAG_LEN,
+    },
+    validation::{fill_commitment, get_wsol_pda, merkle_leaf, verify_merkle_proof},
+};
 use utils::load_program;
 
 mod mints;
@@ -15,6 +44,748 @@ mod utils;
 
 const PROGRAM_KEY: Pubkey = Pubkey::from_str_const("GKTd9AGFpPGNKK28ncHeGGuT7rBJLzPxNjCUPKn8Yik8");
 
+/// Shared setup for swap tests: the program is loaded, the maker holds maker-mint
+/// tokens, the taker holds taker-mint tokens and all receiving ATAs exist.
+struct SwapEnv {
+    svm: LiteSVM,
+    maker: Keypair,
+    taker: Keypair,
+    maker_mint: Pubkey,
+    taker_mint: Pubkey,
+    order_id: u64,
+    /// Owner of the fee accounts passed to `CompleteSwap`
+    fee_collector: Pubkey,
+}
+
+impl SwapEnv {
+    fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(PROGRAM_KEY, load_program("splerg_p2p.so"))
+            .unwrap();
+
+        let maker = Keypair::new();
+        let taker = Keypair::new();
+        svm.airdrop(&maker.pubkey(), 100_000_000_000).unwrap();
+        svm.airdrop(&taker.pubkey(), 100_000_000_000).unwrap();
+
+        let maker_mint_setup = setup_mint(svm, &maker, 9).unwrap();
+        let taker_mint_setup = setup_mint(maker_mint_setup.svm, &maker, 9).unwrap();
+        let maker_mint = maker_mint_setup.mint;
+        let taker_mint = taker_mint_setup.mint;
+
+        let svm = mint_to_ata(
+            taker_mint_setup.svm,
+            &maker,
+            &maker_mint,
+            1_000_000,
+            &maker.pubkey(),
+        )
+        .unwrap();
+        let svm = mint_to_ata(svm, &maker, &taker_mint, 1_000_000, &taker.pubkey()).unwrap();
+
+        let mut env = Self {
+            svm,
+            maker,
+            taker,
+            maker_mint: maker_mint.pubkey(),
+            taker_mint: taker_mint.pubkey(),
+            order_id: 0,
+            fee_collector: Pubkey::new_unique(),
+        };
+
+        let order_pda = env.order_pda();
+        let create_atas_ix = vec![
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &env.maker.pubkey(),
+                &order_pda,
+                &env.maker_mint,
+                &spl_token::id(),
+            ),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &env.maker.pubkey(),
+                &env.maker.pubkey(),
+                &env.taker_mint,
+                &spl_token::id(),
+            ),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &env.maker.pubkey(),
+                &env.taker.pubkey(),
+                &env.maker_mint,
+                &spl_token::id(),
+            ),
+        ];
+        let maker = env.maker.insecure_clone();
+        env.send(&create_atas_ix, &maker).unwrap();
+
+        env
+    }
+
+    /// Creates the escrow ATA for the order currently selected by `order_id`
+    fn create_order_ata(&mut self) {
+        let ix = spl_associated_token_account::instruction::create_associated_token_account(
+            &self.maker.pubkey(),
+            &self.order_pda(),
+            &self.maker_mint,
+            &spl_token::id(),
+        );
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker).unwrap();
+    }
+
+    /// Creates a new mint with the maker as mint authority
+    fn create_mint(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let mint_len = spl_token::state::Mint::LEN;
+        let ixs = [
+            solana_sdk::system_instruction::create_account(
+                &self.maker.pubkey(),
+                &mint.pubkey(),
+                self.svm.minimum_balance_for_rent_exemption(mint_len),
+                mint_len as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint2(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &self.maker.pubkey(),
+                None,
+                9,
+            )
+            .unwrap(),
+        ];
+        let tx = Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&self.maker.pubkey()),
+            &[&self.maker, &mint],
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(tx).unwrap();
+        mint.pubkey()
+    }
+
+    /// Mints `amount` of a maker-authority mint to `wallet`, creating its ATA if needed
+    fn fund(&mut self, wallet: &Pubkey, mint: &Pubkey, amount: u64) {
+        let ata = self.ata(wallet, mint);
+        let maker = self.maker.insecure_clone();
+        let mut ixs = Vec::new();
+        if self.svm.get_account(&ata).is_none() {
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &maker.pubkey(),
+                    wallet,
+                    mint,
+                    &spl_token::id(),
+                ),
+            );
+        }
+        if amount > 0 {
+            ixs.push(
+                spl_token::instruction::mint_to(
+                    &spl_token::id(),
+                    mint,
+                    &ata,
+                    &maker.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            );
+        }
+        self.send(&ixs, &maker).unwrap();
+    }
+
+    fn send(&mut self, instructions: &[Instruction], signer: &Keypair) -> TransactionResult {
+        self.svm.expire_blockhash();
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signer.pubkey()),
+            &[signer],
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(tx)
+    }
+
+    fn order_pda(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"order",
+                self.maker.pubkey().as_ref(),
+                self.maker_mint.as_ref(),
+                self.taker_mint.as_ref(),
+                &self.order_id.to_le_bytes(),
+            ],
+            &PROGRAM_KEY,
+        )
+        .0
+    }
+
+    fn ata(&self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address(wallet, mint)
+    }
+
+    fn token_balance(&self, token_account: &Pubkey) -> u64 {
+        let account = self.svm.get_account(token_account).unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    fn order(&self) -> SwapOrder {
+        SwapOrder::unpack(&self.svm.get_account(&self.order_pda()).unwrap().data).unwrap()
+    }
+
+    fn initialize_order(
+        &mut self,
+        taker: &Pubkey,
+        maker_amount: u64,
+        taker_amount: u64,
+    ) -> TransactionResult {
+        let instruction = SwapInstruction::InitializeOrder {
+            order_id: self.order_id,
+            maker_amount,
+            taker_amount,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        };
+        self.initialize(taker, &instruction)
+    }
+
+    fn initialize(&mut self, taker: &Pubkey, instruction: &SwapInstruction) -> TransactionResult {
+        self.initialize_with_accounts(taker, instruction, &[])
+    }
+
+    fn initialize_with_accounts(
+        &mut self,
+        taker: &Pubkey,
+        instruction: &SwapInstruction,
+        extra_accounts: &[AccountMeta],
+    ) -> TransactionResult {
+        let order_pda = self.order_pda();
+        let mut ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&self.maker.pubkey(), &self.maker_mint), false),
+                AccountMeta::new(self.ata(&order_pda, &self.maker_mint), false),
+                AccountMeta::new_readonly(*taker, false),
+                AccountMeta::new_readonly(self.maker_mint, false),
+                AccountMeta::new_readonly(self.taker_mint, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(config_pda(), false),
+            ],
+            data: instruction.pack(),
+        };
+        ix.accounts.extend_from_slice(extra_accounts);
+        ix.accounts.extend([
+            AccountMeta::new_readonly(blocked_mint_pda(&self.maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&self.taker_mint), false),
+            AccountMeta::new(maker_index_pda(&self.maker.pubkey()), false),
+            AccountMeta::new(
+                registry_page_pda(&self.maker_mint, &self.taker_mint, 0),
+                false,
+            ),
+        ]);
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker)
+    }
+
+    fn change_taker(&mut self, new_taker: &Pubkey) -> TransactionResult {
+        let mut data = INSTRUCTION_DISCRIMINATORS[2].to_vec(); // ChangeTaker
+        data.extend_from_slice(&new_taker.to_bytes());
+
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new(self.order_pda(), false),
+                AccountMeta::new_readonly(*new_taker, false),
+            ],
+            data,
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker)
+    }
+
+    /// Creates the order's escrow ATA for the taker mint, used by two-sided flows
+    fn create_order_taker_ata(&mut self) {
+        let ix = spl_associated_token_account::instruction::create_associated_token_account(
+            &self.maker.pubkey(),
+            &self.order_pda(),
+            &self.taker_mint,
+            &spl_token::id(),
+        );
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker).unwrap();
+    }
+
+    fn accept_order(&mut self, taker: &Keypair) -> TransactionResult {
+        self.deposit_taker_side(taker, SwapInstruction::AcceptOrder)
+    }
+
+    fn deposit_taker_side(
+        &mut self,
+        taker: &Keypair,
+        instruction: SwapInstruction,
+    ) -> TransactionResult {
+        let order_pda = self.order_pda();
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(taker.pubkey(), true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&taker.pubkey(), &self.taker_mint), false),
+                AccountMeta::new(self.ata(&order_pda, &self.taker_mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.taker_mint, false),
+            ],
+            data: instruction.pack(),
+        };
+        self.send(&[ix], taker)
+    }
+
+    fn confirm_swap(&mut self, taker: &Pubkey) -> TransactionResult {
+        let order_pda = self.order_pda();
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&order_pda, &self.maker_mint), false),
+                AccountMeta::new(self.ata(&order_pda, &self.taker_mint), false),
+                AccountMeta::new(self.ata(&self.maker.pubkey(), &self.taker_mint), false),
+                AccountMeta::new(self.ata(taker, &self.maker_mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.maker_mint, false),
+                AccountMeta::new_readonly(self.taker_mint, false),
+                AccountMeta::new_readonly(config_pda(), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.maker_mint), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.taker_mint), false),
+                AccountMeta::new(treasury_pda(&self.taker_mint), false),
+                AccountMeta::new_readonly(fee_exemption_pda(&self.maker.pubkey()), false),
+                AccountMeta::new(user_stats_pda(&self.maker.pubkey()), false),
+            ],
+            data: SwapInstruction::ConfirmSwap.pack(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker)
+    }
+
+    fn propose_counter_offer(
+        &mut self,
+        taker: &Keypair,
+        new_maker_amount: u64,
+        new_taker_amount: u64,
+    ) -> TransactionResult {
+        let order_pda = self.order_pda();
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(taker.pubkey(), true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&taker.pubkey(), &self.taker_mint), false),
+                AccountMeta::new(self.ata(&order_pda, &self.taker_mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.taker_mint, false),
+                AccountMeta::new_readonly(config_pda(), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.maker_mint), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.taker_mint), false),
+            ],
+            data: SwapInstruction::ProposeCounterOffer {
+                new_maker_amount,
+                new_taker_amount,
+            }
+            .pack(),
+        };
+        self.send(&[ix], taker)
+    }
+
+    fn accept_counter_offer(&mut self, taker: &Pubkey) -> TransactionResult {
+        let order_pda = self.order_pda();
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&order_pda, &self.maker_mint), false),
+                AccountMeta::new(self.ata(&order_pda, &self.taker_mint), false),
+                AccountMeta::new(self.ata(&self.maker.pubkey(), &self.maker_mint), false),
+                AccountMeta::new(self.ata(&self.maker.pubkey(), &self.taker_mint), false),
+                AccountMeta::new(self.ata(taker, &self.maker_mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.maker_mint, false),
+                AccountMeta::new_readonly(self.taker_mint, false),
+                AccountMeta::new_readonly(config_pda(), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.maker_mint), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.taker_mint), false),
+                AccountMeta::new(treasury_pda(&self.taker_mint), false),
+                AccountMeta::new_readonly(fee_exemption_pda(&self.maker.pubkey()), false),
+                AccountMeta::new(user_stats_pda(&self.maker.pubkey()), false),
+            ],
+            data: SwapInstruction::AcceptCounterOffer.pack(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker)
+    }
+
+    /// Sends a maker-only instruction that takes just the maker and order accounts
+    fn maker_order_ix(&mut self, instruction: &SwapInstruction) -> TransactionResult {
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new(self.order_pda(), false),
+            ],
+            data: instruction.pack(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker)
+    }
+
+    fn decline_order(&mut self, taker: &Keypair) -> TransactionResult {
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(taker.pubkey(), true),
+                AccountMeta::new(self.order_pda(), false),
+            ],
+            data: SwapInstruction::DeclineOrder.pack(),
+        };
+        self.send(&[ix], taker)
+    }
+
+    fn close_order_ix(&self, authority: &Pubkey) -> Instruction {
+        let order_pda = self.order_pda();
+        Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(*authority, true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&order_pda, &self.maker_mint), false),
+                AccountMeta::new(self.ata(&self.maker.pubkey(), &self.maker_mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.maker_mint, false),
+                AccountMeta::new(self.maker.pubkey(), false),
+                AccountMeta::new(maker_index_pda(&self.maker.pubkey()), false),
+            ],
+            data: SwapInstruction::CloseOrder { memo: None }.pack(),
+        }
+    }
+
+    fn place_bid(
+        &mut self,
+        bidder: &Keypair,
+        amount: u64,
+        previous_bidder: Option<&Pubkey>,
+    ) -> TransactionResult {
+        let order_pda = self.order_pda();
+        let mut ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(bidder.pubkey(), true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&bidder.pubkey(), &self.taker_mint), false),
+                AccountMeta::new(self.ata(&order_pda, &self.taker_mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.taker_mint, false),
+            ],
+            data: SwapInstruction::PlaceBid { amount }.pack(),
+        };
+        if let Some(previous_bidder) = previous_bidder {
+            ix.accounts.push(AccountMeta::new(
+                self.ata(previous_bidder, &self.taker_mint),
+                false,
+            ));
+        }
+        self.send(&[ix], bidder)
+    }
+
+    fn settle_auction(&mut self, caller: &Keypair, winner: &Pubkey) -> TransactionResult {
+        let order_pda = self.order_pda();
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(caller.pubkey(), true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&order_pda, &self.maker_mint), false),
+                AccountMeta::new(self.ata(&order_pda, &self.taker_mint), false),
+                AccountMeta::new(self.ata(&self.maker.pubkey(), &self.taker_mint), false),
+                AccountMeta::new(self.ata(winner, &self.maker_mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.maker_mint, false),
+                AccountMeta::new_readonly(self.taker_mint, false),
+                AccountMeta::new_readonly(config_pda(), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.maker_mint), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.taker_mint), false),
+            ],
+            data: SwapInstruction::SettleAuction.pack(),
+        };
+        self.send(&[ix], caller)
+    }
+
+    fn complete_swap_ix(&self, taker: &Pubkey) -> Instruction {
+        let order_pda = self.order_pda();
+        Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(*taker, true),
+                AccountMeta::new(order_pda, false),
+                AccountMeta::new(self.ata(&self.maker.pubkey(), &self.taker_mint), false),
+                AccountMeta::new(self.ata(taker, &self.taker_mint), false),
+                AccountMeta::new(self.ata(taker, &self.maker_mint), false),
+                AccountMeta::new(self.ata(&order_pda, &self.maker_mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.maker_mint, false),
+                AccountMeta::new_readonly(self.taker_mint, false),
+                AccountMeta::new_readonly(config_pda(), false),
+                AccountMeta::new(treasury_pda(&self.taker_mint), false),
+                AccountMeta::new_readonly(fee_exemption_pda(&self.maker.pubkey()), false),
+                AccountMeta::new(user_stats_pda(&self.maker.pubkey()), false),
+                AccountMeta::new(user_stats_pda(taker), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.maker_mint), false),
+                AccountMeta::new_readonly(blocked_mint_pda(&self.taker_mint), false),
+                AccountMeta::new(protocol_stats_pda(), false),
+                AccountMeta::new(pair_stats_pda(&self.maker_mint, &self.taker_mint), false),
+            ],
+            data: SwapInstruction::CompleteSwap {
+                fill_amount: 0,
+                max_taker_amount: u64::MAX,
+                min_maker_amount: 0,
+                taker_proof: vec![],
+                memo: None,
+            }
+            .pack(),
+        }
+    }
+
+    /// Makes the maker the program's upgrade authority and initializes the config with it
+    /// as admin and `fee_collector` collecting `fee_bps`
+    fn initialize_config(&mut self, fee_bps: u16) -> TransactionResult {
+        let program_data = set_upgrade_authority(&mut self.svm, &self.maker.pubkey());
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new(config_pda(), false),
+                AccountMeta::new_readonly(program_data, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: SwapInstruction::InitializeConfig {
+                fee_bps,
+                fee_collector: self.fee_collector,
+            }
+            .pack(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker)
+    }
+
+    /// Creates the treasury collecting protocol fees in `mint`; needs the config
+    fn initialize_treasury(&mut self, mint: &Pubkey) -> TransactionResult {
+        let ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new_readonly(config_pda(), false),
+                AccountMeta::new(treasury_pda(mint), false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: SwapInstruction::InitializeTreasury.pack(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker)
+    }
+
+    /// Blocks or unblocks `mint` as the admin; needs the config
+    fn set_mint_blocked(&mut self, mint: &Pubkey, blocked: bool) -> TransactionResult {
+        let mut ix = Instruction {
+            program_id: PROGRAM_KEY,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new_readonly(config_pda(), false),
+                AccountMeta::new(blocked_mint_pda(mint), false),
+            ],
+            data: SwapInstruction::UnblockMint { mint: *mint }.pack(),
+        };
+        if blocked {
+            ix.accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+            ix.data = SwapInstruction::BlockMint { mint: *mint }.pack();
+        }
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &maker)
+    }
+
+    fn complete_swap(&mut self, taker: &Keypair) -> TransactionResult {
+        let ix = self.complete_swap_ix(&taker.pubkey());
+        self.send(&[ix], taker)
+    }
+}
+
+/// The program error a failed transaction ended with
+fn swap_error(result: TransactionResult) -> Option<SwapError> {
+    client::error::transaction_swap_error(&result.unwrap_err().err)
+}
+
+/// Decodes the program's events from a transaction's logs
+fn events(logs: &[String]) -> Vec<SwapEvent> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| SwapEvent::unpack(&BASE64_STANDARD.decode(data).unwrap()).ok())
+        .collect()
+}
+
+fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &PROGRAM_KEY).0
+}
+
+fn bundle_pda(maker: &Pubkey, bundle_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"bundle", maker.as_ref(), &bundle_id.to_le_bytes()],
+        &PROGRAM_KEY,
+    )
+    .0
+}
+
+fn vault_pda(order: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"vault", order.as_ref(), mint.as_ref()], &PROGRAM_KEY).0
+}
+
+fn fee_exemption_pda(account: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"fee_exempt", account.as_ref()], &PROGRAM_KEY).0
+}
+
+fn blocked_mint_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"blocked_mint", mint.as_ref()], &PROGRAM_KEY).0
+}
+
+fn maker_index_pda(maker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"maker_index", maker.as_ref()], &PROGRAM_KEY).0
+}
+
+fn protocol_stats_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"protocol_stats"], &PROGRAM_KEY).0
+}
+
+fn pair_stats_pda(maker_mint: &Pubkey, taker_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"pair_stats", maker_mint.as_ref(), taker_mint.as_ref()],
+        &PROGRAM_KEY,
+    )
+    .0
+}
+
+fn registry_page_pda(maker_mint: &Pubkey, taker_mint: &Pubkey, page: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"registry",
+            maker_mint.as_ref(),
+            taker_mint.as_ref(),
+            &page.to_le_bytes(),
+        ],
+        &PROGRAM_KEY,
+    )
+    .0
+}
+
+#[cfg(feature = "event-cpi")]
+fn event_authority_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &PROGRAM_KEY).0
+}
+
+fn fill_receipt_pda(order: &Pubkey, fill_index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"receipt", order.as_ref(), &fill_index.to_le_bytes()],
+        &PROGRAM_KEY,
+    )
+    .0
+}
+
+fn trade_history_pda(maker_mint: &Pubkey, taker_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"trade_history", maker_mint.as_ref(), taker_mint.as_ref()],
+        &PROGRAM_KEY,
+    )
+    .0
+}
+
+fn treasury_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"treasury", mint.as_ref()], &PROGRAM_KEY).0
+}
+
+fn user_stats_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"user_stats", user.as_ref()], &PROGRAM_KEY).0
+}
+
+/// Writes the program's `ProgramData` account with `authority` as upgrade authority and
+/// returns its address
+fn set_upgrade_authority(svm: &mut LiteSVM, authority: &Pubkey) -> Pubkey {
+    let program_data = Pubkey::find_program_address(
+        &[PROGRAM_KEY.as_ref()],
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    )
+    .0;
+    let mut data = 3u32.to_le_bytes().to_vec(); // UpgradeableLoaderState::ProgramData
+    data.extend_from_slice(&0u64.to_le_bytes()); // slot
+    data.push(1);
+    data.extend_from_slice(authority.as_ref());
+
+    svm.set_account(
+        program_data,
+        solana_sdk::account::Account {
+            lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: solana_sdk::bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+    program_data
+}
+
+/// Builds an Ed25519 program instruction verifying `signer`'s signature over `message`
+/// with all data inlined in the instruction itself
+fn ed25519_ix(signer: &Keypair, message: &[u8]) -> Instruction {
+    const DATA_START: u16 = 16;
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+
+    let mut data = vec![1, 0];
+    for field in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(signer.pubkey().as_ref());
+    data.extend_from_slice(signer.sign_message(message).as_ref());
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: solana_sdk::ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
 #[test]
 fn test_splerg_p2p() {
     let mut svm = LiteSVM::new();
@@ -52,6 +823,7 @@ fn test_splerg_p2p() {
             payer.pubkey().as_ref(),
             maker_mint_setup.mint.pubkey().as_ref(),
             taker_mint_setup.mint.pubkey().as_ref(),
+            &0u64.to_le_bytes(),
         ],
         &PROGRAM_KEY,
     );
@@ -85,9 +857,14 @@ fn test_splerg_p2p() {
     // Create instruction data
     let maker_amount = 100_000u64;
     let taker_amount = 200_000u64;
-    let mut ix_data = vec![0]; // variant 0 for InitializeOrder
+    let mut ix_data = INSTRUCTION_DISCRIMINATORS[0].to_vec(); // InitializeOrder
+    ix_data.extend_from_slice(&0u64.to_le_bytes()); // order_id
     ix_data.extend_from_slice(&maker_amount.to_le_bytes());
     ix_data.extend_from_slice(&taker_amount.to_le_bytes());
+    ix_data.push(0); // requires_confirmation
+    ix_data.push(0); // dual_escrow
+    ix_data.push(0); // all_or_none
+    ix_data.extend_from_slice(&[0; MEMO_LEN]); // memo
 
     // Create the transaction
     let initialize_order_ix = solana_program::instruction::Instruction {
@@ -102,7 +879,20 @@ fn test_splerg_p2p() {
             AccountMeta::new_readonly(taker_mint_setup.mint.pubkey(), false), // taker token mint
             AccountMeta::new_readonly(system_program::id(), false), // system program
             AccountMeta::new_readonly(sysvar::id(), false),   // rent sysvar
-            AccountMeta::new_readonly(spl_token::id(), false), // token program
+            AccountMeta::new_readonly(spl_token::id(), false), // maker token program
+            AccountMeta::new_readonly(spl_token::id(), false), // taker token program
+            AccountMeta::new_readonly(config_pda(), false),   // config PDA
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint_setup.mint.pubkey()), false), // maker mint blocklist
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint_setup.mint.pubkey()), false), // taker mint blocklist
+            AccountMeta::new(maker_index_pda(&payer.pubkey()), false), // maker order index
+            AccountMeta::new(
+                registry_page_pda(
+                    &maker_mint_setup.mint.pubkey(),
+                    &taker_mint_setup.mint.pubkey(),
+                    0,
+                ),
+                false,
+            ), // registry page
         ],
         data: ix_data,
     };
@@ -119,12 +909,12 @@ fn test_splerg_p2p() {
 
     // Verify the order was created correctly
     let order_account = svm.get_account(&order_pda).unwrap();
-    let order_data = SwapOrder::try_from_slice(&order_account.data).unwrap();
+    let order_data = SwapOrder::unpack(&order_account.data).unwrap();
 
     assert_eq!(order_data.maker, payer.pubkey());
     assert_eq!(order_data.taker, taker.pubkey());
-    assert_eq!(order_data.maker_amount, maker_amount);
-    assert_eq!(order_data.taker_amount, taker_amount);
+    assert_eq!({ order_data.maker_amount }, maker_amount);
+    assert_eq!({ order_data.taker_amount }, taker_amount);
 
     // Verify tokens were transferred to the order account
     let order_token_account = svm.get_account(&order_maker_token_ata).unwrap();
@@ -169,6 +959,7 @@ fn test_change_order() {
             payer.pubkey().as_ref(),
             maker_mint_setup.mint.pubkey().as_ref(),
             taker_mint_setup.mint.pubkey().as_ref(),
+            &0u64.to_le_bytes(),
         ],
         &PROGRAM_KEY,
     );
@@ -202,9 +993,14 @@ fn test_change_order() {
     // Create instruction data
     let maker_amount = 50_000u64;
     let taker_amount = 100_000u64;
-    let mut amount_data = vec![0]; // variant 1 for ChangeOrderAmounts
+    let mut amount_data = INSTRUCTION_DISCRIMINATORS[0].to_vec(); // InitializeOrder
+    amount_data.extend_from_slice(&0u64.to_le_bytes()); // order_id
     amount_data.extend_from_slice(&maker_amount.to_le_bytes());
     amount_data.extend_from_slice(&taker_amount.to_le_bytes());
+    amount_data.push(0); // requires_confirmation
+    amount_data.push(0); // dual_escrow
+    amount_data.push(0); // all_or_none
+    amount_data.extend_from_slice(&[0; MEMO_LEN]); // memo
 
     // Create the transaction
     let initialize_order_ix = solana_program::instruction::Instruction {
@@ -219,7 +1015,20 @@ fn test_change_order() {
             AccountMeta::new_readonly(taker_mint_setup.mint.pubkey(), false), // taker token mint
             AccountMeta::new_readonly(system_program::id(), false), // system program
             AccountMeta::new_readonly(sysvar::id(), false),   // rent sysvar
-            AccountMeta::new_readonly(spl_token::id(), false), // token program
+            AccountMeta::new_readonly(spl_token::id(), false), // maker token program
+            AccountMeta::new_readonly(spl_token::id(), false), // taker token program
+            AccountMeta::new_readonly(config_pda(), false),   // config PDA
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint_setup.mint.pubkey()), false), // maker mint blocklist
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint_setup.mint.pubkey()), false), // taker mint blocklist
+            AccountMeta::new(maker_index_pda(&payer.pubkey()), false), // maker order index
+            AccountMeta::new(
+                registry_page_pda(
+                    &maker_mint_setup.mint.pubkey(),
+                    &taker_mint_setup.mint.pubkey(),
+                    0,
+                ),
+                false,
+            ), // registry page
         ],
         data: amount_data,
     };
@@ -236,17 +1045,17 @@ fn test_change_order() {
 
     // Verify the order was created correctly
     let order_account = svm.get_account(&order_pda).unwrap();
-    let order_data = SwapOrder::try_from_slice(&order_account.data).unwrap();
+    let order_data = SwapOrder::unpack(&order_account.data).unwrap();
 
     assert_eq!(order_data.maker, payer.pubkey());
     assert_eq!(order_data.taker, taker.pubkey());
-    assert_eq!(order_data.maker_amount, maker_amount);
-    assert_eq!(order_data.taker_amount, taker_amount);
+    assert_eq!({ order_data.maker_amount }, maker_amount);
+    assert_eq!({ order_data.taker_amount }, taker_amount);
 
     // Test ChangeOrderAmounts
     let new_maker_amount = 100_000u64;
     let new_taker_amount = 200_000u64;
-    let mut change_amount_data = vec![1]; // variant 1 for ChangeOrderAmounts
+    let mut change_amount_data = INSTRUCTION_DISCRIMINATORS[1].to_vec(); // ChangeOrderAmounts
     change_amount_data.extend_from_slice(&new_maker_amount.to_le_bytes());
     change_amount_data.extend_from_slice(&new_taker_amount.to_le_bytes());
 
@@ -258,6 +1067,8 @@ fn test_change_order() {
             AccountMeta::new(order_maker_token_ata, false),
             AccountMeta::new(maker_token_ata, false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(maker_mint_setup.mint.pubkey(), false), // maker mint
+            AccountMeta::new_readonly(taker_mint_setup.mint.pubkey(), false), // taker mint
         ],
         data: change_amount_data,
     };
@@ -272,13 +1083,13 @@ fn test_change_order() {
     svm.send_transaction(tx).unwrap();
 
     // Verify amounts changed
-    let order_data = SwapOrder::try_from_slice(&svm.get_account(&order_pda).unwrap().data).unwrap();
-    assert_eq!(order_data.maker_amount, new_maker_amount);
-    assert_eq!(order_data.taker_amount, new_taker_amount);
+    let order_data = SwapOrder::unpack(&svm.get_account(&order_pda).unwrap().data).unwrap();
+    assert_eq!({ order_data.maker_amount }, new_maker_amount);
+    assert_eq!({ order_data.taker_amount }, new_taker_amount);
 
     // Test ChangeTaker
     let new_taker = Keypair::new();
-    let mut change_taker_data = vec![2]; // variant 2 for ChangeTaker
+    let mut change_taker_data = INSTRUCTION_DISCRIMINATORS[2].to_vec(); // ChangeTaker
     change_taker_data.extend_from_slice(&new_taker.pubkey().to_bytes());
 
     let change_taker_ix = solana_program::instruction::Instruction {
@@ -301,10 +1112,55 @@ fn test_change_order() {
     svm.send_transaction(tx).unwrap();
 
     // Verify taker changed
-    let order_data = SwapOrder::try_from_slice(&svm.get_account(&order_pda).unwrap().data).unwrap();
+    let order_data = SwapOrder::unpack(&svm.get_account(&order_pda).unwrap().data).unwrap();
     assert_eq!(order_data.taker, new_taker.pubkey());
 }
 
+#[test]
+fn test_change_taker_validates_order_account() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    env.initialize_order(&env.taker.pubkey(), 100, 200).unwrap();
+    let order_pda = env.order_pda();
+    let order_account = env.svm.get_account(&order_pda).unwrap();
+    let new_taker = Pubkey::new_unique();
+    let change_taker_ix = |order: Pubkey| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order, false),
+            AccountMeta::new_readonly(new_taker, false),
+        ],
+        data: SwapInstruction::ChangeTaker {
+            new_taker: new_taker.to_bytes(),
+        }
+        .pack(),
+    };
+
+    // A copy of the order at another address is not the order
+    let copy = Pubkey::new_unique();
+    env.svm.set_account(copy, order_account.clone()).unwrap();
+    let failure = env.send(&[change_taker_ix(copy)], &maker).unwrap_err();
+    assert_eq!(
+        client::error::transaction_swap_error(&failure.err),
+        Some(SwapError::InvalidOrderAddress)
+    );
+
+    // Nor is the order's data under another owner
+    let mut foreign = order_account.clone();
+    foreign.owner = Pubkey::new_unique();
+    env.svm.set_account(order_pda, foreign).unwrap();
+    let failure = env.send(&[change_taker_ix(order_pda)], &maker).unwrap_err();
+    assert_eq!(
+        client::error::transaction_swap_error(&failure.err),
+        Some(SwapError::WrongAccountOwner)
+    );
+
+    env.svm.set_account(order_pda, order_account).unwrap();
+    env.send(&[change_taker_ix(order_pda)], &maker).unwrap();
+    assert_eq!({ env.order().taker }, new_taker);
+}
+
 #[test]
 fn test_change_taker() {
     let mut svm = LiteSVM::new();
@@ -342,6 +1198,7 @@ fn test_change_taker() {
             payer.pubkey().as_ref(),
             maker_mint_setup.mint.pubkey().as_ref(),
             taker_mint_setup.mint.pubkey().as_ref(),
+            &0u64.to_le_bytes(),
         ],
         &PROGRAM_KEY,
     );
@@ -375,9 +1232,14 @@ fn test_change_taker() {
     // Create instruction data
     let maker_amount = 100_000u64;
     let taker_amount = 200_000u64;
-    let mut ix_data = vec![0]; // variant 0 for InitializeOrder
+    let mut ix_data = INSTRUCTION_DISCRIMINATORS[0].to_vec(); // InitializeOrder
+    ix_data.extend_from_slice(&0u64.to_le_bytes()); // order_id
     ix_data.extend_from_slice(&maker_amount.to_le_bytes());
     ix_data.extend_from_slice(&taker_amount.to_le_bytes());
+    ix_data.push(0); // requires_confirmation
+    ix_data.push(0); // dual_escrow
+    ix_data.push(0); // all_or_none
+    ix_data.extend_from_slice(&[0; MEMO_LEN]); // memo
 
     // Create the transaction
     let initialize_order_ix = solana_program::instruction::Instruction {
@@ -392,7 +1254,20 @@ fn test_change_taker() {
             AccountMeta::new_readonly(taker_mint_setup.mint.pubkey(), false), // taker token mint
             AccountMeta::new_readonly(system_program::id(), false), // system program
             AccountMeta::new_readonly(sysvar::id(), false),   // rent sysvar
-            AccountMeta::new_readonly(spl_token::id(), false), // token program
+            AccountMeta::new_readonly(spl_token::id(), false), // maker token program
+            AccountMeta::new_readonly(spl_token::id(), false), // taker token program
+            AccountMeta::new_readonly(config_pda(), false),   // config PDA
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint_setup.mint.pubkey()), false), // maker mint blocklist
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint_setup.mint.pubkey()), false), // taker mint blocklist
+            AccountMeta::new(maker_index_pda(&payer.pubkey()), false), // maker order index
+            AccountMeta::new(
+                registry_page_pda(
+                    &maker_mint_setup.mint.pubkey(),
+                    &taker_mint_setup.mint.pubkey(),
+                    0,
+                ),
+                false,
+            ), // registry page
         ],
         data: ix_data,
     };
@@ -409,12 +1284,12 @@ fn test_change_taker() {
 
     // Verify initial taker
     let order_account = svm.get_account(&order_pda).unwrap();
-    let order_data = SwapOrder::try_from_slice(&order_account.data).unwrap();
+    let order_data = SwapOrder::unpack(&order_account.data).unwrap();
     assert_eq!(order_data.taker, taker.pubkey());
 
     // Test ChangeTaker
     let new_taker = Keypair::new();
-    let mut change_taker_data = vec![2]; // variant 2 for ChangeTaker
+    let mut change_taker_data = INSTRUCTION_DISCRIMINATORS[2].to_vec(); // ChangeTaker
     change_taker_data.extend_from_slice(&new_taker.pubkey().to_bytes());
 
     let change_taker_ix = solana_program::instruction::Instruction {
@@ -437,7 +1312,7 @@ fn test_change_taker() {
     svm.send_transaction(tx).unwrap();
 
     // Verify taker changed
-    let order_data = SwapOrder::try_from_slice(&svm.get_account(&order_pda).unwrap().data).unwrap();
+    let order_data = SwapOrder::unpack(&svm.get_account(&order_pda).unwrap().data).unwrap();
     assert_eq!(order_data.taker, new_taker.pubkey());
 }
 
@@ -488,6 +1363,7 @@ fn test_complete_swap() {
             payer.pubkey().as_ref(),
             maker_mint_setup.mint.pubkey().as_ref(),
             taker_mint_setup.mint.pubkey().as_ref(),
+            &0u64.to_le_bytes(),
         ],
         &PROGRAM_KEY,
     );
@@ -539,9 +1415,14 @@ fn test_complete_swap() {
     // Initialize order
     let maker_amount = 100_000u64;
     let taker_amount = 200_000u64;
-    let mut ix_data = vec![0];
+    let mut ix_data = INSTRUCTION_DISCRIMINATORS[0].to_vec(); // InitializeOrder
+    ix_data.extend_from_slice(&0u64.to_le_bytes()); // order_id
     ix_data.extend_from_slice(&maker_amount.to_le_bytes());
     ix_data.extend_from_slice(&taker_amount.to_le_bytes());
+    ix_data.push(0); // requires_confirmation
+    ix_data.push(0); // dual_escrow
+    ix_data.push(0); // all_or_none
+    ix_data.extend_from_slice(&[0; MEMO_LEN]); // memo
 
     let initialize_order_ix = solana_program::instruction::Instruction {
         program_id: PROGRAM_KEY,
@@ -556,6 +1437,19 @@ fn test_complete_swap() {
             AccountMeta::new_readonly(system_program::id(), false),
             AccountMeta::new_readonly(sysvar::id(), false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint_setup.mint.pubkey()), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint_setup.mint.pubkey()), false),
+            AccountMeta::new(maker_index_pda(&payer.pubkey()), false),
+            AccountMeta::new(
+                registry_page_pda(
+                    &maker_mint_setup.mint.pubkey(),
+                    &taker_mint_setup.mint.pubkey(),
+                    0,
+                ),
+                false,
+            ),
         ],
         data: ix_data,
     };
@@ -570,8 +1464,17 @@ fn test_complete_swap() {
     svm.send_transaction(tx).unwrap();
 
     // Complete the swap
-    let complete_swap_data = vec![3]; // variant 3 for CompleteSwap
+    let mut complete_swap_data = INSTRUCTION_DISCRIMINATORS[3].to_vec(); // CompleteSwap
+    complete_swap_data.extend_from_slice(&0u64.to_le_bytes()); // fill_amount (everything)
+    complete_swap_data.extend_from_slice(&u64::MAX.to_le_bytes()); // max_taker_amount
+    complete_swap_data.extend_from_slice(&0u64.to_le_bytes()); // min_maker_amount
+    complete_swap_data.extend_from_slice(&0u32.to_le_bytes()); // taker_proof (empty)
+    complete_swap_data.push(0); // memo (none)
 
+    let pair_stats = pair_stats_pda(
+        &maker_mint_setup.mint.pubkey(),
+        &taker_mint_setup.mint.pubkey(),
+    );
     let complete_swap_ix = solana_program::instruction::Instruction {
         program_id: PROGRAM_KEY,
         accounts: vec![
@@ -581,7 +1484,19 @@ fn test_complete_swap() {
             AccountMeta::new(taker_taker_token_ata, false), // taker's sending ATA
             AccountMeta::new(taker_maker_token_ata, false), // taker's receiving ATA
             AccountMeta::new(order_maker_token_ata, false), // order's maker token ATA
-            AccountMeta::new_readonly(spl_token::id(), false), // token program
+            AccountMeta::new_readonly(spl_token::id(), false), // maker token program
+            AccountMeta::new_readonly(spl_token::id(), false), // taker token program
+            AccountMeta::new_readonly(maker_mint_setup.mint.pubkey(), false), // maker mint
+            AccountMeta::new_readonly(taker_mint_setup.mint.pubkey(), false), // taker mint
+            AccountMeta::new_readonly(config_pda(), false), // config PDA (not initialized)
+            AccountMeta::new(Pubkey::new_unique(), false),  // fee account (no fee charged)
+            AccountMeta::new_readonly(fee_exemption_pda(&payer.pubkey()), false), // fee exemption
+            AccountMeta::new(user_stats_pda(&payer.pubkey()), false), // maker stats (none)
+            AccountMeta::new(user_stats_pda(&taker.pubkey()), false), // taker stats (none)
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint_setup.mint.pubkey()), false), // maker mint blocklist
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint_setup.mint.pubkey()), false), // taker mint blocklist
+            AccountMeta::new(protocol_stats_pda(), false), // protocol stats (none)
+            AccountMeta::new(pair_stats, false),           // pair stats (none)
         ],
         data: complete_swap_data,
     };
@@ -648,6 +1563,7 @@ fn test_close_order() {
             payer.pubkey().as_ref(),
             maker_mint_setup.mint.pubkey().as_ref(),
             taker_mint_setup.mint.pubkey().as_ref(),
+            &0u64.to_le_bytes(),
         ],
         &PROGRAM_KEY,
     );
@@ -681,9 +1597,14 @@ fn test_close_order() {
     // Create instruction data
     let maker_amount = 100_000u64;
     let taker_amount = 200_000u64;
-    let mut ix_data = vec![0]; // variant 0 for InitializeOrder
+    let mut ix_data = INSTRUCTION_DISCRIMINATORS[0].to_vec(); // InitializeOrder
+    ix_data.extend_from_slice(&0u64.to_le_bytes()); // order_id
     ix_data.extend_from_slice(&maker_amount.to_le_bytes());
     ix_data.extend_from_slice(&taker_amount.to_le_bytes());
+    ix_data.push(0); // requires_confirmation
+    ix_data.push(0); // dual_escrow
+    ix_data.push(0); // all_or_none
+    ix_data.extend_from_slice(&[0; MEMO_LEN]); // memo
 
     // Create the transaction
     let initialize_order_ix = solana_program::instruction::Instruction {
@@ -698,7 +1619,20 @@ fn test_close_order() {
             AccountMeta::new_readonly(taker_mint_setup.mint.pubkey(), false), // taker token mint
             AccountMeta::new_readonly(system_program::id(), false), // system program
             AccountMeta::new_readonly(sysvar::id(), false),   // rent sysvar
-            AccountMeta::new_readonly(spl_token::id(), false), // token program
+            AccountMeta::new_readonly(spl_token::id(), false), // maker token program
+            AccountMeta::new_readonly(spl_token::id(), false), // taker token program
+            AccountMeta::new_readonly(config_pda(), false),   // config PDA
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint_setup.mint.pubkey()), false), // maker mint blocklist
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint_setup.mint.pubkey()), false), // taker mint blocklist
+            AccountMeta::new(maker_index_pda(&payer.pubkey()), false), // maker order index
+            AccountMeta::new(
+                registry_page_pda(
+                    &maker_mint_setup.mint.pubkey(),
+                    &taker_mint_setup.mint.pubkey(),
+                    0,
+                ),
+                false,
+            ), // registry page
         ],
         data: ix_data,
     };
@@ -719,7 +1653,8 @@ fn test_close_order() {
             .amount;
 
     // Close order
-    let close_order_data = vec![4]; // variant 4 for CloseOrder
+    let mut close_order_data = INSTRUCTION_DISCRIMINATORS[4].to_vec(); // CloseOrder
+    close_order_data.push(0); // memo (none)
 
     let close_order_ix = solana_program::instruction::Instruction {
         program_id: PROGRAM_KEY,
@@ -729,6 +1664,9 @@ fn test_close_order() {
             AccountMeta::new(order_maker_token_ata, false), // order's token account
             AccountMeta::new(maker_token_ata, false), // maker's token account
             AccountMeta::new_readonly(spl_token::id(), false), // token program
+            AccountMeta::new_readonly(maker_mint_setup.mint.pubkey(), false), // maker mint
+            AccountMeta::new(payer.pubkey(), false), // rent destination
+            AccountMeta::new(maker_index_pda(&payer.pubkey()), false), // maker order index
         ],
         data: close_order_data,
     };
@@ -758,3 +1696,6360 @@ fn test_close_order() {
         initial_maker_token_balance + maker_amount
     );
 }
+
+#[test]
+fn test_open_order_any_taker() {
+    let mut env = SwapEnv::new();
+    let maker_amount = 100_000u64;
+    let taker_amount = 200_000u64;
+
+    env.initialize_order(&Pubkey::default(), maker_amount, taker_amount)
+        .unwrap();
+    assert!(env.order().accepts_any_taker());
+
+    // Restricting the order locks out everyone but the named taker
+    let designated = Keypair::new();
+    env.change_taker(&designated.pubkey()).unwrap();
+    let taker = env.taker.insecure_clone();
+    assert!(env.complete_swap(&taker).is_err());
+
+    // Re-opening the order lets any signer fill it
+    env.change_taker(&Pubkey::default()).unwrap();
+    env.complete_swap(&taker).unwrap();
+
+    let maker_receiving = env.ata(&env.maker.pubkey(), &env.taker_mint);
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&maker_receiving), taker_amount);
+    assert_eq!(env.token_balance(&taker_receiving), maker_amount);
+}
+
+#[test]
+fn test_multiple_orders_same_pair() {
+    let mut env = SwapEnv::new();
+
+    env.initialize_order(&env.taker.pubkey(), 100_000, 200_000)
+        .unwrap();
+    let first_order = env.order_pda();
+
+    env.order_id = 1;
+    env.create_order_ata();
+    env.initialize_order(&env.taker.pubkey(), 50_000, 75_000)
+        .unwrap();
+    assert_ne!(env.order_pda(), first_order);
+
+    let order = env.order();
+    assert_eq!({ order.order_id }, 1);
+    assert_eq!({ order.maker_amount }, 50_000);
+
+    let maker_ata = env.ata(&env.maker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&maker_ata), 1_000_000 - 150_000);
+}
+
+#[test]
+fn test_completed_order_rejects_changes() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 200_000)
+        .unwrap();
+    assert_eq!(env.order().status, OrderStatus::Open);
+
+    let taker = env.taker.insecure_clone();
+    env.complete_swap(&taker).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+
+    // Neither a second fill nor a retarget is allowed once completed
+    assert!(env.complete_swap(&taker).is_err());
+    assert!(env.change_taker(&Pubkey::default()).is_err());
+}
+
+#[test]
+fn test_accept_and_confirm_swap() {
+    let mut env = SwapEnv::new();
+    let maker_amount = 100_000u64;
+    let taker_amount = 200_000u64;
+
+    env.create_order_taker_ata();
+    let instruction = SwapInstruction::InitializeOrder {
+        order_id: env.order_id,
+        maker_amount,
+        taker_amount,
+        requires_confirmation: true,
+        dual_escrow: false,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    };
+    env.initialize(&env.taker.pubkey(), &instruction).unwrap();
+
+    // Confirmation orders cannot be filled atomically, nor quoted in UI amounts
+    let taker = env.taker.insecure_clone();
+    assert!(env.complete_swap(&taker).is_err());
+    let set_ui_amounts_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(env.maker.pubkey(), true),
+            AccountMeta::new(env.order_pda(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+        ],
+        data: SwapInstruction::SetUiAmounts { ui_amounts: true }.pack(),
+    };
+    let maker = env.maker.insecure_clone();
+    assert_eq!(
+        swap_error(env.send(&[set_ui_amounts_ix], &maker)),
+        Some(SwapError::IncompatibleOrderOptions)
+    );
+
+    env.accept_order(&taker).unwrap();
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::Accepted);
+    assert_eq!(order.accepted_taker, taker.pubkey());
+
+    let order_taker_ata = env.ata(&env.order_pda(), &env.taker_mint);
+    assert_eq!(env.token_balance(&order_taker_ata), taker_amount);
+
+    // Nor can it be confirmed once one of its mints is blocked
+    let taker_mint = env.taker_mint;
+    env.initialize_config(100).unwrap();
+    env.initialize_treasury(&taker_mint).unwrap();
+    env.set_mint_blocked(&taker_mint, true).unwrap();
+    assert_eq!(
+        swap_error(env.confirm_swap(&taker.pubkey())),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&taker_mint, false).unwrap();
+
+    // The maker signs writable, so the completed order closes on the fill
+    env.confirm_swap(&taker.pubkey()).unwrap();
+    assert!(env.svm.get_account(&env.order_pda()).is_none());
+
+    // 1% of the escrowed taker side goes to the treasury instead of the maker
+    let maker_receiving = env.ata(&env.maker.pubkey(), &env.taker_mint);
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&treasury_pda(&taker_mint)), 2_000);
+    assert_eq!(env.token_balance(&maker_receiving), taker_amount - 2_000);
+    assert_eq!(env.token_balance(&taker_receiving), maker_amount);
+    assert_eq!(env.token_balance(&order_taker_ata), 0);
+}
+
+#[test]
+fn test_counter_offer() {
+    let mut env = SwapEnv::new();
+    env.create_order_taker_ata();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 200_000)
+        .unwrap();
+
+    let maker_mint = env.maker_mint;
+    let taker_mint = env.taker_mint;
+    env.initialize_config(50).unwrap();
+    env.initialize_treasury(&taker_mint).unwrap();
+
+    // No counter-offer can be escrowed for a blocked pair
+    let taker = env.taker.insecure_clone();
+    env.set_mint_blocked(&maker_mint, true).unwrap();
+    assert_eq!(
+        swap_error(env.propose_counter_offer(&taker, 120_000, 220_000)),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&maker_mint, false).unwrap();
+
+    // The taker asks for more maker tokens at a slightly higher price
+    env.propose_counter_offer(&taker, 120_000, 220_000).unwrap();
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::CounterOffered);
+    assert_eq!({ order.counter_maker_amount }, 120_000);
+    assert_eq!({ order.counter_taker_amount }, 220_000);
+
+    env.set_mint_blocked(&maker_mint, true).unwrap();
+    assert_eq!(
+        swap_error(env.accept_counter_offer(&taker.pubkey())),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&maker_mint, false).unwrap();
+
+    env.accept_counter_offer(&taker.pubkey()).unwrap();
+    assert!(env.svm.get_account(&env.order_pda()).is_none());
+
+    // 0.5% of the counter-offered taker amount goes to the treasury
+    let maker_receiving = env.ata(&env.maker.pubkey(), &env.taker_mint);
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+    let maker_ata = env.ata(&env.maker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&treasury_pda(&taker_mint)), 1_100);
+    assert_eq!(env.token_balance(&maker_receiving), 220_000 - 1_100);
+    assert_eq!(env.token_balance(&taker_receiving), 120_000);
+    assert_eq!(env.token_balance(&maker_ata), 1_000_000 - 120_000);
+}
+
+#[test]
+fn test_dual_escrow_requires_taker_deposit() {
+    let mut env = SwapEnv::new();
+    let maker_amount = 100_000u64;
+    let taker_amount = 200_000u64;
+
+    env.create_order_taker_ata();
+    let order_taker_ata = env.ata(&env.order_pda(), &env.taker_mint);
+    let instruction = SwapInstruction::InitializeOrder {
+        order_id: env.order_id,
+        maker_amount,
+        taker_amount,
+        requires_confirmation: false,
+        dual_escrow: true,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    };
+    env.initialize_with_accounts(
+        &env.taker.pubkey(),
+        &instruction,
+        &[AccountMeta::new_readonly(order_taker_ata, false)],
+    )
+    .unwrap();
+
+    let taker = env.taker.insecure_clone();
+    assert!(env.complete_swap(&taker).is_err());
+
+    env.deposit_taker_side(&taker, SwapInstruction::DepositTakerSide)
+        .unwrap();
+    assert_eq!(env.token_balance(&order_taker_ata), taker_amount);
+
+    // The taker leg is now paid out of the order's taker-mint escrow
+    let mut ix = env.complete_swap_ix(&taker.pubkey());
+    ix.accounts[3] = AccountMeta::new(order_taker_ata, false);
+    env.send(&[ix], &taker).unwrap();
+
+    let maker_receiving = env.ata(&env.maker.pubkey(), &env.taker_mint);
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&maker_receiving), taker_amount);
+    assert_eq!(env.token_balance(&taker_receiving), maker_amount);
+    assert_eq!(env.token_balance(&order_taker_ata), 0);
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_declined_order_closed_by_crank() {
+    let mut env = SwapEnv::new();
+    let maker_amount = 100_000u64;
+    env.initialize_order(&env.taker.pubkey(), maker_amount, 200_000)
+        .unwrap();
+
+    // Only the designated taker may decline
+    let stranger = Keypair::new();
+    env.svm.airdrop(&stranger.pubkey(), 1_000_000_000).unwrap();
+    assert!(env.decline_order(&stranger).is_err());
+
+    let taker = env.taker.insecure_clone();
+    env.decline_order(&taker).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Declined);
+    assert!(env.complete_swap(&taker).is_err());
+
+    // A crank closes the order, routing tokens and rent back to the maker
+    let maker_ata = env.ata(&env.maker.pubkey(), &env.maker_mint);
+    let maker_tokens_before = env.token_balance(&maker_ata);
+    let maker_lamports_before = env.svm.get_account(&env.maker.pubkey()).unwrap().lamports;
+
+    let ix = env.close_order_ix(&stranger.pubkey());
+    env.send(&[ix], &stranger).unwrap();
+
+    assert_eq!(
+        env.token_balance(&maker_ata),
+        maker_tokens_before + maker_amount
+    );
+    assert!(env.svm.get_account(&env.maker.pubkey()).unwrap().lamports > maker_lamports_before);
+}
+
+#[test]
+fn test_pause_and_resume_order() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 200_000)
+        .unwrap();
+
+    env.maker_order_ix(&SwapInstruction::PauseOrder).unwrap();
+    assert!(env.order().paused);
+    assert!(env.maker_order_ix(&SwapInstruction::PauseOrder).is_err());
+
+    let taker = env.taker.insecure_clone();
+    assert!(env.complete_swap(&taker).is_err());
+
+    env.maker_order_ix(&SwapInstruction::ResumeOrder).unwrap();
+    assert!(!env.order().paused);
+    env.complete_swap(&taker).unwrap();
+}
+
+#[test]
+fn test_update_expiry() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 200_000)
+        .unwrap();
+
+    let now = env.svm.get_sysvar::<Clock>().unix_timestamp;
+    assert!(env
+        .maker_order_ix(&SwapInstruction::UpdateExpiry {
+            new_expires_at: now - 1
+        })
+        .is_err());
+
+    env.maker_order_ix(&SwapInstruction::UpdateExpiry {
+        new_expires_at: now + 100,
+    })
+    .unwrap();
+    assert_eq!({ env.order().expires_at }, now + 100);
+
+    // Once the expiry passes the order can no longer be filled
+    let mut clock = env.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = now + 100;
+    env.svm.set_sysvar(&clock);
+
+    let taker = env.taker.insecure_clone();
+    assert!(env.complete_swap(&taker).is_err());
+}
+
+#[test]
+fn test_dutch_auction_price_decay() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 200_000)
+        .unwrap();
+
+    // The floor must sit below the starting taker amount
+    assert!(env
+        .maker_order_ix(&SwapInstruction::StartDutchAuction {
+            floor_taker_amount: 200_000,
+            duration: 100,
+        })
+        .is_err());
+
+    env.maker_order_ix(&SwapInstruction::StartDutchAuction {
+        floor_taker_amount: 100_000,
+        duration: 100,
+    })
+    .unwrap();
+    let order = env.order();
+    assert_eq!(order.auction_end_ts - order.auction_start_ts, 100);
+
+    // Halfway through the auction the taker pays the midpoint
+    let mut clock = env.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = order.auction_start_ts + 50;
+    env.svm.set_sysvar(&clock);
+
+    let taker = env.taker.insecure_clone();
+    env.complete_swap(&taker).unwrap();
+
+    let maker_receiving = env.ata(&env.maker.pubkey(), &env.taker_mint);
+    assert_eq!(env.token_balance(&maker_receiving), 150_000);
+}
+
+#[test]
+fn test_english_auction() {
+    let mut env = SwapEnv::new();
+    env.create_order_taker_ata();
+    env.initialize_order(&Pubkey::default(), 100_000, 150_000)
+        .unwrap();
+    env.maker_order_ix(&SwapInstruction::StartEnglishAuction { duration: 60 })
+        .unwrap();
+
+    // A second bidder with their own taker-mint tokens
+    let rival = Keypair::new();
+    env.svm.airdrop(&rival.pubkey(), 1_000_000_000).unwrap();
+    let maker = env.maker.insecure_clone();
+    let taker_mint = env.taker_mint;
+    let rival_ata = env.ata(&rival.pubkey(), &taker_mint);
+    let taker_ata = env.ata(&env.taker.pubkey(), &taker_mint);
+    let fund_rival = vec![
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &maker.pubkey(),
+            &rival.pubkey(),
+            &taker_mint,
+            &spl_token::id(),
+        ),
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &maker.pubkey(),
+            &rival.pubkey(),
+            &env.maker_mint,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &taker_mint,
+            &rival_ata,
+            &maker.pubkey(),
+            &[],
+            500_000,
+        )
+        .unwrap(),
+    ];
+    env.send(&fund_rival, &maker).unwrap();
+
+    let taker = env.taker.insecure_clone();
+    // Bids below the reserve are rejected
+    assert!(env.place_bid(&taker, 100_000, None).is_err());
+    env.place_bid(&taker, 160_000, None).unwrap();
+
+    // Outbidding refunds the previous leader
+    assert!(env
+        .place_bid(&rival, 160_000, Some(&taker.pubkey()))
+        .is_err());
+    env.place_bid(&rival, 180_000, Some(&taker.pubkey()))
+        .unwrap();
+    assert_eq!(env.token_balance(&taker_ata), 1_000_000);
+    assert_eq!({ env.order().best_bid }, 180_000);
+    assert_eq!(env.order().accepted_taker, rival.pubkey());
+
+    // The leading bid locks the order until it settles
+    let close_ix = env.close_order_ix(&maker.pubkey());
+    assert_eq!(
+        swap_error(env.send(&[close_ix], &maker)),
+        Some(SwapError::InvalidOrderState)
+    );
+    assert_eq!(
+        swap_error(env.change_taker(&taker.pubkey())),
+        Some(SwapError::InvalidOrderState)
+    );
+    assert_eq!(
+        swap_error(env.maker_order_ix(&SwapInstruction::UpdateOrder {
+            new_maker_amount: None,
+            new_taker_amount: None,
+            new_taker: Some(taker.pubkey()),
+            expires_at: None,
+        })),
+        Some(SwapError::InvalidOrderState)
+    );
+
+    // Settlement waits for the deadline
+    assert!(env.settle_auction(&taker, &rival.pubkey()).is_err());
+    let mut clock = env.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = env.order().auction_end_ts;
+    env.svm.set_sysvar(&clock);
+
+    // A blocked mint holds the settlement back
+    env.initialize_config(0).unwrap();
+    env.set_mint_blocked(&taker_mint, true).unwrap();
+    assert_eq!(
+        swap_error(env.settle_auction(&taker, &rival.pubkey())),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&taker_mint, false).unwrap();
+
+    env.settle_auction(&taker, &rival.pubkey()).unwrap();
+    let maker_receiving = env.ata(&env.maker.pubkey(), &taker_mint);
+    let rival_receiving = env.ata(&rival.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&maker_receiving), 180_000);
+    assert_eq!(env.token_balance(&rival_receiving), 100_000);
+    // The settlement is booked as the order's one fill, at the clearing price
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::Completed);
+    assert_eq!({ order.filled_amount }, 100_000);
+    assert_eq!({ order.fill_count }, 1);
+    assert_eq!({ order.taker_amount }, 180_000);
+}
+
+#[test]
+fn test_fill_quote() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let rfq_authority =
+        Pubkey::find_program_address(&[b"rfq", maker.pubkey().as_ref()], &PROGRAM_KEY).0;
+    let maker_sending = env.ata(&maker.pubkey(), &env.maker_mint);
+    let maker_receiving = env.ata(&maker.pubkey(), &env.taker_mint);
+    let taker_sending = env.ata(&taker.pubkey(), &env.taker_mint);
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+
+    // The maker lets the RFQ authority move their tokens once per signed quote
+    let approve_ix = spl_token::instruction::approve(
+        &spl_token::id(),
+        &maker_sending,
+        &rfq_authority,
+        &maker.pubkey(),
+        &[],
+        500_000,
+    )
+    .unwrap();
+    env.send(&[approve_ix], &maker).unwrap();
+
+    let quote = Quote {
+        maker: maker.pubkey(),
+        taker: taker.pubkey(),
+        maker_token_mint: env.maker_mint,
+        taker_token_mint: env.taker_mint,
+        maker_amount: 100_000,
+        taker_amount: 150_000,
+        expires_at: env.svm.get_sysvar::<Clock>().unix_timestamp + 60,
+        nonce: 7,
+    };
+    let nonce_pda = Pubkey::find_program_address(
+        &[
+            b"quote",
+            maker.pubkey().as_ref(),
+            &quote.nonce.to_le_bytes(),
+        ],
+        &PROGRAM_KEY,
+    )
+    .0;
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+    let fill_ix = |quote: &Quote| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(nonce_pda, false),
+            AccountMeta::new(maker_sending, false),
+            AccountMeta::new(maker_receiving, false),
+            AccountMeta::new(taker_sending, false),
+            AccountMeta::new(taker_receiving, false),
+            AccountMeta::new_readonly(rfq_authority, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(taker_mint, false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint), false),
+        ],
+        data: SwapInstruction::FillQuote {
+            quote: quote.clone(),
+        }
+        .pack(),
+    };
+    let message = borsh::to_vec(&quote).unwrap();
+
+    // Missing signature instruction, a tampered quote and a foreign signer all fail
+    assert!(env.send(&[fill_ix(&quote)], &taker).is_err());
+    let mut tampered = quote.clone();
+    tampered.maker_amount = 200_000;
+    assert!(env
+        .send(&[ed25519_ix(&maker, &message), fill_ix(&tampered)], &taker)
+        .is_err());
+    assert!(env
+        .send(&[ed25519_ix(&taker, &message), fill_ix(&quote)], &taker)
+        .is_err());
+
+    // A blocked maker mint refuses the quote
+    env.initialize_config(0).unwrap();
+    env.set_mint_blocked(&maker_mint, true).unwrap();
+    assert_eq!(
+        swap_error(env.send(&[ed25519_ix(&maker, &message), fill_ix(&quote)], &taker)),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&maker_mint, false).unwrap();
+
+    // As are quotes the trade guards refuse as orders
+    let mut same_mint = quote.clone();
+    same_mint.taker_token_mint = maker_mint;
+    let same_mint_message = borsh::to_vec(&same_mint).unwrap();
+    assert_eq!(
+        swap_error(env.send(
+            &[ed25519_ix(&maker, &same_mint_message), fill_ix(&same_mint)],
+            &taker
+        )),
+        Some(SwapError::SameMint)
+    );
+
+    env.send(&[ed25519_ix(&maker, &message), fill_ix(&quote)], &taker)
+        .unwrap();
+    assert_eq!(env.token_balance(&maker_receiving), 150_000);
+    assert_eq!(env.token_balance(&taker_receiving), 100_000);
+    assert_eq!(env.token_balance(&maker_sending), 900_000);
+
+    // The nonce marker blocks replays
+    assert!(env
+        .send(&[ed25519_ix(&maker, &message), fill_ix(&quote)], &taker)
+        .is_err());
+}
+
+/// Writes a fully verified Pyth `PriceUpdateV2` account into the SVM
+fn set_pyth_price(svm: &mut LiteSVM, address: &Pubkey, price: i64, conf: u64, publish_time: i64) {
+    let mut data = vec![34, 241, 35, 99, 157, 126, 244, 205];
+    data.extend_from_slice(&[0; 32]); // write_authority
+    data.push(1); // VerificationLevel::Full
+    data.extend_from_slice(&[7; 32]); // feed_id
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&conf.to_le_bytes());
+    data.extend_from_slice(&(-8i32).to_le_bytes());
+    data.extend_from_slice(&publish_time.to_le_bytes());
+    data.extend_from_slice(&[0; 8 * 4]); // prev_publish_time, ema_price, ema_conf, posted_slot
+
+    svm.set_account(
+        *address,
+        solana_sdk::account::Account {
+            lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: splerg_p2p::oracle::PYTH_RECEIVER_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_oracle_priced_order() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 1)
+        .unwrap();
+
+    let price_feed = Pubkey::new_unique();
+    let now = env.svm.get_sysvar::<Clock>().unix_timestamp;
+    // 1.5 taker tokens per maker token
+    set_pyth_price(&mut env.svm, &price_feed, 150_000_000, 10_000, now);
+
+    let maker = env.maker.insecure_clone();
+    let set_oracle_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(env.order_pda(), false),
+            AccountMeta::new_readonly(price_feed, false),
+        ],
+        data: SwapInstruction::SetPriceOracle { spread_bps: 100 }.pack(),
+    };
+    env.send(&[set_oracle_ix], &maker).unwrap();
+    assert_eq!(env.order().price_feed, price_feed);
+
+    let taker = env.taker.insecure_clone();
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    // The price account is required
+    assert!(env.send(&[fill_ix.clone()], &taker).is_err());
+    fill_ix
+        .accounts
+        .push(AccountMeta::new_readonly(price_feed, false));
+
+    // Stale prices and wide confidence intervals are rejected
+    set_pyth_price(&mut env.svm, &price_feed, 150_000_000, 10_000, now - 120);
+    assert!(env.send(&[fill_ix.clone()], &taker).is_err());
+    set_pyth_price(&mut env.svm, &price_feed, 150_000_000, 5_000_000, now);
+    assert!(env.send(&[fill_ix.clone()], &taker).is_err());
+
+    set_pyth_price(&mut env.svm, &price_feed, 150_000_000, 10_000, now);
+    env.send(&[fill_ix], &taker).unwrap();
+
+    // 100_000 * 1.5 plus a 1% spread
+    let maker_receiving = env.ata(&maker.pubkey(), &env.taker_mint);
+    assert_eq!(env.token_balance(&maker_receiving), 151_500);
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_complete_swap_slippage_bounds() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    let taker = env.taker.insecure_clone();
+    let fill_ix = |env: &SwapEnv, max_taker_amount, min_maker_amount| {
+        let mut ix = env.complete_swap_ix(&taker.pubkey());
+        ix.data = SwapInstruction::CompleteSwap {
+            fill_amount: 0,
+            max_taker_amount,
+            min_maker_amount,
+            taker_proof: vec![],
+            memo: None,
+        }
+        .pack();
+        ix
+    };
+
+    // The taker's bounds are checked against the stored amounts
+    let ix = fill_ix(&env, 149_999, 100_000);
+    assert!(env.send(&[ix], &taker).is_err());
+    let ix = fill_ix(&env, 150_000, 100_001);
+    assert!(env.send(&[ix], &taker).is_err());
+
+    let ix = fill_ix(&env, 150_000, 100_000);
+    env.send(&[ix], &taker).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_partial_fill_and_all_or_none() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    let taker = env.taker.insecure_clone();
+    let fill_ix = |env: &SwapEnv, fill_amount| {
+        let mut ix = env.complete_swap_ix(&taker.pubkey());
+        ix.data = SwapInstruction::CompleteSwap {
+            fill_amount,
+            max_taker_amount: u64::MAX,
+            min_maker_amount: 0,
+            taker_proof: vec![],
+            memo: None,
+        }
+        .pack();
+        ix
+    };
+    let maker_receiving = env.ata(&env.maker.pubkey(), &env.taker_mint);
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+
+    // A partial fill pays pro rata and leaves the remainder open
+    let ix = fill_ix(&env, 40_000);
+    env.send(&[ix], &taker).unwrap();
+    assert_eq!(env.token_balance(&maker_receiving), 60_000);
+    assert_eq!(env.token_balance(&taker_receiving), 40_000);
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::Open);
+    assert_eq!({ order.maker_amount }, 60_000);
+    assert_eq!({ order.taker_amount }, 90_000);
+
+    // Once all-or-none is set only the full remaining size can be taken
+    env.maker_order_ix(&SwapInstruction::SetAllOrNone { all_or_none: true })
+        .unwrap();
+    let ix = fill_ix(&env, 30_000);
+    assert!(env.send(&[ix], &taker).is_err());
+    let ix = fill_ix(&env, 60_000);
+    env.send(&[ix], &taker).unwrap();
+    assert_eq!(env.token_balance(&maker_receiving), 150_000);
+    assert_eq!(env.token_balance(&taker_receiving), 100_000);
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_complete_swap_and_close() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    let taker = env.taker.insecure_clone();
+    let order_pda = env.order_pda();
+    let escrow = env.ata(&order_pda, &env.maker_mint);
+    let maker_lamports = env.svm.get_account(&env.maker.pubkey()).unwrap().lamports;
+
+    let mut ix = env.complete_swap_ix(&taker.pubkey());
+    ix.accounts
+        .insert(0, AccountMeta::new(env.maker.pubkey(), false));
+    ix.data = SwapInstruction::CompleteSwapAndClose {
+        max_taker_amount: 150_000,
+        min_maker_amount: 100_000,
+    }
+    .pack();
+    env.send(&[ix], &taker).unwrap();
+
+    let maker_receiving = env.ata(&env.maker.pubkey(), &env.taker_mint);
+    assert_eq!(env.token_balance(&maker_receiving), 150_000);
+    // Both the order and its escrow are gone and their rent went to the maker
+    assert!(env
+        .svm
+        .get_account(&order_pda)
+        .is_none_or(|account| account.lamports == 0));
+    assert!(env
+        .svm
+        .get_account(&escrow)
+        .is_none_or(|account| account.lamports == 0));
+    assert!(env.svm.get_account(&env.maker.pubkey()).unwrap().lamports > maker_lamports);
+}
+
+#[test]
+fn test_complete_swap_chain() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let (mint_x, mint_y) = (env.maker_mint, env.taker_mint);
+
+    // First hop: the maker sells X for Y
+    env.initialize_order(&Pubkey::default(), 100_000, 150_000)
+        .unwrap();
+    let first_order = env.order_pda();
+
+    // Second hop: another maker sells a third mint Z for X
+    let maker2 = Keypair::new();
+    env.svm.airdrop(&maker2.pubkey(), 10_000_000_000).unwrap();
+    let mint_z = env.create_mint();
+    env.fund(&maker2.pubkey(), &mint_z, 1_000_000);
+    env.fund(&maker2.pubkey(), &mint_x, 0);
+    env.fund(&taker.pubkey(), &mint_z, 0);
+
+    env.maker = maker2.insecure_clone();
+    env.maker_mint = mint_z;
+    env.taker_mint = mint_x;
+    env.create_order_ata();
+    env.initialize_order(&Pubkey::default(), 50_000, 80_000)
+        .unwrap();
+    let second_order = env.order_pda();
+
+    let hop = |order: Pubkey, maker: Pubkey, sells: Pubkey, wants: Pubkey| {
+        vec![
+            AccountMeta::new(order, false),
+            AccountMeta::new(get_associated_token_address(&maker, &wants), false),
+            AccountMeta::new(get_associated_token_address(&taker.pubkey(), &wants), false),
+            AccountMeta::new(get_associated_token_address(&taker.pubkey(), &sells), false),
+            AccountMeta::new(get_associated_token_address(&order, &sells), false),
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(fee_exemption_pda(&maker), false),
+            AccountMeta::new(user_stats_pda(&maker), false),
+            AccountMeta::new_readonly(sells, false),
+            AccountMeta::new_readonly(wants, false),
+            AccountMeta::new_readonly(blocked_mint_pda(&sells), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&wants), false),
+            AccountMeta::new(pair_stats_pda(&sells, &wants), false),
+        ]
+    };
+    let chain_ix = |max_taker_amount, min_maker_amount| {
+        let mut accounts = vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(user_stats_pda(&taker.pubkey()), false),
+            AccountMeta::new(protocol_stats_pda(), false),
+        ];
+        accounts.extend(hop(first_order, maker.pubkey(), mint_x, mint_y));
+        accounts.extend(hop(second_order, maker2.pubkey(), mint_z, mint_x));
+        Instruction {
+            program_id: PROGRAM_KEY,
+            accounts,
+            data: SwapInstruction::CompleteSwapChain {
+                max_taker_amount,
+                min_maker_amount,
+            }
+            .pack(),
+        }
+    };
+
+    // The end-to-end bounds apply to the first payment and the last delivery
+    assert!(env.send(&[chain_ix(149_999, 50_000)], &taker).is_err());
+    assert!(env.send(&[chain_ix(150_000, 50_001)], &taker).is_err());
+    env.send(&[chain_ix(150_000, 50_000)], &taker).unwrap();
+
+    // Y → X → Z, keeping the 20_000 X left over from the first hop
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &mint_y)),
+        850_000
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &mint_x)),
+        20_000
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &mint_z)),
+        50_000
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&maker.pubkey(), &mint_y)),
+        150_000
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&maker2.pubkey(), &mint_x)),
+        80_000
+    );
+}
+
+#[test]
+fn test_settle_ring() {
+    let mut env = SwapEnv::new();
+    let maker_a = env.maker.insecure_clone();
+    let maker_b = env.taker.insecure_clone();
+    let maker_c = Keypair::new();
+    env.svm.airdrop(&maker_c.pubkey(), 10_000_000_000).unwrap();
+    let (mint_x, mint_y) = (env.maker_mint, env.taker_mint);
+    let mint_z = env.create_mint();
+    env.fund(&maker_b.pubkey(), &mint_z, 0);
+    env.fund(&maker_c.pubkey(), &mint_z, 1_000_000);
+    env.fund(&maker_c.pubkey(), &mint_x, 0);
+
+    // A sells X for Y, B sells Y for Z, C sells Z for X
+    let mut create_order = |maker: &Keypair, sells: Pubkey, wants: Pubkey, amounts: (u64, u64)| {
+        env.maker = maker.insecure_clone();
+        env.maker_mint = sells;
+        env.taker_mint = wants;
+        if env
+            .svm
+            .get_account(&env.ata(&env.order_pda(), &sells))
+            .is_none()
+        {
+            env.create_order_ata();
+        }
+        env.initialize_order(&Pubkey::default(), amounts.0, amounts.1)
+            .unwrap();
+        env.order_pda()
+    };
+    let order_a = create_order(&maker_a, mint_x, mint_y, (100_000, 200_000));
+    let order_b = create_order(&maker_b, mint_y, mint_z, (200_000, 300_000));
+    let order_c = create_order(&maker_c, mint_z, mint_x, (300_000, 100_000));
+
+    let leg = |order: Pubkey, maker: &Keypair, sells: Pubkey, wants: Pubkey| {
+        vec![
+            AccountMeta::new(order, false),
+            AccountMeta::new(get_associated_token_address(&order, &sells), false),
+            AccountMeta::new(get_associated_token_address(&maker.pubkey(), &wants), false),
+            AccountMeta::new_readonly(sells, false),
+            AccountMeta::new_readonly(blocked_mint_pda(&sells), false),
+            AccountMeta::new(treasury_pda(&wants), false),
+            AccountMeta::new_readonly(fee_exemption_pda(&maker.pubkey()), false),
+            AccountMeta::new(user_stats_pda(&maker.pubkey()), false),
+        ]
+    };
+    let mut accounts = vec![
+        AccountMeta::new(maker_a.pubkey(), true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(config_pda(), false),
+    ];
+    accounts.extend(leg(order_a, &maker_a, mint_x, mint_y));
+    accounts.extend(leg(order_b, &maker_b, mint_y, mint_z));
+    accounts.extend(leg(order_c, &maker_c, mint_z, mint_x));
+    accounts.push(AccountMeta::new(protocol_stats_pda(), false));
+    let settle_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts,
+        data: SwapInstruction::SettleRing.pack(),
+    };
+    let init_stats_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker_a.pubkey(), true),
+            AccountMeta::new(protocol_stats_pda(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::InitializeProtocolStats.pack(),
+    };
+    env.send(&[init_stats_ix], &maker_a).unwrap();
+
+    // Blocking any mint of the ring stops the whole settlement
+    env.initialize_config(100).unwrap();
+    for mint in [mint_x, mint_y, mint_z] {
+        env.initialize_treasury(&mint).unwrap();
+    }
+    env.set_mint_blocked(&mint_z, true).unwrap();
+    assert_eq!(
+        swap_error(env.send(&[settle_ix.clone()], &maker_a)),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&mint_z, false).unwrap();
+
+    // Every mint must belong to the token program passed
+    let mut wrong_program_ix = settle_ix.clone();
+    wrong_program_ix.accounts[1] = AccountMeta::new_readonly(spl_token_2022::id(), false);
+    assert_eq!(
+        swap_error(env.send(&[wrong_program_ix], &maker_a)),
+        Some(SwapError::InvalidTokenProgram)
+    );
+
+    env.send(&[settle_ix], &maker_a).unwrap();
+
+    // Each maker receives its taker amount less the 1% protocol fee
+    for (maker, mint, amount) in [
+        (&maker_a, mint_y, 200_000),
+        (&maker_b, mint_z, 300_000),
+        (&maker_c, mint_x, 100_000),
+    ] {
+        assert_eq!(
+            env.token_balance(&env.ata(&maker.pubkey(), &mint)),
+            amount - amount / 100
+        );
+        assert_eq!(env.token_balance(&treasury_pda(&mint)), amount / 100);
+    }
+    // Each order is booked as a fill; the settler's own order closes on it
+    assert!(env.svm.get_account(&order_a).is_none());
+    for (order, maker_amount) in [(order_b, 200_000), (order_c, 300_000)] {
+        let order = SwapOrder::unpack(&env.svm.get_account(&order).unwrap().data).unwrap();
+        assert_eq!(order.status, OrderStatus::Completed);
+        assert_eq!({ order.filled_amount }, maker_amount);
+        assert_eq!({ order.fill_count }, 1);
+    }
+    let stats =
+        ProtocolStats::try_from_slice(&env.svm.get_account(&protocol_stats_pda()).unwrap().data)
+            .unwrap();
+    assert_eq!(stats.fills, 3);
+    assert_eq!(stats.maker_volume, 600_000);
+    assert_eq!(stats.taker_volume, 600_000);
+}
+
+#[test]
+fn test_htlc_claim_and_refund() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let preimage = [42u8; 32];
+    let hashlock = solana_program::hash::hash(&preimage).to_bytes();
+    let timeout = env.svm.get_sysvar::<Clock>().unix_timestamp + 3_600;
+
+    env.initialize_order(&taker.pubkey(), 100_000, 1).unwrap();
+    env.maker_order_ix(&SwapInstruction::StartHtlc { hashlock, timeout })
+        .unwrap();
+
+    // The maker cannot pull the escrow while the taker can still claim it
+    let close_ix = env.close_order_ix(&maker.pubkey());
+    assert!(env.send(&[close_ix], &maker).is_err());
+
+    let order_pda = env.order_pda();
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+    let claim_ix = |preimage: [u8; 32]| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(
+                get_associated_token_address(&taker.pubkey(), &maker_mint),
+                false,
+            ),
+            AccountMeta::new(get_associated_token_address(&order_pda, &maker_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint), false),
+        ],
+        data: SwapInstruction::ClaimHtlc { preimage }.pack(),
+    };
+    assert!(env.send(&[claim_ix([0; 32])], &taker).is_err());
+
+    // Even a correct preimage cannot release a blocked mint
+    env.initialize_config(0).unwrap();
+    env.set_mint_blocked(&maker_mint, true).unwrap();
+    assert_eq!(
+        swap_error(env.send(&[claim_ix(preimage)], &taker)),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&maker_mint, false).unwrap();
+
+    env.send(&[claim_ix(preimage)], &taker).unwrap();
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&taker_receiving), 100_000);
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::Completed);
+    assert_eq!({ order.filled_amount }, 100_000);
+    assert_eq!({ order.fill_count }, 1);
+
+    // A second HTLC that is never claimed refunds to the maker after the timeout
+    env.order_id = 1;
+    env.create_order_ata();
+    env.initialize_order(&taker.pubkey(), 50_000, 1).unwrap();
+    env.maker_order_ix(&SwapInstruction::StartHtlc { hashlock, timeout })
+        .unwrap();
+    let mut clock = env.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = timeout;
+    env.svm.set_sysvar(&clock);
+
+    let order_pda = env.order_pda();
+    let late_claim_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(taker_receiving, false),
+            AccountMeta::new(env.ata(&order_pda, &env.maker_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+        ],
+        data: SwapInstruction::ClaimHtlc { preimage }.pack(),
+    };
+    assert!(env.send(&[late_claim_ix], &taker).is_err());
+
+    let close_ix = env.close_order_ix(&maker.pubkey());
+    env.send(&[close_ix], &maker).unwrap();
+    let maker_ata = env.ata(&maker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&maker_ata), 1_000_000 - 100_000);
+}
+
+#[test]
+fn test_vesting_claims() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.maker_order_ix(&SwapInstruction::SetVesting {
+        cliff: 100,
+        duration: 1_000,
+    })
+    .unwrap();
+
+    // The fill pays the maker but leaves the maker side in escrow
+    env.complete_swap(&taker).unwrap();
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&taker_receiving), 0);
+    let start = env.order().vesting_start_ts;
+
+    let order_pda = env.order_pda();
+    let claim_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(taker_receiving, false),
+            AccountMeta::new(env.ata(&order_pda, &env.maker_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+        ],
+        data: SwapInstruction::ClaimVested.pack(),
+    };
+    let set_time = |env: &mut SwapEnv, unix_timestamp| {
+        let mut clock = env.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = unix_timestamp;
+        env.svm.set_sysvar(&clock);
+    };
+
+    // Nothing vests before the cliff
+    set_time(&mut env, start + 50);
+    assert!(env.send(&[claim_ix.clone()], &taker).is_err());
+
+    set_time(&mut env, start + 500);
+    env.send(&[claim_ix.clone()], &taker).unwrap();
+    assert_eq!(env.token_balance(&taker_receiving), 50_000);
+
+    // The maker cannot close the order while tokens are still owed
+    let close_ix = env.close_order_ix(&maker.pubkey());
+    assert!(env.send(&[close_ix.clone()], &maker).is_err());
+
+    set_time(&mut env, start + 1_000);
+    env.send(&[claim_ix], &taker).unwrap();
+    assert_eq!(env.token_balance(&taker_receiving), 100_000);
+
+    // The drained escrow closes with the order
+    let escrow = env.ata(&env.order_pda(), &env.maker_mint);
+    env.send(&[close_ix], &maker).unwrap();
+    assert!(env
+        .svm
+        .get_account(&escrow)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_streaming_tranches() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.create_order_taker_ata();
+    let order_pda = env.order_pda();
+    let order_taker_ata = env.ata(&order_pda, &env.taker_mint);
+    let instruction = SwapInstruction::InitializeOrder {
+        order_id: env.order_id,
+        maker_amount: 90_000,
+        taker_amount: 120_000,
+        requires_confirmation: false,
+        dual_escrow: true,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    };
+    env.initialize_with_accounts(
+        &taker.pubkey(),
+        &instruction,
+        &[AccountMeta::new_readonly(order_taker_ata, false)],
+    )
+    .unwrap();
+    env.maker_order_ix(&SwapInstruction::SetStreaming {
+        tranches: 3,
+        interval: 100,
+    })
+    .unwrap();
+    env.deposit_taker_side(&taker, SwapInstruction::DepositTakerSide)
+        .unwrap();
+    let start = env.order().stream_start_ts;
+
+    let maker_receiving = env.ata(&maker.pubkey(), &env.taker_mint);
+    let taker_receiving = env.ata(&taker.pubkey(), &env.maker_mint);
+    let settle_ix = |caller: &Pubkey| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(*caller, true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(
+                get_associated_token_address(&order_pda, &env.maker_mint),
+                false,
+            ),
+            AccountMeta::new(order_taker_ata, false),
+            AccountMeta::new(maker_receiving, false),
+            AccountMeta::new(taker_receiving, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+        ],
+        data: SwapInstruction::SettleTranche.pack(),
+    };
+    let first_settle = settle_ix(&taker.pubkey());
+    let maker_settle = settle_ix(&maker.pubkey());
+    let set_time = |env: &mut SwapEnv, unix_timestamp| {
+        let mut clock = env.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = unix_timestamp;
+        env.svm.set_sysvar(&clock);
+    };
+
+    // No tranche is due until the first interval has passed
+    assert!(env.send(&[first_settle.clone()], &taker).is_err());
+
+    set_time(&mut env, start + 100);
+
+    // A blocked mint holds back even a due tranche
+    env.initialize_config(0).unwrap();
+    let taker_mint = env.taker_mint;
+    env.set_mint_blocked(&taker_mint, true).unwrap();
+    assert_eq!(
+        swap_error(env.send(&[first_settle.clone()], &taker)),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&taker_mint, false).unwrap();
+
+    env.send(&[first_settle.clone()], &taker).unwrap();
+    assert_eq!(env.token_balance(&maker_receiving), 40_000);
+    assert_eq!(env.token_balance(&taker_receiving), 30_000);
+    assert!(env.send(&[first_settle], &taker).is_err());
+
+    // Missed tranches are released together, by either party
+    set_time(&mut env, start + 300);
+    env.send(&[maker_settle], &maker).unwrap();
+    assert_eq!(env.token_balance(&maker_receiving), 120_000);
+    assert_eq!(env.token_balance(&taker_receiving), 90_000);
+    // Each settlement is booked as a fill, the order keeping its full amounts
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::Completed);
+    assert_eq!({ order.filled_amount }, 90_000);
+    assert_eq!({ order.fill_count }, 2);
+    assert_eq!({ order.maker_amount }, 90_000);
+}
+
+#[test]
+fn test_recurring_order_renewal() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.maker_order_ix(&SwapInstruction::SetRecurrence {
+        interval: 604_800,
+        renewals: 1,
+    })
+    .unwrap();
+
+    // The maker funds future renewals through a delegation to the order PDA
+    let order_pda = env.order_pda();
+    let maker_ata = env.ata(&maker.pubkey(), &env.maker_mint);
+    let escrow = env.ata(&order_pda, &env.maker_mint);
+    let approve_ix = spl_token::instruction::approve(
+        &spl_token::id(),
+        &maker_ata,
+        &order_pda,
+        &maker.pubkey(),
+        &[],
+        100_000,
+    )
+    .unwrap();
+    env.send(&[approve_ix], &maker).unwrap();
+
+    env.complete_swap(&taker).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+
+    let renew_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(maker_ata, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+        ],
+        data: SwapInstruction::RenewOrder.pack(),
+    };
+    assert!(env.send(&[renew_ix.clone()], &taker).is_err());
+
+    let mut clock = env.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = env.order().next_renewal_ts;
+    env.svm.set_sysvar(&clock);
+    env.send(&[renew_ix.clone()], &taker).unwrap();
+
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::Open);
+    assert_eq!({ order.maker_amount }, 100_000);
+    assert_eq!({ order.taker_amount }, 150_000);
+    assert_eq!(env.token_balance(&escrow), 100_000);
+    assert_eq!(env.token_balance(&maker_ata), 1_000_000 - 200_000);
+
+    // The last renewal has been used up
+    env.complete_swap(&taker).unwrap();
+    clock.unix_timestamp = env.order().next_renewal_ts;
+    env.svm.set_sysvar(&clock);
+    assert!(env.send(&[renew_ix], &taker).is_err());
+}
+
+#[test]
+fn test_clone_order() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    let source = env.order_pda();
+    let clone = |order_id, amounts: Option<(u64, u64)>| SwapInstruction::CloneOrder {
+        order_id,
+        maker_amount: amounts.map(|amounts| amounts.0),
+        taker_amount: amounts.map(|amounts| amounts.1),
+    };
+
+    // A partial fill leaves the source with only what remained of its terms
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 40_000,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!({ env.order().maker_amount }, 60_000);
+
+    env.order_id = 1;
+    env.create_order_ata();
+    // The clone must keep the source order's counterparty
+    assert!(env
+        .initialize_with_accounts(
+            &Pubkey::new_unique(),
+            &clone(1, None),
+            &[AccountMeta::new_readonly(source, false)],
+        )
+        .is_err());
+    // Explicit amounts override the ones partial fills left on the source
+    env.initialize_with_accounts(
+        &taker.pubkey(),
+        &clone(1, Some((100_000, 150_000))),
+        &[AccountMeta::new_readonly(source, false)],
+    )
+    .unwrap();
+
+    let order = env.order();
+    assert_eq!({ order.order_id }, 1);
+    assert_eq!(order.status, OrderStatus::Open);
+    assert_eq!(order.taker, taker.pubkey());
+    assert_eq!({ order.maker_amount }, 100_000);
+    assert_eq!({ order.taker_amount }, 150_000);
+    assert_eq!(
+        env.token_balance(&env.ata(&env.order_pda(), &env.maker_mint)),
+        100_000
+    );
+
+    // The final fill closes the source, whose receipt it can still be cloned from
+    env.order_id = 0;
+    let receipt = fill_receipt_pda(&source, 1);
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts.extend([
+        AccountMeta::new(receipt, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(maker.pubkey(), false),
+    ]);
+    env.send(&[fill_ix], &taker).unwrap();
+    assert!(env
+        .svm
+        .get_account(&source)
+        .is_none_or(|account| account.lamports == 0));
+
+    env.order_id = 2;
+    env.create_order_ata();
+    assert!(env
+        .initialize_with_accounts(
+            &Pubkey::new_unique(),
+            &clone(2, None),
+            &[AccountMeta::new_readonly(receipt, false)],
+        )
+        .is_err());
+    // Without explicit amounts the clone repeats the fill the receipt records
+    env.initialize_with_accounts(
+        &Pubkey::default(),
+        &clone(2, None),
+        &[AccountMeta::new_readonly(receipt, false)],
+    )
+    .unwrap();
+    let order = env.order();
+    assert_eq!(order.taker, Pubkey::default());
+    assert_eq!({ order.maker_amount }, 60_000);
+    assert_eq!({ order.taker_amount }, 90_000);
+}
+
+#[test]
+fn test_transfer_order_authority() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    let order_pda = env.order_pda();
+
+    let new_maker = Keypair::new();
+    env.svm.airdrop(&new_maker.pubkey(), 1_000_000_000).unwrap();
+    let maker_mint = env.maker_mint;
+    let new_maker_ata = env.ata(&new_maker.pubkey(), &maker_mint);
+    env.send(
+        &[
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &new_maker.pubkey(),
+                &new_maker.pubkey(),
+                &maker_mint,
+                &spl_token::id(),
+            ),
+        ],
+        &new_maker,
+    )
+    .unwrap();
+
+    env.maker_order_ix(&SwapInstruction::TransferOrderAuthority {
+        new_maker: new_maker.pubkey(),
+    })
+    .unwrap();
+    assert_eq!(env.order().maker, new_maker.pubkey());
+
+    // The original maker no longer controls the order
+    let close_ix = env.close_order_ix(&env.maker.pubkey());
+    let maker = env.maker.insecure_clone();
+    assert!(env.send(&[close_ix], &maker).is_err());
+
+    let close_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(new_maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(env.ata(&order_pda, &maker_mint), false),
+            AccountMeta::new(new_maker_ata, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new(new_maker.pubkey(), false),
+            // The order stays in the index of the maker it was created by
+            AccountMeta::new(maker_index_pda(&env.maker.pubkey()), false),
+        ],
+        data: SwapInstruction::CloseOrder { memo: None }.pack(),
+    };
+    env.send(&[close_ix], &new_maker).unwrap();
+    assert_eq!(env.token_balance(&new_maker_ata), 100_000);
+    assert!(env
+        .svm
+        .get_account(&order_pda)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_order_delegate() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    let order_pda = env.order_pda();
+    let maker_mint = env.maker_mint;
+    let taker_mint = env.taker_mint;
+    let maker_ata = env.ata(&env.maker.pubkey(), &maker_mint);
+    let escrow = env.ata(&order_pda, &maker_mint);
+
+    let delegate = Keypair::new();
+    env.svm.airdrop(&delegate.pubkey(), 1_000_000_000).unwrap();
+    let change_amounts_ix = |authority: &Pubkey| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(maker_ata, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(taker_mint, false),
+        ],
+        data: SwapInstruction::ChangeOrderAmounts {
+            new_maker_amount: 60_000,
+            new_taker_amount: 90_000,
+        }
+        .pack(),
+    };
+
+    // Not a delegate yet
+    assert!(env
+        .send(&[change_amounts_ix(&delegate.pubkey())], &delegate)
+        .is_err());
+
+    env.maker_order_ix(&SwapInstruction::SetDelegate {
+        delegate: delegate.pubkey(),
+    })
+    .unwrap();
+    let maker_balance = env.token_balance(&maker_ata);
+    env.send(&[change_amounts_ix(&delegate.pubkey())], &delegate)
+        .unwrap();
+    assert_eq!({ env.order().maker_amount }, 60_000);
+    assert_eq!(env.token_balance(&maker_ata), maker_balance + 40_000);
+
+    // A delegate closes the order, with the refund and rent going to the maker
+    let close_ix = env.close_order_ix(&delegate.pubkey());
+    env.send(&[close_ix], &delegate).unwrap();
+    assert_eq!(env.token_balance(&maker_ata), maker_balance + 100_000);
+    assert!(env
+        .svm
+        .get_account(&order_pda)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_allowed_takers() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    let desk = Keypair::new();
+    let outsider = Keypair::new();
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+    for wallet in [&desk, &outsider] {
+        env.svm.airdrop(&wallet.pubkey(), 1_000_000_000).unwrap();
+        env.fund(&wallet.pubkey(), &taker_mint, 150_000);
+        env.fund(&wallet.pubkey(), &maker_mint, 0);
+    }
+
+    assert!(env
+        .maker_order_ix(&SwapInstruction::SetAllowedTakers {
+            takers: vec![Pubkey::new_unique(); MAX_ALLOWED_TAKERS + 1],
+        })
+        .is_err());
+    env.maker_order_ix(&SwapInstruction::SetAllowedTakers {
+        takers: vec![desk.pubkey()],
+    })
+    .unwrap();
+    assert_eq!(env.order().allowed_takers[0], desk.pubkey());
+
+    assert!(env.complete_swap(&outsider).is_err());
+    env.complete_swap(&desk).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+    assert_eq!(
+        env.token_balance(&env.ata(&desk.pubkey(), &maker_mint)),
+        100_000
+    );
+}
+
+#[test]
+fn test_merkle_taker_allowlist() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&Pubkey::default(), 100_000, 150_000)
+        .unwrap();
+
+    let members: Vec<Keypair> = (0..5).map(|_| Keypair::new()).collect();
+    let allowlist: Vec<Pubkey> = members.iter().map(|member| member.pubkey()).collect();
+    let root = merkle_root(&allowlist);
+    for (index, taker) in allowlist.iter().enumerate() {
+        let proof = merkle_proof(&allowlist, index).unwrap();
+        assert!(verify_merkle_proof(&root, merkle_leaf(taker), &proof));
+    }
+    assert!(merkle_proof(&allowlist, allowlist.len()).is_none());
+
+    env.maker_order_ix(&SwapInstruction::SetTakerMerkleRoot { root })
+        .unwrap();
+    assert!(!env.order().accepts_any_taker());
+
+    let member = &members[4];
+    let outsider = Keypair::new();
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+    for wallet in [member, &outsider] {
+        env.svm.airdrop(&wallet.pubkey(), 1_000_000_000).unwrap();
+        env.fund(&wallet.pubkey(), &taker_mint, 150_000);
+        env.fund(&wallet.pubkey(), &maker_mint, 0);
+    }
+
+    let fill_ix = |env: &SwapEnv, taker: &Pubkey, taker_proof: Vec<[u8; 32]>| {
+        let mut ix = env.complete_swap_ix(taker);
+        ix.data = SwapInstruction::CompleteSwap {
+            fill_amount: 0,
+            max_taker_amount: u64::MAX,
+            min_maker_amount: 0,
+            taker_proof,
+            memo: None,
+        }
+        .pack();
+        ix
+    };
+
+    // Someone else's proof does not work for the outsider
+    let member_proof = merkle_proof(&allowlist, 4).unwrap();
+    let ix = fill_ix(&env, &outsider.pubkey(), member_proof.clone());
+    assert!(env.send(&[ix], &outsider).is_err());
+
+    let ix = fill_ix(&env, &member.pubkey(), member_proof);
+    env.send(&[ix], member).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_token_gated_order() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    let gate_mint = env.create_mint();
+    env.maker_order_ix(&SwapInstruction::SetTokenGate {
+        gate_mint,
+        min_balance: 1,
+    })
+    .unwrap();
+    let gate_account = env.ata(&taker.pubkey(), &gate_mint);
+    env.fund(&taker.pubkey(), &gate_mint, 0);
+
+    let gated_fill = |env: &SwapEnv| {
+        let mut ix = env.complete_swap_ix(&taker.pubkey());
+        ix.accounts
+            .push(AccountMeta::new_readonly(gate_account, false));
+        ix
+    };
+
+    // Without the gating account, or without a balance in it, the fill fails
+    let ix = env.complete_swap_ix(&taker.pubkey());
+    assert!(env.send(&[ix], &taker).is_err());
+    let ix = gated_fill(&env);
+    assert!(env.send(&[ix], &taker).is_err());
+
+    env.fund(&taker.pubkey(), &gate_mint, 1);
+    let ix = gated_fill(&env);
+    env.send(&[ix], &taker).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_commit_reveal_fill() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&Pubkey::default(), 100_000, 150_000)
+        .unwrap();
+    env.maker_order_ix(&SwapInstruction::SetCommitReveal { enabled: true })
+        .unwrap();
+    let order_pda = env.order_pda();
+
+    // Direct fills are refused
+    assert!(env.complete_swap(&taker).is_err());
+
+    let salt = [7; 32];
+    let commit_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+        ],
+        data: SwapInstruction::CommitFill {
+            commitment: fill_commitment(&taker.pubkey(), &salt),
+        }
+        .pack(),
+    };
+    env.send(&[commit_ix], &taker).unwrap();
+
+    let reveal_ix = |env: &SwapEnv, salt| {
+        let mut ix = env.complete_swap_ix(&taker.pubkey());
+        ix.data = SwapInstruction::RevealFill {
+            salt,
+            fill_amount: 0,
+            max_taker_amount: u64::MAX,
+            min_maker_amount: 0,
+        }
+        .pack();
+        ix
+    };
+
+    // Revealing in the commitment's own slot is too early
+    let ix = reveal_ix(&env, salt);
+    assert!(env.send(&[ix], &taker).is_err());
+
+    let slot = env.svm.get_sysvar::<Clock>().slot;
+    env.svm.warp_to_slot(slot + 1);
+    let ix = reveal_ix(&env, [8; 32]);
+    assert!(env.send(&[ix], &taker).is_err());
+    let ix = reveal_ix(&env, salt);
+    env.send(&[ix], &taker).unwrap();
+
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::Completed);
+    assert_eq!(order.fill_commitment, [0; 32]);
+    assert_eq!(order.revealed_taker, Pubkey::default());
+}
+
+#[test]
+fn test_secret_claim_gift_link() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&Pubkey::default(), 100_000, 150_000)
+        .unwrap();
+    let secret = [42; 32];
+    let start_ix = SwapInstruction::StartSecretClaim {
+        hashlock: solana_program::hash::hash(&secret).to_bytes(),
+        taker_amount: 0,
+    };
+
+    // Whoever saw the preimage could claim an open link, so the link designates the
+    // key that claims it
+    assert_eq!(
+        swap_error(env.maker_order_ix(&start_ix)),
+        Some(SwapError::IncompatibleOrderOptions)
+    );
+    let claimer = Keypair::new();
+    env.change_taker(&claimer.pubkey()).unwrap();
+    env.maker_order_ix(&start_ix).unwrap();
+    let order_pda = env.order_pda();
+
+    env.svm.airdrop(&claimer.pubkey(), 1_000_000_000).unwrap();
+    let maker_mint = env.maker_mint;
+    env.fund(&claimer.pubkey(), &maker_mint, 0);
+    let claimer_ata = env.ata(&claimer.pubkey(), &maker_mint);
+
+    // Secret-claim orders cannot be filled as regular swaps
+    assert!(env.complete_swap(&claimer).is_err());
+
+    let claim_ix = |env: &SwapEnv, claimer: &Keypair, preimage| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(claimer.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(claimer_ata, false),
+            AccountMeta::new(env.ata(&order_pda, &maker_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+        ],
+        data: SwapInstruction::ClaimSecret { preimage }.pack(),
+    };
+    let ix = claim_ix(&env, &claimer, [41; 32]);
+    assert!(env.send(&[ix], &claimer).is_err());
+
+    // A front-runner replaying the revealed preimage cannot sign as the link's key
+    let front_runner = env.taker.insecure_clone();
+    let mut ix = claim_ix(&env, &front_runner, secret);
+    ix.accounts[2] = AccountMeta::new(env.ata(&front_runner.pubkey(), &maker_mint), false);
+    assert_eq!(
+        swap_error(env.send(&[ix], &front_runner)),
+        Some(SwapError::UnauthorizedSigner)
+    );
+
+    // The link stops working while its mint is blocked
+    env.initialize_config(0).unwrap();
+    env.set_mint_blocked(&maker_mint, true).unwrap();
+    let ix = claim_ix(&env, &claimer, secret);
+    assert_eq!(
+        swap_error(env.send(&[ix], &claimer)),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&maker_mint, false).unwrap();
+
+    let ix = claim_ix(&env, &claimer, secret);
+    env.send(&[ix], &claimer).unwrap();
+
+    assert_eq!(env.token_balance(&claimer_ata), 100_000);
+    let order = env.order();
+    assert_eq!(order.status, OrderStatus::Completed);
+    assert_eq!(order.accepted_taker, claimer.pubkey());
+}
+
+#[test]
+fn test_referral_fee() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    assert!(env
+        .maker_order_ix(&SwapInstruction::SetReferralFee {
+            referral_bps: MAX_REFERRAL_BPS + 1,
+        })
+        .is_err());
+
+    // The maker names the referrer along with the cut
+    let referrer = Pubkey::new_unique();
+    let maker = env.maker.insecure_clone();
+    let set_referral_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(env.order_pda(), false),
+            AccountMeta::new_readonly(referrer, false),
+        ],
+        data: SwapInstruction::SetReferralFee { referral_bps: 250 }.pack(),
+    };
+    env.send(&[set_referral_ix], &maker).unwrap();
+    assert_eq!(env.order().referrer, referrer);
+
+    let maker_mint = env.maker_mint;
+    env.fund(&referrer, &maker_mint, 0);
+    let referrer_ata = env.ata(&referrer, &maker_mint);
+
+    // The taker cannot leave the referrer out or route the cut to themselves
+    let ix = env.complete_swap_ix(&taker.pubkey());
+    assert!(env.send(&[ix], &taker).is_err());
+    let mut ix = env.complete_swap_ix(&taker.pubkey());
+    ix.accounts.push(AccountMeta::new(
+        env.ata(&taker.pubkey(), &maker_mint),
+        false,
+    ));
+    assert_eq!(
+        swap_error(env.send(&[ix], &taker)),
+        Some(SwapError::WrongTokenAccountOwner)
+    );
+
+    let mut ix = env.complete_swap_ix(&taker.pubkey());
+    ix.accounts.push(AccountMeta::new(referrer_ata, false));
+    env.send(&[ix], &taker).unwrap();
+
+    assert_eq!(env.token_balance(&referrer_ata), 2_500);
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &maker_mint)),
+        97_500
+    );
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_protocol_fee() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    // Only the upgrade authority can initialize the config, and only once
+    let intruder = Keypair::new();
+    env.svm.airdrop(&intruder.pubkey(), 1_000_000_000).unwrap();
+    let program_data = set_upgrade_authority(&mut env.svm, &env.maker.pubkey());
+    let ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(intruder.pubkey(), true),
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new_readonly(program_data, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::InitializeConfig {
+            fee_bps: 0,
+            fee_collector: intruder.pubkey(),
+        }
+        .pack(),
+    };
+    assert!(env.send(&[ix], &intruder).is_err());
+    env.initialize_config(100).unwrap();
+    assert!(env.initialize_config(100).is_err());
+
+    let fee_collector = env.fee_collector;
+    let update_ix = |admin: &Pubkey, fee_bps| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_pda(), false),
+        ],
+        data: SwapInstruction::UpdateConfig {
+            fee_bps,
+            fee_collector,
+        }
+        .pack(),
+    };
+    let ix = update_ix(&intruder.pubkey(), 0);
+    assert!(env.send(&[ix], &intruder).is_err());
+    let ix = update_ix(&env.maker.pubkey(), 200);
+    let maker = env.maker.insecure_clone();
+    env.send(&[ix], &maker).unwrap();
+    let config = Config::try_from_slice(&env.svm.get_account(&config_pda()).unwrap().data).unwrap();
+    assert_eq!(config.admin, env.maker.pubkey());
+    assert_eq!(config.fee_bps, 200);
+
+    let taker_mint = env.taker_mint;
+    env.initialize_treasury(&taker_mint).unwrap();
+    env.complete_swap(&taker).unwrap();
+
+    // 2% of the taker side goes to the treasury instead of the maker
+    assert_eq!(env.token_balance(&treasury_pda(&taker_mint)), 3_000);
+    assert_eq!(
+        env.token_balance(&env.ata(&env.maker.pubkey(), &taker_mint)),
+        147_000
+    );
+
+    // Only the admin can pay the treasury out, and only to the fee collector
+    env.fund(&fee_collector, &taker_mint, 0);
+    env.fund(&intruder.pubkey(), &taker_mint, 0);
+    let withdraw_ix = |admin: &Pubkey, destination: Pubkey| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(treasury_pda(&taker_mint), false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(taker_mint, false),
+        ],
+        data: SwapInstruction::WithdrawFees { amount: 0 }.pack(),
+    };
+    let ix = withdraw_ix(&intruder.pubkey(), env.ata(&intruder.pubkey(), &taker_mint));
+    assert!(env.send(&[ix], &intruder).is_err());
+    let ix = withdraw_ix(&maker.pubkey(), env.ata(&maker.pubkey(), &taker_mint));
+    assert!(env.send(&[ix], &maker).is_err());
+    let ix = withdraw_ix(&maker.pubkey(), env.ata(&fee_collector, &taker_mint));
+    env.send(&[ix], &maker).unwrap();
+    assert_eq!(env.token_balance(&treasury_pda(&taker_mint)), 0);
+    assert_eq!(
+        env.token_balance(&env.ata(&fee_collector, &taker_mint)),
+        3_000
+    );
+}
+
+#[test]
+fn test_token_2022_treasury() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    env.initialize_config(0).unwrap();
+
+    // Accounts of a transfer-fee mint carry a `TransferFeeAmount` extension
+    let mint = Keypair::new();
+    let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+    let create_mint_ixs = [
+        solana_sdk::system_instruction::create_account(
+            &maker.pubkey(),
+            &mint.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(mint_len),
+            mint_len as u64,
+            &spl_token_2022::id(),
+        ),
+        spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            None,
+            None,
+            0,
+            0,
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            &maker.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &create_mint_ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    let mint = mint.pubkey();
+
+    let ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(treasury_pda(&mint), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::InitializeTreasury.pack(),
+    };
+    env.send(&[ix], &maker).unwrap();
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &mint,
+        &treasury_pda(&mint),
+        &maker.pubkey(),
+        &[],
+        5_000,
+    )
+    .unwrap();
+    env.send(&[mint_ix], &maker).unwrap();
+
+    let fee_collector = env.fee_collector;
+    let destination = create_token_2022_account(&mut env, &fee_collector, &mint);
+    let ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(treasury_pda(&mint), false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(mint, false),
+        ],
+        data: SwapInstruction::WithdrawFees { amount: 0 }.pack(),
+    };
+    env.send(&[ix], &maker).unwrap();
+    assert_eq!(env.token_balance(&treasury_pda(&mint)), 0);
+    assert_eq!(env.token_balance(&destination), 5_000);
+}
+
+#[test]
+fn test_protocol_pause() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.initialize_config(0).unwrap();
+
+    let pause_ix = |admin: &Pubkey, paused| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_pda(), false),
+        ],
+        data: SwapInstruction::SetProtocolPaused { paused }.pack(),
+    };
+
+    // Only the admin can pause the protocol
+    let intruder = Keypair::new();
+    env.svm.airdrop(&intruder.pubkey(), 1_000_000_000).unwrap();
+    let ix = pause_ix(&intruder.pubkey(), true);
+    assert!(env.send(&[ix], &intruder).is_err());
+    env.send(&[pause_ix(&maker.pubkey(), true)], &maker)
+        .unwrap();
+    let config = Config::try_from_slice(&env.svm.get_account(&config_pda()).unwrap().data).unwrap();
+    assert!(config.paused);
+
+    // No new orders and no fills while paused
+    env.order_id = 1;
+    env.create_order_ata();
+    assert!(env
+        .initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .is_err());
+    env.order_id = 0;
+    let fill_ix = env.complete_swap_ix(&taker.pubkey());
+    assert!(env.send(&[fill_ix], &taker).is_err());
+
+    env.send(&[pause_ix(&maker.pubkey(), false)], &maker)
+        .unwrap();
+    env.complete_swap(&taker).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+
+    // Makers can still withdraw from open orders while paused
+    env.order_id = 1;
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.send(&[pause_ix(&maker.pubkey(), true)], &maker)
+        .unwrap();
+    let close_ix = env.close_order_ix(&maker.pubkey());
+    env.send(&[close_ix], &maker).unwrap();
+    assert!(env
+        .svm
+        .get_account(&env.order_pda())
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_pause_leaves_claims_open() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let preimage = [42u8; 32];
+    let hashlock = solana_program::hash::hash(&preimage).to_bytes();
+    let timeout = env.svm.get_sysvar::<Clock>().unix_timestamp + 3_600;
+    env.initialize_order(&taker.pubkey(), 100_000, 1).unwrap();
+    env.maker_order_ix(&SwapInstruction::StartHtlc { hashlock, timeout })
+        .unwrap();
+    env.initialize_config(0).unwrap();
+    let pause_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(config_pda(), false),
+        ],
+        data: SwapInstruction::SetProtocolPaused { paused: true }.pack(),
+    };
+    env.send(&[pause_ix], &maker).unwrap();
+
+    // The taker can still claim what the HTLC holds for them
+    let order_pda = env.order_pda();
+    let claim_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(env.ata(&taker.pubkey(), &env.maker_mint), false),
+            AccountMeta::new(env.ata(&order_pda, &env.maker_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+        ],
+        data: SwapInstruction::ClaimHtlc { preimage }.pack(),
+    };
+    env.send(&[claim_ix], &taker).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+
+    // Only opening and filling orders is paused
+    assert!(SwapInstruction::ConfirmSwap.is_pausable());
+    assert!(SwapInstruction::SettleRing.is_pausable());
+    assert!(SwapInstruction::SettleAuction.is_pausable());
+    for instruction in [
+        SwapInstruction::ClaimHtlc { preimage },
+        SwapInstruction::ClaimSecret { preimage },
+        SwapInstruction::ClaimVested,
+        SwapInstruction::SettleTranche,
+        SwapInstruction::CloseOrder { memo: None },
+        SwapInstruction::CancelAcceptance,
+        SwapInstruction::DepositTakerSide,
+    ] {
+        assert!(!instruction.is_pausable(), "{instruction:?}");
+    }
+}
+
+#[test]
+fn test_older_config_layout() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_config(0).unwrap();
+
+    // A config written before the pause and trade guards existed lacks their 3 bytes
+    let mut account = env.svm.get_account(&config_pda()).unwrap();
+    account.data.truncate(Config::LEN - 3);
+    account.lamports = env
+        .svm
+        .minimum_balance_for_rent_exemption(account.data.len());
+    env.svm.set_account(config_pda(), account).unwrap();
+
+    // It still loads, unpaused and with both guards up
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    env.order_id = 1;
+    assert_eq!(
+        client::error::transaction_swap_error(
+            &env.initialize_order(&maker.pubkey(), 100, 200)
+                .unwrap_err()
+                .err
+        ),
+        Some(SwapError::SelfTrade)
+    );
+
+    // Its first change grows it, with the admin paying the rent through the system program
+    let mut pause_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(config_pda(), false),
+        ],
+        data: SwapInstruction::SetProtocolPaused { paused: true }.pack(),
+    };
+    assert!(env.send(&[pause_ix.clone()], &maker).is_err());
+    pause_ix
+        .accounts
+        .push(AccountMeta::new_readonly(system_program::id(), false));
+    env.send(&[pause_ix], &maker).unwrap();
+    let account = env.svm.get_account(&config_pda()).unwrap();
+    assert_eq!(account.data.len(), Config::LEN);
+    assert_eq!(
+        account.lamports,
+        env.svm.minimum_balance_for_rent_exemption(Config::LEN)
+    );
+    let config = Config::try_from_slice(&account.data).unwrap();
+    assert!(config.paused && !config.allow_self_trade && !config.allow_same_mint);
+    assert_eq!(config.admin, maker.pubkey());
+}
+
+#[test]
+fn test_trade_guards() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let taker_mint = env.taker_mint;
+
+    // Orders with the maker as taker or one mint on both sides are refused by default,
+    // before and after the config is initialized
+    for initialize_config in [false, true] {
+        if initialize_config {
+            env.initialize_config(0).unwrap();
+        }
+        assert_eq!(
+            swap_error(env.initialize_order(&maker.pubkey(), 100, 200)),
+            Some(SwapError::SelfTrade)
+        );
+        env.taker_mint = env.maker_mint;
+        assert_eq!(
+            swap_error(env.initialize_order(&taker.pubkey(), 100, 200)),
+            Some(SwapError::SameMint)
+        );
+        env.taker_mint = taker_mint;
+    }
+
+    let guards_ix = |admin: &Pubkey, allow_self_trade, allow_same_mint| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_pda(), false),
+        ],
+        data: SwapInstruction::SetTradeGuards {
+            allow_self_trade,
+            allow_same_mint,
+        }
+        .pack(),
+    };
+
+    // Only the admin can lift the guards
+    let intruder = Keypair::new();
+    env.svm.airdrop(&intruder.pubkey(), 1_000_000_000).unwrap();
+    let ix = guards_ix(&intruder.pubkey(), true, true);
+    assert!(env.send(&[ix], &intruder).is_err());
+
+    // Each guard is lifted on its own
+    env.send(&[guards_ix(&maker.pubkey(), true, false)], &maker)
+        .unwrap();
+    let config = Config::try_from_slice(&env.svm.get_account(&config_pda()).unwrap().data).unwrap();
+    assert!(config.allow_self_trade && !config.allow_same_mint);
+    env.initialize_order(&maker.pubkey(), 100, 200).unwrap();
+    assert_eq!({ env.order().taker }, maker.pubkey());
+
+    env.order_id = 1;
+    env.taker_mint = env.maker_mint;
+    assert_eq!(
+        swap_error(env.initialize_order(&taker.pubkey(), 100, 200)),
+        Some(SwapError::SameMint)
+    );
+    env.send(&[guards_ix(&maker.pubkey(), false, true)], &maker)
+        .unwrap();
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    let order = env.order();
+    assert_eq!({ order.maker_token_mint }, { order.taker_token_mint });
+
+    env.order_id = 2;
+    env.taker_mint = taker_mint;
+    assert_eq!(
+        swap_error(env.initialize_order(&maker.pubkey(), 100, 200)),
+        Some(SwapError::SelfTrade)
+    );
+
+    // Nor can an open order be turned into a self-trade by a new taker or maker
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    let change_taker_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(env.order_pda(), false),
+            AccountMeta::new_readonly(maker.pubkey(), false),
+        ],
+        data: SwapInstruction::ChangeTaker {
+            new_taker: maker.pubkey().to_bytes(),
+        }
+        .pack(),
+    };
+    assert_eq!(
+        swap_error(env.send(&[change_taker_ix], &maker)),
+        Some(SwapError::SelfTrade)
+    );
+    for instruction in [
+        SwapInstruction::UpdateOrder {
+            new_maker_amount: None,
+            new_taker_amount: None,
+            new_taker: Some(maker.pubkey()),
+            expires_at: None,
+        },
+        SwapInstruction::TransferOrderAuthority {
+            new_maker: taker.pubkey(),
+        },
+        SwapInstruction::SetAllowedTakers {
+            takers: vec![taker.pubkey(), maker.pubkey()],
+        },
+    ] {
+        assert_eq!(
+            swap_error(env.maker_order_ix(&instruction)),
+            Some(SwapError::SelfTrade)
+        );
+    }
+    assert_eq!({ env.order().taker }, taker.pubkey());
+}
+
+#[test]
+fn test_mint_blocklist() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.initialize_config(0).unwrap();
+
+    let taker_mint = env.taker_mint;
+    let blocklist_ix = |admin: &Pubkey, instruction: &SwapInstruction| {
+        let mut accounts = vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(blocked_mint_pda(&taker_mint), false),
+        ];
+        if matches!(instruction, SwapInstruction::BlockMint { .. }) {
+            accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        Instruction {
+            program_id: PROGRAM_KEY,
+            accounts,
+            data: instruction.pack(),
+        }
+    };
+    let block = SwapInstruction::BlockMint { mint: taker_mint };
+    let unblock = SwapInstruction::UnblockMint { mint: taker_mint };
+
+    // Only the admin manages the blocklist
+    let intruder = Keypair::new();
+    env.svm.airdrop(&intruder.pubkey(), 1_000_000_000).unwrap();
+    let ix = blocklist_ix(&intruder.pubkey(), &block);
+    assert!(env.send(&[ix], &intruder).is_err());
+    env.send(&[blocklist_ix(&maker.pubkey(), &block)], &maker)
+        .unwrap();
+
+    // A blocked mint can neither be listed nor filled, whichever side it is on
+    env.order_id = 1;
+    env.create_order_ata();
+    assert!(env
+        .initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .is_err());
+    env.order_id = 0;
+    let fill_ix = env.complete_swap_ix(&taker.pubkey());
+    assert!(env.send(&[fill_ix], &taker).is_err());
+
+    env.send(&[blocklist_ix(&maker.pubkey(), &unblock)], &maker)
+        .unwrap();
+    assert!(env
+        .send(&[blocklist_ix(&maker.pubkey(), &unblock)], &maker)
+        .is_err());
+    env.complete_swap(&taker).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_fee_exempt_maker() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.initialize_config(200).unwrap();
+
+    let maker = env.maker.insecure_clone();
+    let exemption_ix = |instruction: &SwapInstruction| {
+        let mut accounts = vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(fee_exemption_pda(&maker.pubkey()), false),
+        ];
+        if matches!(instruction, SwapInstruction::AddFeeExemption { .. }) {
+            accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        Instruction {
+            program_id: PROGRAM_KEY,
+            accounts,
+            data: instruction.pack(),
+        }
+    };
+    let add = exemption_ix(&SwapInstruction::AddFeeExemption {
+        account: maker.pubkey(),
+    });
+    env.send(&[add], &maker).unwrap();
+
+    // No fee is taken from an exempt maker's fills, so no fee account needs to exist
+    env.complete_swap(&taker).unwrap();
+    let taker_mint = env.taker_mint;
+    assert_eq!(
+        env.token_balance(&env.ata(&maker.pubkey(), &taker_mint)),
+        150_000
+    );
+
+    let remove = exemption_ix(&SwapInstruction::RemoveFeeExemption {
+        account: maker.pubkey(),
+    });
+    env.send(&[remove], &maker).unwrap();
+    assert!(env
+        .svm
+        .get_account(&fee_exemption_pda(&maker.pubkey()))
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_fee_mint() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.initialize_config(200).unwrap();
+
+    let maker = env.maker.insecure_clone();
+    let fee_mint = env.create_mint();
+    let set_fee_mint_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new_readonly(fee_mint, false),
+        ],
+        data: SwapInstruction::SetFeeMint { fee_mint }.pack(),
+    };
+    env.send(&[set_fee_mint_ix], &maker).unwrap();
+    let config = Config::try_from_slice(&env.svm.get_account(&config_pda()).unwrap().data).unwrap();
+    assert_eq!(config.fee_mint, fee_mint);
+    assert_eq!(config.fee_mint_decimals, 9);
+
+    // 0.5 fee-mint tokens per taker-mint token
+    let price_feed = Pubkey::new_unique();
+    let now = env.svm.get_sysvar::<Clock>().unix_timestamp;
+    set_pyth_price(&mut env.svm, &price_feed, 50_000_000, 10_000, now);
+    let fee_feed =
+        Pubkey::find_program_address(&[b"fee_feed", env.taker_mint.as_ref()], &PROGRAM_KEY).0;
+    let set_fee_feed_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(fee_feed, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::SetFeeFeed { price_feed }.pack(),
+    };
+    env.send(&[set_fee_feed_ix], &maker).unwrap();
+
+    env.initialize_treasury(&fee_mint).unwrap();
+    env.fund(&taker.pubkey(), &fee_mint, 10_000);
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[11] = AccountMeta::new(treasury_pda(&fee_mint), false);
+    // The fee feed, its price, the taker's fee-mint account and the fee mint are required
+    assert!(env.send(&[fill_ix.clone()], &taker).is_err());
+    fill_ix.accounts.extend([
+        AccountMeta::new_readonly(fee_feed, false),
+        AccountMeta::new_readonly(price_feed, false),
+        AccountMeta::new(env.ata(&taker.pubkey(), &fee_mint), false),
+        AccountMeta::new_readonly(fee_mint, false),
+    ]);
+    env.send(&[fill_ix], &taker).unwrap();
+
+    // The maker receives the full taker side; the 3_000 fee is paid as 1_500 fee-mint tokens
+    assert_eq!(
+        env.token_balance(&env.ata(&env.maker.pubkey(), &env.taker_mint)),
+        150_000
+    );
+    assert_eq!(env.token_balance(&treasury_pda(&fee_mint)), 1_500);
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &fee_mint)),
+        8_500
+    );
+}
+
+#[test]
+fn test_volume_fee_tiers() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.initialize_config(200).unwrap();
+
+    let maker = env.maker.insecure_clone();
+    let set_tiers_ix = |tiers: Vec<FeeTier>| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(config_pda(), false),
+        ],
+        data: SwapInstruction::SetFeeTiers { tiers }.pack(),
+    };
+    // Tiers must ascend
+    let unordered = vec![
+        FeeTier {
+            min_volume: 200_000,
+            fee_bps: 100,
+        },
+        FeeTier {
+            min_volume: 100_000,
+            fee_bps: 50,
+        },
+    ];
+    assert!(env.send(&[set_tiers_ix(unordered)], &maker).is_err());
+    let tiers = vec![FeeTier {
+        min_volume: 100_000,
+        fee_bps: 50,
+    }];
+    env.send(&[set_tiers_ix(tiers)], &maker).unwrap();
+
+    let init_stats_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(user_stats_pda(&maker.pubkey()), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::InitializeUserStats {
+            user: maker.pubkey(),
+        }
+        .pack(),
+    };
+    env.send(&[init_stats_ix], &maker).unwrap();
+
+    let taker_mint = env.taker_mint;
+    env.initialize_treasury(&taker_mint).unwrap();
+    env.complete_swap(&taker).unwrap();
+
+    // The first fill pays the base rate and counts toward the maker's volume
+    assert_eq!(env.token_balance(&treasury_pda(&taker_mint)), 3_000);
+    let stats_account = env
+        .svm
+        .get_account(&user_stats_pda(&maker.pubkey()))
+        .unwrap();
+    assert_eq!(
+        UserStats::try_from_slice(&stats_account.data)
+            .unwrap()
+            .volume,
+        150_000
+    );
+
+    // The next fill is charged at the maker's new tier
+    env.order_id = 1;
+    env.create_order_ata();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    env.complete_swap(&taker).unwrap();
+    assert_eq!(env.token_balance(&treasury_pda(&taker_mint)), 3_750);
+}
+
+#[test]
+fn test_protocol_stats() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    // Anyone can start tracking; fills before that go uncounted
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+    let init_ix = |stats: Pubkey, instruction: SwapInstruction| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(stats, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction.pack(),
+    };
+    let init_protocol = init_ix(
+        protocol_stats_pda(),
+        SwapInstruction::InitializeProtocolStats,
+    );
+    let init_pair = init_ix(
+        pair_stats_pda(&maker_mint, &taker_mint),
+        SwapInstruction::InitializePairStats {
+            maker_mint,
+            taker_mint,
+        },
+    );
+    env.send(&[init_protocol.clone(), init_pair], &taker)
+        .unwrap();
+    assert!(env.send(&[init_protocol], &taker).is_err());
+
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 40_000,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    env.send(&[fill_ix], &taker).unwrap();
+    env.complete_swap(&taker).unwrap();
+
+    // Both fills count, each once per side for the token program tallies
+    let stats =
+        ProtocolStats::try_from_slice(&env.svm.get_account(&protocol_stats_pda()).unwrap().data)
+            .unwrap();
+    assert_eq!(stats.fills, 2);
+    assert_eq!(stats.token_fills, 4);
+    assert_eq!(stats.token_2022_fills, 0);
+    assert_eq!(stats.maker_volume, 100_000);
+    assert_eq!(stats.taker_volume, 150_000);
+    let pair = PairStats::try_from_slice(
+        &env.svm
+            .get_account(&pair_stats_pda(&maker_mint, &taker_mint))
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(pair.maker_mint, maker_mint);
+    assert_eq!(pair.fills, 2);
+    assert_eq!(pair.maker_volume, 100_000);
+    assert_eq!(pair.taker_volume, 150_000);
+
+    // The stats of another pair are refused
+    env.order_id = 1;
+    env.create_order_ata();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[18] = AccountMeta::new(pair_stats_pda(&taker_mint, &maker_mint), false);
+    assert!(env.send(&[fill_ix], &taker).is_err());
+}
+
+#[test]
+fn test_order_memo() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    assert_eq!(env.order().memo_str(), "");
+
+    let memo_of = |text: &[u8]| {
+        let mut memo = [0; MEMO_LEN];
+        memo[..text.len()].copy_from_slice(text);
+        memo
+    };
+    env.maker_order_ix(&SwapInstruction::UpdateOrderMetadata {
+        memo: memo_of(b"deal #42"),
+    })
+    .unwrap();
+    assert_eq!(env.order().memo_str(), "deal #42");
+
+    // Memos must be UTF-8 and only the maker or delegate can change them
+    assert!(env
+        .maker_order_ix(&SwapInstruction::UpdateOrderMetadata {
+            memo: memo_of(&[0xff, 0xfe]),
+        })
+        .is_err());
+    let ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(env.order_pda(), false),
+        ],
+        data: SwapInstruction::UpdateOrderMetadata {
+            memo: memo_of(b"mine"),
+        }
+        .pack(),
+    };
+    assert!(env.send(&[ix], &taker).is_err());
+    assert_eq!(env.order().memo_str(), "deal #42");
+}
+
+#[test]
+fn test_order_tag() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    let tag = *b"OTC\0\0\0\0\0";
+    env.maker_order_ix(&SwapInstruction::SetOrderTag { tag })
+        .unwrap();
+    assert_eq!(env.order().tag, tag);
+
+    // Indexers can filter on the raw bytes at the fixed tag offset
+    let data = env.svm.get_account(&env.order_pda()).unwrap().data;
+    assert_eq!(
+        &data[SwapOrder::TAG_OFFSET..SwapOrder::TAG_OFFSET + TAG_LEN],
+        &tag
+    );
+}
+
+#[test]
+fn test_migrate_legacy_order() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    let order = env.order();
+
+    // Orders open with their discriminator
+    let order_pda = env.order_pda();
+    let mut account = env.svm.get_account(&order_pda).unwrap();
+    assert!(SwapOrder::has_discriminator(&account.data));
+
+    // Rewrite the order in the layout used before the discriminator and version byte
+    account.data.drain(..9);
+    account.data.truncate(SwapOrder::LEGACY_LEN);
+    account.lamports = env
+        .svm
+        .minimum_balance_for_rent_exemption(SwapOrder::LEGACY_LEN);
+    env.svm.set_account(order_pda, account).unwrap();
+
+    // Outdated orders are rejected until migrated
+    let tag = *b"DCA\0\0\0\0\0";
+    assert!(env
+        .maker_order_ix(&SwapInstruction::SetOrderTag { tag })
+        .is_err());
+
+    let taker = env.taker.insecure_clone();
+    let migrate_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::MigrateOrder.pack(),
+    };
+    env.send(&[migrate_ix.clone()], &taker).unwrap();
+    assert_eq!(env.order(), order);
+    let account = env.svm.get_account(&order_pda).unwrap();
+    assert_eq!(
+        account.lamports,
+        env.svm.minimum_balance_for_rent_exemption(SwapOrder::LEN)
+    );
+
+    // Current orders cannot be migrated again
+    assert!(env.send(&[migrate_ix], &taker).is_err());
+    env.maker_order_ix(&SwapInstruction::SetOrderTag { tag })
+        .unwrap();
+}
+
+#[test]
+fn test_native_sol_maker_side() {
+    let mut env = SwapEnv::new();
+    env.maker_mint = spl_token::native_mint::id();
+    let order_pda = env.order_pda();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    // The maker's wallet and the order PDA stand in for the token accounts
+    let mut init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(maker.pubkey(), false),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new_readonly(taker.pubkey(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+            AccountMeta::new(
+                registry_page_pda(&env.maker_mint, &env.taker_mint, 0),
+                false,
+            ),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: 0,
+            maker_amount: 2_000_000_000,
+            taker_amount: 150_000,
+            requires_confirmation: false,
+            dual_escrow: true,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    };
+    // Native orders cannot use dual escrow
+    assert!(env.send(&[init_ix.clone()], &maker).is_err());
+    init_ix.data = SwapInstruction::InitializeOrder {
+        order_id: 0,
+        maker_amount: 2_000_000_000,
+        taker_amount: 150_000,
+        requires_confirmation: false,
+        dual_escrow: false,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    }
+    .pack();
+    env.send(&[init_ix], &maker).unwrap();
+    let order_rent = env.svm.minimum_balance_for_rent_exemption(SwapOrder::LEN);
+    assert_eq!(
+        env.svm.get_account(&order_pda).unwrap().lamports,
+        order_rent + 2_000_000_000
+    );
+
+    // Half the SOL is paid straight into the taker's wallet
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[4] = AccountMeta::new(taker.pubkey(), false);
+    fill_ix.accounts[5] = AccountMeta::new(order_pda, false);
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 1_000_000_000,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    let taker_lamports = env.svm.get_account(&taker.pubkey()).unwrap().lamports;
+    env.send(&[fill_ix], &taker).unwrap();
+    let taker_gain = env.svm.get_account(&taker.pubkey()).unwrap().lamports - taker_lamports;
+    // Less the transaction fee
+    assert!(taker_gain > 999_000_000 && taker_gain < 1_000_000_000);
+    assert_eq!(
+        env.token_balance(&env.ata(&maker.pubkey(), &env.taker_mint)),
+        75_000
+    );
+
+    // Closing refunds the rest of the SOL along with the rent
+    let mut close_ix = env.close_order_ix(&maker.pubkey());
+    close_ix.accounts[2] = AccountMeta::new(order_pda, false);
+    close_ix.accounts[3] = AccountMeta::new(maker.pubkey(), false);
+    let maker_lamports = env.svm.get_account(&maker.pubkey()).unwrap().lamports;
+    env.send(&[close_ix], &maker).unwrap();
+    let maker_gain = env.svm.get_account(&maker.pubkey()).unwrap().lamports - maker_lamports;
+    assert!(maker_gain > order_rent + 999_000_000);
+}
+
+#[test]
+fn test_native_sol_taker_side() {
+    let mut env = SwapEnv::new();
+    env.taker_mint = spl_token::native_mint::id();
+    env.create_order_ata();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 1_500_000_000)
+        .unwrap();
+
+    // The taker pays lamports through the system program, straight to the maker's wallet.
+    // With the maker's wallet passed, the full fill also closes the order and its escrow.
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[2] = AccountMeta::new(maker.pubkey(), false);
+    fill_ix.accounts[3] = AccountMeta::new_readonly(system_program::id(), false);
+    let order_pda = env.order_pda();
+    let rent = env.svm.get_account(&order_pda).unwrap().lamports
+        + env
+            .svm
+            .get_account(&env.ata(&order_pda, &env.maker_mint))
+            .unwrap()
+            .lamports;
+    let maker_lamports = env.svm.get_account(&maker.pubkey()).unwrap().lamports;
+    env.send(&[fill_ix], &taker).unwrap();
+
+    assert_eq!(
+        env.svm.get_account(&maker.pubkey()).unwrap().lamports,
+        maker_lamports + 1_500_000_000 + rent
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &env.maker_mint)),
+        100_000
+    );
+    assert!(env
+        .svm
+        .get_account(&order_pda)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+/// Installs the native mint when the test validator does not ship it
+fn ensure_native_mint(svm: &mut LiteSVM) {
+    let native_mint = spl_token::native_mint::id();
+    if svm.get_account(&native_mint).is_some() {
+        return;
+    }
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        decimals: spl_token::native_mint::DECIMALS,
+        is_initialized: true,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    svm.set_account(
+        native_mint,
+        solana_sdk::account::Account {
+            lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_wrap_and_unwrap_sol() {
+    let mut env = SwapEnv::new();
+    ensure_native_mint(&mut env.svm);
+    let taker = env.taker.insecure_clone();
+    let wsol = get_wsol_pda(&PROGRAM_KEY, &taker.pubkey()).0;
+
+    // Wrapping creates the account on first use and tops it up afterwards
+    env.send(
+        &[wrap_sol(&PROGRAM_KEY, &taker.pubkey(), 1_000_000_000)],
+        &taker,
+    )
+    .unwrap();
+    assert_eq!(env.token_balance(&wsol), 1_000_000_000);
+    env.send(
+        &[wrap_sol(&PROGRAM_KEY, &taker.pubkey(), 500_000_000)],
+        &taker,
+    )
+    .unwrap();
+    assert_eq!(env.token_balance(&wsol), 1_500_000_000);
+
+    // Only the owner can unwrap
+    let mut steal_ix = unwrap_sol(&PROGRAM_KEY, &taker.pubkey());
+    steal_ix.accounts[0] = AccountMeta::new(env.maker.pubkey(), true);
+    let maker = env.maker.insecure_clone();
+    assert!(env.send(&[steal_ix], &maker).is_err());
+
+    let taker_lamports = env.svm.get_account(&taker.pubkey()).unwrap().lamports;
+    env.send(&[unwrap_sol(&PROGRAM_KEY, &taker.pubkey())], &taker)
+        .unwrap();
+    assert!(env.svm.get_account(&wsol).is_none_or(|a| a.lamports == 0));
+    let taker_gain = env.svm.get_account(&taker.pubkey()).unwrap().lamports - taker_lamports;
+    assert!(taker_gain > 1_500_000_000);
+
+    // Bracketed instructions spend wrapped SOL as a token, with the rest unwrapped after
+    env.fund(&env.maker.pubkey(), &spl_token::native_mint::id(), 0);
+    let maker_wsol = env.ata(&env.maker.pubkey(), &spl_token::native_mint::id());
+    let pay_ix = spl_token::instruction::transfer(
+        &spl_token::id(),
+        &wsol,
+        &maker_wsol,
+        &taker.pubkey(),
+        &[],
+        400_000_000,
+    )
+    .unwrap();
+    let ixs = wrap_sol_instructions(&PROGRAM_KEY, &taker.pubkey(), 1_000_000_000, &[pay_ix]);
+    env.send(&ixs, &taker).unwrap();
+    assert_eq!(env.token_balance(&maker_wsol), 400_000_000);
+    assert!(env.svm.get_account(&wsol).is_none_or(|a| a.lamports == 0));
+}
+
+/// Creates an extension-free Token-2022 account owned by `owner`
+fn create_token_2022_account(env: &mut SwapEnv, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let account = Keypair::new();
+    // Sized for whatever account extensions the mint's extensions require
+    let mint_data = env.svm.get_account(mint).unwrap().data;
+    let mint_state =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data).unwrap();
+    let account_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+        &ExtensionType::get_required_init_account_extensions(
+            &mint_state.get_extension_types().unwrap(),
+        ),
+    )
+    .unwrap();
+    let maker = env.maker.insecure_clone();
+    let ixs = [
+        solana_sdk::system_instruction::create_account(
+            &maker.pubkey(),
+            &account.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(account_len),
+            account_len as u64,
+            &spl_token_2022::id(),
+        ),
+        spl_token_2022::instruction::initialize_account3(
+            &spl_token_2022::id(),
+            &account.pubkey(),
+            mint,
+            owner,
+        )
+        .unwrap(),
+    ];
+    env.svm.expire_blockhash();
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &account],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    account.pubkey()
+}
+
+#[test]
+fn test_mixed_token_programs() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    // A Token-2022 maker mint against the classic taker mint
+    let mint = Keypair::new();
+    let mint_len = spl_token_2022::state::Mint::LEN;
+    let create_mint_ixs = [
+        solana_sdk::system_instruction::create_account(
+            &maker.pubkey(),
+            &mint.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(mint_len),
+            mint_len as u64,
+            &spl_token_2022::id(),
+        ),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            &maker.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &create_mint_ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    let maker_mint = mint.pubkey();
+    let taker_mint = env.taker_mint;
+    env.maker_mint = maker_mint;
+    let order_pda = env.order_pda();
+    let maker_account = create_token_2022_account(&mut env, &maker.pubkey(), &maker_mint);
+    let escrow = vault_pda(&order_pda, &maker_mint);
+    let taker_receiving = create_token_2022_account(&mut env, &taker.pubkey(), &maker_mint);
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &maker_mint,
+        &maker_account,
+        &maker.pubkey(),
+        &[],
+        100_000,
+    )
+    .unwrap();
+    env.send(&[mint_ix], &maker).unwrap();
+
+    let init_ix = |maker_token_program: Pubkey, taker_token_program: Pubkey| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(maker_account, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(taker.pubkey(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(maker_token_program, false),
+            AccountMeta::new_readonly(taker_token_program, false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+            AccountMeta::new(registry_page_pda(&maker_mint, &taker_mint, 0), false),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: 0,
+            maker_amount: 100_000,
+            taker_amount: 150_000,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    };
+    // Each mint is checked against its own program
+    for (maker_program, taker_program) in [
+        (spl_token::id(), spl_token::id()),
+        (spl_token_2022::id(), spl_token_2022::id()),
+        (spl_token::id(), spl_token_2022::id()),
+    ] {
+        let ix = init_ix(maker_program, taker_program);
+        assert!(env.send(&[ix], &maker).is_err());
+    }
+    let ix = init_ix(spl_token_2022::id(), spl_token::id());
+    env.send(&[ix], &maker).unwrap();
+    assert_eq!(env.order().maker_decimals, 6);
+
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[4] = AccountMeta::new(taker_receiving, false);
+    fill_ix.accounts[5] = AccountMeta::new(escrow, false);
+    // A single program for both legs no longer fits a mixed order
+    assert!(env.send(&[fill_ix.clone()], &taker).is_err());
+    fill_ix.accounts[6] = AccountMeta::new_readonly(spl_token_2022::id(), false);
+    env.send(&[fill_ix], &taker).unwrap();
+
+    assert_eq!(env.token_balance(&taker_receiving), 100_000);
+    assert_eq!(
+        env.token_balance(&env.ata(&maker.pubkey(), &taker_mint)),
+        150_000
+    );
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_transfers_check_mints() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+
+    // Every transfer is checked against the mint passed for its side
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts.swap(8, 9);
+    assert!(env.send(&[fill_ix], &taker).is_err());
+
+    let fill_ix = env.complete_swap_ix(&taker.pubkey());
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &env.maker_mint)),
+        100_000
+    );
+}
+
+#[test]
+fn test_transfer_fee_mint() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    // A Token-2022 maker mint withholding 1% of every transfer
+    let mint = Keypair::new();
+    let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+    let create_mint_ixs = [
+        solana_sdk::system_instruction::create_account(
+            &maker.pubkey(),
+            &mint.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(mint_len),
+            mint_len as u64,
+            &spl_token_2022::id(),
+        ),
+        spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            Some(&maker.pubkey()),
+            Some(&maker.pubkey()),
+            100,
+            u64::MAX,
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            &maker.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &create_mint_ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    let maker_mint = mint.pubkey();
+    let taker_mint = env.taker_mint;
+    env.maker_mint = maker_mint;
+    let order_pda = env.order_pda();
+    let maker_account = create_token_2022_account(&mut env, &maker.pubkey(), &maker_mint);
+    let escrow = vault_pda(&order_pda, &maker_mint);
+    let taker_receiving = create_token_2022_account(&mut env, &taker.pubkey(), &maker_mint);
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &maker_mint,
+        &maker_account,
+        &maker.pubkey(),
+        &[],
+        200_000,
+    )
+    .unwrap();
+    env.send(&[mint_ix], &maker).unwrap();
+
+    let init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(maker_account, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(taker.pubkey(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+            AccountMeta::new(registry_page_pda(&maker_mint, &taker_mint, 0), false),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: 0,
+            maker_amount: 100_000,
+            taker_amount: 150_000,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    };
+    env.send(&[init_ix], &maker).unwrap();
+
+    // The maker pays the fee on top, so the full maker amount sits in escrow
+    let order = env.order();
+    assert_eq!(env.token_balance(&escrow), 100_000);
+    assert_eq!({ order.maker_amount_gross }, 101_011);
+    assert_eq!(env.token_balance(&maker_account), 200_000 - 101_011);
+    assert_eq!({ order.taker_amount_net }, 150_000);
+
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[4] = AccountMeta::new(taker_receiving, false);
+    fill_ix.accounts[5] = AccountMeta::new(escrow, false);
+    fill_ix.accounts[6] = AccountMeta::new_readonly(spl_token_2022::id(), false);
+    // Harvesting the escrow's withheld fees on close writes to the mint
+    fill_ix.accounts[8] = AccountMeta::new(maker_mint, false);
+    fill_ix
+        .accounts
+        .insert(0, AccountMeta::new(maker.pubkey(), false));
+    let close_ix = |min_maker_amount| {
+        let mut ix = fill_ix.clone();
+        ix.data = SwapInstruction::CompleteSwapAndClose {
+            max_taker_amount: 150_000,
+            min_maker_amount,
+        }
+        .pack();
+        ix
+    };
+
+    // Slippage is measured on what the taker nets after the fee
+    assert!(env.send(&[close_ix(100_000)], &taker).is_err());
+    env.send(&[close_ix(99_000)], &taker).unwrap();
+    assert_eq!(env.token_balance(&taker_receiving), 99_000);
+    assert_eq!(
+        env.token_balance(&env.ata(&maker.pubkey(), &taker_mint)),
+        150_000
+    );
+    assert!(env
+        .svm
+        .get_account(&escrow)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_transfer_hook_accounts_required() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    // A Token-2022 maker mint whose transfers run a hook program
+    let hook_program = Pubkey::new_unique();
+    let mint = Keypair::new();
+    let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferHook,
+    ])
+    .unwrap();
+    let create_mint_ixs = [
+        solana_sdk::system_instruction::create_account(
+            &maker.pubkey(),
+            &mint.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(mint_len),
+            mint_len as u64,
+            &spl_token_2022::id(),
+        ),
+        spl_token_2022::extension::transfer_hook::instruction::initialize(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            Some(maker.pubkey()),
+            Some(hook_program),
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            &maker.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &create_mint_ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    let maker_mint = mint.pubkey();
+    let taker_mint = env.taker_mint;
+    env.maker_mint = maker_mint;
+    let order_pda = env.order_pda();
+    let maker_account = create_token_2022_account(&mut env, &maker.pubkey(), &maker_mint);
+    let escrow = vault_pda(&order_pda, &maker_mint);
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &maker_mint,
+        &maker_account,
+        &maker.pubkey(),
+        &[],
+        100_000,
+    )
+    .unwrap();
+    env.send(&[mint_ix], &maker).unwrap();
+
+    let init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(maker_account, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(taker.pubkey(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+            AccountMeta::new(registry_page_pda(&maker_mint, &taker_mint, 0), false),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: 0,
+            maker_amount: 100_000,
+            taker_amount: 150_000,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    };
+    // The hook program has to be among the trailing accounts for the transfer to resolve
+    assert!(env.send(&[init_ix], &maker).is_err());
+    assert!(env.svm.get_account(&order_pda).is_none());
+}
+
+#[test]
+fn test_ui_amount_order() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    // A Token-2022 maker mint accruing 50% a year
+    let mint = Keypair::new();
+    let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::InterestBearingConfig,
+    ])
+    .unwrap();
+    let create_mint_ixs = [
+        solana_sdk::system_instruction::create_account(
+            &maker.pubkey(),
+            &mint.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(mint_len),
+            mint_len as u64,
+            &spl_token_2022::id(),
+        ),
+        spl_token_2022::extension::interest_bearing_mint::instruction::initialize(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            Some(maker.pubkey()),
+            5_000,
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            &maker.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &create_mint_ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    let maker_mint = mint.pubkey();
+    let taker_mint = env.taker_mint;
+    env.maker_mint = maker_mint;
+    let order_pda = env.order_pda();
+    let maker_account = create_token_2022_account(&mut env, &maker.pubkey(), &maker_mint);
+    let escrow = vault_pda(&order_pda, &maker_mint);
+    let taker_receiving = create_token_2022_account(&mut env, &taker.pubkey(), &maker_mint);
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &maker_mint,
+        &maker_account,
+        &maker.pubkey(),
+        &[],
+        100_000,
+    )
+    .unwrap();
+    env.send(&[mint_ix], &maker).unwrap();
+
+    let init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(maker_account, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(taker.pubkey(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+            AccountMeta::new(registry_page_pda(&maker_mint, &taker_mint, 0), false),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: 0,
+            maker_amount: 100_000,
+            taker_amount: 150_000,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    };
+    env.send(&[init_ix.clone()], &maker).unwrap();
+
+    // No interest has accrued yet, so the amounts carry over unchanged
+    let set_ui_amounts_ix = |mints: [Pubkey; 2]| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new_readonly(mints[0], false),
+            AccountMeta::new_readonly(mints[1], false),
+        ],
+        data: SwapInstruction::SetUiAmounts { ui_amounts: true }.pack(),
+    };
+    assert!(env
+        .send(&[set_ui_amounts_ix([taker_mint, maker_mint])], &maker)
+        .is_err());
+    env.send(&[set_ui_amounts_ix([maker_mint, taker_mint])], &maker)
+        .unwrap();
+    let order = env.order();
+    assert!(order.ui_amounts);
+    assert_eq!({ order.maker_amount }, 100_000);
+    assert_eq!({ order.taker_amount }, 150_000);
+
+    // Order kinds settled outside CompleteSwap would move the UI amounts as raw ones
+    let hashlock = [7; 32];
+    for instruction in [
+        SwapInstruction::StartHtlc {
+            hashlock,
+            timeout: env.svm.get_sysvar::<Clock>().unix_timestamp + 3_600,
+        },
+        SwapInstruction::StartSecretClaim {
+            hashlock,
+            taker_amount: 0,
+        },
+        SwapInstruction::StartEnglishAuction { duration: 3_600 },
+    ] {
+        assert_eq!(
+            swap_error(env.maker_order_ix(&instruction)),
+            Some(SwapError::IncompatibleOrderOptions)
+        );
+    }
+
+    // A year later each raw token is worth more, so the quoted UI amount takes fewer
+    let mut clock = env.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += 365 * 24 * 60 * 60;
+    env.svm.set_sysvar(&clock);
+    let mint_data = env.svm.get_account(&maker_mint).unwrap().data;
+    let interest_config = *StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)
+        .unwrap()
+        .get_extension::<spl_token_2022::extension::interest_bearing_mint::InterestBearingConfig>()
+        .unwrap();
+    let expected_raw = interest_config
+        .try_ui_amount_into_amount("0.1", 6, clock.unix_timestamp)
+        .unwrap();
+    assert!(expected_raw < 100_000);
+
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[4] = AccountMeta::new(taker_receiving, false);
+    fill_ix.accounts[5] = AccountMeta::new(escrow, false);
+    fill_ix.accounts[6] = AccountMeta::new_readonly(spl_token_2022::id(), false);
+    env.send(&[fill_ix], &taker).unwrap();
+    // The program converts in fixed point, Token-2022 in floating point: they agree to
+    // the unit
+    let received = env.token_balance(&taker_receiving);
+    assert!(received.abs_diff(expected_raw) <= 1);
+    assert_eq!(
+        env.token_balance(&env.ata(&maker.pubkey(), &taker_mint)),
+        150_000
+    );
+    // The surplus stays in escrow for the maker
+    assert_eq!(env.token_balance(&escrow), 100_000 - received);
+
+    // A clone settles raw amounts, at what the source's UI amounts are worth now
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &maker_mint,
+        &maker_account,
+        &maker.pubkey(),
+        &[],
+        100_000,
+    )
+    .unwrap();
+    env.send(&[mint_ix], &maker).unwrap();
+    env.order_id = 1;
+    let clone_pda = env.order_pda();
+    let mut clone_ix = init_ix;
+    clone_ix.accounts[1] = AccountMeta::new(clone_pda, false);
+    clone_ix.accounts[3] = AccountMeta::new(vault_pda(&clone_pda, &maker_mint), false);
+    clone_ix
+        .accounts
+        .insert(12, AccountMeta::new_readonly(order_pda, false));
+    clone_ix.data = SwapInstruction::CloneOrder {
+        order_id: 1,
+        maker_amount: None,
+        taker_amount: None,
+    }
+    .pack();
+    env.send(&[clone_ix], &maker).unwrap();
+    let clone = env.order();
+    assert!(!clone.ui_amounts);
+    assert_eq!({ clone.maker_amount }, received);
+    assert_eq!({ clone.taker_amount }, 150_000);
+}
+
+/// Creates `owner`'s Token-2022 ATA for `mint`, paid by the maker
+fn create_token_2022_ata(env: &mut SwapEnv, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let maker = env.maker.insecure_clone();
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &maker.pubkey(),
+        owner,
+        mint,
+        &spl_token_2022::id(),
+    );
+    env.send(&[ix], &maker).unwrap();
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        owner,
+        mint,
+        &spl_token_2022::id(),
+    )
+}
+
+/// Creates a 6-decimal Token-2022 mint with the maker as mint authority. `extension_ixs`
+/// initializes `extensions` on the new mint before the mint itself.
+fn create_token_2022_mint(
+    env: &mut SwapEnv,
+    extensions: &[ExtensionType],
+    extension_ixs: impl FnOnce(&Pubkey) -> Vec<Instruction>,
+    freeze_authority: Option<&Pubkey>,
+) -> Pubkey {
+    let maker = env.maker.insecure_clone();
+    let mint = Keypair::new();
+    let mint_len =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions)
+            .unwrap();
+    let mut ixs = vec![solana_sdk::system_instruction::create_account(
+        &maker.pubkey(),
+        &mint.pubkey(),
+        env.svm.minimum_balance_for_rent_exemption(mint_len),
+        mint_len as u64,
+        &spl_token_2022::id(),
+    )];
+    ixs.extend(extension_ixs(&mint.pubkey()));
+    ixs.push(
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            &maker.pubkey(),
+            freeze_authority,
+            6,
+        )
+        .unwrap(),
+    );
+    env.svm.expire_blockhash();
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    mint.pubkey()
+}
+
+/// Switches `env` to a Token-2022 maker mint and returns an `InitializeOrder` escrowing
+/// `maker_amount` of it from a funded maker account into the order PDA's ATA
+fn token_2022_order_ix(env: &mut SwapEnv, maker_mint: Pubkey, maker_amount: u64) -> Instruction {
+    let maker = env.maker.insecure_clone();
+    env.maker_mint = maker_mint;
+    let order_pda = env.order_pda();
+    let maker_account = create_token_2022_account(env, &maker.pubkey(), &maker_mint);
+    let escrow = create_token_2022_ata(env, &order_pda, &maker_mint);
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &maker_mint,
+        &maker_account,
+        &maker.pubkey(),
+        &[],
+        maker_amount,
+    )
+    .unwrap();
+    env.send(&[mint_ix], &maker).unwrap();
+
+    Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(maker_account, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(env.taker.pubkey(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+            AccountMeta::new(registry_page_pda(&maker_mint, &env.taker_mint, 0), false),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: env.order_id,
+            maker_amount,
+            taker_amount: 150_000,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    }
+}
+
+#[test]
+fn test_mint_risk_checks() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let maker_pubkey = maker.pubkey();
+
+    // Tokens that can never leave the escrow are refused outright
+    let soulbound = create_token_2022_mint(
+        &mut env,
+        &[ExtensionType::NonTransferable],
+        |mint| {
+            vec![
+                spl_token_2022::instruction::initialize_non_transferable_mint(
+                    &spl_token_2022::id(),
+                    mint,
+                )
+                .unwrap(),
+            ]
+        },
+        None,
+    );
+    let init_ix = token_2022_order_ix(&mut env, soulbound, 100_000);
+    assert!(env.send(&[init_ix], &maker).is_err());
+
+    // A permanent delegate and a freeze authority are recorded for clients to flag
+    let risky = create_token_2022_mint(
+        &mut env,
+        &[ExtensionType::PermanentDelegate],
+        |mint| {
+            vec![spl_token_2022::instruction::initialize_permanent_delegate(
+                &spl_token_2022::id(),
+                mint,
+                &maker_pubkey,
+            )
+            .unwrap()]
+        },
+        Some(&maker_pubkey),
+    );
+    let init_ix = token_2022_order_ix(&mut env, risky, 100_000);
+    env.send(&[init_ix], &maker).unwrap();
+    let order = env.order();
+    assert_eq!(
+        order.maker_mint_risk,
+        MINT_RISK_PERMANENT_DELEGATE | MINT_RISK_FREEZE_AUTHORITY
+    );
+    assert_eq!(order.taker_mint_risk, 0);
+}
+
+#[test]
+fn test_default_frozen_mint() {
+    use spl_token_2022::{
+        extension::default_account_state::instruction::initialize_default_account_state,
+        state::AccountState,
+    };
+
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let maker_pubkey = maker.pubkey();
+
+    // Every account of this mint starts frozen until the maker, its freeze authority,
+    // thaws it
+    let maker_mint = create_token_2022_mint(
+        &mut env,
+        &[ExtensionType::DefaultAccountState],
+        |mint| {
+            vec![initialize_default_account_state(
+                &spl_token_2022::id(),
+                mint,
+                &AccountState::Frozen,
+            )
+            .unwrap()]
+        },
+        Some(&maker_pubkey),
+    );
+    env.maker_mint = maker_mint;
+    let order_pda = env.order_pda();
+    let maker_account = create_token_2022_account(&mut env, &maker_pubkey, &maker_mint);
+    // Vaults created by the program would start out frozen, so the escrow is the order
+    // PDA's ATA
+    let escrow = create_token_2022_ata(&mut env, &order_pda, &maker_mint);
+    let thaw_ix = |account: &Pubkey| {
+        spl_token_2022::instruction::thaw_account(
+            &spl_token_2022::id(),
+            account,
+            &maker_mint,
+            &maker_pubkey,
+            &[],
+        )
+        .unwrap()
+    };
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &maker_mint,
+        &maker_account,
+        &maker_pubkey,
+        &[],
+        100_000,
+    )
+    .unwrap();
+    env.send(&[thaw_ix(&maker_account), mint_ix], &maker)
+        .unwrap();
+
+    let init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker_pubkey, true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(maker_account, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(env.taker.pubkey(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker_pubkey), false),
+            AccountMeta::new(registry_page_pda(&maker_mint, &env.taker_mint, 0), false),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: 0,
+            maker_amount: 100_000,
+            taker_amount: 150_000,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    };
+    // A frozen escrow is refused up front
+    assert!(env.send(&[init_ix.clone()], &maker).is_err());
+
+    // The freeze authority thaws it in the same transaction
+    env.send(&[thaw_ix(&escrow), init_ix], &maker).unwrap();
+    assert_eq!(env.token_balance(&escrow), 100_000);
+    assert_eq!(env.order().maker_mint_risk, MINT_RISK_FREEZE_AUTHORITY);
+}
+
+#[test]
+fn test_memo_required_receiving_account() {
+    use spl_token_2022::extension::memo_transfer::instruction::enable_required_transfer_memos;
+
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let maker_mint = create_token_2022_mint(&mut env, &[], |_| vec![], None);
+    let init_ix = token_2022_order_ix(&mut env, maker_mint, 100_000);
+    let escrow = init_ix.accounts[3].pubkey;
+    env.send(&[init_ix], &maker).unwrap();
+
+    // The taker's receiving account only accepts transfers that come with a memo
+    let taker_receiving = create_token_2022_account(&mut env, &taker.pubkey(), &maker_mint);
+    let require_memo_ixs = [
+        spl_token_2022::instruction::reallocate(
+            &spl_token_2022::id(),
+            &taker_receiving,
+            &taker.pubkey(),
+            &taker.pubkey(),
+            &[],
+            &[ExtensionType::MemoTransfer],
+        )
+        .unwrap(),
+        enable_required_transfer_memos(
+            &spl_token_2022::id(),
+            &taker_receiving,
+            &taker.pubkey(),
+            &[],
+        )
+        .unwrap(),
+    ];
+    env.send(&require_memo_ixs, &taker).unwrap();
+
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[4] = AccountMeta::new(taker_receiving, false);
+    fill_ix.accounts[5] = AccountMeta::new(escrow, false);
+    fill_ix.accounts[6] = AccountMeta::new_readonly(spl_token_2022::id(), false);
+    assert!(env.send(&[fill_ix.clone()], &taker).is_err());
+
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 0,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: Some("invoice 42".to_string()),
+    }
+    .pack();
+    fill_ix
+        .accounts
+        .push(AccountMeta::new_readonly(spl_memo::id(), false));
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(env.token_balance(&taker_receiving), 100_000);
+}
+
+#[test]
+fn test_cpi_guard_prefunded_escrow() {
+    use spl_token_2022::extension::cpi_guard::instruction::enable_cpi_guard;
+
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let maker_pubkey = maker.pubkey();
+
+    let maker_mint = create_token_2022_mint(&mut env, &[], |_| vec![], None);
+    let init_ix = token_2022_order_ix(&mut env, maker_mint, 100_000);
+    let maker_account = init_ix.accounts[2].pubkey;
+    let escrow = init_ix.accounts[3].pubkey;
+
+    // The maker's account refuses transfers its owner signs through another program
+    let guard_ixs = [
+        spl_token_2022::instruction::reallocate(
+            &spl_token_2022::id(),
+            &maker_account,
+            &maker_pubkey,
+            &maker_pubkey,
+            &[],
+            &[ExtensionType::CpiGuard],
+        )
+        .unwrap(),
+        enable_cpi_guard(&spl_token_2022::id(), &maker_account, &maker_pubkey, &[]).unwrap(),
+    ];
+    env.send(&guard_ixs, &maker).unwrap();
+    assert!(env.send(&[init_ix.clone()], &maker).is_err());
+
+    // Funding the escrow directly in the same transaction works around it
+    let deposit_ix = spl_token_2022::instruction::transfer_checked(
+        &spl_token_2022::id(),
+        &maker_account,
+        &maker_mint,
+        &escrow,
+        &maker_pubkey,
+        &[],
+        100_000,
+        6,
+    )
+    .unwrap();
+    env.send(&[deposit_ix, init_ix], &maker).unwrap();
+    assert_eq!(env.token_balance(&escrow), 100_000);
+    assert_eq!(env.token_balance(&maker_account), 0);
+    assert_eq!({ env.order().maker_amount }, 100_000);
+}
+
+#[test]
+fn test_nft_order() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let maker_pubkey = maker.pubkey();
+    let nft_order = SwapInstruction::InitializeNftOrder {
+        order_id: 0,
+        taker_amount: 500,
+    };
+
+    // A fungible maker mint is refused
+    env.create_order_ata();
+    assert!(env.initialize(&Pubkey::default(), &nft_order).is_err());
+
+    let nft = create_nft(&mut env);
+    env.fund(&taker.pubkey(), &nft, 0);
+    env.maker_mint = nft;
+    env.create_order_ata();
+
+    env.initialize(&Pubkey::default(), &nft_order).unwrap();
+    let order = env.order();
+    assert!(order.nft);
+    assert!(order.all_or_none);
+    assert_eq!({ order.maker_amount }, 1);
+    assert_eq!({ order.taker_amount }, 500);
+
+    // The amount economics of fungible orders do not apply
+    let set_ui_amounts_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker_pubkey, true),
+            AccountMeta::new(env.order_pda(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+        ],
+        data: SwapInstruction::SetUiAmounts { ui_amounts: true }.pack(),
+    };
+    assert!(env.send(&[set_ui_amounts_ix], &maker).is_err());
+
+    let fill_ix = env.complete_swap_ix(&taker.pubkey());
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &env.maker_mint)),
+        1
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&maker_pubkey, &env.taker_mint)),
+        500
+    );
+}
+
+/// Creates a 0-decimal SPL Token mint and mints its single token to the maker
+fn create_nft(env: &mut SwapEnv) -> Pubkey {
+    let maker = env.maker.insecure_clone();
+    let nft = Keypair::new();
+    let mint_len = spl_token::state::Mint::LEN;
+    let create_mint_ixs = [
+        solana_sdk::system_instruction::create_account(
+            &maker.pubkey(),
+            &nft.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(mint_len),
+            mint_len as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::id(),
+            &nft.pubkey(),
+            &maker.pubkey(),
+            None,
+            0,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &create_mint_ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &nft],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    env.fund(&maker.pubkey(), &nft.pubkey(), 1);
+    nft.pubkey()
+}
+
+/// Stores a Token Metadata account for `mint` with the given `token_standard` and
+/// `(collection, verified)`
+fn set_nft_metadata(
+    env: &mut SwapEnv,
+    mint: &Pubkey,
+    token_standard: u8,
+    collection: Option<(&Pubkey, bool)>,
+) -> Pubkey {
+    let mut data = vec![4]; // Key::MetadataV1
+    data.extend_from_slice(env.maker.pubkey().as_ref()); // update_authority
+    data.extend_from_slice(mint.as_ref());
+    for field in [&b"Test NFT"[..], b"TEST", b""] {
+        data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        data.extend_from_slice(field);
+    }
+    data.extend_from_slice(&500u16.to_le_bytes()); // seller_fee_basis_points
+    data.extend_from_slice(&[0, 0, 1]); // no creators, primary_sale_happened, is_mutable
+    data.extend_from_slice(&[1, 255]); // edition_nonce
+    data.extend_from_slice(&[1, token_standard]);
+    match collection {
+        Some((key, verified)) => {
+            data.extend_from_slice(&[1, verified as u8]);
+            data.extend_from_slice(key.as_ref());
+        }
+        None => data.push(0),
+    }
+
+    let metadata = find_metadata_address(mint);
+    env.svm
+        .set_account(
+            metadata,
+            solana_sdk::account::Account {
+                lamports: env.svm.minimum_balance_for_rent_exemption(data.len()),
+                data,
+                owner: TOKEN_METADATA_PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        )
+        .unwrap();
+    metadata
+}
+
+#[test]
+fn test_programmable_nft_detection() {
+    let mut env = SwapEnv::new();
+    env.maker_mint = create_nft(&mut env);
+    env.create_order_ata();
+    let nft_order = SwapInstruction::InitializeNftOrder {
+        order_id: 0,
+        taker_amount: 500,
+    };
+
+    // A programmable NFT is routed through Token Metadata, which has to be passed
+    let maker_mint = env.maker_mint;
+    let metadata = set_nft_metadata(&mut env, &maker_mint, 4, None);
+    assert!(env
+        .initialize_with_accounts(
+            &Pubkey::default(),
+            &nft_order,
+            &[AccountMeta::new(metadata, false)],
+        )
+        .is_err());
+
+    // Plain NFTs keep moving with token transfers
+    set_nft_metadata(&mut env, &maker_mint, 0, None);
+    env.initialize_with_accounts(
+        &Pubkey::default(),
+        &nft_order,
+        &[AccountMeta::new(metadata, false)],
+    )
+    .unwrap();
+    let order = env.order();
+    assert!(order.nft);
+    assert!(!order.programmable);
+    assert_eq!(
+        env.token_balance(&env.ata(&env.order_pda(), &maker_mint)),
+        1
+    );
+}
+
+#[test]
+fn test_bundle_order() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let (maker_pubkey, taker_pubkey) = (maker.pubkey(), taker.pubkey());
+    let bundle = bundle_pda(&maker_pubkey, 0);
+
+    // Two maker mints for two taker mints
+    let maker_mints = [env.maker_mint, env.create_mint()];
+    let taker_mints = [env.taker_mint, env.create_mint()];
+    env.fund(&maker_pubkey, &maker_mints[1], 1_000_000);
+    env.fund(&taker_pubkey, &taker_mints[1], 1_000_000);
+    env.fund(&maker_pubkey, &taker_mints[1], 0);
+    env.fund(&taker_pubkey, &maker_mints[1], 0);
+    for mint in maker_mints {
+        env.fund(&bundle, &mint, 0);
+    }
+
+    let mut init_accounts = vec![
+        AccountMeta::new(maker_pubkey, true),
+        AccountMeta::new(bundle, false),
+        AccountMeta::new_readonly(taker_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(config_pda(), false),
+    ];
+    for mint in maker_mints {
+        init_accounts.extend([
+            AccountMeta::new(env.ata(&maker_pubkey, &mint), false),
+            AccountMeta::new(env.ata(&bundle, &mint), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+    init_accounts.extend(
+        taker_mints
+            .iter()
+            .map(|mint| AccountMeta::new_readonly(*mint, false)),
+    );
+    let blocked_accounts = maker_mints
+        .iter()
+        .chain(&taker_mints)
+        .map(|mint| AccountMeta::new_readonly(blocked_mint_pda(mint), false))
+        .collect::<Vec<_>>();
+    init_accounts.extend(blocked_accounts.clone());
+    let init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: init_accounts,
+        data: SwapInstruction::InitializeBundle {
+            bundle_id: 0,
+            maker_amounts: vec![100, 200],
+            taker_amounts: vec![300, 400],
+        }
+        .pack(),
+    };
+
+    // A bundle with its maker as taker or a mint on both sides is refused, as orders are
+    let mut self_trade_ix = init_ix.clone();
+    self_trade_ix.accounts[2] = AccountMeta::new_readonly(maker_pubkey, false);
+    let mut same_mint_ix = init_ix.clone();
+    same_mint_ix.accounts[14] = AccountMeta::new_readonly(maker_mints[1], false);
+    for (ix, error) in [
+        (self_trade_ix, SwapError::SelfTrade),
+        (same_mint_ix, SwapError::SameMint),
+    ] {
+        let result = env.send(&[ix], &maker);
+        assert_eq!(
+            client::error::transaction_swap_error(&result.unwrap_err().err),
+            Some(error)
+        );
+    }
+
+    // Neither side of a bundle may use a blocked mint
+    env.initialize_config(0).unwrap();
+    env.set_mint_blocked(&taker_mints[1], true).unwrap();
+    assert_eq!(
+        swap_error(env.send(&[init_ix.clone()], &maker)),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&taker_mints[1], false).unwrap();
+
+    env.send(&[init_ix], &maker).unwrap();
+    assert_eq!(env.token_balance(&env.ata(&bundle, &maker_mints[0])), 100);
+    assert_eq!(env.token_balance(&env.ata(&bundle, &maker_mints[1])), 200);
+    let order = BundleOrder::try_from_slice(&env.svm.get_account(&bundle).unwrap().data).unwrap();
+    assert_eq!(order.maker_legs.len(), 2);
+    assert_eq!(order.taker_legs[1].amount, 400);
+
+    let mut fill_accounts = vec![
+        AccountMeta::new(taker_pubkey, true),
+        AccountMeta::new(bundle, false),
+        AccountMeta::new_readonly(config_pda(), false),
+    ];
+    for mint in taker_mints {
+        fill_accounts.extend([
+            AccountMeta::new(env.ata(&taker_pubkey, &mint), false),
+            AccountMeta::new(env.ata(&maker_pubkey, &mint), false),
+            AccountMeta::new(treasury_pda(&mint), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+    for mint in maker_mints {
+        fill_accounts.extend([
+            AccountMeta::new(env.ata(&bundle, &mint), false),
+            AccountMeta::new(env.ata(&taker_pubkey, &mint), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+    fill_accounts.extend(blocked_accounts);
+    let fill_ix = |fill_amount| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: fill_accounts.clone(),
+        data: SwapInstruction::CompleteSwap {
+            fill_amount,
+            max_taker_amount: u64::MAX,
+            min_maker_amount: 0,
+            taker_proof: vec![],
+            memo: None,
+        }
+        .pack(),
+    };
+    // Bundles do not fill partially
+    assert!(env.send(&[fill_ix(50)], &taker).is_err());
+    env.set_mint_blocked(&maker_mints[1], true).unwrap();
+    assert_eq!(
+        swap_error(env.send(&[fill_ix(0)], &taker)),
+        Some(SwapError::MintBlocked)
+    );
+    env.set_mint_blocked(&maker_mints[1], false).unwrap();
+    env.send(&[fill_ix(0)], &taker).unwrap();
+    assert_eq!(
+        env.token_balance(&env.ata(&taker_pubkey, &maker_mints[1])),
+        200
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&maker_pubkey, &taker_mints[0])),
+        300
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&maker_pubkey, &taker_mints[1])),
+        400
+    );
+    assert!(env.send(&[fill_ix(0)], &taker).is_err());
+
+    // Closing returns the rent of the bundle and its escrows to the maker
+    let mut close_accounts = vec![
+        AccountMeta::new(maker_pubkey, true),
+        AccountMeta::new(bundle, false),
+    ];
+    for mint in maker_mints {
+        close_accounts.extend([
+            AccountMeta::new(env.ata(&bundle, &mint), false),
+            AccountMeta::new(env.ata(&maker_pubkey, &mint), false),
+            AccountMeta::new(mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+    let close_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: close_accounts,
+        data: SwapInstruction::CloseOrder { memo: None }.pack(),
+    };
+    env.send(&[close_ix], &maker).unwrap();
+    assert!(env
+        .svm
+        .get_account(&bundle)
+        .is_none_or(|account| account.lamports == 0));
+    assert!(env
+        .svm
+        .get_account(&env.ata(&bundle, &maker_mints[0]))
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_collection_order() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let (maker_pubkey, taker_pubkey) = (maker.pubkey(), taker.pubkey());
+
+    // The order asks for one NFT of the collection whose mint is the taker mint
+    let collection = create_nft(&mut env);
+    env.taker_mint = collection;
+    env.create_order_ata();
+    env.initialize_order(&Pubkey::default(), 1_000, 1).unwrap();
+    let set_collection_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker_pubkey, true),
+            AccountMeta::new(env.order_pda(), false),
+            AccountMeta::new_readonly(collection, false),
+        ],
+        data: SwapInstruction::SetTakerCollection { enabled: true }.pack(),
+    };
+    env.send(&[set_collection_ix], &maker).unwrap();
+    assert!(env.order().taker_collection);
+
+    // Hands the taker an NFT of the maker's minting with the given collection
+    let taker_nft = |env: &mut SwapEnv, verified| {
+        let nft = create_nft(env);
+        env.fund(&taker_pubkey, &nft, 0);
+        let ix = spl_token::instruction::transfer(
+            &spl_token::id(),
+            &env.ata(&maker_pubkey, &nft),
+            &env.ata(&taker_pubkey, &nft),
+            &maker_pubkey,
+            &[],
+            1,
+        )
+        .unwrap();
+        env.send(&[ix], &maker).unwrap();
+        let metadata = set_nft_metadata(env, &nft, 0, Some((&collection, verified)));
+        (nft, metadata)
+    };
+    let fill_ix = |env: &SwapEnv, nft: &Pubkey, metadata: &Pubkey| {
+        let mut ix = env.complete_swap_ix(&taker_pubkey);
+        ix.accounts[2] = AccountMeta::new(env.ata(&maker_pubkey, nft), false);
+        ix.accounts[3] = AccountMeta::new(env.ata(&taker_pubkey, nft), false);
+        ix.accounts[9] = AccountMeta::new_readonly(*nft, false);
+        ix.accounts[11] = AccountMeta::new(treasury_pda(nft), false);
+        ix.accounts[16] = AccountMeta::new_readonly(blocked_mint_pda(nft), false);
+        ix.accounts
+            .push(AccountMeta::new_readonly(*metadata, false));
+        ix
+    };
+
+    // An unverified claim to the collection is refused
+    let (unverified, unverified_metadata) = taker_nft(&mut env, false);
+    let ix = fill_ix(&env, &unverified, &unverified_metadata);
+    assert!(env.send(&[ix], &taker).is_err());
+
+    let (nft, metadata) = taker_nft(&mut env, true);
+    let mut ix = fill_ix(&env, &nft, &metadata);
+    ix.accounts.pop();
+    assert!(env.send(&[ix], &taker).is_err());
+    let ix = fill_ix(&env, &nft, &metadata);
+    env.send(&[ix], &taker).unwrap();
+    assert_eq!(env.token_balance(&env.ata(&maker_pubkey, &nft)), 1);
+    assert_eq!(
+        env.token_balance(&env.ata(&taker_pubkey, &env.maker_mint)),
+        1_000
+    );
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+/// Creates a Token-2022 mint with a group member extension in itself, initialized as a
+/// member of `group` with the maker as the group's update authority
+fn create_group_member(env: &mut SwapEnv, group: &Pubkey) -> Pubkey {
+    let maker = env.maker.insecure_clone();
+    let member = create_token_2022_mint(
+        env,
+        &[
+            ExtensionType::GroupMemberPointer,
+            ExtensionType::TokenGroupMember,
+        ],
+        |mint| {
+            vec![
+                spl_token_2022::extension::group_member_pointer::instruction::initialize(
+                    &spl_token_2022::id(),
+                    mint,
+                    Some(maker.pubkey()),
+                    Some(*mint),
+                )
+                .unwrap(),
+            ]
+        },
+        None,
+    );
+    let ix = spl_token_group_interface::instruction::initialize_member(
+        &spl_token_2022::id(),
+        &member,
+        &member,
+        &maker.pubkey(),
+        group,
+        &maker.pubkey(),
+    );
+    env.send(&[ix], &maker).unwrap();
+    member
+}
+
+#[test]
+fn test_token_groups() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let (maker_pubkey, taker_pubkey) = (maker.pubkey(), taker.pubkey());
+
+    let group = create_token_2022_mint(
+        &mut env,
+        &[ExtensionType::GroupPointer, ExtensionType::TokenGroup],
+        |mint| {
+            vec![
+                spl_token_2022::extension::group_pointer::instruction::initialize(
+                    &spl_token_2022::id(),
+                    mint,
+                    Some(maker_pubkey),
+                    Some(*mint),
+                )
+                .unwrap(),
+            ]
+        },
+        None,
+    );
+    let ix = spl_token_group_interface::instruction::initialize_group(
+        &spl_token_2022::id(),
+        &group,
+        &group,
+        &maker_pubkey,
+        Some(maker_pubkey),
+        10,
+    );
+    env.send(&[ix], &maker).unwrap();
+    let listed_member = create_group_member(&mut env, &group);
+    let paid_member = create_group_member(&mut env, &group);
+
+    env.taker_mint = listed_member;
+    env.create_order_ata();
+    env.initialize_order(&Pubkey::default(), 1_000, 500)
+        .unwrap();
+    let (order_pda, maker_mint) = (env.order_pda(), env.maker_mint);
+    let set_groups_ix = |maker_group, taker_group| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker_pubkey, true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new_readonly(maker_mint, false),
+        ],
+        data: SwapInstruction::SetTokenGroups {
+            maker_group,
+            taker_group,
+        }
+        .pack(),
+    };
+
+    // The maker mint is a plain SPL Token mint outside any group
+    let ix = set_groups_ix(group, Pubkey::default());
+    assert!(env.send(&[ix], &maker).is_err());
+    let ix = set_groups_ix(Pubkey::default(), group);
+    env.send(&[ix], &maker).unwrap();
+    assert_eq!(env.order().taker_group, group);
+    assert!(env.order().takes_any_member());
+
+    // Another member of the group pays for the order
+    let maker_account = create_token_2022_account(&mut env, &maker_pubkey, &paid_member);
+    let taker_account = create_token_2022_account(&mut env, &taker_pubkey, &paid_member);
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &paid_member,
+        &taker_account,
+        &maker_pubkey,
+        &[],
+        500,
+    )
+    .unwrap();
+    env.send(&[mint_ix], &maker).unwrap();
+    let mut fill_ix = env.complete_swap_ix(&taker_pubkey);
+    fill_ix.accounts[2] = AccountMeta::new(maker_account, false);
+    fill_ix.accounts[3] = AccountMeta::new(taker_account, false);
+    fill_ix.accounts[7] = AccountMeta::new_readonly(spl_token_2022::id(), false);
+    fill_ix.accounts[11] = AccountMeta::new(treasury_pda(&paid_member), false);
+
+    // A mint outside the group does not
+    let mut outsider_ix = fill_ix.clone();
+    outsider_ix.accounts[9] = AccountMeta::new_readonly(env.maker_mint, false);
+    assert!(env.send(&[outsider_ix], &taker).is_err());
+
+    fill_ix.accounts[9] = AccountMeta::new_readonly(paid_member, false);
+    fill_ix.accounts[16] = AccountMeta::new_readonly(blocked_mint_pda(&paid_member), false);
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(env.token_balance(&maker_account), 500);
+    assert_eq!(env.order().status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_compressed_order_requires_bubblegum() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let merkle_tree = Pubkey::new_unique();
+    let proof = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    // The transfer the order makes carries the leaf and its proof as Bubblegum expects
+    let transfer_ix = bubblegum::transfer(
+        &merkle_tree,
+        &maker.pubkey(),
+        &maker.pubkey(),
+        &PROGRAM_KEY,
+        [1; 32],
+        [2; 32],
+        [3; 32],
+        7,
+        7,
+        &proof,
+    );
+    assert_eq!(transfer_ix.program_id, BUBBLEGUM_PROGRAM_ID);
+    assert_eq!(transfer_ix.data.len(), 8 + 32 * 3 + 8 + 4);
+    assert_eq!(transfer_ix.accounts.len(), 8 + proof.len());
+    assert_eq!(
+        transfer_ix.accounts[0].pubkey,
+        find_tree_config_address(&merkle_tree)
+    );
+    assert!(transfer_ix.accounts[1].is_signer);
+
+    // Orders only ever move leaves through the Bubblegum program
+    let order_pda = Pubkey::find_program_address(
+        &[b"cnft_order", maker.pubkey().as_ref(), &0u64.to_le_bytes()],
+        &PROGRAM_KEY,
+    )
+    .0;
+    let mut accounts = vec![
+        AccountMeta::new(maker.pubkey(), true),
+        AccountMeta::new(order_pda, false),
+        AccountMeta::new_readonly(Pubkey::default(), false),
+        AccountMeta::new_readonly(env.taker_mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_tree_config_address(&merkle_tree), false),
+        AccountMeta::new_readonly(maker.pubkey(), false),
+        AccountMeta::new(merkle_tree, false),
+        AccountMeta::new_readonly(bubblegum::NOOP_PROGRAM_ID, false),
+        AccountMeta::new_readonly(bubblegum::ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        AccountMeta::new_readonly(config_pda(), false),
+        AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+    ];
+    accounts.extend(
+        proof
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false)),
+    );
+    let init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts,
+        data: SwapInstruction::InitializeCompressedOrder {
+            order_id: 0,
+            taker_amount: 100,
+            root: [1; 32],
+            data_hash: [2; 32],
+            creator_hash: [3; 32],
+            nonce: 7,
+            index: 7,
+        }
+        .pack(),
+    };
+    assert!(env.send(&[init_ix.clone()], &maker).is_err());
+    assert!(env.svm.get_account(&order_pda).is_none());
+
+    // Compressed orders are held to the trade guards like any other
+    let mut self_trade_ix = init_ix.clone();
+    self_trade_ix.accounts[2] = AccountMeta::new_readonly(maker.pubkey(), false);
+    self_trade_ix.accounts[10] = AccountMeta::new_readonly(BUBBLEGUM_PROGRAM_ID, false);
+    let result = env.send(&[self_trade_ix], &maker);
+    assert_eq!(
+        client::error::transaction_swap_error(&result.unwrap_err().err),
+        Some(SwapError::SelfTrade)
+    );
+
+    // And to the blocklist, before the leaf is ever touched
+    env.initialize_config(0).unwrap();
+    let taker_mint = env.taker_mint;
+    env.set_mint_blocked(&taker_mint, true).unwrap();
+    assert_eq!(
+        swap_error(env.send(&[init_ix], &maker)),
+        Some(SwapError::MintBlocked)
+    );
+}
+
+#[test]
+fn test_initialize_creates_escrow() {
+    let mut env = SwapEnv::new();
+    let escrow = env.ata(&env.order_pda(), &env.maker_mint);
+
+    // Without the ATA program the missing escrow cannot be created
+    assert!(env.initialize_order(&Pubkey::default(), 100, 200).is_err());
+
+    let instruction = SwapInstruction::InitializeOrder {
+        order_id: env.order_id,
+        maker_amount: 100,
+        taker_amount: 200,
+        requires_confirmation: false,
+        dual_escrow: false,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    };
+    env.initialize_with_accounts(
+        &Pubkey::default(),
+        &instruction,
+        &[AccountMeta::new_readonly(
+            spl_associated_token_account::id(),
+            false,
+        )],
+    )
+    .unwrap();
+    assert_eq!(env.token_balance(&escrow), 100);
+    assert_eq!({ env.order().maker_amount }, 100);
+}
+
+#[test]
+fn test_vault_escrow() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let order_pda = env.order_pda();
+    let vault = vault_pda(&order_pda, &env.maker_mint);
+
+    // Any other token account of the order PDA is refused as escrow
+    let stray = Keypair::new();
+    let account_len = spl_token::state::Account::LEN;
+    let create_stray_ixs = [
+        solana_sdk::system_instruction::create_account(
+            &maker.pubkey(),
+            &stray.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(account_len),
+            account_len as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account3(
+            &spl_token::id(),
+            &stray.pubkey(),
+            &env.maker_mint,
+            &order_pda,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &create_stray_ixs,
+        Some(&maker.pubkey()),
+        &[&maker, &stray],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    let mut init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(env.ata(&maker.pubkey(), &env.maker_mint), false),
+            AccountMeta::new(stray.pubkey(), false),
+            AccountMeta::new_readonly(Pubkey::default(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+            AccountMeta::new(
+                registry_page_pda(&env.maker_mint, &env.taker_mint, 0),
+                false,
+            ),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: env.order_id,
+            maker_amount: 100,
+            taker_amount: 200,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    };
+    assert!(env.send(&[init_ix.clone()], &maker).is_err());
+
+    // The vault is created by the order itself
+    init_ix.accounts[3] = AccountMeta::new(vault, false);
+    env.send(&[init_ix], &maker).unwrap();
+    assert_eq!(env.token_balance(&vault), 100);
+
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts[5] = AccountMeta::new(vault, false);
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(env.token_balance(&vault), 0);
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &env.maker_mint)),
+        100
+    );
+}
+
+#[test]
+fn test_sponsored_rent() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let sponsor = Keypair::new();
+    env.svm.airdrop(&sponsor.pubkey(), 1_000_000_000).unwrap();
+    let order_pda = env.order_pda();
+    let vault = vault_pda(&order_pda, &env.maker_mint);
+
+    // The sponsor pays the fee and every rent, so the maker's SOL is untouched
+    let maker_lamports = env.svm.get_account(&maker.pubkey()).unwrap().lamports;
+    let init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(env.ata(&maker.pubkey(), &env.maker_mint), false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(Pubkey::default(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(sponsor.pubkey(), true),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.maker_mint), false),
+            AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+            AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+            AccountMeta::new(
+                registry_page_pda(&env.maker_mint, &env.taker_mint, 0),
+                false,
+            ),
+        ],
+        data: SwapInstruction::InitializeOrder {
+            order_id: env.order_id,
+            maker_amount: 100,
+            taker_amount: 200,
+            requires_confirmation: false,
+            dual_escrow: false,
+            all_or_none: false,
+            memo: [0; MEMO_LEN],
+        }
+        .pack(),
+    };
+    let initialize = |env: &mut SwapEnv| {
+        env.svm.expire_blockhash();
+        let tx = Transaction::new_signed_with_payer(
+            &[init_ix.clone()],
+            Some(&sponsor.pubkey()),
+            &[&sponsor, &maker],
+            env.svm.latest_blockhash(),
+        );
+        env.svm.send_transaction(tx).unwrap();
+    };
+    initialize(&mut env);
+    assert_eq!(
+        env.svm.get_account(&maker.pubkey()).unwrap().lamports,
+        maker_lamports
+    );
+    assert_eq!(env.order().rent_payer, sponsor.pubkey());
+    assert_eq!(env.token_balance(&vault), 100);
+
+    // Closing refunds the tokens to the maker and the rent to the sponsor or the maker,
+    // but to no other account
+    let rent = env.svm.get_account(&order_pda).unwrap().lamports
+        + env.svm.get_account(&vault).unwrap().lamports;
+    let mut close_ix = env.close_order_ix(&maker.pubkey());
+    close_ix.accounts[2] = AccountMeta::new(vault, false);
+    close_ix.accounts[6] = AccountMeta::new(env.taker.pubkey(), false);
+    assert!(env.send(&[close_ix.clone()], &maker).is_err());
+
+    let sponsor_lamports = env.svm.get_account(&sponsor.pubkey()).unwrap().lamports;
+    close_ix.accounts[6] = AccountMeta::new(sponsor.pubkey(), false);
+    env.send(&[close_ix.clone()], &maker).unwrap();
+    assert_eq!(
+        env.svm.get_account(&sponsor.pubkey()).unwrap().lamports,
+        sponsor_lamports + rent
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&maker.pubkey(), &env.maker_mint)),
+        1_000_000
+    );
+
+    // The same order sponsored again may return its rent to the maker instead
+    initialize(&mut env);
+    let sponsor_lamports = env.svm.get_account(&sponsor.pubkey()).unwrap().lamports;
+    let maker_lamports = env.svm.get_account(&maker.pubkey()).unwrap().lamports;
+    close_ix.accounts[6] = AccountMeta::new(maker.pubkey(), false);
+    let taker = env.taker.insecure_clone();
+    let tx = Transaction::new_signed_with_payer(
+        &[close_ix],
+        Some(&taker.pubkey()),
+        &[&taker, &maker],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    assert_eq!(
+        env.svm.get_account(&maker.pubkey()).unwrap().lamports,
+        maker_lamports + rent
+    );
+    assert_eq!(
+        env.svm.get_account(&sponsor.pubkey()).unwrap().lamports,
+        sponsor_lamports
+    );
+
+    // A writable signer anywhere but right after the fixed accounts is not a rent payer,
+    // so the maker pays
+    let mut init_ix = init_ix.clone();
+    let extra_signer = init_ix.accounts.remove(11);
+    init_ix.accounts.push(extra_signer);
+    let maker_lamports = env.svm.get_account(&maker.pubkey()).unwrap().lamports;
+    env.svm.expire_blockhash();
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix],
+        Some(&sponsor.pubkey()),
+        &[&sponsor, &maker],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    assert_eq!(env.order().rent_payer, Pubkey::default());
+    assert!(env.svm.get_account(&maker.pubkey()).unwrap().lamports < maker_lamports);
+}
+
+#[test]
+fn test_rent_destination() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+
+    // Rent cannot be routed anywhere but to the maker or the account that paid it
+    let mut close_ix = env.close_order_ix(&maker.pubkey());
+    close_ix.accounts[6] = AccountMeta::new(taker.pubkey(), false);
+    assert!(env.send(&[close_ix], &maker).is_err());
+
+    let rent = env.svm.get_account(&env.order_pda()).unwrap().lamports
+        + env
+            .svm
+            .get_account(&env.ata(&env.order_pda(), &env.maker_mint))
+            .unwrap()
+            .lamports;
+    let maker_lamports = env.svm.get_account(&maker.pubkey()).unwrap().lamports;
+    // The maker gets it back even when another account pays the transaction fee
+    let close_ix = env.close_order_ix(&maker.pubkey());
+    let tx = Transaction::new_signed_with_payer(
+        &[close_ix],
+        Some(&taker.pubkey()),
+        &[&taker, &maker],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+    assert_eq!(
+        env.svm.get_account(&maker.pubkey()).unwrap().lamports,
+        maker_lamports + rent
+    );
+}
+
+#[test]
+fn test_close_on_full_fill() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    let order_pda = env.order_pda();
+    let escrow = env.ata(&order_pda, &env.maker_mint);
+
+    // A partial fill leaves the order open even with the maker passed
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 40,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    fill_ix
+        .accounts
+        .push(AccountMeta::new(maker.pubkey(), false));
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!({ env.order().maker_amount }, 60);
+
+    // The final fill closes the order and its escrow, with the rent going to the maker
+    let rent = env.svm.get_account(&order_pda).unwrap().lamports
+        + env.svm.get_account(&escrow).unwrap().lamports;
+    let maker_lamports = env.svm.get_account(&maker.pubkey()).unwrap().lamports;
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix
+        .accounts
+        .push(AccountMeta::new(maker.pubkey(), false));
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(
+        env.svm.get_account(&maker.pubkey()).unwrap().lamports,
+        maker_lamports + rent
+    );
+    for account in [order_pda, escrow] {
+        assert!(env
+            .svm
+            .get_account(&account)
+            .is_none_or(|account| account.lamports == 0));
+    }
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &env.maker_mint)),
+        100
+    );
+}
+
+#[test]
+fn test_fill_creates_maker_ata() {
+    let mut env = SwapEnv::new();
+    env.taker_mint = env.create_mint();
+    env.create_order_ata();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.fund(&taker.pubkey(), &env.taker_mint.clone(), 1_000);
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    let maker_ata = env.ata(&maker.pubkey(), &env.taker_mint);
+    assert!(env.svm.get_account(&maker_ata).is_none());
+
+    // The maker's missing receiving account needs the accounts to create it
+    let fill_ix = env.complete_swap_ix(&taker.pubkey());
+    assert!(env.send(&[fill_ix.clone()], &taker).is_err());
+
+    let mut fill_ix = fill_ix;
+    fill_ix.accounts.extend([
+        AccountMeta::new_readonly(maker.pubkey(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ]);
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(env.token_balance(&maker_ata), 200);
+}
+
+#[test]
+fn test_fill_creates_taker_ata() {
+    let mut env = SwapEnv::new();
+    let fresh_taker = Keypair::new();
+    env.svm
+        .airdrop(&fresh_taker.pubkey(), 1_000_000_000)
+        .unwrap();
+    env.fund(&fresh_taker.pubkey(), &env.taker_mint.clone(), 1_000);
+    env.initialize_order(&Pubkey::default(), 100, 200).unwrap();
+    let taker_ata = env.ata(&fresh_taker.pubkey(), &env.maker_mint);
+    assert!(env.svm.get_account(&taker_ata).is_none());
+
+    // The taker's maker-mint ATA is created in the fill itself
+    let mut fill_ix = env.complete_swap_ix(&fresh_taker.pubkey());
+    fill_ix.accounts.extend([
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ]);
+    env.send(&[fill_ix], &fresh_taker).unwrap();
+    assert_eq!(env.token_balance(&taker_ata), 100);
+}
+
+#[test]
+fn test_extend_order() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    let order_pda = env.order_pda();
+    let escrow = env.ata(&order_pda, &env.maker_mint);
+
+    // Rewrite the order in the layout used before the discriminator and version byte
+    let mut account = env.svm.get_account(&order_pda).unwrap();
+    account.data.drain(..9);
+    account.data.truncate(SwapOrder::LEGACY_LEN);
+    account.lamports = env
+        .svm
+        .minimum_balance_for_rent_exemption(SwapOrder::LEGACY_LEN);
+    env.svm.set_account(order_pda, account).unwrap();
+
+    let mut memo = [0; MEMO_LEN];
+    memo[..5].copy_from_slice(b"hello");
+    let extend_ix = |authority: &Pubkey| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::ExtendOrder {
+            expires_at: None,
+            memo: Some(memo),
+            taker_merkle_root: Some([7; 32]),
+        }
+        .pack(),
+    };
+
+    // Only the maker extends its order
+    assert!(env.send(&[extend_ix(&taker.pubkey())], &taker).is_err());
+
+    // The order grows in place, keeping its escrow, and takes the new fields
+    env.send(&[extend_ix(&maker.pubkey())], &maker).unwrap();
+    let account = env.svm.get_account(&order_pda).unwrap();
+    assert_eq!(account.data.len(), SwapOrder::LEN);
+    assert_eq!(
+        account.lamports,
+        env.svm.minimum_balance_for_rent_exemption(SwapOrder::LEN)
+    );
+    let order = env.order();
+    assert_eq!(order.memo_str(), "hello");
+    assert_eq!(order.taker_merkle_root, [7; 32]);
+    assert_eq!({ order.maker_amount }, 100_000);
+    assert_eq!(env.token_balance(&escrow), 100_000);
+
+    // A current order is extended without being migrated again
+    env.send(&[extend_ix(&maker.pubkey())], &maker).unwrap();
+}
+
+#[test]
+fn test_order_zero_copy_layout() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100, 200).unwrap();
+    let mut data = env.svm.get_account(&env.order_pda()).unwrap().data;
+
+    // Fields sit at fixed offsets: the maker right after the discriminator and version
+    let order = SwapOrder::view(&data).unwrap();
+    assert_eq!(order.status, OrderStatus::Open);
+    assert_eq!(&data[9..41], env.maker.pubkey().as_ref());
+
+    // Writing through the view changes the bytes in place
+    SwapOrder::view_mut(&mut data).unwrap().maker_amount = 50;
+    assert_eq!({ SwapOrder::unpack(&data).unwrap().maker_amount }, 50);
+
+    // Invalid enum discriminants and bools are rejected; the status follows the order id
+    let status_offset = 9 + 32 * 4 + 8 + 8 + 1 + 8;
+    data[status_offset] = 200;
+    assert!(SwapOrder::view(&data).is_err());
+}
+
+#[test]
+fn test_reap_expired_order() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let reaper = Keypair::new();
+    env.svm.airdrop(&reaper.pubkey(), 1_000_000_000).unwrap();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 200_000)
+        .unwrap();
+    let now = env.svm.get_sysvar::<Clock>().unix_timestamp;
+    env.maker_order_ix(&SwapInstruction::UpdateExpiry {
+        new_expires_at: now + 100,
+    })
+    .unwrap();
+
+    let order_pda = env.order_pda();
+    let mut reap_ix = env.close_order_ix(&reaper.pubkey());
+    reap_ix.data = SwapInstruction::ReapExpiredOrder.pack();
+
+    // Live orders cannot be reaped
+    assert!(env.send(&[reap_ix.clone()], &reaper).is_err());
+
+    let mut clock = env.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = now + 100;
+    env.svm.set_sysvar(&clock);
+
+    // Once expired anyone reaps it: the maker gets the tokens and the rent minus the
+    // reaper's bounty
+    let order_rent = env.svm.get_account(&order_pda).unwrap().lamports;
+    let escrow_rent = env
+        .svm
+        .get_account(&env.ata(&order_pda, &env.maker_mint))
+        .unwrap()
+        .lamports;
+    let maker_lamports = env.svm.get_account(&maker.pubkey()).unwrap().lamports;
+    let reaper_lamports = env.svm.get_account(&reaper.pubkey()).unwrap().lamports;
+    env.send(&[reap_ix], &reaper).unwrap();
+
+    assert_eq!(
+        env.token_balance(&env.ata(&maker.pubkey(), &env.maker_mint)),
+        1_000_000
+    );
+    assert_eq!(
+        env.svm.get_account(&maker.pubkey()).unwrap().lamports,
+        maker_lamports + order_rent + escrow_rent - REAP_BOUNTY_LAMPORTS
+    );
+    // The bounty more than covers the reaper's transaction fee
+    assert!(env.svm.get_account(&reaper.pubkey()).unwrap().lamports > reaper_lamports);
+    assert!(env
+        .svm
+        .get_account(&order_pda)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_initialize_orders_batch() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let params = |order_id: u64, maker_amount: u64| OrderParams {
+        order_id,
+        maker_amount,
+        taker_amount: 2 * maker_amount,
+        requires_confirmation: false,
+        dual_escrow: false,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    };
+
+    // One group of `InitializeOrder` accounts per order, escrowed in vaults
+    let mut accounts = Vec::new();
+    for order_id in [1, 2] {
+        env.order_id = order_id;
+        let order_pda = env.order_pda();
+        accounts.extend([
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(env.ata(&maker.pubkey(), &env.maker_mint), false),
+            AccountMeta::new(vault_pda(&order_pda, &env.maker_mint), false),
+            AccountMeta::new_readonly(Pubkey::default(), false),
+            AccountMeta::new_readonly(env.maker_mint, false),
+            AccountMeta::new_readonly(env.taker_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(config_pda(), false),
+        ]);
+    }
+    accounts.extend([
+        AccountMeta::new_readonly(blocked_mint_pda(&env.maker_mint), false),
+        AccountMeta::new_readonly(blocked_mint_pda(&env.taker_mint), false),
+        AccountMeta::new(maker_index_pda(&maker.pubkey()), false),
+        AccountMeta::new(
+            registry_page_pda(&env.maker_mint, &env.taker_mint, 0),
+            false,
+        ),
+    ]);
+    let batch_ix = |orders: Vec<OrderParams>| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: accounts.clone(),
+        data: SwapInstruction::InitializeOrders { orders }.pack(),
+    };
+
+    // Every order needs its account group
+    assert!(env
+        .send(
+            &[batch_ix(vec![params(1, 100), params(2, 300), params(3, 1)])],
+            &maker
+        )
+        .is_err());
+
+    env.send(&[batch_ix(vec![params(1, 100), params(2, 300)])], &maker)
+        .unwrap();
+    for (order_id, maker_amount) in [(1, 100), (2, 300)] {
+        env.order_id = order_id;
+        let order = env.order();
+        assert_eq!({ order.maker_amount }, maker_amount);
+        assert_eq!({ order.taker_amount }, 2 * maker_amount);
+        assert_eq!(
+            env.token_balance(&vault_pda(&env.order_pda(), &env.maker_mint)),
+            maker_amount
+        );
+    }
+}
+
+#[test]
+fn test_close_orders_batch() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let mut accounts = vec![AccountMeta::new(maker.pubkey(), true)];
+    for order_id in [1, 2, 3] {
+        env.order_id = order_id;
+        env.create_order_ata();
+        env.initialize_order(&env.taker.pubkey(), 100, 200).unwrap();
+        let order_pda = env.order_pda();
+        accounts.extend([
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(env.ata(&order_pda, &env.maker_mint), false),
+            AccountMeta::new(env.ata(&maker.pubkey(), &env.maker_mint), false),
+            AccountMeta::new(env.maker_mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+    let maker_ata = env.ata(&maker.pubkey(), &env.maker_mint);
+    assert_eq!(env.token_balance(&maker_ata), 1_000_000 - 300);
+
+    // Two of the three orders close together, the third stays open
+    accounts.truncate(1 + 2 * 5);
+    accounts.push(AccountMeta::new(maker_index_pda(&maker.pubkey()), false));
+    let close_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts,
+        data: SwapInstruction::CloseOrders { count: 2 }.pack(),
+    };
+    env.send(&[close_ix], &maker).unwrap();
+    assert_eq!(env.token_balance(&maker_ata), 1_000_000 - 100);
+    for order_id in [1, 2] {
+        env.order_id = order_id;
+        assert!(env
+            .svm
+            .get_account(&env.order_pda())
+            .is_none_or(|account| account.lamports == 0));
+    }
+    env.order_id = 3;
+    assert_eq!(env.order().status, OrderStatus::Open);
+}
+
+#[test]
+fn test_order_registry() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+    let first_page = registry_page_pda(&maker_mint, &taker_mint, 0);
+    let page = |env: &SwapEnv, key: &Pubkey| {
+        let account = env.svm.get_account(key).unwrap();
+        let page = RegistryPage::try_from_slice(&account.data).unwrap();
+        assert_eq!(account.data.len(), RegistryPage::space(page.entries.len()));
+        page
+    };
+    let entry = |order, maker_amount, taker_amount| RegistryEntry {
+        order,
+        maker: maker.pubkey(),
+        maker_amount,
+        taker_amount,
+    };
+
+    // The pair's first order creates page 0 and every later one is listed after it
+    let mut orders = Vec::new();
+    for order_id in [1, 2] {
+        env.order_id = order_id;
+        env.create_order_ata();
+        env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+        orders.push(env.order_pda());
+    }
+    let registry = page(&env, &first_page);
+    assert_eq!(
+        (registry.maker_mint, registry.taker_mint),
+        (maker_mint, taker_mint)
+    );
+    assert_eq!(registry.next, Pubkey::default());
+    assert_eq!(
+        registry.entries,
+        [entry(orders[0], 100, 200), entry(orders[1], 100, 200)]
+    );
+
+    // A partial fill passed the page updates the remaining amounts
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 40,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    fill_ix.accounts.push(AccountMeta::new(first_page, false));
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(
+        page(&env, &first_page).entries[1],
+        entry(orders[1], 60, 120)
+    );
+
+    // Closing drops the order and the page shrinks to fit
+    env.order_id = 1;
+    let mut close_ix = env.close_order_ix(&maker.pubkey());
+    close_ix.accounts.push(AccountMeta::new(first_page, false));
+    env.send(&[close_ix], &maker).unwrap();
+    assert_eq!(page(&env, &first_page).entries, [entry(orders[1], 60, 120)]);
+
+    // Later pages are linked from the one before them
+    let second_page = registry_page_pda(&maker_mint, &taker_mint, 1);
+    let mut page_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(second_page, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::InitializeRegistryPage {
+            maker_mint,
+            taker_mint,
+            page: 1,
+        }
+        .pack(),
+    };
+    assert!(env.send(&[page_ix.clone()], &maker).is_err());
+    page_ix.accounts.push(AccountMeta::new(first_page, false));
+    env.send(&[page_ix], &maker).unwrap();
+    assert_eq!(page(&env, &first_page).next, second_page);
+    assert_eq!(page(&env, &second_page).page, 1);
+    assert!(page(&env, &second_page).entries.is_empty());
+
+    // Full pages do not block orders: one passed the page after the pair's full last page
+    // creates and links it, and one passed no page with room is created unlisted
+    for key in [first_page, second_page] {
+        let mut registry = page(&env, &key);
+        registry
+            .entries
+            .resize(REGISTRY_PAGE_ENTRIES, entry(Pubkey::new_unique(), 1, 1));
+        let mut account = env.svm.get_account(&key).unwrap();
+        account.data = borsh::to_vec(&registry).unwrap();
+        account.lamports = env
+            .svm
+            .minimum_balance_for_rent_exemption(account.data.len());
+        env.svm.set_account(key, account).unwrap();
+    }
+    let third_page = registry_page_pda(&maker_mint, &taker_mint, 2);
+    env.order_id = 3;
+    env.create_order_ata();
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    assert_eq!(env.order().status, OrderStatus::Open);
+    assert!(env.svm.get_account(&third_page).is_none());
+
+    env.order_id = 4;
+    env.create_order_ata();
+    let instruction = SwapInstruction::InitializeOrder {
+        order_id: 4,
+        maker_amount: 100,
+        taker_amount: 200,
+        requires_confirmation: false,
+        dual_escrow: false,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    };
+    env.initialize_with_accounts(
+        &taker.pubkey(),
+        &instruction,
+        &[
+            AccountMeta::new(second_page, false),
+            AccountMeta::new(third_page, false),
+        ],
+    )
+    .unwrap();
+    assert_eq!(page(&env, &second_page).next, third_page);
+    let registry = page(&env, &third_page);
+    assert_eq!(registry.page, 2);
+    assert_eq!(registry.entries, [entry(env.order_pda(), 100, 200)]);
+}
+
+#[test]
+fn test_order_events() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let order_pda = env.order_pda();
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+
+    let result = env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    assert_eq!(
+        events(&result.logs),
+        [SwapEvent::OrderCreated(OrderCreated {
+            order: order_pda,
+            maker: maker.pubkey(),
+            taker: taker.pubkey(),
+            maker_mint,
+            taker_mint,
+            order_id: env.order_id,
+            maker_amount: 100,
+            taker_amount: 200,
+        })]
+    );
+
+    let change_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+            AccountMeta::new(env.ata(&order_pda, &maker_mint), false),
+            AccountMeta::new(env.ata(&maker.pubkey(), &maker_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(maker_mint, false),
+            AccountMeta::new_readonly(taker_mint, false),
+        ],
+        data: SwapInstruction::ChangeOrderAmounts {
+            new_maker_amount: 80,
+            new_taker_amount: 240,
+        }
+        .pack(),
+    };
+    let result = env.send(&[change_ix], &maker).unwrap();
+    assert_eq!(
+        events(&result.logs),
+        [SwapEvent::OrderAmountsChanged(OrderAmountsChanged {
+            order: order_pda,
+            maker_amount: 80,
+            taker_amount: 240,
+        })]
+    );
+
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 20,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    let result = env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(
+        events(&result.logs),
+        [SwapEvent::OrderFilled(OrderFilled {
+            order: order_pda,
+            taker: taker.pubkey(),
+            maker_amount: 20,
+            taker_amount: 60,
+            remaining_maker_amount: 60,
+            status: OrderStatus::Open,
+        })]
+    );
+
+    // A fill that completes the order and closes it logs both
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix
+        .accounts
+        .push(AccountMeta::new(maker.pubkey(), false));
+    let result = env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!(
+        events(&result.logs),
+        [
+            SwapEvent::OrderFilled(OrderFilled {
+                order: order_pda,
+                taker: taker.pubkey(),
+                maker_amount: 60,
+                taker_amount: 180,
+                remaining_maker_amount: 0,
+                status: OrderStatus::Completed,
+            }),
+            SwapEvent::OrderClosed(OrderClosed {
+                order: order_pda,
+                maker: maker.pubkey(),
+                status: OrderStatus::Completed,
+            }),
+        ]
+    );
+}
+
+#[cfg(feature = "event-cpi")]
+#[test]
+fn test_order_events_cpi() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let order_pda = env.order_pda();
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+
+    // With the event authority passed, the fill event also lands in the inner instructions
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts.extend([
+        AccountMeta::new_readonly(event_authority_pda(), false),
+        AccountMeta::new_readonly(PROGRAM_KEY, false),
+    ]);
+    let result = env.send(&[fill_ix], &taker).unwrap();
+    let cpi_events: Vec<_> = result
+        .inner_instructions
+        .iter()
+        .flatten()
+        .filter_map(|inner| SwapEvent::unpack_cpi(&inner.instruction.data).ok())
+        .collect();
+    assert_eq!(cpi_events, events(&result.logs));
+    assert_eq!(
+        cpi_events[0],
+        SwapEvent::OrderFilled(OrderFilled {
+            order: order_pda,
+            taker: taker.pubkey(),
+            maker_amount: 100,
+            taker_amount: 200,
+            remaining_maker_amount: 0,
+            status: OrderStatus::Completed,
+        })
+    );
+
+    // Nobody but the program can sign for the event authority
+    let mut forged_data = EVENT_IX_TAG.to_vec();
+    forged_data.extend_from_slice(
+        &OrderClosed {
+            order: order_pda,
+            maker: maker.pubkey(),
+            status: OrderStatus::Cancelled,
+        }
+        .data(),
+    );
+    let forged_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![AccountMeta::new_readonly(event_authority_pda(), false)],
+        data: forged_data,
+    };
+    assert!(env.send(&[forged_ix], &maker).is_err());
+}
+
+#[test]
+fn test_fill_receipts() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let order_pda = env.order_pda();
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+
+    let partial_fill = SwapInstruction::CompleteSwap {
+        fill_amount: 40,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = partial_fill.clone();
+    fill_ix.accounts.extend([
+        AccountMeta::new(fill_receipt_pda(&order_pda, 0), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+    env.send(&[fill_ix], &taker).unwrap();
+
+    let clock = env.svm.get_sysvar::<Clock>();
+    let account = env
+        .svm
+        .get_account(&fill_receipt_pda(&order_pda, 0))
+        .unwrap();
+    assert_eq!(account.owner, PROGRAM_KEY);
+    assert_eq!(
+        FillReceipt::try_from_slice(&account.data).unwrap(),
+        FillReceipt {
+            discriminator: FillReceipt::DISCRIMINATOR,
+            order: order_pda,
+            fill_index: 0,
+            maker: maker.pubkey(),
+            taker: taker.pubkey(),
+            maker_mint: env.maker_mint,
+            taker_mint: env.taker_mint,
+            maker_amount: 40,
+            taker_amount: 80,
+            price: 2_000_000_000,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+            bump: Pubkey::find_program_address(
+                &[b"receipt", order_pda.as_ref(), &0u64.to_le_bytes()],
+                &PROGRAM_KEY
+            )
+            .1,
+        }
+    );
+    assert_eq!({ env.order().fill_count }, 1);
+
+    // Without the receipt passed the fill is still counted, just not recorded
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = partial_fill;
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!({ env.order().fill_count }, 2);
+    assert!(env
+        .svm
+        .get_account(&fill_receipt_pda(&order_pda, 1))
+        .is_none_or(|account| account.lamports == 0));
+
+    // A receipt for the wrong fill index is ignored
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts.extend([
+        AccountMeta::new(fill_receipt_pda(&order_pda, 0), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+    env.send(&[fill_ix], &taker).unwrap();
+    assert_eq!({ env.order().fill_count }, 3);
+}
+
+#[test]
+fn test_fill_settles_before_paying() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    let escrow = env.ata(&env.order_pda(), &env.maker_mint);
+
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 40,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    env.send(&[fill_ix], &taker).unwrap();
+    let order = env.order();
+    assert_eq!(({ order.filled_amount }, { order.maker_amount }), (40, 60));
+
+    // Tokens sent to the escrow on top of the order do not make it fillable again
+    let top_up_ix = spl_token::instruction::transfer(
+        &spl_token::id(),
+        &env.ata(&maker.pubkey(), &env.maker_mint),
+        &escrow,
+        &maker.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    env.send(&[top_up_ix], &maker).unwrap();
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.data = SwapInstruction::CompleteSwap {
+        fill_amount: 61,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+        taker_proof: vec![],
+        memo: None,
+    }
+    .pack();
+    let failure = env.send(&[fill_ix], &taker).unwrap_err();
+    assert_eq!(
+        client::error::transaction_swap_error(&failure.err),
+        Some(SwapError::InvalidAmount)
+    );
+
+    // A second fill of the rest in the same transaction finds the order settled
+    let fill_ix = env.complete_swap_ix(&taker.pubkey());
+    let failure = env
+        .send(&[fill_ix.clone(), fill_ix.clone()], &taker)
+        .unwrap_err();
+    assert_eq!(
+        client::error::transaction_swap_error(&failure.err),
+        Some(SwapError::OrderNotOpen)
+    );
+    assert_eq!(env.token_balance(&escrow), 160);
+
+    env.send(&[fill_ix], &taker).unwrap();
+    let order = env.order();
+    assert_eq!({ order.status }, OrderStatus::Completed);
+    assert_eq!({ order.filled_amount }, 100);
+    assert_eq!(env.token_balance(&escrow), 100);
+}
+
+#[test]
+fn test_trade_history() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+    let history_pda = trade_history_pda(&maker_mint, &taker_mint);
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+
+    let init_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![
+            AccountMeta::new(taker.pubkey(), true),
+            AccountMeta::new(history_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SwapInstruction::InitializeTradeHistory {
+            maker_mint,
+            taker_mint,
+        }
+        .pack(),
+    };
+    env.send(&[init_ix.clone()], &taker).unwrap();
+    assert!(env.send(&[init_ix], &taker).is_err());
+
+    let mut slots = vec![];
+    for fill_amount in [10, 30] {
+        let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+        fill_ix.data = SwapInstruction::CompleteSwap {
+            fill_amount,
+            max_taker_amount: u64::MAX,
+            min_maker_amount: 0,
+            taker_proof: vec![],
+            memo: None,
+        }
+        .pack();
+        fill_ix.accounts.push(AccountMeta::new(history_pda, false));
+        env.send(&[fill_ix], &taker).unwrap();
+        slots.push(env.svm.get_sysvar::<Clock>().slot);
+    }
+
+    let account = env.svm.get_account(&history_pda).unwrap();
+    assert_eq!(account.data.len(), TradeHistory::LEN);
+    let history = TradeHistory::try_from_slice(&account.data).unwrap();
+    assert_eq!(history.maker_mint, maker_mint);
+    assert_eq!(history.taker_mint, taker_mint);
+    assert_eq!(history.total, 2);
+    assert_eq!(
+        history.recent().copied().collect::<Vec<_>>(),
+        [
+            TradePrint {
+                price: 2_000_000_000,
+                size: 30,
+                slot: slots[1],
+            },
+            TradePrint {
+                price: 2_000_000_000,
+                size: 10,
+                slot: slots[0],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_maker_index() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let index_pda = maker_index_pda(&maker.pubkey());
+    let index = |env: &SwapEnv| {
+        let account = env.svm.get_account(&index_pda).unwrap();
+        assert_eq!(account.owner, PROGRAM_KEY);
+        let index = MakerIndex::try_from_slice(&account.data).unwrap();
+        assert_eq!(account.data.len(), MakerIndex::space(index.orders.len()));
+        index
+    };
+
+    // Each new order is listed in the index, created by the first one
+    let mut orders = Vec::new();
+    for order_id in [1, 2, 3] {
+        env.order_id = order_id;
+        env.create_order_ata();
+        env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+        orders.push(env.order_pda());
+    }
+    assert_eq!(index(&env).maker, maker.pubkey());
+    assert_eq!(index(&env).orders, orders);
+
+    // Closing an order drops it and the index shrinks to fit
+    env.order_id = 2;
+    let close_ix = env.close_order_ix(&maker.pubkey());
+    env.send(&[close_ix], &maker).unwrap();
+    assert_eq!(index(&env).orders, [orders[0], orders[2]]);
+
+    // The index is optional on close; without it the order closes and stays listed
+    env.order_id = 1;
+    let mut close_ix = env.close_order_ix(&maker.pubkey());
+    close_ix.accounts.pop();
+    env.send(&[close_ix], &maker).unwrap();
+    assert!(env
+        .svm
+        .get_account(&orders[0])
+        .is_none_or(|account| account.lamports == 0));
+    assert_eq!(index(&env).orders, [orders[0], orders[2]]);
+
+    // A fill that closes the order drops it when the index is passed along
+    env.order_id = 3;
+    let mut fill_ix = env.complete_swap_ix(&taker.pubkey());
+    fill_ix.accounts.extend([
+        AccountMeta::new(maker.pubkey(), false),
+        AccountMeta::new(index_pda, false),
+    ]);
+    env.send(&[fill_ix], &taker).unwrap();
+    assert!(env
+        .svm
+        .get_account(&orders[2])
+        .is_none_or(|account| account.lamports == 0));
+    assert_eq!(index(&env).orders, [orders[0]]);
+}
+
+#[test]
+fn test_complete_swaps_batch() {
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    let mut accounts = Vec::new();
+    for order_id in [1, 2] {
+        env.order_id = order_id;
+        env.create_order_ata();
+        env.initialize_order(&taker.pubkey(), 100 * order_id, 200 * order_id)
+            .unwrap();
+        accounts.extend(env.complete_swap_ix(&taker.pubkey()).accounts);
+    }
+    let fill = |fill_amount: u64| FillParams {
+        fill_amount,
+        max_taker_amount: u64::MAX,
+        min_maker_amount: 0,
+    };
+
+    // One order filled whole and the other in part, in the same instruction
+    let batch_ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts,
+        data: SwapInstruction::CompleteSwaps {
+            fills: vec![fill(0), fill(50)],
+        }
+        .pack(),
+    };
+    env.send(&[batch_ix], &taker).unwrap();
+
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &env.maker_mint)),
+        150
+    );
+    assert_eq!(
+        env.token_balance(&env.ata(&env.maker.pubkey(), &env.taker_mint)),
+        300
+    );
+    env.order_id = 1;
+    assert_eq!(env.order().status, OrderStatus::Completed);
+    env.order_id = 2;
+    assert_eq!({ env.order().maker_amount }, 150);
+}
+
+#[test]
+fn test_update_order() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100_000, 150_000)
+        .unwrap();
+    let order_pda = env.order_pda();
+    let escrow = env.ata(&order_pda, &env.maker_mint);
+    let maker_ata = env.ata(&maker.pubkey(), &env.maker_mint);
+    let maker_balance = env.token_balance(&maker_ata);
+    let new_taker = Pubkey::new_unique();
+    let expires_at = env.svm.get_sysvar::<Clock>().unix_timestamp + 100;
+
+    let update_ix = |instruction: SwapInstruction, accounts: Vec<AccountMeta>| Instruction {
+        program_id: PROGRAM_KEY,
+        accounts,
+        data: instruction.pack(),
+    };
+    let amount_accounts = vec![
+        AccountMeta::new(maker.pubkey(), true),
+        AccountMeta::new(order_pda, false),
+        AccountMeta::new(escrow, false),
+        AccountMeta::new(maker_ata, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(env.maker_mint, false),
+        AccountMeta::new_readonly(env.taker_mint, false),
+    ];
+
+    // Price, taker and expiry change together, and the escrow is refunded the difference
+    let ix = update_ix(
+        SwapInstruction::UpdateOrder {
+            new_maker_amount: Some(60_000),
+            new_taker_amount: None,
+            new_taker: Some(new_taker),
+            expires_at: Some(expires_at),
+        },
+        amount_accounts.clone(),
+    );
+    env.send(&[ix], &maker).unwrap();
+    let order = env.order();
+    assert_eq!({ order.maker_amount }, 60_000);
+    assert_eq!({ order.taker_amount }, 150_000);
+    assert_eq!(order.taker, new_taker);
+    assert_eq!({ order.expires_at }, expires_at);
+    assert_eq!(env.token_balance(&escrow), 60_000);
+    assert_eq!(env.token_balance(&maker_ata), maker_balance + 40_000);
+
+    // A bad field rejects the whole update
+    let ix = update_ix(
+        SwapInstruction::UpdateOrder {
+            new_maker_amount: None,
+            new_taker_amount: Some(120_000),
+            new_taker: Some(taker.pubkey()),
+            expires_at: Some(expires_at - 200),
+        },
+        amount_accounts,
+    );
+    assert!(env.send(&[ix], &maker).is_err());
+    assert_eq!({ env.order().taker_amount }, 150_000);
+    assert_eq!(env.order().taker, new_taker);
+
+    // Retargeting alone needs only the order
+    let ix = update_ix(
+        SwapInstruction::UpdateOrder {
+            new_maker_amount: None,
+            new_taker_amount: None,
+            new_taker: Some(taker.pubkey()),
+            expires_at: None,
+        },
+        vec![
+            AccountMeta::new(maker.pubkey(), true),
+            AccountMeta::new(order_pda, false),
+        ],
+    );
+    env.send(&[ix], &maker).unwrap();
+    assert_eq!(env.order().taker, taker.pubkey());
+}
+
+#[test]
+fn test_instruction_discriminators() {
+    // Each discriminator is the Anchor-style hash of the instruction's name
+    let expected = solana_program::hash::hash(b"global:initialize_order").to_bytes();
+    assert_eq!(INSTRUCTION_DISCRIMINATORS[0], expected[..8]);
+    let mut seen = std::collections::HashSet::new();
+    assert!(INSTRUCTION_DISCRIMINATORS.iter().all(|d| seen.insert(*d)));
+
+    let instruction = SwapInstruction::UpdateOrder {
+        new_maker_amount: Some(5),
+        new_taker_amount: None,
+        new_taker: None,
+        expires_at: Some(60),
+    };
+    let data = instruction.pack();
+    assert_eq!(data[..8], INSTRUCTION_DISCRIMINATORS[73]);
+    assert_eq!(SwapInstruction::unpack(&data).unwrap().pack(), data);
+
+    // Fields appended by a newer client are ignored
+    let mut extended = data.clone();
+    extended.extend_from_slice(&[1, 2, 3]);
+    assert_eq!(SwapInstruction::unpack(&extended).unwrap().pack(), data);
+
+    // Unknown discriminators and bare enum tags are rejected
+    assert!(SwapInstruction::unpack(&[0xff; 8]).is_err());
+    assert!(SwapInstruction::unpack(&[0, 0]).is_err());
+
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let ix = Instruction {
+        program_id: PROGRAM_KEY,
+        accounts: vec![AccountMeta::new(maker.pubkey(), true)],
+        data: vec![14, 0, 0, 0, 0, 0, 0, 0, 0],
+    };
+    assert!(env.send(&[ix], &maker).is_err());
+}
+
+#[test]
+fn test_instruction_builders() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let params = |order_id: u64| OrderParams {
+        order_id,
+        maker_amount: 100,
+        taker_amount: 200,
+        requires_confirmation: false,
+        dual_escrow: false,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    };
+    let init_accounts = |order_id: u64| {
+        InitializeOrderAccounts::new(
+            &PROGRAM_KEY,
+            &maker.pubkey(),
+            &taker.pubkey(),
+            &env.maker_mint,
+            &env.taker_mint,
+            &spl_token::id(),
+            &spl_token::id(),
+            order_id,
+        )
+        .unwrap()
+    };
+
+    // Orders built this way are escrowed in their vaults
+    let first = init_accounts(1);
+    let second = init_accounts(2);
+    env.send(
+        &[
+            initialize_order(&PROGRAM_KEY, &first, &params(1)),
+            initialize_order(&PROGRAM_KEY, &second, &params(2)),
+        ],
+        &maker,
+    )
+    .unwrap();
+    assert_eq!(env.token_balance(&first.escrow), 100);
+    assert_eq!(env.token_balance(&second.escrow), 100);
+
+    let swap_accounts = CompleteSwapAccounts::new(
+        &PROGRAM_KEY,
+        &first.order,
+        &maker.pubkey(),
+        &taker.pubkey(),
+        &env.maker_mint,
+        &env.taker_mint,
+        &spl_token::id(),
+        &spl_token::id(),
+    );
+    let fill = FillParams {
+        fill_amount: 0,
+        max_taker_amount: 200,
+        min_maker_amount: 100,
+    };
+    env.send(
+        &[complete_swap(&PROGRAM_KEY, &swap_accounts, &fill)],
+        &taker,
+    )
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&swap_accounts.taker_receiving_token_account),
+        100
+    );
+    assert_eq!(
+        env.token_balance(&swap_accounts.maker_receiving_token_account),
+        200
+    );
+
+    let close_accounts = CloseOrderAccounts::new(
+        &PROGRAM_KEY,
+        &second.order,
+        &maker.pubkey(),
+        &maker.pubkey(),
+        &env.maker_mint,
+        &spl_token::id(),
+    );
+    env.send(&[close_order(&PROGRAM_KEY, &close_accounts, None)], &maker)
+        .unwrap();
+    assert!(env.svm.get_account(&second.order).is_none());
+    assert_eq!(
+        env.token_balance(&close_accounts.maker_token_account),
+        1_000_000 - 100
+    );
+}
+
+#[test]
+fn test_client_builders() {
+    let mut env = SwapEnv::new();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let (maker_mint, taker_mint) = (env.maker_mint, env.taker_mint);
+    let token_program = spl_token::id();
+    let params = |order_id: u64| OrderParams {
+        order_id,
+        maker_amount: 100,
+        taker_amount: 200,
+        requires_confirmation: false,
+        dual_escrow: false,
+        all_or_none: false,
+        memo: [0; MEMO_LEN],
+    };
+    let create = |order_id: u64| {
+        client::instructions::initialize_order(
+            &maker.pubkey(),
+            &taker.pubkey(),
+            &maker_mint,
+            &taker_mint,
+            &token_program,
+            &token_program,
+            &params(order_id),
+        )
+        .unwrap()
+    };
+    env.send(&[create(1), create(2)], &maker).unwrap();
+
+    let first = client::pda::order(&maker.pubkey(), &maker_mint, &taker_mint, 1).unwrap();
+    let second = client::pda::order(&maker.pubkey(), &maker_mint, &taker_mint, 2).unwrap();
+    let fetch = |env: &SwapEnv, order: &Pubkey| {
+        client::decode_order(&env.svm.get_account(order).unwrap().data).unwrap()
+    };
+    assert_eq!(
+        env.token_balance(&client::pda::vault(&first, &maker_mint)),
+        100
+    );
+
+    let order = fetch(&env, &first);
+    env.send(
+        &[
+            client::instructions::change_order_amounts(
+                &maker.pubkey(),
+                &first,
+                &order,
+                &token_program,
+                80,
+                160,
+            ),
+            client::instructions::change_taker(&maker.pubkey(), &first, &Pubkey::default()),
+        ],
+        &maker,
+    )
+    .unwrap();
+    let order = fetch(&env, &first);
+    assert_eq!(({ order.maker_amount }, { order.taker_amount }), (80, 160));
+    assert_eq!({ order.taker }, Pubkey::default());
+
+    let fill = FillParams {
+        fill_amount: 0,
+        max_taker_amount: 160,
+        min_maker_amount: 80,
+    };
+    env.send(
+        &[client::instructions::complete_swap(
+            &taker.pubkey(),
+            &first,
+            &order,
+            &token_program,
+            &token_program,
+            &fill,
+        )],
+        &taker,
+    )
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &maker_mint)),
+        80
+    );
+    assert_eq!({ fetch(&env, &first).status }, OrderStatus::Completed);
+
+    let order = fetch(&env, &second);
+    env.send(
+        &[client::instructions::close_order(
+            &maker.pubkey(),
+            &second,
+            &order,
+            &token_program,
+            None,
+        )],
+        &maker,
+    )
+    .unwrap();
+    assert!(env.svm.get_account(&second).is_none());
+
+    // Other accounts are not orders
+    let mint_account = env.svm.get_account(&maker_mint).unwrap();
+    assert!(matches!(
+        client::decode_order(&mint_account.data),
+        Err(client::ClientError::NotAnOrder)
+    ));
+}
+
+#[test]
+fn test_order_offsets() {
+    use splerg_p2p::offsets::*;
+
+    // Every published offset is where the field sits in the packed layout
+    macro_rules! assert_offsets {
+        ($($field:ident => $offset:expr,)*) => {
+            $(assert_eq!(
+                std::mem::offset_of!(SwapOrder, $field),
+                $offset,
+                stringify!($field)
+            );)*
+        };
+    }
+    assert_offsets! {
+        discriminator => DISCRIMINATOR,
+        version => VERSION,
+        maker => MAKER,
+        taker => TAKER,
+        maker_token_mint => MAKER_TOKEN_MINT,
+        taker_token_mint => TAKER_TOKEN_MINT,
+        maker_amount => MAKER_AMOUNT,
+        taker_amount => TAKER_AMOUNT,
+        bump => BUMP,
+        order_id => ORDER_ID,
+        status => STATUS,
+        requires_confirmation => REQUIRES_CONFIRMATION,
+        accepted_taker => ACCEPTED_TAKER,
+        counter_maker_amount => COUNTER_MAKER_AMOUNT,
+        counter_taker_amount => COUNTER_TAKER_AMOUNT,
+        dual_escrow => DUAL_ESCROW,
+        paused => PAUSED,
+        expires_at => EXPIRES_AT,
+        order_type => ORDER_TYPE,
+        auction_start_ts => AUCTION_START_TS,
+        auction_end_ts => AUCTION_END_TS,
+        floor_taker_amount => FLOOR_TAKER_AMOUNT,
+        best_bid => BEST_BID,
+        maker_decimals => MAKER_DECIMALS,
+        taker_decimals => TAKER_DECIMALS,
+        price_feed => PRICE_FEED,
+        spread_bps => SPREAD_BPS,
+        all_or_none => ALL_OR_NONE,
+        hashlock => HASHLOCK,
+        htlc_timeout => HTLC_TIMEOUT,
+        vesting_cliff => VESTING_CLIFF,
+        vesting_duration => VESTING_DURATION,
+        vesting_start_ts => VESTING_START_TS,
+        vesting_total => VESTING_TOTAL,
+        vesting_claimed => VESTING_CLAIMED,
+        tranche_count => TRANCHE_COUNT,
+        tranche_interval => TRANCHE_INTERVAL,
+        tranches_settled => TRANCHES_SETTLED,
+        stream_start_ts => STREAM_START_TS,
+        recurrence_interval => RECURRENCE_INTERVAL,
+        recurrence_maker_amount => RECURRENCE_MAKER_AMOUNT,
+        recurrence_taker_amount => RECURRENCE_TAKER_AMOUNT,
+        renewals_remaining => RENEWALS_REMAINING,
+        next_renewal_ts => NEXT_RENEWAL_TS,
+        seed_maker => SEED_MAKER,
+        delegate => DELEGATE,
+        allowed_takers => ALLOWED_TAKERS,
+        taker_merkle_root => TAKER_MERKLE_ROOT,
+        gate_mint => GATE_MINT,
+        gate_min_balance => GATE_MIN_BALANCE,
+        commit_reveal => COMMIT_REVEAL,
+        fill_commitment => FILL_COMMITMENT,
+        commit_slot => COMMIT_SLOT,
+        revealed_taker => REVEALED_TAKER,
+        referral_bps => REFERRAL_BPS,
+        memo => MEMO,
+        tag => TAG,
+        maker_amount_gross => MAKER_AMOUNT_GROSS,
+        taker_amount_net => TAKER_AMOUNT_NET,
+        ui_amounts => UI_AMOUNTS,
+        maker_mint_risk => MAKER_MINT_RISK,
+        taker_mint_risk => TAKER_MINT_RISK,
+        nft => NFT,
+        programmable => PROGRAMMABLE,
+        taker_collection => TAKER_COLLECTION,
+        maker_group => MAKER_GROUP,
+        taker_group => TAKER_GROUP,
+        rent_payer => RENT_PAYER,
+        fill_count => FILL_COUNT,
+        filled_amount => FILLED_AMOUNT,
+        referrer => REFERRER,
+    }
+
+    // The bytes of a written order sit at those offsets, the filters match them and a
+    // filter for anything else does not
+    let (maker, taker) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (maker_mint, taker_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut order = SwapOrder::new(maker, taker, maker_mint, taker_mint, 100, 200, 254, 7);
+    order.status = OrderStatus::Accepted;
+    order.fill_count = 3;
+    let mut data = vec![0; SwapOrder::LEN];
+    order.pack(&mut data).unwrap();
+    assert_eq!(&data[MAKER..MAKER + 32], maker.as_ref());
+    assert_eq!(&data[TAKER..TAKER + 32], taker.as_ref());
+    assert_eq!(
+        &data[MAKER_TOKEN_MINT..MAKER_TOKEN_MINT + 32],
+        maker_mint.as_ref()
+    );
+    assert_eq!(
+        &data[TAKER_TOKEN_MINT..TAKER_TOKEN_MINT + 32],
+        taker_mint.as_ref()
+    );
+    assert_eq!(&data[MAKER_AMOUNT..MAKER_AMOUNT + 8], &100u64.to_le_bytes());
+    assert_eq!(&data[TAKER_AMOUNT..TAKER_AMOUNT + 8], &200u64.to_le_bytes());
+    assert_eq!(data[BUMP], 254);
+    assert_eq!(&data[ORDER_ID..ORDER_ID + 8], &7u64.to_le_bytes());
+    assert_eq!(data[STATUS], OrderStatus::Accepted as u8);
+    assert_eq!(&data[FILL_COUNT..FILL_COUNT + 8], &3u64.to_le_bytes());
+
+    let matches = |filters: Vec<RpcFilterType>| {
+        filters.iter().all(|filter| match filter {
+            RpcFilterType::DataSize(size) => *size == data.len() as u64,
+            RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&data),
+            _ => unreachable!(),
+        })
+    };
+    assert!(matches(client::filters::orders()));
+    assert!(matches(client::filters::by_maker(&maker)));
+    assert!(matches(client::filters::by_taker(&taker)));
+    assert!(matches(client::filters::by_mint_pair(
+        &maker_mint,
+        &taker_mint
+    )));
+    assert!(matches(client::filters::by_status(OrderStatus::Accepted)));
+    assert!(!matches(client::filters::by_maker(&taker)));
+    assert!(!matches(client::filters::by_mint_pair(
+        &taker_mint,
+        &maker_mint
+    )));
+    assert!(!matches(client::filters::by_status(OrderStatus::Open)));
+}
+
+#[test]
+fn test_fill_preflight() {
+    use client::preflight::{
+        check_order, check_paying_account, check_receiving_account, FillDiagnostic,
+        TokenAccountState,
+    };
+
+    let mut env = SwapEnv::new();
+    let taker = env.taker.pubkey();
+    env.initialize_order(&taker, 100, 200).unwrap();
+    let order = env.order();
+    let now = env.svm.get_sysvar::<Clock>().unix_timestamp;
+    let fill = |fill_amount: u64, max_taker_amount: u64| FillParams {
+        fill_amount,
+        max_taker_amount,
+        min_maker_amount: 0,
+    };
+
+    // A fill of 0 takes the whole remaining size
+    assert_eq!(check_order(&order, &taker, &fill(0, 200), now), Ok(100));
+    assert_eq!(check_order(&order, &taker, &fill(40, 80), now), Ok(40));
+    let stranger = Pubkey::new_unique();
+    assert_eq!(
+        check_order(&order, &stranger, &fill(0, 200), now),
+        Err(FillDiagnostic::TakerNotAllowed(stranger))
+    );
+    assert_eq!(
+        check_order(&order, &taker, &fill(101, 300), now),
+        Err(FillDiagnostic::InvalidAmount {
+            fill_amount: 101,
+            remaining: 100
+        })
+    );
+    assert_eq!(
+        check_order(&order, &taker, &fill(0, 199), now),
+        Err(FillDiagnostic::SlippageExceeded {
+            taker_amount: 200,
+            max_taker_amount: 199
+        })
+    );
+
+    // A missing receiving account is created by the fill, a missing paying account fails it
+    let sending = env.ata(&taker, &env.taker_mint);
+    let state = TokenAccountState::unpack(&env.svm.get_account(&sending).unwrap().data);
+    assert_eq!(
+        check_paying_account(&sending, state.as_ref(), &taker, &env.taker_mint, 200),
+        Ok(())
+    );
+    assert_eq!(
+        check_paying_account(&sending, state.as_ref(), &taker, &env.taker_mint, 2_000_000),
+        Err(FillDiagnostic::InsufficientBalance {
+            account: sending,
+            needed: 2_000_000,
+            available: 1_000_000
+        })
+    );
+    assert_eq!(
+        check_paying_account(&sending, state.as_ref(), &taker, &env.maker_mint, 200),
+        Err(FillDiagnostic::WrongTokenAccount(sending))
+    );
+    let missing = Pubkey::new_unique();
+    assert_eq!(
+        check_receiving_account(&missing, None, &env.maker.pubkey(), &env.taker_mint),
+        Ok(())
+    );
+    assert_eq!(
+        check_paying_account(&missing, None, &env.maker.pubkey(), &env.taker_mint, 1),
+        Err(FillDiagnostic::MissingTokenAccount(missing))
+    );
+
+    let taker_keypair = env.taker.insecure_clone();
+    env.complete_swap(&taker_keypair).unwrap();
+    assert_eq!(
+        check_order(&env.order(), &taker, &fill(0, 200), now),
+        Err(FillDiagnostic::NotOpen(OrderStatus::Completed))
+    );
+}
+
+#[test]
+fn test_transaction_builder() {
+    use client::transaction::TransactionBuilder;
+    use solana_program::address_lookup_table::AddressLookupTableAccount;
+
+    let mut env = SwapEnv::new();
+    let taker = env.taker.insecure_clone();
+    env.initialize_order(&taker.pubkey(), 100, 200).unwrap();
+    let order = env.order();
+    let token_program = spl_token::id();
+    let fill = client::instructions::complete_swap(
+        &taker.pubkey(),
+        &env.order_pda(),
+        &order,
+        &token_program,
+        &token_program,
+        &FillParams {
+            fill_amount: 0,
+            max_taker_amount: 200,
+            min_maker_amount: 100,
+        },
+    );
+    let builder = TransactionBuilder::new(&taker.pubkey())
+        .compute_unit_limit(400_000)
+        .priority_fee(1_000)
+        .create_ata(&taker.pubkey(), &env.maker_mint, &token_program)
+        .create_ata(&taker.pubkey(), &env.maker_mint, &token_program)
+        .instruction(fill.clone());
+
+    // Compute budget first, the ATA once, then the fill
+    let instructions = builder.to_instructions();
+    assert_eq!(instructions.len(), 4);
+    assert_eq!(
+        instructions[2].program_id,
+        spl_associated_token_account::id()
+    );
+    assert_eq!(instructions[3], fill);
+
+    // Unsigned transactions carry a placeholder for each signer
+    let unsigned = builder.build(env.svm.latest_blockhash()).unwrap();
+    assert_eq!(unsigned.signatures, vec![Default::default()]);
+
+    // Accounts found in a lookup table leave the message's static keys
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: vec![client::pda::config(), client::pda::protocol_stats()],
+    };
+    let with_table = builder
+        .clone()
+        .lookup_table(table)
+        .build(env.svm.latest_blockhash())
+        .unwrap();
+    let static_keys = with_table.message.static_account_keys();
+    assert!(!static_keys.contains(&client::pda::config()));
+    assert!(!static_keys.contains(&client::pda::protocol_stats()));
+    assert!(unsigned
+        .message
+        .static_account_keys()
+        .contains(&client::pda::config()));
+
+    env.svm.expire_blockhash();
+    let transaction = builder
+        .build_signed(env.svm.latest_blockhash(), &[&taker])
+        .unwrap();
+    env.svm.send_transaction(transaction).unwrap();
+    assert_eq!({ env.order().status }, OrderStatus::Completed);
+    assert_eq!(
+        env.token_balance(&env.ata(&taker.pubkey(), &env.maker_mint)),
+        100
+    );
+}
+
+#[test]
+fn test_order_helpers() {
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100_000, 200_000)
+        .unwrap();
+    let order = env.order();
+    assert!(order.is_open());
+    assert_eq!(order.remaining(), 100_000);
+    assert_eq!(order.pda(&PROGRAM_KEY).unwrap(), env.order_pda());
+    let price = order.price();
+    assert_eq!(
+        price,
+        Price {
+            taker_amount: 200_000,
+            maker_amount: 100_000
+        }
+    );
+    assert_eq!(price.taker_amount_for(333), 666);
+    assert_eq!(price.taker_amount_for(1), 2);
+    assert_eq!(price.per_maker_token(9), 2_000_000_000);
+
+    let taker = env.taker.insecure_clone();
+    env.complete_swap(&taker).unwrap();
+    let order = env.order();
+    assert!(!order.is_open());
+    assert_eq!(order.remaining(), 0);
+}
+
+#[test]
+fn test_error_codes() {
+    // Every code maps back to the variant raised with it, up to the first undefined one
+    let mut code = 0;
+    while let Some(error) = client::error::swap_error(code) {
+        assert_eq!(error as u32, code);
+        code += 1;
+    }
+    assert_eq!(code, SwapError::InvalidReferrer as u32 + 1);
+    assert_eq!(
+        client::error::error_message(code),
+        format!("unknown error code {code}")
+    );
+
+    let mut env = SwapEnv::new();
+    env.initialize_order(&env.taker.pubkey(), 100, 200).unwrap();
+    env.maker_order_ix(&SwapInstruction::PauseOrder).unwrap();
+    let taker = env.taker.insecure_clone();
+    let failure = env.complete_swap(&taker).unwrap_err();
+    assert_eq!(
+        client::error::transaction_swap_error(&failure.err),
+        Some(SwapError::OrderPaused)
+    );
+    assert_eq!(
+        client::error::error_message(SwapError::OrderPaused as u32),
+        "order is paused"
+    );
+
+    let error = client::ClientError::Program(SwapError::SlippageExceeded.into());
+    assert_eq!(error.swap_error(), Some(SwapError::SlippageExceeded));
+}
+
+proptest! {
+    /// Any order survives a pack and unpack and fills exactly `SwapOrder::LEN` bytes, so a
+    /// field added without its `LEN` term shows up here
+    #[test]
+    fn prop_order_round_trip(bytes in collection::vec(any::<u8>(), SwapOrder::LEN)) {
+        let order = SwapOrder::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        assert_eq!(std::mem::size_of_val(&order), SwapOrder::LEN);
+
+        let mut data = vec![0; SwapOrder::LEN];
+        order.pack(&mut data).unwrap();
+        assert_eq!(SwapOrder::unpack(&data).unwrap(), order);
+    }
+
+    /// Any instruction decodes from its packed data to an instruction that packs the same
+    #[test]
+    fn prop_instruction_round_trip(bytes in collection::vec(any::<u8>(), 0..2048)) {
+        let Ok(instruction) = SwapInstruction::arbitrary(&mut Unstructured::new(&bytes)) else {
+            return Ok(());
+        };
+        let data = instruction.pack();
+        let variant = borsh::to_vec(&instruction).unwrap()[0];
+        assert_eq!(data[..8], INSTRUCTION_DISCRIMINATORS[variant as usize]);
+
+        let unpacked = SwapInstruction::unpack(&data).unwrap();
+        assert_eq!(unpacked.pack(), data);
+        assert_eq!(format!("{unpacked:?}"), format!("{instruction:?}"));
+    }
+}
@@ -0,0 +1,28 @@
+#!/bin/bash
+
+# Generates idl/splerg_p2p.json from the Shank annotations on `SwapInstruction` and the
+# account structs. Needs shank-cli (`cargo install shank-cli`) and node.
+set -e
+
+shank idl \
+    --crate-root programs/splerg-p2p \
+    --out-dir idl \
+    --out-filename splerg_p2p.json \
+    --program-id GKTd9AGFpPGNKK28ncHeGGuT7rBJLzPxNjCUPKn8Yik8
+
+# Shank numbers instructions by variant, but the program reads the 8-byte
+# sha256("global:<instruction_name>") prefix, so swap those in
+node -e '
+const fs = require("fs");
+const { createHash } = require("crypto");
+const path = "idl/splerg_p2p.json";
+const idl = JSON.parse(fs.readFileSync(path, "utf8"));
+for (const ix of idl.instructions) {
+    const name = ix.name.replace(/[A-Z]/g, (c) => "_" + c.toLowerCase());
+    const hash = createHash("sha256").update("global:" + name).digest();
+    ix.discriminant = { type: { array: ["u8", 8] }, value: [...hash.subarray(0, 8)] };
+}
+fs.writeFileSync(path, JSON.stringify(idl, null, 2) + "\n");
+'
+
+echo "IDL written to idl/splerg_p2p.json"